@@ -0,0 +1,442 @@
+//! A blocking client for APRS-IS, the internet backbone of APRS.
+//!
+//! After connecting, the client logs in with a callsign, passcode and optional
+//! server-side filter. Received lines are TNC2 packets, except for server
+//! comments and keepalives, which start with `#` and are skipped.
+//!
+//! ```no_run
+//! use aprs_parser::aprs_is::{AprsIsClient, Login};
+//! use aprs_parser::Callsign;
+//!
+//! let login = Login::new(Callsign::new_no_ssid("N0CALL"), -1).with_filter("r/49/-72/50");
+//! let client = AprsIsClient::connect("rotate.aprs2.net:14580", &login).unwrap();
+//!
+//! for packet in client {
+//!     println!("{:?}", packet);
+//! }
+//! ```
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+use AprsPacket;
+use Callsign;
+use DecodeError;
+use EncodeError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AprsIsError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+}
+
+/// Login details sent to the server after connecting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Login {
+    pub callsign: Callsign,
+    /// `-1` logs in receive-only
+    pub passcode: i32,
+    pub filter: Option<String>,
+    pub software: String,
+    pub version: String,
+}
+
+impl Login {
+    pub fn new(callsign: Callsign, passcode: i32) -> Self {
+        Self {
+            callsign,
+            passcode,
+            filter: None,
+            software: "aprs-parser".to_owned(),
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+        }
+    }
+
+    /// Sets a server-side filter, e.g. `r/49/-72/50`.
+    pub fn with_filter(mut self, filter: impl Into<String>) -> Self {
+        self.filter = Some(filter.into());
+        self
+    }
+
+    pub fn with_software(
+        mut self,
+        software: impl Into<String>,
+        version: impl Into<String>,
+    ) -> Self {
+        self.software = software.into();
+        self.version = version.into();
+        self
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> io::Result<()> {
+        write!(
+            buf,
+            "user {} pass {} vers {} {}",
+            self.callsign, self.passcode, self.software, self.version
+        )?;
+        if let Some(filter) = &self.filter {
+            write!(buf, " filter {}", filter)?;
+        }
+        write!(buf, "\r\n")
+    }
+
+    /// Parses a login line as received by a server, e.g.
+    /// `user N0CALL pass 12345 vers aprs-parser 0.4 filter r/49/-72/50`.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut words = line.trim_end_matches(&['\r', '\n'][..]).split(' ');
+        if words.next()? != "user" {
+            return None;
+        }
+        let mut login = Self::new(Callsign::new(words.next()?)?, -1);
+        login.software.clear();
+        login.version.clear();
+
+        while let Some(word) = words.next() {
+            match word {
+                "pass" => login.passcode = words.next()?.parse().ok()?,
+                "vers" => {
+                    login.software = words.next()?.to_owned();
+                    login.version = words.next().unwrap_or_default().to_owned();
+                }
+                "filter" => {
+                    let filter = words.by_ref().collect::<Vec<_>>().join(" ");
+                    login.filter = Some(filter).filter(|f| !f.is_empty());
+                }
+                "" => {}
+                _ => return None,
+            }
+        }
+
+        Some(login)
+    }
+}
+
+/// The server's reply to a login, e.g. `# logresp N0CALL verified, server T2TEST`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginResponse {
+    pub callsign: Callsign,
+    pub verified: bool,
+    pub server: Option<String>,
+}
+
+impl LoginResponse {
+    /// Parses a server line, returning `None` if it isn't a login response.
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = line.strip_prefix('#')?;
+        let mut words = line.split(&[' ', ','][..]).filter(|w| !w.is_empty());
+        if words.next()? != "logresp" {
+            return None;
+        }
+
+        let callsign = Callsign::new(words.next()?)?;
+        let verified = match words.next()? {
+            "verified" => true,
+            "unverified" => false,
+            _ => return None,
+        };
+        let server = match words.next() {
+            Some("server") => words.next().map(str::to_owned),
+            _ => None,
+        };
+
+        Some(Self {
+            callsign,
+            verified,
+            server,
+        })
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> io::Result<()> {
+        write!(
+            buf,
+            "# logresp {} {}",
+            self.callsign,
+            if self.verified {
+                "verified"
+            } else {
+                "unverified"
+            }
+        )?;
+        if let Some(server) = &self.server {
+            write!(buf, ", server {}", server)?;
+        }
+        write!(buf, "\r\n")
+    }
+}
+
+/// The passcode for logging in as `callsign`, which is a hash of the
+/// callsign without its SSID.
+pub fn passcode(callsign: &Callsign) -> u16 {
+    let call = callsign.call().to_ascii_uppercase();
+
+    let mut hash: u16 = 0x73e2;
+    for pair in call.as_bytes().chunks(2) {
+        hash ^= u16::from(pair[0]) << 8;
+        if let Some(&b) = pair.get(1) {
+            hash ^= u16::from(b);
+        }
+    }
+
+    hash & 0x7fff
+}
+
+/// Whether `passcode` is the one for `callsign`.
+/// The receive-only passcode `-1` never is.
+pub fn verify_passcode(callsign: &Callsign, passcode: i32) -> bool {
+    i32::from(self::passcode(callsign)) == passcode
+}
+
+pub struct AprsIsClient<S> {
+    stream: BufReader<S>,
+    login_response: Option<LoginResponse>,
+}
+
+impl AprsIsClient<TcpStream> {
+    /// Connects to a server, e.g. `rotate.aprs2.net:14580`, and logs in.
+    pub fn connect<A: ToSocketAddrs>(addr: A, login: &Login) -> Result<Self, AprsIsError> {
+        Self::new(TcpStream::connect(addr)?, login)
+    }
+}
+
+impl<S: Read + Write> AprsIsClient<S> {
+    /// Logs in over an already connected stream.
+    pub fn new(mut stream: S, login: &Login) -> Result<Self, AprsIsError> {
+        let mut buf = vec![];
+        login.encode(&mut buf)?;
+        stream.write_all(&buf)?;
+        stream.flush()?;
+
+        Ok(Self {
+            stream: BufReader::new(stream),
+            login_response: None,
+        })
+    }
+
+    /// Whether the server accepted the passcode, once it has replied to the login.
+    pub fn is_verified(&self) -> Option<bool> {
+        self.login_response.as_ref().map(|r| r.verified)
+    }
+
+    /// The server's reply to the login, once received.
+    pub fn login_response(&self) -> Option<&LoginResponse> {
+        self.login_response.as_ref()
+    }
+
+    pub fn send(&mut self, packet: &AprsPacket) -> Result<(), AprsIsError> {
+        let mut buf = vec![];
+        packet.encode_textual(&mut buf)?;
+        buf.extend_from_slice(b"\r\n");
+
+        let stream = self.stream.get_mut();
+        stream.write_all(&buf)?;
+        stream.flush()?;
+
+        Ok(())
+    }
+
+    /// Waits for the next packet. Returns `None` once the server closes the connection.
+    pub fn recv(&mut self) -> Option<Result<AprsPacket, AprsIsError>> {
+        let mut line = vec![];
+        loop {
+            line.clear();
+            match self.stream.read_until(b'\n', &mut line) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e.into())),
+            }
+
+            match ServerLine::parse(&line) {
+                ServerLine::Packet(p) => return Some(p.map_err(AprsIsError::from)),
+                ServerLine::LoginResponse(r) => self.login_response = Some(r),
+                ServerLine::Other => {}
+            }
+        }
+    }
+}
+
+// A line received from the server. Short-lived, so not worth boxing the packet
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum ServerLine {
+    Packet(Result<AprsPacket, DecodeError>),
+    LoginResponse(LoginResponse),
+    // empty lines, keepalives and other comments
+    Other,
+}
+
+impl ServerLine {
+    pub(crate) fn parse(line: &[u8]) -> Self {
+        let end = line
+            .iter()
+            .rposition(|&b| b != b'\r' && b != b'\n')
+            .map_or(0, |i| i + 1);
+        let line = &line[..end];
+
+        if line.is_empty() {
+            return Self::Other;
+        }
+        if line.starts_with(b"#") {
+            return match LoginResponse::parse(&String::from_utf8_lossy(line)) {
+                Some(response) => Self::LoginResponse(response),
+                None => Self::Other,
+            };
+        }
+
+        Self::Packet(AprsPacket::decode_textual(line))
+    }
+}
+
+impl<S: Read + Write> Iterator for AprsIsClient<S> {
+    type Item = Result<AprsPacket, AprsIsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    struct MockStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn client(input: &[u8], login: &Login) -> AprsIsClient<MockStream> {
+        let stream = MockStream {
+            input: Cursor::new(input.to_vec()),
+            output: vec![],
+        };
+        AprsIsClient::new(stream, login).unwrap()
+    }
+
+    #[test]
+    fn passcodes() {
+        assert_eq!(13023, passcode(&Callsign::new_no_ssid("N0CALL")));
+        assert_eq!(13023, passcode(&Callsign::new_with_ssid("n0call", "9")));
+        assert_eq!(25988, passcode(&Callsign::new_no_ssid("W1AW")));
+
+        assert!(verify_passcode(&Callsign::new_no_ssid("N0CALL"), 13023));
+        assert!(!verify_passcode(&Callsign::new_no_ssid("N0CALL"), 13024));
+        assert!(!verify_passcode(&Callsign::new_no_ssid("N0CALL"), -1));
+    }
+
+    #[test]
+    fn login() {
+        let login = Login::new(Callsign::new_with_ssid("N0CALL", "10"), 12345)
+            .with_software("test", "1.0")
+            .with_filter("r/49/-72/50");
+        let client = client(b"", &login);
+        assert_eq!(
+            &b"user N0CALL-10 pass 12345 vers test 1.0 filter r/49/-72/50\r\n"[..],
+            &client.stream.get_ref().output[..]
+        );
+    }
+
+    #[test]
+    fn parse_login() {
+        let login = Login::new(Callsign::new_with_ssid("N0CALL", "10"), 12345)
+            .with_software("test", "1.0")
+            .with_filter("r/49/-72/50 -t/w");
+        let mut buf = vec![];
+        login.encode(&mut buf).unwrap();
+        assert_eq!(
+            Some(login),
+            Login::parse(std::str::from_utf8(&buf).unwrap())
+        );
+
+        let login = Login::parse("user N0CALL pass -1").unwrap();
+        assert_eq!(-1, login.passcode);
+        assert_eq!(None, login.filter);
+        assert_eq!("", login.software);
+
+        assert_eq!(None, Login::parse("user N0CALL pass abc"));
+        assert_eq!(None, Login::parse("N0CALL pass 12345"));
+    }
+
+    #[test]
+    fn parse_login_response() {
+        let response =
+            LoginResponse::parse("# logresp N0CALL-9 unverified, server T2TEST").unwrap();
+        assert_eq!(
+            LoginResponse {
+                callsign: Callsign::new_with_ssid("N0CALL", "9"),
+                verified: false,
+                server: Some("T2TEST".to_owned()),
+            },
+            response
+        );
+
+        let mut buf = vec![];
+        response.encode(&mut buf).unwrap();
+        assert_eq!(
+            &b"# logresp N0CALL-9 unverified, server T2TEST\r\n"[..],
+            &buf[..]
+        );
+
+        assert_eq!(None, LoginResponse::parse("# aprsc 2.1.14"));
+        assert_eq!(None, LoginResponse::parse("# logresp N0CALL maybe"));
+    }
+
+    #[test]
+    fn receive() {
+        let mut client = client(
+            b"# aprsc 2.1.14\r\n\
+              # logresp N0CALL verified, server T2TEST\r\n\
+              N1CALL>APRS,TCPIP*,qAC,T2TEST:>Hello\r\n\
+              \r\n\
+              # 22 Jun 2023 16:10:01 GMT T2TEST\r\n\
+              Invalid\r\n\
+              N2CALL>APRS,TCPIP*,qAC,T2TEST:>World",
+            &Login::new(Callsign::new_no_ssid("N0CALL"), 12345),
+        );
+        assert_eq!(None, client.is_verified());
+
+        let packet = client.next().unwrap().unwrap();
+        assert_eq!(Callsign::new_no_ssid("N1CALL"), packet.from);
+        assert_eq!(Some(true), client.is_verified());
+        assert_eq!(
+            Some("T2TEST"),
+            client.login_response().unwrap().server.as_deref()
+        );
+
+        assert!(matches!(client.next(), Some(Err(AprsIsError::Decode(_)))));
+        let packet = client.next().unwrap().unwrap();
+        assert_eq!(Callsign::new_no_ssid("N2CALL"), packet.from);
+        assert!(client.next().is_none());
+    }
+
+    #[test]
+    fn send() {
+        let mut client = client(b"", &Login::new(Callsign::new_no_ssid("N0CALL"), -1));
+        client.stream.get_mut().output.clear();
+
+        let packet = AprsPacket::decode_textual(b"N0CALL>APRS,TCPIP*:>Hello").unwrap();
+        client.send(&packet).unwrap();
+        assert_eq!(
+            &b"N0CALL>APRS,TCPIP*:>Hello\r\n"[..],
+            &client.stream.get_ref().output[..]
+        );
+    }
+}
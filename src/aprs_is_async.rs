@@ -0,0 +1,317 @@
+//! An async client for APRS-IS, built on tokio. It speaks the same protocol
+//! as the blocking [`AprsIsClient`](crate::aprs_is::AprsIsClient), but splits
+//! the connection into an [`AprsIsSender`] and a [`Stream`] of packets, so
+//! both can be used from different tasks.
+//!
+//! ```rust,edition2018,no_run
+//! use aprs_parser::aprs_is::Login;
+//! use aprs_parser::aprs_is_async;
+//! use aprs_parser::Callsign;
+//! # use tokio::io::{AsyncRead, AsyncWrite};
+//!
+//! # async fn run<S: AsyncRead + AsyncWrite>(stream: S) -> Result<(), aprs_parser::aprs_is::AprsIsError> {
+//! // `stream` is e.g. a `tokio::net::TcpStream` connected to rotate.aprs2.net:14580
+//! let (mut sender, mut packets) = aprs_is_async::split(stream);
+//!
+//! let login = Login::new(Callsign::new_no_ssid("N0CALL"), -1).with_filter("r/49/-72/50");
+//! sender.login(&login).await?;
+//!
+//! while let Some(packet) = packets.recv().await {
+//!     println!("{:?}", packet);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncBufRead, AsyncRead, AsyncWrite, BufReader, ReadHalf, WriteHalf};
+
+use aprs_is::{AprsIsError, Login, LoginResponse, ServerLine};
+use AprsPacket;
+
+/// Splits a connected stream into its sending and receiving halves.
+/// Nothing is sent until [`AprsIsSender::login`] is awaited.
+pub fn split<S: AsyncRead + AsyncWrite>(stream: S) -> (AprsIsSender<S>, AprsIsStream<S>) {
+    let (reader, writer) = tokio::io::split(stream);
+    let sender = AprsIsSender {
+        writer,
+        buf: vec![],
+    };
+    let stream = AprsIsStream {
+        reader: BufReader::new(reader),
+        line: vec![],
+        login_response: None,
+    };
+
+    (sender, stream)
+}
+
+pub struct AprsIsSender<S> {
+    writer: WriteHalf<S>,
+    buf: Vec<u8>,
+}
+
+impl<S: AsyncWrite> AprsIsSender<S> {
+    /// Logs in. This should be the first thing sent after connecting.
+    pub fn login(&mut self, login: &Login) -> WriteLine<'_, S> {
+        self.buf.clear();
+        let error = login.encode(&mut self.buf).err().map(AprsIsError::from);
+        self.write_line(error)
+    }
+
+    pub fn send(&mut self, packet: &AprsPacket) -> WriteLine<'_, S> {
+        self.buf.clear();
+        let error = packet
+            .encode_textual(&mut self.buf)
+            .err()
+            .map(AprsIsError::from);
+        self.buf.extend_from_slice(b"\r\n");
+        self.write_line(error)
+    }
+
+    fn write_line(&mut self, error: Option<AprsIsError>) -> WriteLine<'_, S> {
+        WriteLine {
+            sender: self,
+            written: 0,
+            error,
+        }
+    }
+}
+
+/// The future returned by [`AprsIsSender::login`] and [`AprsIsSender::send`].
+#[must_use = "futures do nothing unless polled"]
+pub struct WriteLine<'a, S> {
+    sender: &'a mut AprsIsSender<S>,
+    written: usize,
+    error: Option<AprsIsError>,
+}
+
+impl<S: AsyncWrite> Future for WriteLine<'_, S> {
+    type Output = Result<(), AprsIsError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Some(e) = this.error.take() {
+            return Poll::Ready(Err(e));
+        }
+
+        let sender = &mut *this.sender;
+        while this.written < sender.buf.len() {
+            match Pin::new(&mut sender.writer).poll_write(cx, &sender.buf[this.written..]) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(io::Error::from(io::ErrorKind::WriteZero).into()))
+                }
+                Poll::Ready(Ok(n)) => this.written += n,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e.into())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Pin::new(&mut sender.writer)
+            .poll_flush(cx)
+            .map_err(AprsIsError::from)
+    }
+}
+
+pub struct AprsIsStream<S> {
+    reader: BufReader<ReadHalf<S>>,
+    // the line read so far
+    line: Vec<u8>,
+    login_response: Option<LoginResponse>,
+}
+
+impl<S: AsyncRead> AprsIsStream<S> {
+    /// Whether the server accepted the passcode, once it has replied to the login.
+    pub fn is_verified(&self) -> Option<bool> {
+        self.login_response.as_ref().map(|r| r.verified)
+    }
+
+    /// The server's reply to the login, once received.
+    pub fn login_response(&self) -> Option<&LoginResponse> {
+        self.login_response.as_ref()
+    }
+
+    /// Waits for the next packet. Resolves to `None` once the server closes the connection.
+    pub fn recv(&mut self) -> Recv<'_, S> {
+        Recv { stream: self }
+    }
+
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<AprsPacket, AprsIsError>>> {
+        loop {
+            let buf = match Pin::new(&mut self.reader).poll_fill_buf(cx) {
+                Poll::Ready(Ok(buf)) => buf,
+                Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            // an empty buffer means the connection was closed
+            let (complete, used) = match buf.iter().position(|&b| b == b'\n') {
+                Some(i) => (true, i + 1),
+                None => (buf.is_empty(), buf.len()),
+            };
+            self.line.extend_from_slice(&buf[..used]);
+            Pin::new(&mut self.reader).consume(used);
+
+            if !complete {
+                continue;
+            }
+            if self.line.is_empty() {
+                return Poll::Ready(None);
+            }
+
+            let line = std::mem::take(&mut self.line);
+            match ServerLine::parse(&line) {
+                ServerLine::Packet(p) => return Poll::Ready(Some(p.map_err(AprsIsError::from))),
+                ServerLine::LoginResponse(r) => self.login_response = Some(r),
+                ServerLine::Other => {}
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead> Stream for AprsIsStream<S> {
+    type Item = Result<AprsPacket, AprsIsError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+
+/// The future returned by [`AprsIsStream::recv`].
+#[must_use = "futures do nothing unless polled"]
+pub struct Recv<'a, S> {
+    stream: &'a mut AprsIsStream<S>,
+}
+
+impl<S: AsyncRead> Future for Recv<'_, S> {
+    type Output = Option<Result<AprsPacket, AprsIsError>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.get_mut().stream.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::ptr;
+    use std::task::{RawWaker, RawWakerVTable, Waker};
+    use tokio::io::ReadBuf;
+    use Callsign;
+
+    struct MockStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl AsyncRead for MockStream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            let this = self.get_mut();
+            let n = io::Read::read(&mut this.input, buf.initialize_unfilled())?;
+            buf.advance(n);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for MockStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.get_mut().output.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn split_mock(input: &[u8]) -> (AprsIsSender<MockStream>, AprsIsStream<MockStream>) {
+        split(MockStream {
+            input: Cursor::new(input.to_vec()),
+            output: vec![],
+        })
+    }
+
+    // The mock stream never blocks, so a single poll is enough
+    fn now<F: Future + Unpin>(mut future: F) -> F::Output {
+        fn noop(_: *const ()) {}
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(ptr::null(), &VTABLE)
+        }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let waker = unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) };
+        match Pin::new(&mut future).poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    fn output(sender: AprsIsSender<MockStream>, packets: AprsIsStream<MockStream>) -> Vec<u8> {
+        packets.reader.into_inner().unsplit(sender.writer).output
+    }
+
+    #[test]
+    fn receive() {
+        let (_, mut packets) = split_mock(
+            b"# aprsc 2.1.14\r\n\
+              # logresp N0CALL verified, server T2TEST\r\n\
+              N1CALL>APRS,TCPIP*,qAC,T2TEST:>Hello\r\n\
+              \r\n\
+              Invalid\r\n\
+              N2CALL>APRS,TCPIP*,qAC,T2TEST:>World",
+        );
+        assert_eq!(None, packets.is_verified());
+
+        let packet = now(packets.recv()).unwrap().unwrap();
+        assert_eq!(Callsign::new_no_ssid("N1CALL"), packet.from);
+        assert_eq!(Some(true), packets.is_verified());
+        assert_eq!(
+            Some("T2TEST"),
+            packets.login_response().unwrap().server.as_deref()
+        );
+
+        assert!(matches!(
+            now(packets.recv()),
+            Some(Err(AprsIsError::Decode(_)))
+        ));
+        let packet = now(packets.recv()).unwrap().unwrap();
+        assert_eq!(Callsign::new_no_ssid("N2CALL"), packet.from);
+        assert!(now(packets.recv()).is_none());
+    }
+
+    #[test]
+    fn login_and_send() {
+        let (mut sender, packets) = split_mock(b"");
+
+        let login = Login::new(Callsign::new_no_ssid("N0CALL"), 13023).with_software("test", "1.0");
+        now(sender.login(&login)).unwrap();
+        let packet = AprsPacket::decode_textual(b"N0CALL>APRS,TCPIP*:>Hello").unwrap();
+        now(sender.send(&packet)).unwrap();
+
+        assert_eq!(
+            &b"user N0CALL pass 13023 vers test 1.0\r\nN0CALL>APRS,TCPIP*:>Hello\r\n"[..],
+            &output(sender, packets)[..]
+        );
+    }
+}
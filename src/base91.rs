@@ -1,5 +1,6 @@
-use std::io::Write;
-
+#[cfg(not(any(feature = "std", test)))]
+use float::F64Ext;
+use io::Write;
 use EncodeError;
 
 pub(crate) fn encode_ascii<W: Write>(
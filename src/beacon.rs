@@ -0,0 +1,142 @@
+//! Scheduling of periodic beacons for fixed stations.
+//!
+//! With proportional pathing, most beacons are sent direct and only some are
+//! digipeated, so that nearby stations get frequent updates without flooding
+//! the wider network.
+
+use std::time::{Duration, Instant};
+
+use Callsign;
+use Via;
+
+/// The paths used by a [`BeaconScheduler`], one beacon after the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProportionalPath {
+    cycle: Vec<Vec<Via>>,
+    next: usize,
+}
+
+impl ProportionalPath {
+    /// Rotates through the given paths. An empty path means the beacon is sent direct.
+    /// Returns `None` if there are no paths to rotate through.
+    pub fn new(cycle: Vec<Vec<Via>>) -> Option<Self> {
+        if cycle.is_empty() {
+            return None;
+        }
+
+        Some(Self { cycle, next: 0 })
+    }
+
+    /// Always uses the same path.
+    pub fn fixed(path: Vec<Via>) -> Self {
+        Self {
+            cycle: vec![path],
+            next: 0,
+        }
+    }
+
+    /// Returns the path for the next beacon and advances the cycle.
+    pub fn next_path(&mut self) -> &[Via] {
+        let path = &self.cycle[self.next];
+        self.next = (self.next + 1) % self.cycle.len();
+        path
+    }
+}
+
+impl Default for ProportionalPath {
+    /// Direct, `WIDE1-1`, direct, `WIDE2-1`: reaching one hop out every other beacon
+    /// and two hops out every fourth.
+    fn default() -> Self {
+        let via = |call, ssid| vec![Via::Callsign(Callsign::new_with_ssid(call, ssid), false)];
+
+        Self {
+            cycle: vec![vec![], via("WIDE1", "1"), vec![], via("WIDE2", "1")],
+            next: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BeaconScheduler {
+    interval: Duration,
+    paths: ProportionalPath,
+    last_sent: Option<Instant>,
+}
+
+impl BeaconScheduler {
+    /// Beacons every `interval`, using the default [`ProportionalPath`].
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            paths: ProportionalPath::default(),
+            last_sent: None,
+        }
+    }
+
+    pub fn with_paths(mut self, paths: ProportionalPath) -> Self {
+        self.paths = paths;
+        self
+    }
+
+    /// Returns the path to send a beacon with if one is due at `now`.
+    /// The first beacon is due immediately.
+    pub fn poll(&mut self, now: Instant) -> Option<Vec<Via>> {
+        if let Some(last) = self.last_sent {
+            if now.saturating_duration_since(last) < self.interval {
+                return None;
+            }
+        }
+
+        self.last_sent = Some(now);
+        Some(self.paths.next_path().to_vec())
+    }
+
+    /// Returns when the next beacon is due, or `None` if it is due right away.
+    pub fn next_due(&self) -> Option<Instant> {
+        self.last_sent.map(|last| last + self.interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_paths() {
+        let start = Instant::now();
+        let minute = Duration::from_secs(60);
+        let mut scheduler = BeaconScheduler::new(minute * 10);
+
+        let wide = |call, ssid| vec![Via::Callsign(Callsign::new_with_ssid(call, ssid), false)];
+
+        assert_eq!(Some(vec![]), scheduler.poll(start));
+        assert_eq!(None, scheduler.poll(start + minute));
+        assert_eq!(Some(start + minute * 10), scheduler.next_due());
+
+        assert_eq!(
+            Some(wide("WIDE1", "1")),
+            scheduler.poll(start + minute * 10)
+        );
+        assert_eq!(Some(vec![]), scheduler.poll(start + minute * 20));
+        assert_eq!(
+            Some(wide("WIDE2", "1")),
+            scheduler.poll(start + minute * 30)
+        );
+        assert_eq!(Some(vec![]), scheduler.poll(start + minute * 40));
+    }
+
+    #[test]
+    fn fixed_path() {
+        let path = vec![Via::Callsign(Callsign::new_with_ssid("WIDE2", "2"), false)];
+        let mut paths = ProportionalPath::fixed(path.clone());
+
+        assert_eq!(&path[..], paths.next_path());
+        assert_eq!(&path[..], paths.next_path());
+    }
+
+    #[test]
+    fn empty_cycle() {
+        assert!(ProportionalPath::new(vec![]).is_none());
+        assert!(ProportionalPath::new(vec![vec![]]).is_some());
+    }
+}
@@ -0,0 +1,488 @@
+//! Builders for outgoing packets. They fill in the usual defaults, and check
+//! the limits of the spec when building rather than when encoding.
+//!
+//! ```rust
+//! use aprs_parser::{AprsData, AprsPositionBuilder, Callsign, Latitude, Longitude, PacketBuilder};
+//!
+//! let position = AprsPositionBuilder::new(
+//!     Latitude::new(49.0583).unwrap(),
+//!     Longitude::new(-72.0292).unwrap(),
+//! )
+//! .comment("Home")
+//! .build()
+//! .unwrap();
+//!
+//! let packet = PacketBuilder::new(Callsign::new_no_ssid("N0CALL"))
+//!     .build(AprsData::Position(position))
+//!     .unwrap();
+//!
+//! let mut buf = vec![];
+//! packet.encode_textual(&mut buf).unwrap();
+//! assert_eq!(&b"N0CALL>APRS,WIDE1-1,WIDE2-1:!4903.50N/07201.75W-Home"[..], &buf[..]);
+//! ```
+
+use item::is_valid_item_name;
+use object::is_valid_name;
+use path;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsCst;
+use AprsData;
+use AprsItem;
+use AprsMessage;
+use AprsObject;
+use AprsPacket;
+use AprsPosition;
+use AprsStatus;
+use Callsign;
+use DhmTimestamp;
+use EncodeError;
+use Latitude;
+use Longitude;
+use Precision;
+use Symbol;
+use Timestamp;
+use Via;
+
+/// The longest comment of a position report, without a data extension.
+const MAX_POSITION_COMMENT_LEN: usize = 43;
+/// The longest status text, 7 characters less with a timestamp.
+const MAX_STATUS_LEN: usize = 62;
+
+fn default_to() -> Callsign {
+    Callsign::new_no_ssid("APRS")
+}
+
+/// Wraps data in a packet from a station, sent through `WIDE1-1,WIDE2-1` by default.
+#[derive(Debug, Clone)]
+pub struct PacketBuilder {
+    from: Callsign,
+    via: Vec<Via>,
+}
+
+impl PacketBuilder {
+    pub fn new(from: Callsign) -> Self {
+        let via = |call, ssid| Via::Callsign(Callsign::new_with_ssid(call, ssid), false);
+
+        Self {
+            from,
+            via: vec![via("WIDE1", "1"), via("WIDE2", "1")],
+        }
+    }
+
+    /// Sets the path. An empty path sends the packet direct.
+    pub fn via(mut self, via: Vec<Via>) -> Self {
+        self.via = via;
+        self
+    }
+
+    pub fn build(&self, data: AprsData) -> Result<AprsPacket, EncodeError> {
+        self.from
+            .validate()
+            .map_err(|_| EncodeError::InvalidCallsign(self.from.clone()))?;
+        path::validate(&self.via)?;
+
+        Ok(AprsPacket {
+            from: self.from.clone(),
+            via: self.via.clone(),
+            data,
+        })
+    }
+}
+
+/// Builds position reports, with a house symbol by default.
+/// The data type identifier follows from the timestamp and messaging support.
+#[derive(Debug, Clone)]
+pub struct AprsPositionBuilder {
+    to: Callsign,
+    latitude: Latitude,
+    longitude: Longitude,
+    timestamp: Option<Timestamp>,
+    messaging_supported: bool,
+    precision: Precision,
+    symbol: Symbol,
+    comment: Vec<u8>,
+    compressed: bool,
+}
+
+impl AprsPositionBuilder {
+    pub fn new(latitude: Latitude, longitude: Longitude) -> Self {
+        Self {
+            to: default_to(),
+            latitude,
+            longitude,
+            timestamp: None,
+            messaging_supported: false,
+            precision: Precision::default(),
+            symbol: Symbol::from_bytes(b'/', b'-'),
+            comment: vec![],
+            compressed: false,
+        }
+    }
+
+    /// Sets the destination field, usually the tocall of the software. Defaults to `APRS`.
+    pub fn to(mut self, to: Callsign) -> Self {
+        self.to = to;
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn messaging_supported(mut self, messaging_supported: bool) -> Self {
+        self.messaging_supported = messaging_supported;
+        self
+    }
+
+    /// Sets the ambiguity of uncompressed positions.
+    pub fn precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn symbol(mut self, symbol: Symbol) -> Self {
+        self.symbol = symbol;
+        self
+    }
+
+    pub fn comment(mut self, comment: impl Into<Vec<u8>>) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    /// Encodes the position in the compressed format.
+    pub fn compressed(mut self) -> Self {
+        self.compressed = true;
+        self
+    }
+
+    pub fn build(&self) -> Result<AprsPosition, EncodeError> {
+        if self.comment.len() > MAX_POSITION_COMMENT_LEN {
+            return Err(EncodeError::InvalidComment(self.comment.clone()));
+        }
+
+        let mut position = AprsPosition {
+            to: self.to.clone(),
+            data_type_identifier: b'!',
+            timestamp: self.timestamp.clone(),
+            messaging_supported: self.messaging_supported,
+            latitude: self.latitude,
+            longitude: self.longitude,
+            precision: self.precision,
+            symbol: self.symbol,
+            comment: self.comment.clone(),
+            cst: if self.compressed {
+                AprsCst::CompressedNone
+            } else {
+                AprsCst::Uncompressed
+            },
+        };
+        position.data_type_identifier = position.default_identifier();
+
+        Ok(position)
+    }
+}
+
+/// Builds object reports, which need a timestamp.
+#[derive(Debug, Clone)]
+pub struct AprsObjectBuilder {
+    name: Vec<u8>,
+    live: bool,
+    timestamp: Option<Timestamp>,
+    position: AprsPositionBuilder,
+}
+
+impl AprsObjectBuilder {
+    /// The position's timestamp and messaging support are ignored.
+    pub fn new(name: impl Into<Vec<u8>>, position: AprsPositionBuilder) -> Self {
+        Self {
+            name: name.into(),
+            live: true,
+            timestamp: None,
+            position,
+        }
+    }
+
+    pub fn timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Marks the object as killed.
+    pub fn killed(mut self) -> Self {
+        self.live = false;
+        self
+    }
+
+    pub fn build(&self) -> Result<AprsObject, EncodeError> {
+        if !is_valid_name(&self.name, 1) {
+            return Err(EncodeError::InvalidObjectName(self.name.clone()));
+        }
+        let timestamp = self
+            .timestamp
+            .clone()
+            .ok_or(EncodeError::MissingTimestamp)?;
+
+        Ok(AprsObject {
+            name: self.name.clone(),
+            live: self.live,
+            timestamp,
+            position: build_embedded(&self.position)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AprsItemBuilder {
+    name: Vec<u8>,
+    live: bool,
+    position: AprsPositionBuilder,
+}
+
+impl AprsItemBuilder {
+    /// The position's timestamp and messaging support are ignored.
+    pub fn new(name: impl Into<Vec<u8>>, position: AprsPositionBuilder) -> Self {
+        Self {
+            name: name.into(),
+            live: true,
+            position,
+        }
+    }
+
+    /// Marks the item as killed.
+    pub fn killed(mut self) -> Self {
+        self.live = false;
+        self
+    }
+
+    pub fn build(&self) -> Result<AprsItem, EncodeError> {
+        if !is_valid_item_name(&self.name) {
+            return Err(EncodeError::InvalidItemName(self.name.clone()));
+        }
+
+        Ok(AprsItem {
+            name: self.name.clone(),
+            live: self.live,
+            position: build_embedded(&self.position)?,
+        })
+    }
+}
+
+// Objects and items carry their own timestamp, if any
+fn build_embedded(position: &AprsPositionBuilder) -> Result<AprsPosition, EncodeError> {
+    let mut position = position.build()?;
+    position.timestamp = None;
+    position.messaging_supported = false;
+    position.data_type_identifier = b'!';
+
+    Ok(position)
+}
+
+/// Builds a single message. Use [`MessageBuilder`](crate::MessageBuilder)
+/// to number messages and track their acks.
+#[derive(Debug, Clone)]
+pub struct AprsMessageBuilder {
+    to: Callsign,
+    addressee: Vec<u8>,
+    text: Vec<u8>,
+    id: Option<Vec<u8>>,
+}
+
+impl AprsMessageBuilder {
+    pub fn new(addressee: impl Into<Vec<u8>>, text: impl Into<Vec<u8>>) -> Self {
+        Self {
+            to: default_to(),
+            addressee: addressee.into(),
+            text: text.into(),
+            id: None,
+        }
+    }
+
+    /// Sets the destination field, usually the tocall of the software. Defaults to `APRS`.
+    pub fn to(mut self, to: Callsign) -> Self {
+        self.to = to;
+        self
+    }
+
+    pub fn id(mut self, id: impl Into<Vec<u8>>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    pub fn build(&self) -> Result<AprsMessage, EncodeError> {
+        AprsMessage::new(
+            self.to.clone(),
+            &self.addressee,
+            &self.text,
+            self.id.as_deref(),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AprsStatusBuilder {
+    to: Callsign,
+    timestamp: Option<DhmTimestamp>,
+    text: Vec<u8>,
+}
+
+impl AprsStatusBuilder {
+    pub fn new(text: impl Into<Vec<u8>>) -> Self {
+        Self {
+            to: default_to(),
+            timestamp: None,
+            text: text.into(),
+        }
+    }
+
+    /// Sets the destination field, usually the tocall of the software. Defaults to `APRS`.
+    pub fn to(mut self, to: Callsign) -> Self {
+        self.to = to;
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: DhmTimestamp) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn build(&self) -> Result<AprsStatus, EncodeError> {
+        let max_len = match self.timestamp {
+            Some(_) => MAX_STATUS_LEN - 7,
+            None => MAX_STATUS_LEN,
+        };
+        if self.text.len() > max_len || self.text.iter().any(|&b| b == b'|' || b == b'~') {
+            return Err(EncodeError::InvalidComment(self.text.clone()));
+        }
+
+        Ok(AprsStatus::new(
+            self.to.clone(),
+            self.timestamp,
+            self.text.clone(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position() -> AprsPositionBuilder {
+        AprsPositionBuilder::new(
+            Latitude::new(49.0583).unwrap(),
+            Longitude::new(-72.0292).unwrap(),
+        )
+    }
+
+    fn encode(data: AprsData) -> String {
+        let packet = PacketBuilder::new(Callsign::new_no_ssid("N0CALL"))
+            .via(vec![])
+            .build(data)
+            .unwrap();
+
+        let mut buf = vec![];
+        packet.encode_textual(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn positions() {
+        let built = position()
+            .timestamp(Timestamp::HHMMSS(12, 34, 56))
+            .messaging_supported(true)
+            .symbol(Symbol::new('/', '>').unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(b'@', built.data_type_identifier);
+        assert_eq!(
+            "N0CALL>APRS:@123456h4903.50N/07201.75W>",
+            encode(AprsData::Position(built))
+        );
+
+        let built = position().compressed().build().unwrap();
+        assert_eq!(
+            "N0CALL>APRS:!/5`=x<;>q- sT",
+            encode(AprsData::Position(built))
+        );
+
+        assert!(matches!(
+            position().comment(vec![b'x'; 44]).build(),
+            Err(EncodeError::InvalidComment(_))
+        ));
+    }
+
+    #[test]
+    fn objects_and_items() {
+        let object =
+            AprsObjectBuilder::new("LEADER", position().timestamp(Timestamp::HHMMSS(1, 2, 3)))
+                .timestamp(Timestamp::DDHHMM(9, 23, 45))
+                .build()
+                .unwrap();
+        assert_eq!(
+            "N0CALL>APRS:;LEADER   *092345z4903.50N/07201.75W-",
+            encode(AprsData::Object(object))
+        );
+        assert!(matches!(
+            AprsObjectBuilder::new("LEADER", position()).build(),
+            Err(EncodeError::MissingTimestamp)
+        ));
+
+        let item = AprsItemBuilder::new("AID #2", position())
+            .killed()
+            .build()
+            .unwrap();
+        assert_eq!(
+            "N0CALL>APRS:)AID #2_4903.50N/07201.75W-",
+            encode(AprsData::Item(item))
+        );
+        assert!(matches!(
+            AprsItemBuilder::new("A!D", position()).build(),
+            Err(EncodeError::InvalidItemName(_))
+        ));
+    }
+
+    #[test]
+    fn messages_and_status() {
+        let message = AprsMessageBuilder::new("N1CALL", "Hello")
+            .id("42")
+            .build()
+            .unwrap();
+        assert_eq!(
+            "N0CALL>APRS::N1CALL   :Hello{42",
+            encode(AprsData::Message(message))
+        );
+
+        let status = AprsStatusBuilder::new("Net tonight")
+            .timestamp(DhmTimestamp::new(9, 23, 45).unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(
+            "N0CALL>APRS:>092345zNet tonight",
+            encode(AprsData::Status(status))
+        );
+        assert!(AprsStatusBuilder::new(vec![b'x'; 62]).build().is_ok());
+        assert!(AprsStatusBuilder::new(vec![b'x'; 62])
+            .timestamp(DhmTimestamp::new(9, 23, 45).unwrap())
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn packets() {
+        let status = AprsStatusBuilder::new("Hi").build().unwrap();
+        let builder = PacketBuilder::new(Callsign::new_no_ssid("N0CALL"));
+        assert_eq!(
+            2,
+            builder
+                .build(AprsData::Status(status.clone()))
+                .unwrap()
+                .via
+                .len()
+        );
+
+        let builder = PacketBuilder::new(Callsign::new_no_ssid("N0CALL-99"));
+        assert!(builder.build(AprsData::Status(status)).is_err());
+    }
+}
@@ -1,7 +1,16 @@
 // functions for working with byte arrays
 
-pub fn parse_bytes<T: std::str::FromStr>(b: &[u8]) -> Option<T> {
-    std::str::from_utf8(b).ok()?.parse().ok()
+pub fn parse_bytes<T: core::str::FromStr>(b: &[u8]) -> Option<T> {
+    core::str::from_utf8(b).ok()?.parse().ok()
+}
+
+// like parse_bytes, but only accepts ASCII digits, without a sign
+pub fn parse_digits<T: core::str::FromStr>(b: &[u8]) -> Option<T> {
+    if !b.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    parse_bytes(b)
 }
 
 #[cfg(test)]
@@ -23,6 +32,14 @@ mod tests {
         assert_eq!(None, parse_bytes::<u32>(b"\xF0\xA4\xAD"));
     }
 
+    #[test]
+    fn parse_digits_only() {
+        assert_eq!(Some(123), parse_digits::<u32>(b"0123"));
+        assert_eq!(None, parse_digits::<u32>(b"+123"));
+        assert_eq!(None, parse_digits::<u32>(b" 123"));
+        assert_eq!(None, parse_digits::<u32>(b""));
+    }
+
     #[test]
     fn parse_fail_on_not_a_number() {
         assert_eq!(None, parse_bytes::<u32>(b"0123NotANumber"));
@@ -1,6 +1,9 @@
-use std::fmt::{Display, Formatter};
-use std::io::{self, Write};
+use core::fmt::{Display, Formatter};
 
+use io::{self, Write};
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use CallsignError;
 use EncodeError;
 
 pub enum CallsignField {
@@ -9,7 +12,8 @@ pub enum CallsignField {
     Via(bool),
 }
 
-#[derive(Eq, PartialEq, Debug, Clone, Hash)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Callsign {
     call: String,
     ssid: Option<String>,
@@ -18,6 +22,9 @@ pub struct Callsign {
 impl Callsign {
     /// Create a new callsign.
     /// SSID is parsed out.
+    /// This is lenient, since APRS-IS also carries pseudo-callsigns that
+    /// aren't valid AX.25 addresses, e.g. long object originators.
+    /// Use `parse_strict` to enforce the AX.25 rules.
     pub fn new(s: impl AsRef<str>) -> Option<Self> {
         match s.as_ref().split_once('-') {
             Some((call, ssid)) => {
@@ -46,6 +53,48 @@ impl Callsign {
         Callsign { call, ssid }
     }
 
+    /// Parses a callsign that is a valid AX.25 address: up to 6 letters and
+    /// digits, with an optional SSID from 0 to 15.
+    /// The callsign is uppercased, and an SSID of 0 is dropped.
+    pub fn parse_strict(s: &str) -> Result<Self, CallsignError> {
+        let (call, ssid) = match s.split_once('-') {
+            Some((call, ssid)) => (call, Some(ssid)),
+            None => (s, None),
+        };
+
+        if call.is_empty() {
+            return Err(CallsignError::Empty);
+        }
+        if call.len() > 6 {
+            return Err(CallsignError::TooLong(call.to_owned()));
+        }
+        if let Some(c) = call.chars().find(|c| !c.is_ascii_alphanumeric()) {
+            return Err(CallsignError::InvalidCharacter(c));
+        }
+        let call = call.to_ascii_uppercase();
+
+        let ssid = match ssid {
+            None => None,
+            Some(ssid) => {
+                let n = Some(ssid)
+                    .filter(|s| s.len() <= 2 && s.bytes().all(|b| b.is_ascii_digit()))
+                    .and_then(|s| s.parse::<u8>().ok())
+                    .filter(|n| *n <= 15)
+                    .ok_or_else(|| CallsignError::InvalidSsid(ssid.to_owned()))?;
+
+                Some(n).filter(|n| *n != 0).map(|n| n.to_string())
+            }
+        };
+
+        Ok(Self { call, ssid })
+    }
+
+    /// Checks the AX.25 rules `parse_strict` enforces, except that lowercase
+    /// letters are allowed.
+    pub fn validate(&self) -> Result<(), CallsignError> {
+        Self::parse_strict(&self.to_string()).map(|_| ())
+    }
+
     pub fn call(&self) -> &str {
         &self.call
     }
@@ -71,7 +120,7 @@ impl Callsign {
             (bytes, false)
         };
 
-        let s = std::str::from_utf8(bytes).ok()?;
+        let s = core::str::from_utf8(bytes).ok()?;
 
         Self::new(s).map(|c| (c, heard))
     }
@@ -182,7 +231,7 @@ impl Callsign {
 }
 
 impl Display for Callsign {
-    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), core::fmt::Error> {
         write!(f, "{}", self.call)?;
 
         if let Some(ssid) = &self.ssid {
@@ -358,4 +407,43 @@ mod tests {
             format!("{}", Callsign::new_with_ssid("ABCDEF", "12"))
         );
     }
+
+    #[test]
+    fn parse_strict() {
+        assert_eq!(
+            Ok(Callsign::new_with_ssid("N0CALL", "7")),
+            Callsign::parse_strict("n0call-7")
+        );
+        assert_eq!(
+            Ok(Callsign::new_no_ssid("N0CALL")),
+            Callsign::parse_strict("N0CALL-0")
+        );
+        assert_eq!(
+            Ok(Callsign::new_with_ssid("VE9", "15")),
+            Callsign::parse_strict("VE9-15")
+        );
+
+        assert_eq!(Err(CallsignError::Empty), Callsign::parse_strict("-1"));
+        assert_eq!(
+            Err(CallsignError::TooLong("N0CALLS".to_owned())),
+            Callsign::parse_strict("N0CALLS")
+        );
+        assert_eq!(
+            Err(CallsignError::InvalidCharacter('/')),
+            Callsign::parse_strict("N0/CAL")
+        );
+        assert_eq!(
+            Err(CallsignError::InvalidSsid("16".to_owned())),
+            Callsign::parse_strict("N0CALL-16")
+        );
+        assert_eq!(
+            Err(CallsignError::InvalidSsid("+1".to_owned())),
+            Callsign::parse_strict("N0CALL-+1")
+        );
+
+        // the lenient constructor accepts APRS-IS pseudo-callsigns
+        let c = Callsign::new("LONGORIGINATOR").unwrap();
+        assert!(c.validate().is_err());
+        assert!(Callsign::new_no_ssid("ve9").validate().is_ok());
+    }
 }
@@ -0,0 +1,158 @@
+//! A Station Capabilities report describes what a station can do, usually in
+//! reply to an `?IGATE?` query. It starts with the '<' APRS Data Type Identifier,
+//! followed by a comma separated list of capabilities, some of which have a value.
+//!
+//! Example:
+//! - "<IGATE,MSG_CNT=35,LOC_CNT=14"
+
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use Callsign;
+use DecodeError;
+use EncodeError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AprsCapabilities {
+    pub to: Callsign,
+    /// The capabilities in the order they were sent, with their value if they have one
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text_pairs"))]
+    pub capabilities: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+impl AprsCapabilities {
+    pub fn new(to: Callsign) -> Self {
+        Self {
+            to,
+            capabilities: vec![],
+        }
+    }
+
+    /// Adds a capability, e.g. `IGATE` without a value or `MSG_CNT` with `35`.
+    pub fn with_capability(mut self, name: &[u8], value: Option<&[u8]>) -> Self {
+        self.capabilities
+            .push((name.to_owned(), value.map(<[u8]>::to_vec)));
+        self
+    }
+
+    /// Whether the station has this capability, with or without a value.
+    pub fn has(&self, name: &[u8]) -> bool {
+        self.capabilities.iter().any(|(n, _)| n == name)
+    }
+
+    /// The value of a capability, e.g. `35` for `MSG_CNT=35`.
+    pub fn value(&self, name: &[u8]) -> Option<&[u8]> {
+        self.capabilities
+            .iter()
+            .find(|(n, _)| n == name)
+            .and_then(|(_, v)| v.as_deref())
+    }
+
+    /// Whether the station is an IGate.
+    pub fn is_igate(&self) -> bool {
+        self.has(b"IGATE")
+    }
+
+    /// Decodes the information field after the `<`.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        let mut capabilities = vec![];
+        for token in b.split(|&c| c == b',') {
+            let token = trim(token);
+            if token.is_empty() {
+                continue;
+            }
+
+            let mut parts = token.splitn(2, |&c| c == b'=');
+            let name = trim(parts.next().unwrap_or_default());
+            if name.is_empty() {
+                return Err(DecodeError::InvalidCapabilities(b.to_owned(), 0));
+            }
+            let value = parts.next().map(|v| trim(v).to_owned());
+
+            capabilities.push((name.to_owned(), value));
+        }
+
+        Ok(Self { to, capabilities })
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_all(b"<")?;
+        for (i, (name, value)) in self.capabilities.iter().enumerate() {
+            if name.is_empty()
+                || name.iter().any(|&c| c == b',' || c == b'=')
+                || value.as_ref().map_or(false, |v| v.contains(&b','))
+            {
+                return Err(EncodeError::InvalidData);
+            }
+
+            if i > 0 {
+                buf.write_all(b",")?;
+            }
+            buf.write_all(name)?;
+            if let Some(value) = value {
+                buf.write_all(b"=")?;
+                buf.write_all(value)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn trim(b: &[u8]) -> &[u8] {
+    let start = b.iter().position(|c| *c != b' ').unwrap_or(b.len());
+    let end = b.iter().rposition(|c| *c != b' ').map_or(start, |e| e + 1);
+    &b[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_callsign() -> Callsign {
+        Callsign::new_no_ssid("APRS")
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        let original = &b"<IGATE,MSG_CNT=35,LOC_CNT=14"[..];
+        let caps = AprsCapabilities::decode(&original[1..], default_callsign()).unwrap();
+
+        assert!(caps.is_igate());
+        assert!(caps.has(b"MSG_CNT"));
+        assert_eq!(Some(&b"35"[..]), caps.value(b"MSG_CNT"));
+        assert_eq!(Some(&b"14"[..]), caps.value(b"LOC_CNT"));
+        assert_eq!(None, caps.value(b"IGATE"));
+        assert!(!caps.has(b"DIGI"));
+
+        let mut buf = vec![];
+        caps.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn spaces() {
+        let caps = AprsCapabilities::decode(b" IGATE, MSG_CNT = 35,,", default_callsign()).unwrap();
+        assert_eq!(
+            AprsCapabilities::new(default_callsign())
+                .with_capability(b"IGATE", None)
+                .with_capability(b"MSG_CNT", Some(b"35")),
+            caps
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert_eq!(
+            Err(DecodeError::InvalidCapabilities(b"IGATE,=35".to_vec(), 0)),
+            AprsCapabilities::decode(b"IGATE,=35", default_callsign())
+        );
+
+        let caps = AprsCapabilities::new(default_callsign()).with_capability(b"A=B", None);
+        assert!(matches!(
+            caps.encode(&mut vec![]),
+            Err(EncodeError::InvalidData)
+        ));
+    }
+}
@@ -0,0 +1,127 @@
+//! A tokio-util codec for CRLF delimited TNC2 packets, as sent by APRS-IS.
+//!
+//! Lines starting with `#` are server comments and are skipped. A packet that
+//! doesn't decode is returned as an error item, without ending the stream.
+//!
+//! ```rust
+//! # extern crate tokio_util;
+//! use aprs_parser::codec::AprsCodec;
+//! use tokio_util::bytes::BytesMut;
+//! use tokio_util::codec::Decoder;
+//!
+//! let mut buf = BytesMut::from(&b"# aprsc 2.1.14\r\nN0CALL>APRS:>Hello\r\n"[..]);
+//! let packet = AprsCodec::new().decode(&mut buf).unwrap().unwrap().unwrap();
+//! assert_eq!("N0CALL", packet.from.call());
+//! ```
+
+use tokio_util::bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
+
+use aprs_is::{AprsIsError, ServerLine};
+use AprsPacket;
+use DecodeError;
+
+#[derive(Debug, Clone, Default)]
+pub struct AprsCodec {
+    // how far the buffer has been searched for a newline
+    next_index: usize,
+}
+
+impl AprsCodec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn decode_line(line: &[u8]) -> Option<Result<AprsPacket, DecodeError>> {
+        match ServerLine::parse(line) {
+            ServerLine::Packet(p) => Some(p),
+            ServerLine::LoginResponse(_) | ServerLine::Other => None,
+        }
+    }
+}
+
+impl Decoder for AprsCodec {
+    type Item = Result<AprsPacket, DecodeError>;
+    type Error = AprsIsError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        loop {
+            let newline = src[self.next_index..].iter().position(|&b| b == b'\n');
+            let end = match newline {
+                Some(i) => self.next_index + i + 1,
+                None => {
+                    self.next_index = src.len();
+                    return Ok(None);
+                }
+            };
+
+            self.next_index = 0;
+            let line = src.split_to(end);
+            if let Some(packet) = Self::decode_line(&line) {
+                return Ok(Some(packet));
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if let Some(packet) = self.decode(src)? {
+            return Ok(Some(packet));
+        }
+
+        // the last line doesn't need a line ending
+        self.next_index = 0;
+        let line = src.split();
+        Ok(Self::decode_line(&line))
+    }
+}
+
+impl Encoder<&AprsPacket> for AprsCodec {
+    type Error = AprsIsError;
+
+    fn encode(&mut self, packet: &AprsPacket, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let mut buf = vec![];
+        packet.encode_textual(&mut buf)?;
+        dst.extend_from_slice(&buf);
+        dst.extend_from_slice(b"\r\n");
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Callsign;
+
+    #[test]
+    fn decode() {
+        let mut codec = AprsCodec::new();
+        let mut buf = BytesMut::from(
+            &b"# aprsc 2.1.14\r\n\
+               # logresp N0CALL verified, server T2TEST\r\n\
+               N1CALL>APRS,TCPIP*,qAC,T2TEST:>Hello\r\n\
+               \r\n\
+               Invalid\r\n\
+               N2CALL>APRS,TCPIP*,qAC,T2TE"[..],
+        );
+
+        let packet = codec.decode(&mut buf).unwrap().unwrap().unwrap();
+        assert_eq!(Callsign::new_no_ssid("N1CALL"), packet.from);
+        assert!(codec.decode(&mut buf).unwrap().unwrap().is_err());
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+
+        buf.extend_from_slice(b"ST:>World");
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        let packet = codec.decode_eof(&mut buf).unwrap().unwrap().unwrap();
+        assert_eq!(Callsign::new_no_ssid("N2CALL"), packet.from);
+        assert!(codec.decode_eof(&mut buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn encode() {
+        let packet = AprsPacket::decode_textual(b"N0CALL>APRS,TCPIP*:>Hello").unwrap();
+        let mut buf = BytesMut::new();
+        AprsCodec::new().encode(&packet, &mut buf).unwrap();
+        assert_eq!(&b"N0CALL>APRS,TCPIP*:>Hello\r\n"[..], &buf[..]);
+    }
+}
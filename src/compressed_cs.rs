@@ -1,12 +1,14 @@
-use std::io::Write;
-
 use base91;
 use compression_type::NmeaSource;
+#[cfg(not(any(feature = "std", test)))]
+use float::F64Ext;
+use io::Write;
 use AprsCompressionType;
 use DecodeError;
 use EncodeError;
 
 #[derive(PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AprsCompressedCs {
     CourseSpeed(AprsCourseSpeed),
     RadioRange(AprsRadioRange),
@@ -15,8 +17,8 @@ pub enum AprsCompressedCs {
 
 impl AprsCompressedCs {
     pub(crate) fn parse(c: u8, s: u8, t: AprsCompressionType) -> Result<Self, DecodeError> {
-        let c_lwr = base91::digit_from_ascii(c).ok_or(DecodeError::InvalidCs([c, s]))?;
-        let s_lwr = base91::digit_from_ascii(s).ok_or(DecodeError::InvalidCs([c, s]))?;
+        let c_lwr = base91::digit_from_ascii(c).ok_or(DecodeError::InvalidCs([c, s], 0))?;
+        let s_lwr = base91::digit_from_ascii(s).ok_or(DecodeError::InvalidCs([c, s], 0))?;
 
         if t.nmea_source == NmeaSource::Gga {
             Ok(AprsCompressedCs::Altitude(AprsAltitude::from_cs(
@@ -26,7 +28,7 @@ impl AprsCompressedCs {
             let val = match c_lwr {
                 0..=89 => AprsCompressedCs::CourseSpeed(AprsCourseSpeed::from_cs(c_lwr, s_lwr)),
                 90 => AprsCompressedCs::RadioRange(AprsRadioRange::from_s(s_lwr)),
-                _ => return Err(DecodeError::InvalidCs([c, s])),
+                _ => return Err(DecodeError::InvalidCs([c, s], 0)),
             };
 
             Ok(val)
@@ -64,6 +66,7 @@ impl AprsCompressedCs {
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AprsCourseSpeed {
     course_degrees: u16,
     speed_knots: f64,
@@ -110,6 +113,7 @@ impl AprsCourseSpeed {
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AprsRadioRange {
     range_miles: f64,
 }
@@ -139,6 +143,7 @@ impl AprsRadioRange {
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AprsAltitude {
     altitude_feet: f64,
 }
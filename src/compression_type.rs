@@ -1,4 +1,5 @@
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GpsFix {
     Old,
     Current,
@@ -23,6 +24,7 @@ impl From<GpsFix> for bool {
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum NmeaSource {
     Other,
     Gll,
@@ -53,6 +55,7 @@ impl From<NmeaSource> for (bool, bool) {
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Origin {
     Compressed,
     TncBText,
@@ -95,6 +98,7 @@ impl From<Origin> for (bool, bool, bool) {
 }
 
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AprsCompressionType {
     pub gps_fix: GpsFix,
     pub nmea_source: NmeaSource,
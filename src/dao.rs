@@ -0,0 +1,107 @@
+//! The DAO extension adds precision to uncompressed positions. It's a `!W..!` or
+//! `!w..!` field anywhere in the comment, where the two characters are the next
+//! digit of the latitude and longitude minutes.
+//!
+//! Examples:
+//! - "!W52!"                         (thousandths of a minute, as digits)
+//! - "!wZ&!"                         (91ths of a hundredth of a minute, in base-91)
+
+use base91;
+#[cfg(not(any(feature = "std", test)))]
+use float::F64Ext;
+
+pub(crate) const DAO_LEN: usize = 5;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Dao {
+    /// `!Wxy!`, adding a digit to the minutes
+    HumanReadable,
+    /// `!wxy!`, adding a base-91 character to the minutes
+    Base91,
+}
+
+impl Dao {
+    /// Finds the DAO field in a comment.
+    /// Returns where it starts, its style and the extra minutes of the
+    /// latitude and longitude.
+    pub(crate) fn find(comment: &[u8]) -> Option<(usize, Self, f64, f64)> {
+        comment
+            .windows(DAO_LEN)
+            .enumerate()
+            .find_map(|(i, w)| match w {
+                [b'!', b'W', lat, lon, b'!'] => {
+                    let digit = |c: u8| match c {
+                        b' ' => Some(0.0),
+                        b'0'..=b'9' => Some(f64::from(c - b'0') * 0.001),
+                        _ => None,
+                    };
+                    Some((i, Self::HumanReadable, digit(*lat)?, digit(*lon)?))
+                }
+                [b'!', b'w', lat, lon, b'!'] => {
+                    let digit =
+                        |c: u8| base91::digit_from_ascii(c).map(|d| f64::from(d) / 91.0 * 0.01);
+                    Some((i, Self::Base91, digit(*lat)?, digit(*lon)?))
+                }
+                _ => None,
+            })
+    }
+
+    fn steps(&self) -> u32 {
+        match self {
+            Self::HumanReadable => 10,
+            Self::Base91 => 91,
+        }
+    }
+
+    /// Splits an absolute coordinate into whole hundredths of a minute and
+    /// the character that adds the rest.
+    pub(crate) fn split(&self, value: f64) -> (u32, u8) {
+        let steps = self.steps();
+        let total = (value * 6000.0 * f64::from(steps)).round() as u32;
+        let extra = total % steps;
+
+        let c = match self {
+            Self::HumanReadable => b'0' + extra as u8,
+            Self::Base91 => base91::digit_to_ascii(extra as u8),
+        };
+
+        (total / steps, c)
+    }
+
+    pub(crate) fn datum(&self) -> u8 {
+        match self {
+            Self::HumanReadable => b'W',
+            Self::Base91 => b'w',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find() {
+        let (start, dao, lat, lon) = Dao::find(b"Hello !W52! there").unwrap();
+        assert_eq!(6, start);
+        assert_eq!(Dao::HumanReadable, dao);
+        assert_relative_eq!(0.005, lat);
+        assert_relative_eq!(0.002, lon);
+
+        let (_, dao, lat, lon) = Dao::find(b"!w!\"!").unwrap();
+        assert_eq!(Dao::Base91, dao);
+        assert_relative_eq!(0.0, lat);
+        assert_relative_eq!(0.01 / 91.0, lon);
+
+        assert_eq!(None, Dao::find(b"!WAB!"));
+        assert_eq!(None, Dao::find(b"Hello"));
+    }
+
+    #[test]
+    fn split() {
+        // 49 degrees, 3.506 minutes
+        let value = 49.0 + 3.506 / 60.0;
+        assert_eq!((294350, b'6'), Dao::HumanReadable.split(value));
+        assert_eq!((294350, b'X'), Dao::Base91.split(value));
+    }
+}
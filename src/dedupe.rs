@@ -0,0 +1,225 @@
+//! Detection of duplicate packets, e.g. the same packet heard directly and
+//! through several digipeaters. Digipeaters and IGates must only act on the
+//! first copy of each packet.
+//!
+//! Packets are duplicates if they have the same source, destination and
+//! information field, regardless of their paths.
+//!
+//! Times are wall clock times, so that with the `serde` feature the cache can
+//! be saved and restored across restarts.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime};
+
+use AprsPacket;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "DedupeCacheFields"))]
+pub struct DedupeCache {
+    window: Duration,
+    capacity: usize,
+    // the keys in `order`, to look them up
+    #[cfg_attr(feature = "serde", serde(skip_serializing))]
+    seen: HashMap<Vec<u8>, SystemTime>,
+    order: VecDeque<(Vec<u8>, SystemTime)>,
+}
+
+impl DedupeCache {
+    /// Remembers packets for 30 seconds, and at most 10000 of them.
+    pub fn new() -> Self {
+        Self {
+            window: Duration::from_secs(30),
+            capacity: 10_000,
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Sets how many packets are remembered. Beyond that, the oldest are forgotten early.
+    /// A capacity of 0 disables the cache: no packet is a duplicate.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Returns whether `packet` was already seen within the window before `now`.
+    /// If not, it's remembered from `now` on.
+    pub fn is_duplicate(&mut self, packet: &AprsPacket, now: SystemTime) -> bool {
+        self.expire(now);
+
+        let key = match key(packet) {
+            Some(key) => key,
+            None => return false,
+        };
+        if self.seen.contains_key(&key) {
+            return true;
+        }
+
+        self.remember(key, now);
+        false
+    }
+
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.seen.clear();
+        self.order.clear();
+    }
+
+    fn expire(&mut self, now: SystemTime) {
+        while let Some((_, seen)) = self.order.front() {
+            // a clock that went backwards counts as no time having passed
+            if now.duration_since(*seen).unwrap_or_default() < self.window {
+                break;
+            }
+            self.pop_oldest();
+        }
+    }
+
+    fn remember(&mut self, key: Vec<u8>, now: SystemTime) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        while self.order.len() >= self.capacity {
+            self.pop_oldest();
+        }
+        self.seen.insert(key.clone(), now);
+        self.order.push_back((key, now));
+    }
+
+    fn pop_oldest(&mut self) {
+        if let Some((key, _)) = self.order.pop_front() {
+            self.seen.remove(&key);
+        }
+    }
+}
+
+impl Default for DedupeCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "DedupeCache")]
+struct DedupeCacheFields {
+    window: Duration,
+    capacity: usize,
+    order: VecDeque<(Vec<u8>, SystemTime)>,
+}
+
+#[cfg(feature = "serde")]
+impl From<DedupeCacheFields> for DedupeCache {
+    fn from(f: DedupeCacheFields) -> Self {
+        let mut cache = Self {
+            window: f.window,
+            capacity: f.capacity,
+            seen: HashMap::new(),
+            order: VecDeque::new(),
+        };
+        for (key, seen) in f.order {
+            if !cache.seen.contains_key(&key) {
+                cache.remember(key, seen);
+            }
+        }
+
+        cache
+    }
+}
+
+// The packet without its path, e.g. `N0CALL>APRS:>Hello`
+fn key(packet: &AprsPacket) -> Option<Vec<u8>> {
+    let packet = AprsPacket {
+        from: packet.from.clone(),
+        via: vec![],
+        data: packet.data.clone(),
+    };
+
+    let mut buf = vec![];
+    packet.encode_textual(&mut buf).ok()?;
+    Some(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(s: &str) -> AprsPacket {
+        AprsPacket::decode_textual(s.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn duplicates() {
+        let start = SystemTime::now();
+        let second = Duration::from_secs(1);
+        let mut cache = DedupeCache::new();
+
+        assert!(!cache.is_duplicate(&decode("N1CALL>APRS,WIDE1-1:>Hello"), start));
+        assert!(cache.is_duplicate(&decode("N1CALL>APRS,N2CALL*,WIDE1:>Hello"), start + second));
+        assert!(!cache.is_duplicate(&decode("N1CALL>APRS:>World"), start + second));
+        assert!(!cache.is_duplicate(&decode("N1CALL>APZ123:>Hello"), start + second));
+        assert!(!cache.is_duplicate(&decode("N3CALL>APRS:>Hello"), start + second));
+        assert_eq!(4, cache.len());
+
+        // the window starts at the first copy
+        assert!(!cache.is_duplicate(&decode("N1CALL>APRS:>Hello"), start + second * 30));
+        assert!(cache.is_duplicate(&decode("N1CALL>APRS:>Hello"), start + second * 31));
+    }
+
+    #[test]
+    fn bounded() {
+        let now = SystemTime::now();
+        let mut cache = DedupeCache::new().with_capacity(2);
+
+        assert!(!cache.is_duplicate(&decode("N1CALL>APRS:>1"), now));
+        assert!(!cache.is_duplicate(&decode("N1CALL>APRS:>2"), now));
+        assert!(!cache.is_duplicate(&decode("N1CALL>APRS:>3"), now));
+        assert_eq!(2, cache.len());
+
+        assert!(cache.is_duplicate(&decode("N1CALL>APRS:>3"), now));
+        assert!(!cache.is_duplicate(&decode("N1CALL>APRS:>1"), now));
+
+        cache.clear();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn disabled() {
+        let now = SystemTime::now();
+        let mut cache = DedupeCache::new().with_capacity(0);
+
+        assert!(!cache.is_duplicate(&decode("N1CALL>APRS:>1"), now));
+        assert!(!cache.is_duplicate(&decode("N1CALL>APRS:>1"), now));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn restore() {
+        let now = SystemTime::now();
+        let mut cache = DedupeCache::new();
+        cache.is_duplicate(&decode("N1CALL>APRS:>Hello"), now);
+
+        let mut restored = DedupeCache::from(DedupeCacheFields {
+            window: cache.window,
+            capacity: cache.capacity,
+            order: cache.order.clone(),
+        });
+        assert_eq!(1, restored.len());
+        assert!(restored.is_duplicate(&decode("N1CALL>APRS:>Hello"), now + Duration::from_secs(1)));
+    }
+}
@@ -0,0 +1,191 @@
+//! Identification of the transmitting device or software from the destination
+//! ("tocall") field, based on the registry maintained at
+//! <https://github.com/aprsorg/aprs-deviceid>.
+//!
+//! Only a selection of common devices is included. Mic-E packets use the
+//! destination for position data, so they are not identified here.
+
+use Callsign;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum DeviceClass {
+    /// Dedicated tracker
+    Tracker,
+    /// Handheld radio
+    HandHeld,
+    /// Mobile or base radio
+    Rig,
+    /// Desktop software
+    Software,
+    /// Mobile phone or tablet app
+    App,
+    /// Digipeater or igate
+    Digipeater,
+    /// Weather station
+    Weather,
+    /// High-altitude balloon tracker
+    Balloon,
+    /// Generic or unidentified
+    Unknown,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DeviceInfo {
+    pub vendor: &'static str,
+    pub model: &'static str,
+    pub class: DeviceClass,
+}
+
+// `?` matches any single character
+const DEVICES: &[(&str, &str, &str, DeviceClass)] = &[
+    ("APRS", "Unknown", "Generic", DeviceClass::Unknown),
+    ("APZ???", "Unknown", "Experimental", DeviceClass::Unknown),
+    ("APAGW?", "SV2AGW", "AGWtracker", DeviceClass::Software),
+    ("APAND?", "Open Source", "APRSdroid", DeviceClass::App),
+    ("APAVT5", "SainSonic", "AP510", DeviceClass::Tracker),
+    (
+        "APBPQ?",
+        "John Wiseman G8BPQ",
+        "BPQ32",
+        DeviceClass::Software,
+    ),
+    ("APCLEY", "ZS6EY", "EYTraker", DeviceClass::Tracker),
+    ("APDI??", "Bela HA5DI", "DIXPRS", DeviceClass::Software),
+    ("APDR??", "Open Source", "APRSdroid", DeviceClass::App),
+    ("APDW??", "WB2OSZ", "Dire Wolf", DeviceClass::Software),
+    ("APECAN", "KT5TK/DL7AD", "Pecan Pico", DeviceClass::Balloon),
+    ("APFII?", "aprs.fi", "iPhone app", DeviceClass::App),
+    (
+        "APJI??",
+        "Peter Loveall AE5PL",
+        "jAPRSIgate",
+        DeviceClass::Software,
+    ),
+    (
+        "APJS??",
+        "Peter Loveall AE5PL",
+        "javAPRSSrvr",
+        DeviceClass::Software,
+    ),
+    ("APK0??", "Kenwood", "TH-D7", DeviceClass::HandHeld),
+    ("APK003", "Kenwood", "TH-D72", DeviceClass::HandHeld),
+    ("APK004", "Kenwood", "TH-D74", DeviceClass::HandHeld),
+    ("APK005", "Kenwood", "TH-D75", DeviceClass::HandHeld),
+    ("APK1??", "Kenwood", "TM-D700", DeviceClass::Rig),
+    ("APLIG?", "TA2MUN/TA9OHC", "LightAPRS", DeviceClass::Tracker),
+    (
+        "APLRG?",
+        "Ricardo CA2RXU",
+        "LoRa iGate",
+        DeviceClass::Digipeater,
+    ),
+    (
+        "APLRT?",
+        "Ricardo CA2RXU",
+        "LoRa Tracker",
+        DeviceClass::Tracker,
+    ),
+    ("APN3??", "Kantronics", "KPC-3", DeviceClass::Digipeater),
+    ("APNU??", "IW3FQG", "UIdigi", DeviceClass::Digipeater),
+    (
+        "APOT??",
+        "Argent Data Systems",
+        "OpenTracker",
+        DeviceClass::Tracker,
+    ),
+    ("APRX??", "Kenneth OH2MQK", "aprx", DeviceClass::Digipeater),
+    ("APT3??", "Byonics", "TinyTrak3", DeviceClass::Tracker),
+    ("APT4??", "Byonics", "TinyTrak4", DeviceClass::Tracker),
+    ("APTT??", "Byonics", "TinyTrak", DeviceClass::Tracker),
+    ("APTW??", "Byonics", "WXTrak", DeviceClass::Weather),
+    (
+        "APU2??",
+        "Roger Barker G4IDE",
+        "UI-View32",
+        DeviceClass::Software,
+    ),
+    ("APWM??", "KJ4ERJ", "APRSISCE", DeviceClass::Software),
+    ("APWW??", "KJ4ERJ", "APRSIS32", DeviceClass::Software),
+    ("APX???", "Open Source", "Xastir", DeviceClass::Software),
+    ("APY008", "Yaesu", "VX-8", DeviceClass::HandHeld),
+    ("APY01D", "Yaesu", "FT1D", DeviceClass::HandHeld),
+    ("APY02D", "Yaesu", "FT2D", DeviceClass::HandHeld),
+    ("APY03D", "Yaesu", "FT3D", DeviceClass::HandHeld),
+    ("APY05D", "Yaesu", "FT5D", DeviceClass::HandHeld),
+    ("APY100", "Yaesu", "FTM-100D", DeviceClass::Rig),
+    ("APY300", "Yaesu", "FTM-300D", DeviceClass::Rig),
+    ("APY350", "Yaesu", "FTM-350", DeviceClass::Rig),
+    ("APY400", "Yaesu", "FTM-400DR", DeviceClass::Rig),
+];
+
+impl DeviceInfo {
+    /// Looks up the device for a destination callsign. The SSID is ignored.
+    /// When several entries match, the most specific one wins.
+    pub fn from_tocall(tocall: &Callsign) -> Option<Self> {
+        let call = tocall.call().as_bytes();
+
+        DEVICES
+            .iter()
+            .filter(|(pattern, ..)| matches(pattern.as_bytes(), call))
+            .max_by_key(|(pattern, ..)| pattern.bytes().filter(|&b| b != b'?').count())
+            .map(|&(_, vendor, model, class)| Self {
+                vendor,
+                model,
+                class,
+            })
+    }
+}
+
+fn matches(pattern: &[u8], call: &[u8]) -> bool {
+    pattern.len() == call.len()
+        && pattern
+            .iter()
+            .zip(call)
+            .all(|(p, c)| *p == b'?' || p.eq_ignore_ascii_case(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AprsPacket;
+
+    fn lookup(tocall: &str) -> Option<(&'static str, &'static str, DeviceClass)> {
+        DeviceInfo::from_tocall(&Callsign::new(tocall).unwrap())
+            .map(|d| (d.vendor, d.model, d.class))
+    }
+
+    #[test]
+    fn wildcards() {
+        assert_eq!(
+            Some(("Open Source", "APRSdroid", DeviceClass::App)),
+            lookup("APDR16")
+        );
+        assert_eq!(
+            Some(("WB2OSZ", "Dire Wolf", DeviceClass::Software)),
+            lookup("APDW17-1")
+        );
+        assert_eq!(None, lookup("APDW1"));
+        assert_eq!(None, lookup("BEACON"));
+    }
+
+    #[test]
+    fn most_specific_wins() {
+        assert_eq!(
+            Some(("Kenwood", "TH-D74", DeviceClass::HandHeld)),
+            lookup("APK004")
+        );
+        assert_eq!(
+            Some(("Kenwood", "TH-D7", DeviceClass::HandHeld)),
+            lookup("APK002")
+        );
+    }
+
+    #[test]
+    fn packet() {
+        let packet = AprsPacket::decode_textual(&b"N0CALL>APY400,WIDE1-1:>Hi"[..]).unwrap();
+        assert_eq!("FTM-400DR", packet.device_info().unwrap().model);
+
+        let packet = AprsPacket::decode_textual(&br#"N0CALL>PPPPPP:`(_fn"Oj/"#[..]).unwrap();
+        assert_eq!(None, packet.device_info());
+    }
+}
@@ -0,0 +1,203 @@
+//! The decisions of a digipeater, which repeats packets to extend their range.
+//!
+//! A digipeater acts on the first unused entry of a packet's path:
+//! - its own callsign or one of its explicit aliases is replaced by its
+//!   callsign, marked as used
+//! - a `WIDEn-N` alias is decremented, and the digipeater's callsign is inserted
+//!   before it, so that the path traces the hops taken
+//!
+//! Fill-in digipeaters only act on `WIDE1-1`. Paths asking for more hops than
+//! allowed are trapped: they're repeated once, with the rest of the path
+//! replaced by the digipeater's callsign.
+
+use AprsPacket;
+use Callsign;
+use Via;
+
+use path;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digipeater {
+    callsign: Callsign,
+    aliases: Vec<Callsign>,
+    fill_in: bool,
+    max_hops: usize,
+}
+
+impl Digipeater {
+    /// A wide-area digipeater, handling paths of up to 3 hops.
+    pub fn new(callsign: Callsign) -> Self {
+        Self {
+            callsign,
+            aliases: vec![],
+            fill_in: false,
+            max_hops: 3,
+        }
+    }
+
+    /// A fill-in digipeater, which only handles `WIDE1-1`.
+    pub fn fill_in(callsign: Callsign) -> Self {
+        Self {
+            fill_in: true,
+            ..Self::new(callsign)
+        }
+    }
+
+    /// Also acts on `alias` when it's used explicitly, e.g. `RELAY` or a shared
+    /// club callsign.
+    pub fn with_alias(mut self, alias: Callsign) -> Self {
+        self.aliases.push(alias);
+        self
+    }
+
+    /// Traps paths asking for more than `max_hops` hops in total.
+    pub fn with_max_hops(mut self, max_hops: usize) -> Self {
+        self.max_hops = max_hops;
+        self
+    }
+
+    /// Returns the packet to transmit, if this digipeater should repeat `packet`.
+    /// Copies of packets already repeated should be dropped beforehand, e.g.
+    /// with a [`DedupeCache`](crate::DedupeCache).
+    pub fn digipeat(&self, packet: &AprsPacket) -> Option<AprsPacket> {
+        let own = key(&self.callsign);
+        let repeated = packet
+            .via
+            .iter()
+            .filter_map(Via::callsign)
+            .any(|(c, heard)| heard && key(c) == own);
+        if key(&packet.from) == own || repeated {
+            return None;
+        }
+
+        let i = packet.next_hop()?;
+        let (next, _) = packet.via[i].callsign()?;
+        let mut packet = packet.clone();
+
+        if key(next) == own || self.aliases.iter().any(|a| key(a) == key(next)) {
+            packet.via[i] = Via::Callsign(self.callsign.clone(), true);
+            return Some(packet);
+        }
+
+        let (requested, remaining) = path::alias(next)?;
+        if next.call() != format!("WIDE{}", requested) || remaining == 0 {
+            return None;
+        }
+        if self.fill_in && requested != 1 {
+            return None;
+        }
+
+        if packet.hops().requested > self.max_hops {
+            path::trap(&mut packet.via, i, self.callsign.clone());
+            return Some(packet);
+        }
+
+        path::decrement(&mut packet.via, i);
+        // without room to trace the hop, it's only counted down
+        if packet.via.iter().filter_map(Via::callsign).count() < path::MAX_VIAS {
+            path::insert_digipeater(&mut packet.via, i, self.callsign.clone());
+        }
+
+        Some(packet)
+    }
+}
+
+fn key(c: &Callsign) -> String {
+    c.to_string().to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digipeat(digipeater: &Digipeater, s: &str) -> Option<String> {
+        let packet = AprsPacket::decode_textual(s.as_bytes()).unwrap();
+        let packet = digipeater.digipeat(&packet)?;
+
+        let mut buf = vec![];
+        packet.encode_textual(&mut buf).unwrap();
+        Some(String::from_utf8(buf).unwrap())
+    }
+
+    #[test]
+    fn wide() {
+        let digi = Digipeater::new(Callsign::new_with_ssid("N0CALL", "1"));
+
+        assert_eq!(
+            Some("N1CALL>APRS,N0CALL-1,WIDE1*,WIDE2-2:>Hi".to_owned()),
+            digipeat(&digi, "N1CALL>APRS,WIDE1-1,WIDE2-2:>Hi")
+        );
+        assert_eq!(
+            Some("N1CALL>APRS,N2CALL,WIDE1,N0CALL-1*,WIDE2-1:>Hi".to_owned()),
+            digipeat(&digi, "N1CALL>APRS,N2CALL*,WIDE1*,WIDE2-2:>Hi")
+        );
+
+        assert_eq!(None, digipeat(&digi, "N1CALL>APRS:>Hi"));
+        assert_eq!(None, digipeat(&digi, "N1CALL>APRS,WIDE2*:>Hi"));
+        assert_eq!(None, digipeat(&digi, "N1CALL>APRS,TRACE2-2:>Hi"));
+        assert_eq!(None, digipeat(&digi, "N1CALL>APRS,N3CALL:>Hi"));
+        // already repeated by us
+        assert_eq!(
+            None,
+            digipeat(&digi, "N1CALL>APRS,N0CALL-1,WIDE1*,WIDE2-2:>Hi")
+        );
+    }
+
+    #[test]
+    fn explicit() {
+        let digi = Digipeater::new(Callsign::new_with_ssid("N0CALL", "1"))
+            .with_alias(Callsign::new_no_ssid("CLUB"));
+
+        assert_eq!(
+            Some("N1CALL>APRS,N0CALL-1*,WIDE2-1:>Hi".to_owned()),
+            digipeat(&digi, "N1CALL>APRS,n0call-1,WIDE2-1:>Hi")
+        );
+        assert_eq!(
+            Some("N1CALL>APRS,N0CALL-1*:>Hi".to_owned()),
+            digipeat(&digi, "N1CALL>APRS,CLUB:>Hi")
+        );
+    }
+
+    #[test]
+    fn fill_in() {
+        let digi = Digipeater::fill_in(Callsign::new_with_ssid("N0CALL", "1"));
+
+        assert_eq!(
+            Some("N1CALL>APRS,N0CALL-1,WIDE1*,WIDE2-1:>Hi".to_owned()),
+            digipeat(&digi, "N1CALL>APRS,WIDE1-1,WIDE2-1:>Hi")
+        );
+        assert_eq!(None, digipeat(&digi, "N1CALL>APRS,WIDE2-2:>Hi"));
+    }
+
+    #[test]
+    fn trapping() {
+        let digi = Digipeater::new(Callsign::new_with_ssid("N0CALL", "1"));
+
+        assert_eq!(
+            Some("N1CALL>APRS,N0CALL-1*:>Hi".to_owned()),
+            digipeat(&digi, "N1CALL>APRS,WIDE7-7:>Hi")
+        );
+        assert_eq!(
+            Some("N1CALL>APRS,N0CALL-1*:>Hi".to_owned()),
+            digipeat(&digi, "N1CALL>APRS,WIDE1-1,WIDE3-3:>Hi")
+        );
+
+        let digi = digi.with_max_hops(4);
+        assert_eq!(
+            Some("N1CALL>APRS,N0CALL-1,WIDE1*,WIDE3-3:>Hi".to_owned()),
+            digipeat(&digi, "N1CALL>APRS,WIDE1-1,WIDE3-3:>Hi")
+        );
+    }
+
+    #[test]
+    fn full_path() {
+        let digi = Digipeater::new(Callsign::new_with_ssid("N0CALL", "1")).with_max_hops(9);
+
+        assert_eq!(
+            Some("N1CALL>APRS,A,B,C,D,E,F,G*,WIDE2-1:>Hi".to_owned()),
+            digipeat(&digi, "N1CALL>APRS,A*,B*,C*,D*,E*,F*,G*,WIDE2-2:>Hi")
+        );
+    }
+}
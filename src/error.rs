@@ -1,49 +1,412 @@
+use core::fmt;
+
+use io;
+#[cfg(not(feature = "std"))]
+use prelude::*;
 use Callsign;
 
-#[derive(Debug, Eq, PartialEq, thiserror::Error)]
+#[derive(Debug, Eq, PartialEq)]
 pub enum DecodeError {
-    #[error("Invalid Callsign: {0:?}")]
-    InvalidCallsign(Vec<u8>),
-    #[error("Invalid Via: {0:?}")]
-    InvalidVia(Vec<u8>),
-    #[error("Invalid Timestamp: {0:?}")]
-    InvalidTimestamp(Vec<u8>),
-    #[error("Unsupported Position Format: {0:?}")]
-    UnsupportedPositionFormat(Vec<u8>),
-    #[error("Invalid Position: {0:?}")]
-    InvalidPosition(Vec<u8>),
-    #[error("Invalid Latitude: {0:?}")]
-    InvalidLatitude(Vec<u8>),
-    #[error("Invalid Longitude: {0:?}")]
-    InvalidLongitude(Vec<u8>),
-    #[error("Invalid Packet: {0:?}")]
-    InvalidPacket(Vec<u8>),
-    #[error("Invalid Message Destination: {0:?}")]
-    InvalidMessageDestination(Vec<u8>),
-    #[error("Invalid Message ID: {0:?}")]
-    InvalidMessageId(Vec<u8>),
-    #[error("Invalid Compressed cs: {0:?}")]
-    InvalidCs([u8; 2]),
-    #[error("Invalid Mic-E destination address: {0:}")]
-    InvalidMicEDestination(Callsign),
-    #[error("Invalid Mic-E information field: {0:?}")]
-    InvalidMicEInformation(Vec<u8>),
+    InvalidCallsign(Vec<u8>, usize),
+    InvalidVia(Vec<u8>, usize),
+    InvalidTimestamp(Vec<u8>, usize),
+    UnsupportedPositionFormat(Vec<u8>, usize),
+    InvalidPosition(Vec<u8>, usize),
+    InvalidLatitude(Vec<u8>, usize),
+    InvalidLongitude(Vec<u8>, usize),
+    InvalidPacket(Vec<u8>, usize),
+    InvalidMessageDestination(Vec<u8>, usize),
+    InvalidMessageId(Vec<u8>, usize),
+    InvalidCs([u8; 2], usize),
+    InvalidMicEDestination(Callsign, usize),
+    InvalidMicEInformation(Vec<u8>, usize),
+    InvalidTelemetry(Vec<u8>, usize),
+    InvalidWeather(Vec<u8>, usize),
+    InvalidObject(Vec<u8>, usize),
+    InvalidItem(Vec<u8>, usize),
+    InvalidQuery(Vec<u8>, usize),
+    InvalidCapabilities(Vec<u8>, usize),
+    InvalidUserDefined(Vec<u8>, usize),
+    InvalidThirdParty(Vec<u8>, usize),
+    InvalidNmea(Vec<u8>, usize),
+    InvalidFilter(Vec<u8>, usize),
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::InvalidCallsign(v, _) => write!(f, "Invalid Callsign: {:?}", v),
+            DecodeError::InvalidVia(v, _) => write!(f, "Invalid Via: {:?}", v),
+            DecodeError::InvalidTimestamp(v, _) => write!(f, "Invalid Timestamp: {:?}", v),
+            DecodeError::UnsupportedPositionFormat(v, _) => {
+                write!(f, "Unsupported Position Format: {:?}", v)
+            }
+            DecodeError::InvalidPosition(v, _) => write!(f, "Invalid Position: {:?}", v),
+            DecodeError::InvalidLatitude(v, _) => write!(f, "Invalid Latitude: {:?}", v),
+            DecodeError::InvalidLongitude(v, _) => write!(f, "Invalid Longitude: {:?}", v),
+            DecodeError::InvalidPacket(v, _) => write!(f, "Invalid Packet: {:?}", v),
+            DecodeError::InvalidMessageDestination(v, _) => {
+                write!(f, "Invalid Message Destination: {:?}", v)
+            }
+            DecodeError::InvalidMessageId(v, _) => write!(f, "Invalid Message ID: {:?}", v),
+            DecodeError::InvalidCs(v, _) => write!(f, "Invalid Compressed cs: {:?}", v),
+            DecodeError::InvalidMicEDestination(v, _) => {
+                write!(f, "Invalid Mic-E destination address: {}", v)
+            }
+            DecodeError::InvalidMicEInformation(v, _) => {
+                write!(f, "Invalid Mic-E information field: {:?}", v)
+            }
+            DecodeError::InvalidTelemetry(v, _) => write!(f, "Invalid Telemetry: {:?}", v),
+            DecodeError::InvalidWeather(v, _) => write!(f, "Invalid Weather: {:?}", v),
+            DecodeError::InvalidObject(v, _) => write!(f, "Invalid Object: {:?}", v),
+            DecodeError::InvalidItem(v, _) => write!(f, "Invalid Item: {:?}", v),
+            DecodeError::InvalidQuery(v, _) => write!(f, "Invalid Query: {:?}", v),
+            DecodeError::InvalidCapabilities(v, _) => write!(f, "Invalid Capabilities: {:?}", v),
+            DecodeError::InvalidUserDefined(v, _) => {
+                write!(f, "Invalid User-Defined Data: {:?}", v)
+            }
+            DecodeError::InvalidThirdParty(v, _) => {
+                write!(f, "Invalid Third-Party Packet: {:?}", v)
+            }
+            DecodeError::InvalidNmea(v, _) => write!(f, "Invalid NMEA Sentence: {:?}", v),
+            DecodeError::InvalidFilter(v, _) => write!(f, "Invalid Filter: {:?}", v),
+        }
+    }
+}
+
+/// Where in a packet decoding failed, see [`DecodeError::location`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ErrorLocation {
+    /// Byte offset into the packet
+    pub offset: usize,
+    pub len: usize,
+    /// The name of the field that couldn't be decoded, e.g. `latitude`
+    pub field: &'static str,
+}
+
+impl DecodeError {
+    /// The name of the field that couldn't be decoded, e.g. `latitude`.
+    pub fn field(&self) -> &'static str {
+        match self {
+            DecodeError::InvalidCallsign(..) => "callsign",
+            DecodeError::InvalidVia(..) => "via",
+            DecodeError::InvalidTimestamp(..) => "timestamp",
+            DecodeError::UnsupportedPositionFormat(..) | DecodeError::InvalidPosition(..) => {
+                "position"
+            }
+            DecodeError::InvalidLatitude(..) => "latitude",
+            DecodeError::InvalidLongitude(..) => "longitude",
+            DecodeError::InvalidPacket(..) => "packet",
+            DecodeError::InvalidMessageDestination(..) => "message addressee",
+            DecodeError::InvalidMessageId(..) => "message id",
+            DecodeError::InvalidCs(..) => "course/speed",
+            DecodeError::InvalidMicEDestination(..) => "Mic-E destination",
+            DecodeError::InvalidMicEInformation(..) => "Mic-E information",
+            DecodeError::InvalidTelemetry(..) => "telemetry",
+            DecodeError::InvalidWeather(..) => "weather",
+            DecodeError::InvalidObject(..) => "object",
+            DecodeError::InvalidItem(..) => "item",
+            DecodeError::InvalidQuery(..) => "query",
+            DecodeError::InvalidCapabilities(..) => "capabilities",
+            DecodeError::InvalidUserDefined(..) => "user-defined data",
+            DecodeError::InvalidThirdParty(..) => "third-party packet",
+            DecodeError::InvalidNmea(..) => "NMEA sentence",
+            DecodeError::InvalidFilter(..) => "filter",
+        }
+    }
+
+    /// The bytes that couldn't be decoded.
+    pub fn bytes(&self) -> Option<&[u8]> {
+        match self {
+            DecodeError::InvalidCallsign(b, _)
+            | DecodeError::InvalidVia(b, _)
+            | DecodeError::InvalidTimestamp(b, _)
+            | DecodeError::UnsupportedPositionFormat(b, _)
+            | DecodeError::InvalidPosition(b, _)
+            | DecodeError::InvalidLatitude(b, _)
+            | DecodeError::InvalidLongitude(b, _)
+            | DecodeError::InvalidPacket(b, _)
+            | DecodeError::InvalidMessageDestination(b, _)
+            | DecodeError::InvalidMessageId(b, _)
+            | DecodeError::InvalidMicEInformation(b, _)
+            | DecodeError::InvalidTelemetry(b, _)
+            | DecodeError::InvalidWeather(b, _)
+            | DecodeError::InvalidObject(b, _)
+            | DecodeError::InvalidItem(b, _)
+            | DecodeError::InvalidQuery(b, _)
+            | DecodeError::InvalidCapabilities(b, _)
+            | DecodeError::InvalidUserDefined(b, _)
+            | DecodeError::InvalidThirdParty(b, _)
+            | DecodeError::InvalidNmea(b, _)
+            | DecodeError::InvalidFilter(b, _) => Some(b),
+            DecodeError::InvalidCs(b, _) => Some(b),
+            DecodeError::InvalidMicEDestination(..) => None,
+        }
+    }
+
+    /// Byte offset of the field that couldn't be decoded, relative to the
+    /// start of what was passed to the decoder. For
+    /// [`AprsPacket::decode_textual`](crate::AprsPacket::decode_textual) and
+    /// [`AprsPacket::decode_ax25`](crate::AprsPacket::decode_ax25) that's the
+    /// whole packet.
+    pub fn offset(&self) -> usize {
+        match self {
+            DecodeError::InvalidCallsign(_, o)
+            | DecodeError::InvalidVia(_, o)
+            | DecodeError::InvalidTimestamp(_, o)
+            | DecodeError::UnsupportedPositionFormat(_, o)
+            | DecodeError::InvalidPosition(_, o)
+            | DecodeError::InvalidLatitude(_, o)
+            | DecodeError::InvalidLongitude(_, o)
+            | DecodeError::InvalidPacket(_, o)
+            | DecodeError::InvalidMessageDestination(_, o)
+            | DecodeError::InvalidMessageId(_, o)
+            | DecodeError::InvalidMicEInformation(_, o)
+            | DecodeError::InvalidTelemetry(_, o)
+            | DecodeError::InvalidWeather(_, o)
+            | DecodeError::InvalidObject(_, o)
+            | DecodeError::InvalidItem(_, o)
+            | DecodeError::InvalidQuery(_, o)
+            | DecodeError::InvalidCapabilities(_, o)
+            | DecodeError::InvalidUserDefined(_, o)
+            | DecodeError::InvalidThirdParty(_, o)
+            | DecodeError::InvalidNmea(_, o)
+            | DecodeError::InvalidFilter(_, o)
+            | DecodeError::InvalidCs(_, o)
+            | DecodeError::InvalidMicEDestination(_, o) => *o,
+        }
+    }
+
+    /// Where the field that couldn't be decoded is, see [`offset`](Self::offset).
+    pub fn location(&self) -> ErrorLocation {
+        let len = match self {
+            DecodeError::InvalidMicEDestination(c, _) => c.to_string().len(),
+            _ => self.bytes().map_or(0, <[u8]>::len),
+        };
+
+        ErrorLocation {
+            offset: self.offset(),
+            len,
+            field: self.field(),
+        }
+    }
+
+    // Errors from decoding part of the input are relative to that part.
+    // This makes them relative to the whole input again.
+    pub(crate) fn offset_by(mut self, n: usize) -> Self {
+        match &mut self {
+            DecodeError::InvalidCallsign(_, o)
+            | DecodeError::InvalidVia(_, o)
+            | DecodeError::InvalidTimestamp(_, o)
+            | DecodeError::UnsupportedPositionFormat(_, o)
+            | DecodeError::InvalidPosition(_, o)
+            | DecodeError::InvalidLatitude(_, o)
+            | DecodeError::InvalidLongitude(_, o)
+            | DecodeError::InvalidPacket(_, o)
+            | DecodeError::InvalidMessageDestination(_, o)
+            | DecodeError::InvalidMessageId(_, o)
+            | DecodeError::InvalidMicEInformation(_, o)
+            | DecodeError::InvalidTelemetry(_, o)
+            | DecodeError::InvalidWeather(_, o)
+            | DecodeError::InvalidObject(_, o)
+            | DecodeError::InvalidItem(_, o)
+            | DecodeError::InvalidQuery(_, o)
+            | DecodeError::InvalidCapabilities(_, o)
+            | DecodeError::InvalidUserDefined(_, o)
+            | DecodeError::InvalidThirdParty(_, o)
+            | DecodeError::InvalidNmea(_, o)
+            | DecodeError::InvalidFilter(_, o)
+            | DecodeError::InvalidCs(_, o)
+            | DecodeError::InvalidMicEDestination(_, o) => *o += n,
+        }
+        self
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum CallsignError {
+    Empty,
+    TooLong(String),
+    InvalidCharacter(char),
+    InvalidSsid(String),
+}
+
+impl fmt::Display for CallsignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CallsignError::Empty => f.write_str("Callsign is empty"),
+            CallsignError::TooLong(v) => write!(f, "Callsign is longer than 6 characters: {}", v),
+            CallsignError::InvalidCharacter(v) => {
+                write!(f, "Callsign has an invalid character: {:?}", v)
+            }
+            CallsignError::InvalidSsid(v) => write!(f, "Invalid SSID: {}", v),
+        }
+    }
 }
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug)]
 pub enum EncodeError {
-    #[error("Callsign can't be encoded: {0:}")]
     InvalidCallsign(Callsign),
-    #[error("Invalid Latitude: {0}")]
     InvalidLatitude(f64),
-    #[error("Invalid Longitude: {0}")]
     InvalidLongitude(f64),
-    #[error("Invalid Aprs Data")]
     InvalidData,
-    #[error("Invalid Message Addressee: {0:?}")]
     InvalidMessageAddressee(Vec<u8>),
-    #[error("Compressed altitude requires the nmea source to be gga")]
+    InvalidMessageText(Vec<u8>),
+    InvalidMessageId(Vec<u8>),
+    InvalidObjectName(Vec<u8>),
+    InvalidItemName(Vec<u8>),
+    InvalidSignpost(Vec<u8>),
+    InvalidComment(Vec<u8>),
+    InvalidEmailAddress(String),
+    InvalidDataTypeIdentifier(u8),
+    MissingTimestamp,
     NonGgaAltitude,
-    #[error(transparent)]
-    Write(#[from] std::io::Error),
+    InvalidTelemetryValue(f64),
+    EmptyMeasurement,
+    TooManyVias(usize),
+    InvalidVia(Callsign),
+    Write(io::Error),
+}
+
+impl fmt::Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EncodeError::InvalidCallsign(v) => write!(f, "Callsign can't be encoded: {}", v),
+            EncodeError::InvalidLatitude(v) => write!(f, "Invalid Latitude: {}", v),
+            EncodeError::InvalidLongitude(v) => write!(f, "Invalid Longitude: {}", v),
+            EncodeError::InvalidData => f.write_str("Invalid Aprs Data"),
+            EncodeError::InvalidMessageAddressee(v) => {
+                write!(f, "Invalid Message Addressee: {:?}", v)
+            }
+            EncodeError::InvalidMessageText(v) => write!(f, "Invalid Message Text: {:?}", v),
+            EncodeError::InvalidMessageId(v) => write!(f, "Invalid Message ID: {:?}", v),
+            EncodeError::InvalidObjectName(v) => write!(f, "Invalid Object Name: {:?}", v),
+            EncodeError::InvalidItemName(v) => write!(f, "Invalid Item Name: {:?}", v),
+            EncodeError::InvalidSignpost(v) => write!(f, "Invalid Signpost: {:?}", v),
+            EncodeError::InvalidComment(v) => write!(f, "Invalid Comment: {:?}", v),
+            EncodeError::InvalidEmailAddress(v) => write!(f, "Invalid Email Address: {}", v),
+            EncodeError::InvalidDataTypeIdentifier(v) => {
+                write!(f, "Invalid Data Type Identifier: {:?}", v)
+            }
+            EncodeError::MissingTimestamp => {
+                f.write_str("Data Type Identifier requires a timestamp")
+            }
+            EncodeError::NonGgaAltitude => {
+                f.write_str("Compressed altitude requires the nmea source to be gga")
+            }
+            EncodeError::InvalidTelemetryValue(v) => {
+                write!(f, "Telemetry value out of range: {}", v)
+            }
+            EncodeError::EmptyMeasurement => f.write_str("Measurement has no fields"),
+            EncodeError::TooManyVias(v) => write!(
+                f,
+                "Via path has {} entries, more than the 8 AX.25 allows",
+                v
+            ),
+            EncodeError::InvalidVia(v) => write!(f, "Via can't be encoded: {}", v),
+            EncodeError::Write(e) => e.fmt(f),
+        }
+    }
+}
+
+impl From<io::Error> for EncodeError {
+    fn from(e: io::Error) -> Self {
+        EncodeError::Write(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CallsignError {}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EncodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EncodeError::Write(e) => e.source(),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AprsPacket;
+
+    fn locate(raw: &[u8]) -> ErrorLocation {
+        AprsPacket::decode_textual(raw).unwrap_err().location()
+    }
+
+    #[test]
+    fn locate_header() {
+        assert_eq!(
+            ErrorLocation {
+                offset: 12,
+                len: 3,
+                field: "via",
+            },
+            locate(b"N0CALL>APRS,N1-:>Hello")
+        );
+        assert_eq!(
+            ErrorLocation {
+                offset: 7,
+                len: 4,
+                field: "Mic-E destination",
+            },
+            locate(b"N0CALL>APRS,WIDE1-1:`(_fn\"Oj/")
+        );
+    }
+
+    #[test]
+    fn locate_info() {
+        for (raw, field, bytes) in &[
+            (
+                &b"N0CALL>APRS,WIDE1-1:!4903.50N/07201.75X-Test"[..],
+                "longitude",
+                &b"07201.75X"[..],
+            ),
+            (
+                b"N0CALL>APRS:@092345z4903.50N/07201.75X-Test",
+                "longitude",
+                b"07201.75X",
+            ),
+            (b"N0CALL>APRS:/092345z/5L!!<*e7>|s!", "course/speed", b"|s"),
+            (
+                b"N0CALL>APRS:;LEADER   *0923x5z4903.50N/07201.75W>",
+                "timestamp",
+                b"0923x5z",
+            ),
+            // the same bytes earlier in the packet aren't mistaken for the field
+            (
+                b"N0CALL>APRS:!4903.50N/04903.50N-Test",
+                "longitude",
+                b"04903.50N",
+            ),
+        ] {
+            let location = locate(raw);
+            assert_eq!(*field, location.field);
+            assert_eq!(
+                *bytes,
+                &raw[location.offset..location.offset + location.len]
+            );
+        }
+    }
+
+    #[test]
+    fn locate_ax25() {
+        let mut raw = vec![];
+        AprsPacket::decode_textual(b"N0CALL>APRS:!4903.50N/07201.75W-Test")
+            .unwrap()
+            .encode_ax25(&mut raw)
+            .unwrap();
+        let i = raw.len() - b"07201.75W-Test".len();
+        raw[i + 8] = b'X';
+
+        let location = AprsPacket::decode_ax25(&raw).unwrap_err().location();
+        assert_eq!("longitude", location.field);
+        assert_eq!(i, location.offset);
+    }
 }
@@ -0,0 +1,409 @@
+//! Data extensions are fixed length, 7 byte fields at the start of the comment
+//! of uncompressed positions, objects and items.
+//!
+//! Examples:
+//! - "088/036"                       (course and speed)
+//! - "PHG5132"                       (power, antenna height, gain and directivity)
+//! - "RNG0050"                       (pre-calculated radio range in miles)
+//! - "DFS2360"                       (omni-DF signal strength, antenna height, gain and directivity)
+//!
+//! DF reports, with the `/\` symbol, follow the course and speed with the bearing
+//! and its accuracy, e.g. "088/036/270/729".
+
+use bytes::parse_digits;
+use io::Write;
+use EncodeError;
+
+pub(crate) const EXTENSION_LEN: usize = 7;
+
+/// Course in degrees and speed in knots, e.g. `088/036`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CourseSpeed {
+    /// 1 to 360 degrees clockwise from north, or 0 if unknown
+    pub course: u16,
+    pub speed_knots: u16,
+}
+
+/// Station power, antenna height above average terrain, antenna gain and
+/// directivity, e.g. `PHG5132`. The fields hold the encoded digits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Phg {
+    pub power: u8,
+    pub height: u8,
+    pub gain: u8,
+    /// 0 for omnidirectional, otherwise 1 to 8 in 45 degree steps
+    pub directivity: u8,
+}
+
+impl Phg {
+    /// The power is the square of its digit.
+    pub fn power_watts(&self) -> u32 {
+        u32::from(self.power).pow(2)
+    }
+
+    /// The height is 10 feet doubled for every step.
+    /// `None` if the height isn't a single digit.
+    pub fn height_feet(&self) -> Option<u32> {
+        height_feet(self.height)
+    }
+
+    pub fn height_meters(&self) -> Option<f64> {
+        self.height_feet().map(|feet| f64::from(feet) * 0.3048)
+    }
+
+    pub fn gain_db(&self) -> u8 {
+        self.gain
+    }
+
+    /// Direction of maximum gain in degrees, or `None` for an omnidirectional antenna.
+    pub fn directivity_degrees(&self) -> Option<u16> {
+        directivity_degrees(self.directivity)
+    }
+}
+
+/// Signal strength of an omni-DF station, with its antenna height, gain and
+/// directivity, e.g. `DFS2360`. The fields hold the encoded digits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Dfs {
+    /// 0 to 9 S-points
+    pub strength: u8,
+    pub height: u8,
+    pub gain: u8,
+    pub directivity: u8,
+}
+
+impl Dfs {
+    pub fn strength_s_points(&self) -> u8 {
+        self.strength
+    }
+
+    /// The height is 10 feet doubled for every step.
+    /// `None` if the height isn't a single digit.
+    pub fn height_feet(&self) -> Option<u32> {
+        height_feet(self.height)
+    }
+
+    pub fn height_meters(&self) -> Option<f64> {
+        self.height_feet().map(|feet| f64::from(feet) * 0.3048)
+    }
+
+    pub fn gain_db(&self) -> u8 {
+        self.gain
+    }
+
+    /// Direction of maximum gain in degrees, or `None` for an omnidirectional antenna.
+    pub fn directivity_degrees(&self) -> Option<u16> {
+        directivity_degrees(self.directivity)
+    }
+}
+
+/// The bearing of a DF report and its accuracy, e.g. `/270/729`.
+/// The fields hold the encoded digits.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DfBearing {
+    /// 1 to 360 degrees
+    pub bearing: u16,
+    /// Number of hits out of 8, or 9 for a manual report
+    pub hits: u8,
+    pub range: u8,
+    pub quality: u8,
+}
+
+impl DfBearing {
+    pub(crate) const LEN: usize = 8;
+
+    /// Decodes the bearing and accuracy after the course and speed extension.
+    pub fn decode(b: &[u8]) -> Option<Self> {
+        let b = b.get(..Self::LEN)?;
+        if b[0] != b'/' || b[4] != b'/' {
+            return None;
+        }
+
+        let digit = |i: usize| b[i].checked_sub(b'0').filter(|d| *d <= 9);
+        Some(Self {
+            bearing: parse_digits(&b[1..4]).filter(|b| (1..=360).contains(b))?,
+            hits: digit(5)?,
+            range: digit(6)?,
+            quality: digit(7)?,
+        })
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        if !(1..=360).contains(&self.bearing) || self.hits > 9 || self.range > 9 || self.quality > 9
+        {
+            return Err(EncodeError::InvalidData);
+        }
+        write!(
+            buf,
+            "/{:03}/{}{}{}",
+            self.bearing, self.hits, self.range, self.quality
+        )?;
+
+        Ok(())
+    }
+
+    /// Fraction of the time the signal was heard, or `None` if the report
+    /// was made manually.
+    pub fn hit_ratio(&self) -> Option<f64> {
+        match self.hits {
+            9 => None,
+            n => Some(f64::from(n) / 8.0),
+        }
+    }
+
+    /// The range is 1 mile doubled for every step.
+    /// `None` if the range isn't a single digit.
+    pub fn range_miles(&self) -> Option<u32> {
+        if self.range > 9 {
+            return None;
+        }
+
+        Some(1 << self.range)
+    }
+
+    /// Upper limit of the width of the beam the signal is in,
+    /// or `None` if the bearing is useless.
+    pub fn beamwidth_degrees(&self) -> Option<u16> {
+        const BEAMWIDTHS: [u16; 9] = [240, 120, 64, 32, 16, 8, 4, 2, 1];
+
+        match self.quality {
+            0 => None,
+            q => BEAMWIDTHS.get(usize::from(q) - 1).copied(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DataExtension {
+    CourseSpeed(CourseSpeed),
+    Phg(Phg),
+    /// Radio range in miles, e.g. `RNG0050`
+    Rng(u16),
+    Dfs(Dfs),
+}
+
+impl DataExtension {
+    /// Decodes the data extension at the start of a comment, if there is one.
+    pub fn decode(comment: &[u8]) -> Option<Self> {
+        let b = comment.get(..EXTENSION_LEN)?;
+
+        if b[3] == b'/' {
+            let course = parse_digits(&b[0..3]).filter(|c| *c <= 360)?;
+            let speed_knots = parse_digits(&b[4..7])?;
+            return Some(Self::CourseSpeed(CourseSpeed {
+                course,
+                speed_knots,
+            }));
+        }
+
+        if let Some(phg) = b.strip_prefix(b"PHG") {
+            let (power, height, gain, directivity) = decode_antenna(phg)?;
+            return Some(Self::Phg(Phg {
+                power,
+                height,
+                gain,
+                directivity,
+            }));
+        }
+
+        if let Some(dfs) = b.strip_prefix(b"DFS") {
+            let (strength, height, gain, directivity) = decode_antenna(dfs)?;
+            return Some(Self::Dfs(Dfs {
+                strength,
+                height,
+                gain,
+                directivity,
+            }));
+        }
+
+        if let Some(range) = b.strip_prefix(b"RNG") {
+            return Some(Self::Rng(parse_digits(range)?));
+        }
+
+        None
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        match self {
+            Self::CourseSpeed(cs) => {
+                if cs.course > 360 || cs.speed_knots > 999 {
+                    return Err(EncodeError::InvalidData);
+                }
+                write!(buf, "{:03}/{:03}", cs.course, cs.speed_knots)?;
+            }
+            Self::Phg(phg) => {
+                encode_antenna(
+                    buf,
+                    b"PHG",
+                    phg.power,
+                    phg.height,
+                    phg.gain,
+                    phg.directivity,
+                )?;
+            }
+            Self::Dfs(dfs) => {
+                encode_antenna(
+                    buf,
+                    b"DFS",
+                    dfs.strength,
+                    dfs.height,
+                    dfs.gain,
+                    dfs.directivity,
+                )?;
+            }
+            Self::Rng(range) => {
+                if *range > 9999 {
+                    return Err(EncodeError::InvalidData);
+                }
+                write!(buf, "RNG{:04}", range)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// PHG and DFS share the last three digits
+fn decode_antenna(b: &[u8]) -> Option<(u8, u8, u8, u8)> {
+    let digit = |i: usize| b[i].checked_sub(b'0').filter(|d| *d <= 9);
+
+    Some((
+        digit(0)?,
+        digit(1)?,
+        digit(2)?,
+        digit(3).filter(|d| *d <= 8)?,
+    ))
+}
+
+fn encode_antenna<W: Write>(
+    buf: &mut W,
+    prefix: &[u8],
+    first: u8,
+    height: u8,
+    gain: u8,
+    directivity: u8,
+) -> Result<(), EncodeError> {
+    if first > 9 || height > 9 || gain > 9 || directivity > 8 {
+        return Err(EncodeError::InvalidData);
+    }
+    buf.write_all(prefix)?;
+    write!(buf, "{}{}{}{}", first, height, gain, directivity)?;
+
+    Ok(())
+}
+
+fn height_feet(height: u8) -> Option<u32> {
+    if height > 9 {
+        return None;
+    }
+
+    Some(10 << height)
+}
+
+fn directivity_degrees(directivity: u8) -> Option<u16> {
+    match directivity {
+        0 => None,
+        d => Some(u16::from(d) * 45),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn course_speed() {
+        let ext = DataExtension::decode(b"088/036 Moving").unwrap();
+        assert_eq!(
+            DataExtension::CourseSpeed(CourseSpeed {
+                course: 88,
+                speed_knots: 36
+            }),
+            ext
+        );
+
+        let mut buf = vec![];
+        ext.encode(&mut buf).unwrap();
+        assert_eq!(b"088/036", &buf[..]);
+
+        assert_eq!(None, DataExtension::decode(b"361/036"));
+        assert_eq!(None, DataExtension::decode(b".../..."));
+        assert_eq!(None, DataExtension::decode(b"088/03"));
+    }
+
+    #[test]
+    fn phg() {
+        let ext = DataExtension::decode(b"PHG5132Digi").unwrap();
+        let phg = match ext {
+            DataExtension::Phg(phg) => phg,
+            _ => panic!("Unexpected extension"),
+        };
+        assert_eq!(25, phg.power_watts());
+        assert_eq!(Some(20), phg.height_feet());
+        assert_relative_eq!(6.096, phg.height_meters().unwrap());
+        assert_eq!(3, phg.gain_db());
+        assert_eq!(Some(90), phg.directivity_degrees());
+
+        let mut buf = vec![];
+        ext.encode(&mut buf).unwrap();
+        assert_eq!(b"PHG5132", &buf[..]);
+
+        assert_eq!(None, DataExtension::decode(b"PHG513"));
+        assert_eq!(None, DataExtension::decode(b"PHG5139"));
+
+        let phg = Phg { height: 40, ..phg };
+        assert_eq!(None, phg.height_feet());
+    }
+
+    #[test]
+    fn dfs() {
+        let ext = DataExtension::decode(b"DFS2360").unwrap();
+        let dfs = match ext {
+            DataExtension::Dfs(dfs) => dfs,
+            _ => panic!("Unexpected extension"),
+        };
+        assert_eq!(2, dfs.strength_s_points());
+        assert_eq!(Some(80), dfs.height_feet());
+        assert_eq!(6, dfs.gain_db());
+        assert_eq!(None, dfs.directivity_degrees());
+
+        let mut buf = vec![];
+        ext.encode(&mut buf).unwrap();
+        assert_eq!(b"DFS2360", &buf[..]);
+    }
+
+    #[test]
+    fn df_bearing() {
+        let bearing = DfBearing::decode(b"/270/729 Fox").unwrap();
+        assert_eq!(270, bearing.bearing);
+        assert_eq!(Some(0.875), bearing.hit_ratio());
+        assert_eq!(Some(4), bearing.range_miles());
+        assert_eq!(Some(1), bearing.beamwidth_degrees());
+
+        let far = DfBearing {
+            range: 40,
+            ..bearing
+        };
+        assert_eq!(None, far.range_miles());
+
+        let mut buf = vec![];
+        bearing.encode(&mut buf).unwrap();
+        assert_eq!(b"/270/729", &buf[..]);
+
+        assert_eq!(None, DfBearing::decode(b"/000/729"));
+        assert_eq!(None, DfBearing::decode(b"/270/72"));
+    }
+
+    #[test]
+    fn rng() {
+        let ext = DataExtension::decode(b"RNG0050 Repeater").unwrap();
+        assert_eq!(DataExtension::Rng(50), ext);
+
+        let mut buf = vec![];
+        ext.encode(&mut buf).unwrap();
+        assert_eq!(b"RNG0050", &buf[..]);
+
+        assert_eq!(None, DataExtension::decode(b"RNG-050"));
+        assert!(DataExtension::Rng(10000).encode(&mut vec![]).is_err());
+    }
+}
@@ -0,0 +1,330 @@
+//! APRS-IS filters select which packets a server sends to a client.
+//! They are space separated terms, and a packet passes if it matches any term
+//! and none of the terms negated with a leading `-`.
+//!
+//! Examples:
+//! - "r/33.25/-96.5/50"              (within 50 km of a point)
+//! - "p/N0/N1"                       (senders starting with N0 or N1)
+//! - "b/N0CALL*/W1AW"                (senders, with wildcards)
+//! - "t/poimqstunw"                  (packet types)
+//! - "a/50/-73/48/-71"               (within a box: north, west, south, east)
+//! - "m/25"                          (within 25 km of the client)
+//! - "f/N0CALL-9/10"                 (within 10 km of another station)
+//! - "r/33.25/-96.5/50 -t/w"         (nearby, but no weather)
+
+use std::collections::HashMap;
+
+use lonlat::distance_km;
+use AprsData;
+use AprsPacket;
+use DecodeError;
+use Latitude;
+use Longitude;
+use NwsBulletin;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterTerm {
+    /// `r/lat/lon/dist`
+    Range {
+        latitude: f64,
+        longitude: f64,
+        km: f64,
+    },
+    /// `p/aa/bb/cc`, matching the start of the sender's callsign
+    Prefix(Vec<String>),
+    /// `b/call1/call2`, matching the sender's callsign, where `*` matches
+    /// any characters and `?` a single one
+    Budlist(Vec<String>),
+    /// `t/poimqstunw`, matching the types of packets
+    Type(Vec<u8>),
+    /// `a/latN/lonW/latS/lonE`. A box crossing the antimeridian has `west > east`.
+    Area {
+        north: f64,
+        west: f64,
+        south: f64,
+        east: f64,
+    },
+    /// `m/dist`, around the client's own position
+    MyRange(f64),
+    /// `f/call/dist`, around the last position of another station
+    FriendRange { callsign: String, km: f64 },
+}
+
+impl FilterTerm {
+    pub fn parse(s: &str) -> Option<Self> {
+        let mut parts = s.split('/');
+        let kind = parts.next()?;
+        let args: Vec<&str> = parts.collect();
+        let number = |i: usize| args.get(i)?.parse::<f64>().ok().filter(|n| n.is_finite());
+        let list = || {
+            Some(args.iter().map(|a| a.to_ascii_uppercase()).collect())
+                .filter(|l: &Vec<String>| !l.is_empty() && l.iter().all(|a| !a.is_empty()))
+        };
+
+        let term = match (kind, args.len()) {
+            ("r", 3) => Self::Range {
+                latitude: number(0)?,
+                longitude: number(1)?,
+                km: number(2)?,
+            },
+            ("p", _) => Self::Prefix(list()?),
+            ("b", _) => Self::Budlist(list()?),
+            ("t", 1) => {
+                let types = args[0].as_bytes();
+                if types.is_empty() || !types.iter().all(|t| b"poimqstunw".contains(t)) {
+                    return None;
+                }
+                Self::Type(types.to_owned())
+            }
+            ("a", 4) => Self::Area {
+                north: number(0)?,
+                west: number(1)?,
+                south: number(2)?,
+                east: number(3)?,
+            },
+            ("m", 1) => Self::MyRange(number(0)?),
+            ("f", 2) if !args[0].is_empty() => Self::FriendRange {
+                callsign: args[0].to_ascii_uppercase(),
+                km: number(1)?,
+            },
+            _ => return None,
+        };
+
+        Some(term)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Filter {
+    pub include: Vec<FilterTerm>,
+    pub exclude: Vec<FilterTerm>,
+    my_position: Option<(Latitude, Longitude)>,
+    positions: HashMap<String, (Latitude, Longitude)>,
+}
+
+impl Filter {
+    pub fn parse(s: &str) -> Result<Self, DecodeError> {
+        let mut filter = Self::default();
+
+        for term in s.split_whitespace() {
+            let (negated, t) = match term.strip_prefix('-') {
+                Some(t) => (true, t),
+                None => (false, term),
+            };
+            let t = FilterTerm::parse(t).ok_or_else(|| {
+                let offset = term.as_ptr() as usize - s.as_ptr() as usize;
+                DecodeError::InvalidFilter(term.as_bytes().to_owned(), offset)
+            })?;
+
+            if negated {
+                filter.exclude.push(t);
+            } else {
+                filter.include.push(t);
+            }
+        }
+
+        Ok(filter)
+    }
+
+    /// Sets the client's own position, for `m/` terms.
+    pub fn set_my_position(&mut self, latitude: Latitude, longitude: Longitude) {
+        self.my_position = Some((latitude, longitude));
+    }
+
+    /// Remembers the position of the stations named by `f/` terms.
+    /// Every packet should be passed here, not just those that match.
+    pub fn observe(&mut self, packet: &AprsPacket) {
+        let coordinates = match packet.data.coordinates() {
+            Some(c) => c,
+            None => return,
+        };
+
+        let from = packet.from.to_string().to_ascii_uppercase();
+        let followed =
+            self.include.iter().chain(&self.exclude).any(
+                |t| matches!(t, FilterTerm::FriendRange { callsign, .. } if *callsign == from),
+            );
+        if followed {
+            self.positions.insert(from, coordinates);
+        }
+    }
+
+    pub fn matches(&self, packet: &AprsPacket) -> bool {
+        self.include.iter().any(|t| self.term_matches(t, packet))
+            && !self.exclude.iter().any(|t| self.term_matches(t, packet))
+    }
+
+    fn term_matches(&self, term: &FilterTerm, packet: &AprsPacket) -> bool {
+        let from = packet.from.to_string().to_ascii_uppercase();
+        let within = |latitude: f64, longitude: f64, km: f64| match packet.data.coordinates() {
+            Some((lat, lon)) => distance_km(latitude, longitude, *lat, *lon) <= km,
+            None => false,
+        };
+
+        match term {
+            FilterTerm::Range {
+                latitude,
+                longitude,
+                km,
+            } => within(*latitude, *longitude, *km),
+            FilterTerm::Prefix(prefixes) => prefixes.iter().any(|p| from.starts_with(p.as_str())),
+            FilterTerm::Budlist(calls) => calls
+                .iter()
+                .any(|c| wildcard_matches(c.as_bytes(), from.as_bytes())),
+            FilterTerm::Type(types) => types.iter().any(|t| type_matches(*t, &packet.data)),
+            FilterTerm::Area {
+                north,
+                west,
+                south,
+                east,
+            } => match packet.data.coordinates() {
+                Some((lat, lon)) => {
+                    // a box crossing the antimeridian has its west edge east of its east edge
+                    let in_lon = if west <= east {
+                        (*west..=*east).contains(&*lon)
+                    } else {
+                        *lon >= *west || *lon <= *east
+                    };
+                    (*south..=*north).contains(&*lat) && in_lon
+                }
+                None => false,
+            },
+            FilterTerm::MyRange(km) => match self.my_position {
+                Some((lat, lon)) => within(*lat, *lon, *km),
+                None => false,
+            },
+            FilterTerm::FriendRange { callsign, km } => match self.positions.get(callsign) {
+                Some((lat, lon)) => within(**lat, **lon, *km),
+                None => false,
+            },
+        }
+    }
+}
+
+fn type_matches(t: u8, data: &AprsData) -> bool {
+    match (t, data) {
+        (b'p', AprsData::Position(_))
+        | (b'p', AprsData::MicE(_))
+        | (b'p', AprsData::Nmea(_))
+        | (b'o', AprsData::Object(_))
+        | (b'i', AprsData::Item(_))
+        | (b'm', AprsData::Message(_))
+        | (b'q', AprsData::Query(_))
+        | (b's', AprsData::Status(_))
+        | (b't', AprsData::Telemetry(_))
+        | (b'u', AprsData::UserDefined(_))
+        | (b'w', AprsData::Weather(_))
+        | (b'w', AprsData::RawWeather(_)) => true,
+        (b'w', AprsData::Position(p)) => p.weather().is_some(),
+        (b'n', AprsData::Message(m)) => NwsBulletin::decode(&m.addressee, &m.text).is_some(),
+        (_, AprsData::ThirdParty(third_party)) => type_matches(t, &third_party.packet.data),
+        _ => false,
+    }
+}
+
+fn wildcard_matches(pattern: &[u8], s: &[u8]) -> bool {
+    match (pattern.split_first(), s.split_first()) {
+        (None, None) => true,
+        (Some((b'*', rest)), _) => {
+            wildcard_matches(rest, s) || (!s.is_empty() && wildcard_matches(pattern, &s[1..]))
+        }
+        (Some((b'?', rest)), Some((_, s))) => wildcard_matches(rest, s),
+        (Some((p, rest)), Some((c, s))) if p == c => wildcard_matches(rest, s),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(s: &str) -> AprsPacket {
+        AprsPacket::decode_textual(s.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let filter = Filter::parse("r/49.05/-72.03/50 p/n0/W1 -t/w").unwrap();
+        assert_eq!(
+            vec![
+                FilterTerm::Range {
+                    latitude: 49.05,
+                    longitude: -72.03,
+                    km: 50.0
+                },
+                FilterTerm::Prefix(vec!["N0".to_owned(), "W1".to_owned()])
+            ],
+            filter.include
+        );
+        assert_eq!(vec![FilterTerm::Type(b"w".to_vec())], filter.exclude);
+
+        assert_eq!(
+            Err(DecodeError::InvalidFilter(b"r/49/-72".to_vec(), 5)),
+            Filter::parse("p/N0 r/49/-72")
+        );
+        assert!(Filter::parse("t/x").is_err());
+        assert!(Filter::parse("p/").is_err());
+        assert!(Filter::parse("z/1").is_err());
+    }
+
+    #[test]
+    fn range_and_area() {
+        let filter = Filter::parse("r/49/-72/10").unwrap();
+        assert!(filter.matches(&decode("N0CALL>APRS:!4903.50N/07201.75W-")));
+        assert!(!filter.matches(&decode("N0CALL>APRS:!4803.50N/07201.75W-")));
+        assert!(!filter.matches(&decode("N0CALL>APRS:>Hi")));
+
+        let filter = Filter::parse("a/50/-73/48/-71").unwrap();
+        assert!(filter.matches(&decode("N0CALL>APRS:!4903.50N/07201.75W-")));
+        assert!(!filter.matches(&decode("N0CALL>APRS:!4903.50N/07401.75W-")));
+
+        // from 170°E to 170°W
+        let filter = Filter::parse("a/-10/170/-20/-170").unwrap();
+        assert!(filter.matches(&decode("N0CALL>APRS:!1500.00S/17500.00E-")));
+        assert!(filter.matches(&decode("N0CALL>APRS:!1500.00S/17500.00W-")));
+        assert!(!filter.matches(&decode("N0CALL>APRS:!1500.00S/16500.00E-")));
+        assert!(!filter.matches(&decode("N0CALL>APRS:!1500.00S/16500.00W-")));
+        assert!(!filter.matches(&decode("N0CALL>APRS:!0500.00S/17500.00E-")));
+    }
+
+    #[test]
+    fn callsigns() {
+        let filter = Filter::parse("p/N0 b/W1AW*/K?ABC").unwrap();
+        assert!(filter.matches(&decode("N0CALL>APRS:>Hi")));
+        assert!(filter.matches(&decode("W1AW-9>APRS:>Hi")));
+        assert!(filter.matches(&decode("K1ABC>APRS:>Hi")));
+        assert!(!filter.matches(&decode("K1ABCD>APRS:>Hi")));
+        assert!(!filter.matches(&decode("N1CALL>APRS:>Hi")));
+    }
+
+    #[test]
+    fn types() {
+        let filter = Filter::parse("t/pmn -b/N1CALL").unwrap();
+        assert!(filter.matches(&decode("N0CALL>APRS:!4903.50N/07201.75W-")));
+        assert!(filter.matches(&decode("N0CALL>APRS::N1CALL   :Hi")));
+        assert!(filter.matches(&decode(
+            "N0CALL>APRS::NWS-WARN :092010z,THUNDER_STORM,AR_ASHLEY"
+        )));
+        assert!(!filter.matches(&decode("N0CALL>APRS:>Hi")));
+        assert!(!filter.matches(&decode("N1CALL>APRS:!4903.50N/07201.75W-")));
+
+        let filter = Filter::parse("t/w").unwrap();
+        assert!(filter.matches(&decode("N0CALL>APRS:!4903.50N/07201.75W_220/004g005t077")));
+        assert!(!filter.matches(&decode("N0CALL>APRS:!4903.50N/07201.75W-")));
+    }
+
+    #[test]
+    fn relative_ranges() {
+        let mut filter = Filter::parse("m/10 f/N1CALL-9/10").unwrap();
+        let packet = decode("N0CALL>APRS:!4903.50N/07201.75W-");
+        assert!(!filter.matches(&packet));
+
+        filter.set_my_position(Latitude::new(49.0).unwrap(), Longitude::new(-72.0).unwrap());
+        assert!(filter.matches(&packet));
+
+        let packet = decode("N0CALL>APRS:!2903.50N/07201.75W-");
+        assert!(!filter.matches(&packet));
+        filter.observe(&decode("N1CALL-9>APRS:!2903.00N/07201.00W>"));
+        assert!(filter.matches(&packet));
+    }
+}
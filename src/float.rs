@@ -0,0 +1,40 @@
+//! Float math for `no_std` builds, where `f64` has no `sqrt`, `sin` etc.
+//! The methods forward to libm. With `std` this module is empty and the
+//! inherent methods are used, as they are in tests, which link std.
+
+#[cfg(not(any(feature = "std", test)))]
+pub trait F64Ext {
+    fn fract(self) -> f64;
+    fn ln(self) -> f64;
+    fn powf(self, n: f64) -> f64;
+    fn powi(self, n: i32) -> f64;
+    fn round(self) -> f64;
+    fn sqrt(self) -> f64;
+}
+
+#[cfg(not(any(feature = "std", test)))]
+impl F64Ext for f64 {
+    fn fract(self) -> f64 {
+        self - libm::trunc(self)
+    }
+
+    fn ln(self) -> f64 {
+        libm::log(self)
+    }
+
+    fn powf(self, n: f64) -> f64 {
+        libm::pow(self, n)
+    }
+
+    fn powi(self, n: i32) -> f64 {
+        libm::pow(self, f64::from(n))
+    }
+
+    fn round(self) -> f64 {
+        libm::round(self)
+    }
+
+    fn sqrt(self) -> f64 {
+        libm::sqrt(self)
+    }
+}
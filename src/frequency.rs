@@ -0,0 +1,242 @@
+//! The standard frequency format used at the start of comments, e.g.
+//! `146.940MHz T100 -060 R25m`, which lets radios QSY to a repeater or
+//! net frequency advertised over APRS.
+//!
+//! After the frequency come up to three optional fields, in this order:
+//! a tone (`Tnnn` encode tone, `Cnnn` tone squelch, `Dnnn` DCS, `1750` tone burst
+//! or `Toff`), an offset in units of 10 kHz (`+060`) and a range (`R25m` in miles,
+//! `R40k` in kilometers).
+
+use bytes::parse_digits;
+use io::Write;
+use EncodeError;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tone {
+    /// CTCSS tone transmitted, in whole Hz
+    Encode(u16),
+    /// CTCSS tone transmitted and required to open squelch, in whole Hz
+    Squelch(u16),
+    /// DCS code
+    Dcs(u16),
+    /// 1750 Hz tone burst
+    Burst,
+    /// No tone
+    Off,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrequencyRange {
+    Miles(u8),
+    Kilometers(u8),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FrequencyInfo {
+    /// Frequency in kHz
+    pub frequency_khz: u32,
+    pub tone: Option<Tone>,
+    /// Transmit offset in kHz, a multiple of 10
+    pub offset_khz: Option<i32>,
+    pub range: Option<FrequencyRange>,
+}
+
+impl FrequencyInfo {
+    /// Decodes the frequency fields at the start of `b`.
+    /// Returns the decoded info and the rest of `b`, without the separating space.
+    pub fn decode(b: &[u8]) -> Option<(Self, &[u8])> {
+        let freq = b.get(0..10)?;
+        if freq[3] != b'.' || &freq[7..] != b"MHz" {
+            return None;
+        }
+        let mhz: u32 = parse_digits(&freq[0..3])?;
+        let khz: u32 = parse_digits(&freq[4..7])?;
+
+        let mut info = Self {
+            frequency_khz: mhz * 1000 + khz,
+            tone: None,
+            offset_khz: None,
+            range: None,
+        };
+
+        let mut rest = &b[10..];
+        let mut field = 0;
+        while let Some(token) = rest.get(1..5).filter(|_| rest[0] == b' ') {
+            // fields must come in order, each at most once
+            match (
+                field,
+                decode_tone(token),
+                decode_offset(token),
+                decode_range(token),
+            ) {
+                (0, Some(tone), _, _) => {
+                    info.tone = Some(tone);
+                    field = 1;
+                }
+                (0..=1, _, Some(offset), _) => {
+                    info.offset_khz = Some(offset);
+                    field = 2;
+                }
+                (0..=2, _, _, Some(range)) => {
+                    info.range = Some(range);
+                    field = 3;
+                }
+                _ => break,
+            }
+
+            rest = &rest[5..];
+        }
+
+        let rest = rest.strip_prefix(b" ").unwrap_or(rest);
+        Some((info, rest))
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        if self.frequency_khz > 999_999 {
+            return Err(EncodeError::InvalidData);
+        }
+        write!(
+            buf,
+            "{:03}.{:03}MHz",
+            self.frequency_khz / 1000,
+            self.frequency_khz % 1000
+        )?;
+
+        match self.tone {
+            Some(Tone::Encode(t)) if t <= 999 => write!(buf, " T{:03}", t)?,
+            Some(Tone::Squelch(t)) if t <= 999 => write!(buf, " C{:03}", t)?,
+            Some(Tone::Dcs(t)) if t <= 999 => write!(buf, " D{:03}", t)?,
+            Some(Tone::Burst) => write!(buf, " 1750")?,
+            Some(Tone::Off) => write!(buf, " Toff")?,
+            None => {}
+            Some(_) => return Err(EncodeError::InvalidData),
+        }
+
+        match self.offset_khz {
+            Some(o) if o % 10 == 0 && o.abs() <= 9990 => {
+                let sign = if o < 0 { '-' } else { '+' };
+                write!(buf, " {}{:03}", sign, o.abs() / 10)?
+            }
+            None => {}
+            Some(_) => return Err(EncodeError::InvalidData),
+        }
+
+        match self.range {
+            Some(FrequencyRange::Miles(r)) if r <= 99 => write!(buf, " R{:02}m", r)?,
+            Some(FrequencyRange::Kilometers(r)) if r <= 99 => write!(buf, " R{:02}k", r)?,
+            None => {}
+            Some(_) => return Err(EncodeError::InvalidData),
+        }
+
+        Ok(())
+    }
+}
+
+fn decode_tone(b: &[u8]) -> Option<Tone> {
+    match b {
+        b"1750" => return Some(Tone::Burst),
+        b"Toff" | b"toff" => return Some(Tone::Off),
+        _ => {}
+    }
+
+    let value = parse_digits(&b[1..])?;
+    match b[0] {
+        b'T' | b't' => Some(Tone::Encode(value)),
+        b'C' | b'c' => Some(Tone::Squelch(value)),
+        b'D' | b'd' => Some(Tone::Dcs(value)),
+        _ => None,
+    }
+}
+
+fn decode_offset(b: &[u8]) -> Option<i32> {
+    let value: i32 = parse_digits(&b[1..])?;
+    match b[0] {
+        b'+' => Some(value * 10),
+        b'-' => Some(-value * 10),
+        _ => None,
+    }
+}
+
+fn decode_range(b: &[u8]) -> Option<FrequencyRange> {
+    if b[0] != b'R' {
+        return None;
+    }
+
+    let value = parse_digits(&b[1..3])?;
+    match b[3] {
+        b'm' => Some(FrequencyRange::Miles(value)),
+        b'k' => Some(FrequencyRange::Kilometers(value)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_full() {
+        let (info, rest) = FrequencyInfo::decode(b"146.940MHz T100 -060 R25m Net 8pm").unwrap();
+
+        assert_eq!(
+            FrequencyInfo {
+                frequency_khz: 146_940,
+                tone: Some(Tone::Encode(100)),
+                offset_khz: Some(-600),
+                range: Some(FrequencyRange::Miles(25)),
+            },
+            info
+        );
+        assert_eq!(b"Net 8pm", rest);
+    }
+
+    #[test]
+    fn decode_partial() {
+        let (info, rest) = FrequencyInfo::decode(b"438.800MHz +760 R40k").unwrap();
+        assert_eq!(None, info.tone);
+        assert_eq!(Some(7600), info.offset_khz);
+        assert_eq!(Some(FrequencyRange::Kilometers(40)), info.range);
+        assert!(rest.is_empty());
+
+        let (info, rest) = FrequencyInfo::decode(b"145.500MHz Toff Simplex").unwrap();
+        assert_eq!(Some(Tone::Off), info.tone);
+        assert_eq!(None, info.offset_khz);
+        assert_eq!(b"Simplex", rest);
+
+        // out of order fields are left in the comment
+        let (info, rest) = FrequencyInfo::decode(b"145.500MHz +060 T100").unwrap();
+        assert_eq!(None, info.tone);
+        assert_eq!(Some(600), info.offset_khz);
+        assert_eq!(b"T100", rest);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(None, FrequencyInfo::decode(b"146.94MHz"));
+        assert_eq!(None, FrequencyInfo::decode(b"146.940 MHz"));
+        assert_eq!(None, FrequencyInfo::decode(b"14+.940MHz"));
+    }
+
+    #[test]
+    fn encode() {
+        let original = &b"146.940MHz C100 -060 R25m"[..];
+        let mut buf = vec![];
+        FrequencyInfo::decode(original)
+            .unwrap()
+            .0
+            .encode(&mut buf)
+            .unwrap();
+        assert_eq!(original, buf);
+
+        let mut buf = vec![];
+        FrequencyInfo {
+            frequency_khz: 50_000,
+            tone: Some(Tone::Burst),
+            offset_khz: None,
+            range: None,
+        }
+        .encode(&mut buf)
+        .unwrap();
+        assert_eq!(&b"050.000MHz 1750"[..], buf);
+    }
+}
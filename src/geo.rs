@@ -0,0 +1,104 @@
+//! Conversions to and from geo-types, behind the `geo-types` feature.
+//! Points have the longitude as `x` and the latitude as `y`.
+
+use std::convert::TryFrom;
+
+use geo_types::{Coord, Point};
+
+use AprsItem;
+use AprsMicE;
+use AprsNmeaPosition;
+use AprsObject;
+use AprsPosition;
+use AprsPositionBuilder;
+use EncodeError;
+use Latitude;
+use Longitude;
+
+fn coord(latitude: Latitude, longitude: Longitude) -> Coord<f64> {
+    Coord {
+        x: *longitude,
+        y: *latitude,
+    }
+}
+
+macro_rules! from_position {
+    ($ty:ty, $p:ident => $latitude:expr, $longitude:expr) => {
+        impl From<&$ty> for Coord<f64> {
+            fn from($p: &$ty) -> Self {
+                coord($latitude, $longitude)
+            }
+        }
+
+        impl From<&$ty> for Point<f64> {
+            fn from(p: &$ty) -> Self {
+                Point(Coord::from(p))
+            }
+        }
+    };
+}
+
+from_position!(AprsPosition, p => p.latitude, p.longitude);
+from_position!(AprsMicE, p => p.latitude, p.longitude);
+from_position!(AprsNmeaPosition, p => p.latitude, p.longitude);
+from_position!(AprsObject, p => p.latitude(), p.longitude());
+from_position!(AprsItem, p => p.latitude(), p.longitude());
+
+impl TryFrom<Coord<f64>> for AprsPositionBuilder {
+    type Error = EncodeError;
+
+    fn try_from(c: Coord<f64>) -> Result<Self, Self::Error> {
+        let latitude = Latitude::new(c.y).ok_or(EncodeError::InvalidLatitude(c.y))?;
+        let longitude = Longitude::new(c.x).ok_or(EncodeError::InvalidLongitude(c.x))?;
+
+        Ok(Self::new(latitude, longitude))
+    }
+}
+
+impl TryFrom<Point<f64>> for AprsPositionBuilder {
+    type Error = EncodeError;
+
+    fn try_from(p: Point<f64>) -> Result<Self, Self::Error> {
+        Self::try_from(p.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AprsData;
+    use AprsPacket;
+
+    #[test]
+    fn from_position() {
+        let packet = AprsPacket::decode_textual(br"N0CALL>APRS:!4903.50N/07201.75W-").unwrap();
+        let position = match packet.data {
+            AprsData::Position(p) => p,
+            _ => panic!(),
+        };
+
+        let point = Point::from(&position);
+        assert_relative_eq!(-72.02916666666667, point.x());
+        assert_relative_eq!(49.05833333333333, point.y());
+        assert_eq!(point.0, Coord::from(&position));
+    }
+
+    #[test]
+    fn to_position_builder() {
+        let position = AprsPositionBuilder::try_from(Point::new(-72.0, 49.0))
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(Latitude::new(49.0).unwrap(), position.latitude);
+        assert_eq!(Longitude::new(-72.0).unwrap(), position.longitude);
+
+        assert!(matches!(
+            AprsPositionBuilder::try_from(Coord { x: 0.0, y: 91.0 }),
+            Err(EncodeError::InvalidLatitude(_))
+        ));
+        assert!(matches!(
+            AprsPositionBuilder::try_from(Coord { x: 181.0, y: 0.0 }),
+            Err(EncodeError::InvalidLongitude(_))
+        ));
+    }
+}
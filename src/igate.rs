@@ -0,0 +1,266 @@
+//! The gating decisions of an IGate, which connects a local RF network to APRS-IS.
+//!
+//! Everything heard on RF is gated to APRS-IS, except for packets that came
+//! from there in the first place or that ask not to be (`TCPIP`, `TCPXX`,
+//! `NOGATE`, `RFONLY`), and copies of packets already gated.
+//!
+//! Gating from APRS-IS to RF is much more selective, to keep the channel clear:
+//! only messages to stations recently heard nearby are transmitted, as
+//! third-party packets, and only if the sender isn't nearby itself. After
+//! gating a message, the sender's next position is gated too, so that the
+//! recipient can see where it came from.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use AprsData;
+use AprsPacket;
+use AprsThirdParty;
+use Callsign;
+use DedupeCache;
+use QConstruct;
+use Reception;
+use Via;
+
+#[derive(Debug, Clone)]
+pub struct IGate {
+    callsign: Callsign,
+    tocall: Callsign,
+    rf_path: Vec<Via>,
+    max_hops: usize,
+    window: Duration,
+    heard: HashMap<String, SystemTime>,
+    courtesy: HashMap<String, SystemTime>,
+    dedupe: DedupeCache,
+}
+
+impl IGate {
+    /// An IGate running as `callsign`, which counts stations heard directly
+    /// in the last 30 minutes as local and transmits gated packets without a path.
+    pub fn new(callsign: Callsign) -> Self {
+        Self {
+            callsign,
+            tocall: Callsign::new_no_ssid("APRS"),
+            rf_path: vec![],
+            max_hops: 0,
+            window: Duration::from_secs(30 * 60),
+            heard: HashMap::new(),
+            courtesy: HashMap::new(),
+            dedupe: DedupeCache::new(),
+        }
+    }
+
+    /// Sets the destination of packets transmitted on RF,
+    /// usually the tocall of the IGate software.
+    pub fn with_tocall(mut self, tocall: Callsign) -> Self {
+        self.tocall = tocall;
+        self
+    }
+
+    /// Sets the path of packets transmitted on RF, e.g. `WIDE1-1`.
+    pub fn with_rf_path(mut self, path: Vec<Via>) -> Self {
+        self.rf_path = path;
+        self
+    }
+
+    /// Also counts stations heard through up to `max_hops` digipeaters as local.
+    pub fn with_max_hops(mut self, max_hops: usize) -> Self {
+        self.max_hops = max_hops;
+        self
+    }
+
+    /// Sets for how long stations count as local after being heard,
+    /// and for how long a courtesy position is waited for.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Handles a packet heard on RF, returning the packet to send to APRS-IS, if any.
+    /// The packet gets a `qAR` q-construct with the IGate's callsign.
+    pub fn rf_to_is(&mut self, packet: &AprsPacket, now: SystemTime) -> Option<AprsPacket> {
+        self.expire(now);
+
+        let local = match packet.reception() {
+            Reception::Direct => true,
+            Reception::Digipeated(n) => n <= self.max_hops,
+            Reception::Internet => false,
+        };
+        if local {
+            self.heard.insert(key(&packet.from), now);
+        }
+
+        let has_q_construct = packet.via.iter().any(|v| matches!(v, Via::QConstruct(_)));
+        if has_q_construct || !packet.may_gate_to_is() || self.dedupe.is_duplicate(packet, now) {
+            return None;
+        }
+
+        let mut gated = packet.clone();
+        gated.via.push(Via::QConstruct(QConstruct::AR));
+        gated.via.push(Via::Callsign(self.callsign.clone(), false));
+
+        Some(gated)
+    }
+
+    /// Handles a packet received from APRS-IS, returning the third-party packet
+    /// to transmit on RF, if any.
+    pub fn is_to_rf(&mut self, packet: &AprsPacket, now: SystemTime) -> Option<AprsPacket> {
+        self.expire(now);
+
+        if !packet.may_gate_to_rf() {
+            return None;
+        }
+
+        let from = key(&packet.from);
+        let gate = match &packet.data {
+            AprsData::Message(m) => {
+                let addressee = String::from_utf8_lossy(&m.addressee);
+                let addressee = addressee.trim_end().to_ascii_uppercase();
+
+                let gate = self.heard.contains_key(&addressee) && !self.heard.contains_key(&from);
+                if gate {
+                    self.courtesy.insert(from, now);
+                }
+                gate
+            }
+            data if data.coordinates().is_some() => self.courtesy.remove(&from).is_some(),
+            _ => false,
+        };
+        if !gate {
+            return None;
+        }
+
+        AprsThirdParty::gate_to_rf(
+            packet,
+            &self.callsign,
+            self.tocall.clone(),
+            self.rf_path.clone(),
+        )
+    }
+
+    /// Whether `callsign` has been heard nearby within the window.
+    pub fn is_local(&self, callsign: &Callsign, now: SystemTime) -> bool {
+        self.heard
+            .get(&key(callsign))
+            .map_or(false, |heard| elapsed(now, *heard) < self.window)
+    }
+
+    fn expire(&mut self, now: SystemTime) {
+        let window = self.window;
+        let fresh = |_: &String, t: &mut SystemTime| elapsed(now, *t) < window;
+        self.heard.retain(fresh);
+        self.courtesy.retain(fresh);
+    }
+}
+
+// A clock that went backwards counts as no time having passed
+fn elapsed(now: SystemTime, then: SystemTime) -> Duration {
+    now.duration_since(then).unwrap_or_default()
+}
+
+fn key(c: &Callsign) -> String {
+    c.to_string().to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(s: &str) -> AprsPacket {
+        AprsPacket::decode_textual(s.as_bytes()).unwrap()
+    }
+
+    fn encode(packet: &AprsPacket) -> String {
+        let mut buf = vec![];
+        packet.encode_textual(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn igate() -> IGate {
+        IGate::new(Callsign::new_with_ssid("N0CALL", "10"))
+    }
+
+    #[test]
+    fn rf_to_is() {
+        let now = SystemTime::now();
+        let mut igate = igate();
+
+        let gated = igate
+            .rf_to_is(&decode("N1CALL>APRS,N2CALL*,WIDE2-1:>Hello"), now)
+            .unwrap();
+        assert_eq!(
+            "N1CALL>APRS,N2CALL*,WIDE2-1,qAR,N0CALL-10:>Hello",
+            encode(&gated)
+        );
+        assert_eq!(
+            None,
+            igate.rf_to_is(&decode("N1CALL>APRS,N2CALL*,N3CALL*,WIDE2:>Hello"), now)
+        );
+
+        for packet in &[
+            "N1CALL>APRS,NOGATE:>Hello",
+            "N1CALL>APRS,RFONLY:>Hello",
+            "N1CALL>APRS,TCPIP*:>Hello",
+            "N2CALL>APRS:}N1CALL>APRS,TCPIP,N2CALL*:>Hello",
+            "N1CALL>APRS,qAR,N2CALL:>Hello",
+        ] {
+            assert_eq!(None, igate.rf_to_is(&decode(packet), now), "{}", packet);
+        }
+    }
+
+    #[test]
+    fn local_stations() {
+        let now = SystemTime::now();
+        let mut igate = igate();
+        let n1call = Callsign::new_no_ssid("N1CALL");
+
+        igate.rf_to_is(&decode("N1CALL>APRS,N2CALL*:>Hello"), now);
+        assert!(!igate.is_local(&n1call, now));
+
+        igate.rf_to_is(&decode("N1CALL>APRS:>Hello"), now);
+        assert!(igate.is_local(&n1call, now));
+        assert!(!igate.is_local(&n1call, now + Duration::from_secs(30 * 60)));
+
+        let mut igate = igate.with_max_hops(1);
+        igate.rf_to_is(&decode("N3CALL>APRS,N2CALL*:>Hello"), now);
+        assert!(igate.is_local(&Callsign::new_no_ssid("N3CALL"), now));
+    }
+
+    #[test]
+    fn is_to_rf() {
+        let now = SystemTime::now();
+        let mut igate = igate().with_rf_path(vec![Via::Callsign(
+            Callsign::new_with_ssid("WIDE1", "1"),
+            false,
+        )]);
+        igate.rf_to_is(&decode("N1CALL>APRS:>Hello"), now);
+
+        let position = decode("N3CALL>APRS,TCPIP*,qAC,T2TEST:!4903.50N/07201.75W-");
+        assert_eq!(None, igate.is_to_rf(&position, now));
+
+        let message = decode("N3CALL>APRS,TCPIP*,qAC,T2TEST::N1CALL   :Hi{1");
+        let gated = igate.is_to_rf(&message, now).unwrap();
+        assert_eq!(
+            "N0CALL-10>APRS,WIDE1-1:}N3CALL>APRS,TCPIP,N0CALL-10*::N1CALL   :Hi{1",
+            encode(&gated)
+        );
+
+        // courtesy position, once
+        assert!(igate.is_to_rf(&position, now).is_some());
+        assert_eq!(None, igate.is_to_rf(&position, now));
+
+        for packet in &[
+            // not local
+            "N3CALL>APRS,TCPIP*,qAC,T2TEST::N4CALL   :Hi",
+            // not verified
+            "N3CALL>APRS,TCPXX*,qAX,T2TEST::N1CALL   :Hi",
+            "N3CALL>APRS,TCPIP*,qAC,T2TEST:>Hello",
+        ] {
+            assert_eq!(None, igate.is_to_rf(&decode(packet), now), "{}", packet);
+        }
+
+        // the sender is nearby, so the recipient can hear it directly
+        igate.rf_to_is(&decode("N3CALL>APRS:>Hello"), now);
+        assert_eq!(None, igate.is_to_rf(&message, now));
+    }
+}
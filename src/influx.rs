@@ -0,0 +1,238 @@
+//! Conversion of telemetry and weather observations into [InfluxDB line protocol],
+//! for storage in a time-series database.
+//!
+//! [InfluxDB line protocol]: https://docs.influxdata.com/influxdb/v2/reference/syntax/line-protocol/
+
+use std::io::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use Callsign;
+use EncodeError;
+use WeatherData;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    Float(f64),
+    Integer(i64),
+    Boolean(bool),
+    String(String),
+}
+
+/// A single line of line protocol: a measurement name, tags, fields and an optional timestamp.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    pub name: String,
+    pub tags: Vec<(String, String)>,
+    pub fields: Vec<(String, FieldValue)>,
+    pub timestamp: Option<SystemTime>,
+}
+
+impl Measurement {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            tags: vec![],
+            fields: vec![],
+            timestamp: None,
+        }
+    }
+
+    pub fn tag(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.tags.push((key.into(), value.into()));
+        self
+    }
+
+    pub fn field(mut self, key: impl Into<String>, value: FieldValue) -> Self {
+        self.fields.push((key.into(), value));
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: SystemTime) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// A `telemetry` measurement tagged with the station,
+    /// with one float field per named channel value.
+    pub fn telemetry<K: Into<String>>(
+        station: &Callsign,
+        values: impl IntoIterator<Item = (K, f64)>,
+    ) -> Self {
+        values.into_iter().fold(
+            Self::new("telemetry").tag("station", station.to_string()),
+            |m, (k, v)| m.field(k, FieldValue::Float(v)),
+        )
+    }
+
+    /// A `weather` measurement tagged with the station.
+    /// Values are kept in the units used by [`WeatherData`], and missing values are left out.
+    pub fn weather(station: &Callsign, weather: &WeatherData) -> Self {
+        let fields = [
+            ("wind_direction", weather.wind_direction.map(i64::from)),
+            ("wind_speed", weather.wind_speed.map(i64::from)),
+            ("wind_gust", weather.wind_gust.map(i64::from)),
+            ("temperature", weather.temperature.map(i64::from)),
+            ("rain_last_hour", weather.rain_last_hour.map(i64::from)),
+            (
+                "rain_last_24_hours",
+                weather.rain_last_24_hours.map(i64::from),
+            ),
+            (
+                "rain_since_midnight",
+                weather.rain_since_midnight.map(i64::from),
+            ),
+            ("humidity", weather.humidity.map(i64::from)),
+            (
+                "barometric_pressure",
+                weather.barometric_pressure.map(i64::from),
+            ),
+        ];
+
+        fields.iter().fold(
+            Self::new("weather").tag("station", station.to_string()),
+            |m, (k, v)| match v {
+                Some(v) => m.field(*k, FieldValue::Integer(*v)),
+                None => m,
+            },
+        )
+    }
+
+    /// Writes the measurement as one line, without a trailing newline.
+    /// Float fields that are NaN or infinite can't be represented, and are left out.
+    /// Line protocol requires at least one field.
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        let fields: Vec<_> = self
+            .fields
+            .iter()
+            .filter(|(_, v)| match v {
+                FieldValue::Float(f) => f.is_finite(),
+                _ => true,
+            })
+            .collect();
+        if fields.is_empty() {
+            return Err(EncodeError::EmptyMeasurement);
+        }
+
+        write_escaped(buf, &self.name, b", ")?;
+        for (k, v) in &self.tags {
+            buf.write_all(b",")?;
+            write_escaped(buf, k, b",= ")?;
+            buf.write_all(b"=")?;
+            write_escaped(buf, v, b",= ")?;
+        }
+
+        for (i, (k, v)) in fields.into_iter().enumerate() {
+            buf.write_all(if i == 0 { b" " } else { b"," })?;
+            write_escaped(buf, k, b",= ")?;
+            buf.write_all(b"=")?;
+
+            match v {
+                FieldValue::Float(f) => write!(buf, "{}", f)?,
+                FieldValue::Integer(i) => write!(buf, "{}i", i)?,
+                FieldValue::Boolean(b) => write!(buf, "{}", b)?,
+                FieldValue::String(s) => {
+                    buf.write_all(b"\"")?;
+                    write_escaped(buf, s, b"\"\\")?;
+                    buf.write_all(b"\"")?;
+                }
+            }
+        }
+
+        if let Some(t) = self.timestamp {
+            let nanos = t
+                .duration_since(UNIX_EPOCH)
+                .map_err(|_| EncodeError::InvalidData)?
+                .as_nanos();
+            write!(buf, " {}", nanos)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_escaped<W: Write>(buf: &mut W, s: &str, special: &[u8]) -> Result<(), EncodeError> {
+    for &b in s.as_bytes() {
+        if special.contains(&b) {
+            buf.write_all(b"\\")?;
+        }
+        buf.write_all(&[b])?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn encode(m: &Measurement) -> String {
+        let mut buf = vec![];
+        m.encode(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn telemetry() {
+        let m = Measurement::telemetry(
+            &Callsign::new_with_ssid("N0CALL", "11"),
+            vec![("battery voltage", 13.5), ("temp", -2.0)],
+        )
+        .timestamp(UNIX_EPOCH + Duration::from_secs(1_600_000_000));
+
+        assert_eq!(
+            r"telemetry,station=N0CALL-11 battery\ voltage=13.5,temp=-2 1600000000000000000",
+            encode(&m)
+        );
+    }
+
+    #[test]
+    fn weather() {
+        let wx = WeatherData {
+            temperature: Some(77),
+            humidity: Some(50),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "weather,station=N0CALL temperature=77i,humidity=50i",
+            encode(&Measurement::weather(&Callsign::new_no_ssid("N0CALL"), &wx))
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        let m = Measurement::new("a b,c")
+            .tag("k=1", "v 1")
+            .field("s", FieldValue::String(r#"say "hi" \o/"#.to_owned()))
+            .field("ok", FieldValue::Boolean(true));
+
+        assert_eq!(
+            r#"a\ b\,c,k\=1=v\ 1 s="say \"hi\" \\o/",ok=true"#,
+            encode(&m)
+        );
+    }
+
+    #[test]
+    fn empty() {
+        assert!(matches!(
+            Measurement::new("empty").encode(&mut vec![]),
+            Err(EncodeError::EmptyMeasurement)
+        ));
+    }
+
+    #[test]
+    fn non_finite() {
+        let station = Callsign::new_no_ssid("N0CALL");
+        let m = Measurement::telemetry(
+            &station,
+            vec![("a", f64::NAN), ("b", 1.5), ("c", f64::INFINITY)],
+        );
+        assert_eq!("telemetry,station=N0CALL b=1.5", encode(&m));
+
+        assert!(matches!(
+            Measurement::telemetry(&station, vec![("a", f64::NEG_INFINITY)]).encode(&mut vec![]),
+            Err(EncodeError::EmptyMeasurement)
+        ));
+    }
+}
@@ -0,0 +1,227 @@
+//! An Item Report is a simpler Object Report, for things that don't change often.
+//! It starts with the ')' APRS Data Type Identifier, followed by a name of three to
+//! nine characters, a live (`!`) or killed (`_`) flag and a position in the same
+//! formats as position reports. Items have no timestamp.
+//!
+//! Example:
+//! - ")AID #2!4903.50N/07201.75WA"
+
+use alloc::borrow::Cow;
+
+use io::Write;
+use object::{is_valid_name, NAME_LEN};
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsPosition;
+use Callsign;
+use DataExtension;
+use DecodeError;
+use EncodeError;
+use Latitude;
+use Longitude;
+
+const MIN_NAME_LEN: usize = 3;
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AprsItem {
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
+    pub name: Vec<u8>,
+    /// `false` if the item has been killed
+    pub live: bool,
+    /// The position, symbol, data extension and comment of the item.
+    /// Its timestamp is always `None`.
+    pub position: AprsPosition,
+}
+
+impl AprsItem {
+    /// Decodes the information field after the `)`.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        let err = || DecodeError::InvalidItem(b.to_owned(), 0);
+
+        // the name can't contain either flag, so the first one ends it
+        let name_len = b
+            .iter()
+            .take(NAME_LEN + 1)
+            .position(|&c| c == b'!' || c == b'_')
+            .filter(|&len| len >= MIN_NAME_LEN)
+            .ok_or_else(err)?;
+        let live = b[name_len] == b'!';
+        let position = AprsPosition::decode_position(b'!', &b[name_len + 1..], to, None, false)
+            .map_err(|e| e.offset_by(name_len + 1))?;
+
+        Ok(Self {
+            name: b[..name_len].to_owned(),
+            live,
+            position,
+        })
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.live
+    }
+
+    /// Returns the report that kills this item.
+    /// It keeps the name, position and symbol, so that receivers can match it
+    /// to the item they are showing.
+    pub fn killed(&self) -> Self {
+        Self {
+            live: false,
+            ..self.clone()
+        }
+    }
+
+    pub fn to(&self) -> &Callsign {
+        &self.position.to
+    }
+
+    pub fn latitude(&self) -> Latitude {
+        self.position.latitude
+    }
+
+    pub fn longitude(&self) -> Longitude {
+        self.position.longitude
+    }
+
+    pub fn comment(&self) -> &[u8] {
+        &self.position.comment
+    }
+
+    pub fn comment_display(&self) -> Cow<'_, str> {
+        self.position.comment_display()
+    }
+
+    /// Decodes the data extension at the start of the comment, e.g. course and speed.
+    pub fn extension(&self) -> Option<DataExtension> {
+        self.position.extension()
+    }
+
+    /// Sets the data extension at the start of the comment, e.g. the range of a repeater.
+    pub fn set_extension(&mut self, extension: Option<DataExtension>) -> Result<(), EncodeError> {
+        self.position.set_extension(extension)
+    }
+
+    /// Returns the text of a signpost with the `\m` symbol, e.g. a speed limit.
+    pub fn signpost(&self) -> Option<&[u8]> {
+        self.position.signpost()
+    }
+
+    pub fn set_signpost(&mut self, text: &[u8]) -> Result<(), EncodeError> {
+        self.position.set_signpost(text)
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        if !is_valid_item_name(&self.name) {
+            return Err(EncodeError::InvalidItemName(self.name.clone()));
+        }
+
+        buf.write_all(b")")?;
+        buf.write_all(&self.name)?;
+        buf.write_all(if self.live { b"!" } else { b"_" })?;
+        self.position.encode_position(buf)?;
+
+        Ok(())
+    }
+}
+
+// Names can't contain the live and killed flags, which end them
+pub(crate) fn is_valid_item_name(name: &[u8]) -> bool {
+    is_valid_name(name, MIN_NAME_LEN) && !name.iter().any(|&c| c == b'!' || c == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AprsCst;
+
+    fn default_callsign() -> Callsign {
+        Callsign::new_no_ssid("APRS")
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        let original = &b")AID #2!4903.50N/07201.75WA"[..];
+        let item = AprsItem::decode(&original[1..], default_callsign()).unwrap();
+
+        assert_eq!(b"AID #2", &item.name[..]);
+        assert!(item.live);
+        assert_relative_eq!(49.058334, *item.latitude(), epsilon = 0.00001);
+        assert_eq!('A', item.position.symbol.code());
+        assert!(item.comment().is_empty());
+
+        let mut buf = vec![];
+        item.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn killed_and_compressed() {
+        let original = &b")G/WB4APR_/5L!!<*e7>7P[Aid station"[..];
+        let item = AprsItem::decode(&original[1..], default_callsign()).unwrap();
+
+        assert_eq!(b"G/WB4APR", &item.name[..]);
+        assert!(!item.live);
+        assert!(matches!(item.position.cst, AprsCst::CompressedSome { .. }));
+        assert_eq!(b"Aid station", item.comment());
+
+        let mut buf = vec![];
+        item.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn kill() {
+        let item =
+            AprsItem::decode(&b"AID #2!4903.50N/07201.75WA"[..], default_callsign()).unwrap();
+        assert!(item.is_alive());
+
+        let killed = item.killed();
+        assert!(!killed.is_alive());
+
+        let mut buf = vec![];
+        killed.encode(&mut buf).unwrap();
+        assert_eq!(&b")AID #2_4903.50N/07201.75WA"[..], buf);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        for b in [
+            &b"AB!4903.50N/07201.75WA"[..],
+            &b"TENCHARSXX!4903.50N/07201.75WA"[..],
+            &b"NOFLAG"[..],
+        ] {
+            assert_eq!(
+                Err(DecodeError::InvalidItem(b.to_vec(), 0)),
+                AprsItem::decode(b, default_callsign())
+            );
+        }
+    }
+
+    #[test]
+    fn encode_invalid_name() {
+        let mut item =
+            AprsItem::decode(&b"AID #2!4903.50N/07201.75WA"[..], default_callsign()).unwrap();
+
+        for name in [&b"AB"[..], b"AID_2", b"TENCHARSXX"] {
+            item.name = name.to_vec();
+            assert!(matches!(
+                item.encode(&mut vec![]),
+                Err(EncodeError::InvalidItemName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn signpost() {
+        let mut item =
+            AprsItem::decode(&br"EXIT 12!4903.50N\07201.75Wm"[..], default_callsign()).unwrap();
+        assert_eq!(None, item.signpost());
+
+        item.set_signpost(b"12A").unwrap();
+        assert_eq!(Some(&b"12A"[..]), item.signpost());
+
+        let mut buf = vec![];
+        item.encode(&mut buf).unwrap();
+        assert_eq!(&br")EXIT 12!4903.50N\07201.75Wm{12A}"[..], buf);
+    }
+}
@@ -0,0 +1,320 @@
+//! A stable JSON representation of packets, for services written in other
+//! languages. Unlike the `Debug` output, the shape only changes along with
+//! [`JSON_SCHEMA_VERSION`].
+//!
+//! Every packet has these keys:
+//! - `version`: the schema version, currently `1`
+//! - `from`, `to`: callsigns, e.g. `"N0CALL-9"`
+//! - `path`: the via path as written in TNC2, e.g. `["N1CALL*", "WIDE2-1", "qAR", "N2CALL"]`
+//! - `type`: one of `position`, `mic-e`, `nmea`, `object`, `item`, `message`,
+//!   `status`, `telemetry`, `weather`, `raw-weather`, `query`, `capabilities`,
+//!   `user-defined`, `third-party`, `test`, `unknown`
+//!
+//! Depending on the type, these keys are added. Keys that don't apply are left out,
+//! and values that are missing are `null`:
+//! - positions, Mic-E, NMEA, objects and items: `latitude`, `longitude`
+//!   (degrees, north and east positive)
+//! - positions, Mic-E, objects and items: `symbol_table`, `symbol_code`, `comment`
+//! - positions with weather, and weather reports: `weather`, an object with
+//!   `wind_direction` (degrees), `wind_speed`, `wind_gust` (mph), `temperature`
+//!   (°F), `rain_last_hour`, `rain_last_24_hours`, `rain_since_midnight`
+//!   (hundredths of an inch), `humidity` (%) and `barometric_pressure` (tenths of mbar)
+//! - Mic-E: `speed` (knots), `course` (degrees, `0` if unknown)
+//! - objects and items: `name`, `alive`
+//! - messages: `addressee`, `text`, `id`
+//! - status reports: `status`
+//! - telemetry: `sequence`, `analog` (5 numbers), `digital` (8 booleans), `comment`
+//! - third-party: `packet`, the inner packet in the same representation
+//!
+//! Text is converted from UTF-8, with invalid bytes replaced by `�`.
+
+use core::fmt::{self, Display, Formatter, Write};
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use text;
+use AprsData;
+use AprsPacket;
+use Symbol;
+use Via;
+use WeatherData;
+
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    /// Keys in the order they're written.
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Looks up `key` in an object.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonValue::Null => f.write_str("null"),
+            JsonValue::Bool(b) => write!(f, "{}", b),
+            JsonValue::Number(n) if n.is_finite() => write!(f, "{}", n),
+            JsonValue::Number(_) => f.write_str("null"),
+            JsonValue::String(s) => write_string(f, s),
+            JsonValue::Array(values) => {
+                f.write_char('[')?;
+                for (i, v) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", v)?;
+                }
+                f.write_char(']')
+            }
+            JsonValue::Object(entries) => {
+                f.write_char('{')?;
+                for (i, (k, v)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, k)?;
+                    write!(f, ":{}", v)?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut Formatter<'_>, s: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+struct Entries(Vec<(String, JsonValue)>);
+
+impl Entries {
+    fn add(&mut self, key: &str, value: JsonValue) {
+        self.0.push((key.to_owned(), value));
+    }
+
+    fn text(&mut self, key: &str, value: &[u8]) {
+        self.add(key, text(value));
+    }
+
+    fn number(&mut self, key: &str, value: Option<f64>) {
+        self.add(key, value.map_or(JsonValue::Null, JsonValue::Number));
+    }
+
+    fn symbol(&mut self, symbol: Symbol) {
+        self.add(
+            "symbol_table",
+            JsonValue::String(symbol.table().to_string()),
+        );
+        self.add("symbol_code", JsonValue::String(symbol.code().to_string()));
+    }
+}
+
+fn text(b: &[u8]) -> JsonValue {
+    JsonValue::String(text::display_text(b).into_owned())
+}
+
+pub(crate) fn packet(packet: &AprsPacket) -> JsonValue {
+    let mut e = Entries(vec![]);
+    e.add("version", JsonValue::Number(f64::from(JSON_SCHEMA_VERSION)));
+    e.add("from", JsonValue::String(packet.from.to_string()));
+    e.add(
+        "to",
+        JsonValue::String(packet.data.dest_field().to_string()),
+    );
+
+    // like TNC2, only the last used entry is marked
+    let last_used = packet
+        .via
+        .iter()
+        .rposition(|v| matches!(v, Via::Callsign(_, true)));
+    let path = packet
+        .via
+        .iter()
+        .enumerate()
+        .map(|(i, v)| match v {
+            Via::Callsign(c, _) if Some(i) == last_used => JsonValue::String(format!("{}*", c)),
+            Via::Callsign(c, _) => JsonValue::String(c.to_string()),
+            Via::QConstruct(q) => JsonValue::String(q.as_textual().to_owned()),
+        })
+        .collect();
+    e.add("path", JsonValue::Array(path));
+
+    if let Some((latitude, longitude)) = packet.data.coordinates() {
+        if !matches!(packet.data, AprsData::ThirdParty(_)) {
+            e.add("latitude", JsonValue::Number(*latitude));
+            e.add("longitude", JsonValue::Number(*longitude));
+        }
+    }
+
+    let kind = match &packet.data {
+        AprsData::Position(p) => {
+            e.symbol(p.symbol);
+            e.text("comment", &p.comment);
+            if let Some(w) = p.weather() {
+                e.add("weather", weather(&w));
+            }
+            "position"
+        }
+        AprsData::MicE(m) => {
+            e.symbol(m.symbol);
+            e.text("comment", &m.comment);
+            e.add("speed", JsonValue::Number(f64::from(m.speed.knots())));
+            e.add("course", JsonValue::Number(f64::from(m.course.degrees())));
+            "mic-e"
+        }
+        AprsData::Nmea(_) => "nmea",
+        AprsData::Object(o) => {
+            e.text("name", &o.name);
+            e.add("alive", JsonValue::Bool(o.live));
+            e.symbol(o.position.symbol);
+            e.text("comment", &o.position.comment);
+            "object"
+        }
+        AprsData::Item(i) => {
+            e.text("name", &i.name);
+            e.add("alive", JsonValue::Bool(i.live));
+            e.symbol(i.position.symbol);
+            e.text("comment", &i.position.comment);
+            "item"
+        }
+        AprsData::Message(m) => {
+            e.text("addressee", &m.addressee);
+            e.text("text", &m.text);
+            e.add("id", m.id.as_deref().map_or(JsonValue::Null, text));
+            "message"
+        }
+        AprsData::Status(s) => {
+            e.text("status", s.comment());
+            "status"
+        }
+        AprsData::Telemetry(t) => {
+            e.number("sequence", t.sequence.map(f64::from));
+            e.add(
+                "analog",
+                JsonValue::Array(t.analog.iter().map(|&a| JsonValue::Number(a)).collect()),
+            );
+            e.add(
+                "digital",
+                JsonValue::Array(t.digital.iter().map(|&d| JsonValue::Bool(d)).collect()),
+            );
+            e.text("comment", &t.comment);
+            "telemetry"
+        }
+        AprsData::Weather(w) => {
+            e.add("weather", weather(&w.data));
+            "weather"
+        }
+        AprsData::RawWeather(_) => "raw-weather",
+        AprsData::Query(_) => "query",
+        AprsData::Capabilities(_) => "capabilities",
+        AprsData::UserDefined(_) => "user-defined",
+        AprsData::ThirdParty(t) => {
+            e.add("packet", self::packet(&t.packet));
+            "third-party"
+        }
+        AprsData::TestData(_) => "test",
+        AprsData::Unknown(_) => "unknown",
+    };
+    e.0.insert(4, ("type".to_owned(), JsonValue::String(kind.to_owned())));
+
+    JsonValue::Object(e.0)
+}
+
+fn weather(w: &WeatherData) -> JsonValue {
+    let mut e = Entries(vec![]);
+    e.number("wind_direction", w.wind_direction.map(f64::from));
+    e.number("wind_speed", w.wind_speed.map(f64::from));
+    e.number("wind_gust", w.wind_gust.map(f64::from));
+    e.number("temperature", w.temperature.map(f64::from));
+    e.number("rain_last_hour", w.rain_last_hour.map(f64::from));
+    e.number("rain_last_24_hours", w.rain_last_24_hours.map(f64::from));
+    e.number("rain_since_midnight", w.rain_since_midnight.map(f64::from));
+    e.number("humidity", w.humidity.map(f64::from));
+    e.number("barometric_pressure", w.barometric_pressure.map(f64::from));
+
+    JsonValue::Object(e.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(s: &str) -> String {
+        AprsPacket::decode_textual(s.as_bytes())
+            .unwrap()
+            .to_json_value()
+            .to_string()
+    }
+
+    #[test]
+    fn position() {
+        assert_eq!(
+            r#"{"version":1,"from":"N0CALL-9","to":"APRS","path":["N1CALL","WIDE2*","qAR","N2CALL"],"type":"position","latitude":49.05833333333333,"longitude":-72.02916666666667,"symbol_table":"/","symbol_code":"-","comment":"Home \"sweet\" home"}"#,
+            json(
+                r#"N0CALL-9>APRS,N1CALL*,WIDE2*,qAR,N2CALL:!4903.50N/07201.75W-Home "sweet" home"#
+            )
+        );
+    }
+
+    #[test]
+    fn weather() {
+        let value = AprsPacket::decode_textual(b"N0CALL>APRS:!4903.50N/07201.75W_220/004g005t077")
+            .unwrap()
+            .to_json_value();
+        let weather = value.get("weather").unwrap();
+        assert_eq!(Some(&JsonValue::Number(77.0)), weather.get("temperature"));
+        assert_eq!(Some(&JsonValue::Null), weather.get("humidity"));
+    }
+
+    #[test]
+    fn message() {
+        assert_eq!(
+            r#"{"version":1,"from":"N0CALL","to":"APRS","path":[],"type":"message","addressee":"N1CALL","text":"Hi\\there","id":null}"#,
+            json(r"N0CALL>APRS::N1CALL   :Hi\there")
+        );
+    }
+
+    #[test]
+    fn third_party() {
+        let value = AprsPacket::decode_textual(b"N0CALL>APRS:}N1CALL>APRS,TCPIP,N0CALL*:>Hi")
+            .unwrap()
+            .to_json_value();
+        assert_eq!(
+            Some(&JsonValue::String("third-party".to_owned())),
+            value.get("type")
+        );
+        assert_eq!(None, value.get("latitude"));
+
+        let inner = value.get("packet").unwrap();
+        assert_eq!(
+            Some(&JsonValue::String("Hi".to_owned())),
+            inner.get("status")
+        );
+    }
+}
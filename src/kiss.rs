@@ -0,0 +1,370 @@
+//! KISS framing, and a blocking client for KISS-over-TCP TNCs such as
+//! Direwolf's port 8001.
+//!
+//! Each KISS frame starts and ends with `FEND`. Its first byte holds the TNC
+//! port in the upper nibble and the command in the lower one, and the rest is
+//! an AX.25 frame with `FEND` and `FESC` bytes escaped.
+//!
+//! ```no_run
+//! use aprs_parser::kiss::KissClient;
+//!
+//! let client = KissClient::connect("localhost:8001").unwrap();
+//!
+//! for packet in client {
+//!     println!("{:?}", packet);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+use AprsPacket;
+use DecodeError;
+use EncodeError;
+
+pub const FEND: u8 = 0xc0;
+pub const FESC: u8 = 0xdb;
+pub const TFEND: u8 = 0xdc;
+pub const TFESC: u8 = 0xdd;
+
+/// The command of frames carrying data to or from the radio.
+pub const DATA_FRAME: u8 = 0x00;
+
+/// The longest frame [`KissDecoder`] accepts by default, as in Direwolf.
+pub const MAX_FRAME_LEN: usize = 2048;
+
+#[derive(Debug, thiserror::Error)]
+pub enum KissError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Decode(#[from] DecodeError),
+    #[error(transparent)]
+    Encode(#[from] EncodeError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KissFrame {
+    pub port: u8,
+    pub command: u8,
+    pub data: Vec<u8>,
+}
+
+impl KissFrame {
+    /// A data frame for `port`, which must be below 16.
+    pub fn data(port: u8, data: Vec<u8>) -> Self {
+        Self {
+            port,
+            command: DATA_FRAME,
+            data,
+        }
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> io::Result<()> {
+        buf.write_all(&[FEND, (self.port << 4) | (self.command & 0x0f)])?;
+        for &b in &self.data {
+            match b {
+                FEND => buf.write_all(&[FESC, TFEND])?,
+                FESC => buf.write_all(&[FESC, TFESC])?,
+                _ => buf.write_all(&[b])?,
+            }
+        }
+        buf.write_all(&[FEND])
+    }
+}
+
+/// Splits a stream of bytes into KISS frames.
+#[derive(Debug, Clone)]
+pub struct KissDecoder {
+    buf: Vec<u8>,
+    max_len: usize,
+    in_frame: bool,
+    escaped: bool,
+}
+
+impl KissDecoder {
+    pub fn new() -> Self {
+        Self {
+            buf: vec![],
+            max_len: MAX_FRAME_LEN,
+            in_frame: false,
+            escaped: false,
+        }
+    }
+
+    /// Sets the longest frame accepted, including the port and command byte.
+    /// Longer frames are dropped, so a missing `FEND` can't grow the buffer forever.
+    pub fn with_max_frame_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Feeds one byte, returning a frame when it completes one.
+    /// Bytes before the first `FEND`, empty frames and frames longer
+    /// than the maximum are dropped.
+    pub fn push(&mut self, b: u8) -> Option<KissFrame> {
+        if b == FEND {
+            let frame = self.take();
+            self.in_frame = true;
+            return frame;
+        }
+        if !self.in_frame {
+            return None;
+        }
+
+        if self.escaped {
+            self.escaped = false;
+            match b {
+                TFEND => self.buf.push(FEND),
+                TFESC => self.buf.push(FESC),
+                // invalid escape, so the frame is dropped
+                _ => {
+                    self.buf.clear();
+                    self.in_frame = false;
+                }
+            }
+        } else if b == FESC {
+            self.escaped = true;
+        } else {
+            self.buf.push(b);
+        }
+
+        if self.buf.len() > self.max_len {
+            self.buf.clear();
+            self.in_frame = false;
+        }
+
+        None
+    }
+
+    fn take(&mut self) -> Option<KissFrame> {
+        self.escaped = false;
+        let (&first, data) = self.buf.split_first()?;
+        let frame = KissFrame {
+            port: first >> 4,
+            command: first & 0x0f,
+            data: data.to_vec(),
+        };
+        self.buf.clear();
+
+        Some(frame)
+    }
+}
+
+impl Default for KissDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct KissClient<S> {
+    stream: S,
+    decoder: KissDecoder,
+    frames: VecDeque<KissFrame>,
+    port: u8,
+}
+
+impl KissClient<TcpStream> {
+    /// Connects to a KISS TCP port, e.g. `localhost:8001`.
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self, KissError> {
+        Ok(Self::new(TcpStream::connect(addr)?))
+    }
+}
+
+impl<S: Read + Write> KissClient<S> {
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream,
+            decoder: KissDecoder::new(),
+            frames: VecDeque::new(),
+            port: 0,
+        }
+    }
+
+    /// Sets the TNC port that packets are sent on. Defaults to 0.
+    pub fn with_port(mut self, port: u8) -> Self {
+        self.port = port & 0x0f;
+        self
+    }
+
+    pub fn send(&mut self, packet: &AprsPacket) -> Result<(), KissError> {
+        let mut data = vec![];
+        packet.encode_ax25(&mut data)?;
+
+        let mut buf = vec![];
+        KissFrame::data(self.port, data).encode(&mut buf)?;
+        self.stream.write_all(&buf)?;
+        self.stream.flush()?;
+
+        Ok(())
+    }
+
+    /// Waits for the next frame, of any command.
+    /// Returns `None` once the TNC closes the connection.
+    pub fn recv_frame(&mut self) -> Option<Result<KissFrame, KissError>> {
+        let mut buf = [0; 1024];
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Some(Ok(frame));
+            }
+
+            let n = match self.stream.read(&mut buf) {
+                Ok(0) => return None,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e.into())),
+            };
+            for &b in &buf[..n] {
+                if let Some(frame) = self.decoder.push(b) {
+                    self.frames.push_back(frame);
+                }
+            }
+        }
+    }
+
+    /// Waits for the next packet received on any port.
+    pub fn recv(&mut self) -> Option<Result<AprsPacket, KissError>> {
+        loop {
+            match self.recv_frame()? {
+                Ok(frame) if frame.command == DATA_FRAME => {
+                    return Some(AprsPacket::decode_ax25(&frame.data).map_err(KissError::from))
+                }
+                Ok(_) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl<S: Read + Write> Iterator for KissClient<S> {
+    type Item = Result<AprsPacket, KissError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    struct MockStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for MockStream {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            // one byte at a time, to split frames across reads
+            self.input.read(&mut buf[..1])
+        }
+    }
+
+    impl Write for MockStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn ax25(s: &str) -> Vec<u8> {
+        let mut buf = vec![];
+        AprsPacket::decode_textual(s.as_bytes())
+            .unwrap()
+            .encode_ax25(&mut buf)
+            .unwrap();
+        buf
+    }
+
+    #[test]
+    fn framing() {
+        let frame = KissFrame::data(1, vec![0x01, FEND, 0x02, FESC, 0x03]);
+        let mut buf = vec![];
+        frame.encode(&mut buf).unwrap();
+        assert_eq!(
+            vec![FEND, 0x10, 0x01, FESC, TFEND, 0x02, FESC, TFESC, 0x03, FEND],
+            buf
+        );
+
+        let mut decoder = KissDecoder::new();
+        let mut frames = vec![];
+        // junk before the first FEND, and back to back FENDs
+        for &b in [0x42, FEND].iter().chain(&buf) {
+            frames.extend(decoder.push(b));
+        }
+        assert_eq!(vec![frame], frames);
+    }
+
+    #[test]
+    fn invalid_escape() {
+        let mut decoder = KissDecoder::new();
+        let mut frames = vec![];
+        for &b in &[FEND, 0x00, 0x01, FESC, 0x42, 0x02, FEND, 0x00, 0x03, FEND] {
+            frames.extend(decoder.push(b));
+        }
+        assert_eq!(vec![KissFrame::data(0, vec![0x03])], frames);
+    }
+
+    #[test]
+    fn oversized_frame() {
+        let mut decoder = KissDecoder::new().with_max_frame_len(3);
+        let mut frames = vec![];
+        for &b in &[
+            FEND, 0x00, 0x01, 0x02, 0x03, 0x04, FEND, 0x00, 0x05, 0x06, FEND,
+        ] {
+            frames.extend(decoder.push(b));
+        }
+        assert_eq!(vec![KissFrame::data(0, vec![0x05, 0x06])], frames);
+        assert!(decoder.buf.is_empty());
+    }
+
+    #[test]
+    fn client() {
+        let mut input = vec![];
+        KissFrame::data(0, ax25("N0CALL>APRS,WIDE1-1:>Hello"))
+            .encode(&mut input)
+            .unwrap();
+        // a non-data frame is skipped
+        KissFrame {
+            port: 0,
+            command: 0x01,
+            data: vec![50],
+        }
+        .encode(&mut input)
+        .unwrap();
+        KissFrame::data(1, vec![0x01, 0x02])
+            .encode(&mut input)
+            .unwrap();
+        KissFrame::data(1, ax25("N1CALL>APRS:>World"))
+            .encode(&mut input)
+            .unwrap();
+
+        let mut client = KissClient::new(MockStream {
+            input: Cursor::new(input),
+            output: vec![],
+        })
+        .with_port(2);
+
+        let packet = client.next().unwrap().unwrap();
+        assert_eq!(
+            AprsPacket::decode_textual(b"N0CALL>APRS,WIDE1-1:>Hello").unwrap(),
+            packet
+        );
+        assert!(matches!(client.next(), Some(Err(KissError::Decode(_)))));
+        let packet = client.next().unwrap().unwrap();
+        assert_eq!("N1CALL", packet.from.to_string());
+        assert!(client.next().is_none());
+
+        client.send(&packet).unwrap();
+        let mut expected = vec![];
+        KissFrame::data(2, ax25("N1CALL>APRS:>World"))
+            .encode(&mut expected)
+            .unwrap();
+        assert_eq!(expected, client.stream.output);
+    }
+}
@@ -0,0 +1,335 @@
+//! An async client for KISS-over-TCP TNCs, built on tokio. It speaks the same
+//! protocol as the blocking [`KissClient`](crate::kiss::KissClient), but splits
+//! the connection into a [`KissSender`] and a [`Stream`] of packets, so both
+//! can be used from different tasks.
+//!
+//! ```rust,edition2018,no_run
+//! use aprs_parser::kiss_async;
+//! # use tokio::io::{AsyncRead, AsyncWrite};
+//!
+//! # async fn run<S: AsyncRead + AsyncWrite>(stream: S) -> Result<(), aprs_parser::kiss::KissError> {
+//! // `stream` is e.g. a `tokio::net::TcpStream` connected to localhost:8001
+//! let (_sender, mut packets) = kiss_async::split(stream);
+//!
+//! while let Some(packet) = packets.recv().await {
+//!     println!("{:?}", packet);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf, ReadHalf, WriteHalf};
+
+use kiss::{KissDecoder, KissError, KissFrame, DATA_FRAME};
+use AprsPacket;
+
+/// Splits a connected stream into its sending and receiving halves.
+pub fn split<S: AsyncRead + AsyncWrite>(stream: S) -> (KissSender<S>, KissStream<S>) {
+    let (reader, writer) = tokio::io::split(stream);
+    let sender = KissSender {
+        writer,
+        buf: vec![],
+        port: 0,
+    };
+    let stream = KissStream {
+        reader,
+        decoder: KissDecoder::new(),
+        frames: VecDeque::new(),
+    };
+
+    (sender, stream)
+}
+
+pub struct KissSender<S> {
+    writer: WriteHalf<S>,
+    buf: Vec<u8>,
+    port: u8,
+}
+
+impl<S: AsyncWrite> KissSender<S> {
+    /// Sets the TNC port that packets are sent on. Defaults to 0.
+    pub fn with_port(mut self, port: u8) -> Self {
+        self.port = port & 0x0f;
+        self
+    }
+
+    pub fn send(&mut self, packet: &AprsPacket) -> WriteFrame<'_, S> {
+        let mut data = vec![];
+        let error = packet.encode_ax25(&mut data).err().map(KissError::from);
+        self.write_frame(&KissFrame::data(self.port, data), error)
+    }
+
+    /// Sends a frame of any command, e.g. to set TNC parameters.
+    pub fn send_frame(&mut self, frame: &KissFrame) -> WriteFrame<'_, S> {
+        self.write_frame(frame, None)
+    }
+
+    fn write_frame(&mut self, frame: &KissFrame, error: Option<KissError>) -> WriteFrame<'_, S> {
+        self.buf.clear();
+        let error = error.or_else(|| frame.encode(&mut self.buf).err().map(KissError::from));
+
+        WriteFrame {
+            sender: self,
+            written: 0,
+            error,
+        }
+    }
+}
+
+/// The future returned by [`KissSender::send`] and [`KissSender::send_frame`].
+#[must_use = "futures do nothing unless polled"]
+pub struct WriteFrame<'a, S> {
+    sender: &'a mut KissSender<S>,
+    written: usize,
+    error: Option<KissError>,
+}
+
+impl<S: AsyncWrite> Future for WriteFrame<'_, S> {
+    type Output = Result<(), KissError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Some(e) = this.error.take() {
+            return Poll::Ready(Err(e));
+        }
+
+        let sender = &mut *this.sender;
+        while this.written < sender.buf.len() {
+            match Pin::new(&mut sender.writer).poll_write(cx, &sender.buf[this.written..]) {
+                Poll::Ready(Ok(0)) => {
+                    return Poll::Ready(Err(io::Error::from(io::ErrorKind::WriteZero).into()))
+                }
+                Poll::Ready(Ok(n)) => this.written += n,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e.into())),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        Pin::new(&mut sender.writer)
+            .poll_flush(cx)
+            .map_err(KissError::from)
+    }
+}
+
+pub struct KissStream<S> {
+    reader: ReadHalf<S>,
+    decoder: KissDecoder,
+    // frames completed by the last read, but not returned yet
+    frames: VecDeque<KissFrame>,
+}
+
+impl<S: AsyncRead> KissStream<S> {
+    /// Waits for the next packet received on any port.
+    /// Resolves to `None` once the TNC closes the connection.
+    pub fn recv(&mut self) -> Recv<'_, S> {
+        Recv { stream: self }
+    }
+
+    /// Polls for the next frame, of any command.
+    pub fn poll_recv_frame(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<KissFrame, KissError>>> {
+        let mut buf = [0; 1024];
+        loop {
+            if let Some(frame) = self.frames.pop_front() {
+                return Poll::Ready(Some(Ok(frame)));
+            }
+
+            let mut read_buf = ReadBuf::new(&mut buf);
+            match Pin::new(&mut self.reader).poll_read(cx, &mut read_buf) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
+                Poll::Pending => return Poll::Pending,
+            }
+
+            // nothing read means the connection was closed
+            let read = read_buf.filled();
+            if read.is_empty() {
+                return Poll::Ready(None);
+            }
+            for &b in read {
+                if let Some(frame) = self.decoder.push(b) {
+                    self.frames.push_back(frame);
+                }
+            }
+        }
+    }
+
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<AprsPacket, KissError>>> {
+        loop {
+            let frame = match self.poll_recv_frame(cx) {
+                Poll::Ready(Some(Ok(frame))) => frame,
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            if frame.command == DATA_FRAME {
+                let packet = AprsPacket::decode_ax25(&frame.data).map_err(KissError::from);
+                return Poll::Ready(Some(packet));
+            }
+        }
+    }
+}
+
+impl<S: AsyncRead> Stream for KissStream<S> {
+    type Item = Result<AprsPacket, KissError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_recv(cx)
+    }
+}
+
+/// The future returned by [`KissStream::recv`].
+#[must_use = "futures do nothing unless polled"]
+pub struct Recv<'a, S> {
+    stream: &'a mut KissStream<S>,
+}
+
+impl<S: AsyncRead> Future for Recv<'_, S> {
+    type Output = Option<Result<AprsPacket, KissError>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.get_mut().stream.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::ptr;
+    use std::task::{RawWaker, RawWakerVTable, Waker};
+
+    struct MockStream {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl AsyncRead for MockStream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            // one byte at a time, to split frames across reads
+            let this = self.get_mut();
+            let n = io::Read::read(&mut this.input, &mut buf.initialize_unfilled()[..1])?;
+            buf.advance(n);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    impl AsyncWrite for MockStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            self.get_mut().output.extend_from_slice(buf);
+            Poll::Ready(Ok(buf.len()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn split_mock(input: Vec<u8>) -> (KissSender<MockStream>, KissStream<MockStream>) {
+        split(MockStream {
+            input: Cursor::new(input),
+            output: vec![],
+        })
+    }
+
+    // The mock stream never blocks, so a single poll is enough
+    fn now<F: Future + Unpin>(mut future: F) -> F::Output {
+        fn noop(_: *const ()) {}
+        fn clone(_: *const ()) -> RawWaker {
+            RawWaker::new(ptr::null(), &VTABLE)
+        }
+        static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+        let waker = unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) };
+        match Pin::new(&mut future).poll(&mut Context::from_waker(&waker)) {
+            Poll::Ready(output) => output,
+            Poll::Pending => panic!("future is pending"),
+        }
+    }
+
+    fn ax25(s: &str) -> Vec<u8> {
+        let mut buf = vec![];
+        AprsPacket::decode_textual(s.as_bytes())
+            .unwrap()
+            .encode_ax25(&mut buf)
+            .unwrap();
+        buf
+    }
+
+    #[test]
+    fn receive() {
+        let mut input = vec![];
+        KissFrame::data(0, ax25("N0CALL>APRS,WIDE1-1:>Hello"))
+            .encode(&mut input)
+            .unwrap();
+        // a non-data frame is skipped
+        KissFrame {
+            port: 0,
+            command: 0x01,
+            data: vec![50],
+        }
+        .encode(&mut input)
+        .unwrap();
+        KissFrame::data(1, vec![0x01, 0x02])
+            .encode(&mut input)
+            .unwrap();
+        KissFrame::data(1, ax25("N1CALL>APRS:>World"))
+            .encode(&mut input)
+            .unwrap();
+        let (_, mut packets) = split_mock(input);
+
+        let packet = now(packets.recv()).unwrap().unwrap();
+        assert_eq!(
+            AprsPacket::decode_textual(b"N0CALL>APRS,WIDE1-1:>Hello").unwrap(),
+            packet
+        );
+        assert!(matches!(
+            now(packets.recv()),
+            Some(Err(KissError::Decode(_)))
+        ));
+        let packet = now(packets.recv()).unwrap().unwrap();
+        assert_eq!("N1CALL", packet.from.to_string());
+        assert!(now(packets.recv()).is_none());
+    }
+
+    #[test]
+    fn send() {
+        let (sender, packets) = split_mock(vec![]);
+        let mut sender = sender.with_port(2);
+
+        let packet = AprsPacket::decode_textual(b"N1CALL>APRS:>World").unwrap();
+        now(sender.send(&packet)).unwrap();
+
+        let mut expected = vec![];
+        KissFrame::data(2, ax25("N1CALL>APRS:>World"))
+            .encode(&mut expected)
+            .unwrap();
+        let output = packets.reader.unsplit(sender.writer).output;
+        assert_eq!(expected, output);
+    }
+}
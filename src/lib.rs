@@ -8,7 +8,7 @@
 //! ```rust
 //! extern crate aprs_parser;
 //!
-//! use aprs_parser::{AprsCst, AprsData, AprsPacket, AprsPosition, Callsign, Latitude, Longitude, Precision, Timestamp, Via, QConstruct};
+//! use aprs_parser::{AprsCst, AprsData, AprsPacket, AprsPosition, Callsign, Latitude, Longitude, Precision, Symbol, Timestamp, Via, QConstruct};
 //!
 //! fn main() {
 //!     let result = AprsPacket::decode_textual(
@@ -39,8 +39,7 @@
 //!                         latitude: Latitude::new(48.36016666666667).unwrap(),
 //!                         longitude: Longitude::new(12.408166666666666).unwrap(),
 //!                         precision: Precision::HundredthMinute,
-//!                         symbol_table: '\\',
-//!                         symbol_code: '^',
+//!                         symbol: Symbol::new('\\', '^').unwrap(),
 //!                         comment: b"322/103/A=003054".to_vec(),
 //!                         cst: AprsCst::Uncompressed,
 //!                     }
@@ -51,42 +50,205 @@
 //! }
 //! ```
 
+//!
+//! # `no_std`
+//!
+//! Without the default `std` feature the crate only needs `alloc`. Packets
+//! still decode and encode, into anything implementing the crate's own
+//! minimal `Write`, e.g. a `Vec<u8>`. The clients, caches, rate limiters and
+//! other types that need a clock or a `HashMap` are left out.
+
 // `!(-90. ..=90.).contains(&value)` seems worse than `value > 90. || value < -90.`
 #![allow(clippy::manual_range_contains)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate core;
+// the test harness needs std either way
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "tokio")]
+extern crate futures_core;
+#[cfg(feature = "geo-types")]
+extern crate geo_types;
+#[cfg(not(feature = "std"))]
+extern crate libm;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, not(feature = "std")))]
+extern crate std;
+#[cfg(feature = "std")]
 extern crate thiserror;
+#[cfg(feature = "time")]
+extern crate time;
+#[cfg(feature = "tokio")]
+extern crate tokio;
+#[cfg(feature = "tokio-util")]
+extern crate tokio_util;
+#[cfg(feature = "uom")]
+extern crate uom;
 
 #[cfg(test)]
 #[macro_use]
 extern crate approx;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_test;
 
+#[cfg(feature = "aprs-is")]
+pub mod aprs_is;
+#[cfg(feature = "tokio")]
+pub mod aprs_is_async;
 mod base91;
+#[cfg(feature = "std")]
+mod beacon;
+mod builder;
 mod bytes;
 mod callsign;
+mod capabilities;
+#[cfg(feature = "tokio-util")]
+pub mod codec;
 mod compressed_cs;
 mod compression_type;
+mod dao;
+#[cfg(feature = "std")]
+mod dedupe;
+mod device;
+mod digipeater;
 mod error;
+mod extension;
+#[cfg(feature = "std")]
+mod filter;
+mod float;
+mod frequency;
+#[cfg(feature = "geo-types")]
+mod geo;
+#[cfg(feature = "std")]
+mod igate;
+#[cfg(feature = "std")]
+mod influx;
+mod io;
+mod item;
+mod json;
+#[cfg(feature = "kiss")]
+pub mod kiss;
+#[cfg(all(feature = "kiss", feature = "tokio"))]
+pub mod kiss_async;
 mod lonlat;
 mod message;
+#[cfg(feature = "std")]
+mod messaging;
 pub mod mic_e;
+mod nmea;
+mod nws;
+mod object;
+mod options;
+#[cfg(feature = "std")]
+mod ownership;
 mod packet;
+mod packet_ref;
+mod path;
 mod position;
+#[cfg(not(feature = "std"))]
+mod prelude;
+#[cfg(feature = "uom")]
+mod quantity;
+mod query;
+#[cfg(feature = "std")]
+mod rate_limit;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+mod scrub;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod stats;
 mod status;
+mod storm;
+mod symbol;
+#[cfg(feature = "std")]
+mod tactical;
+mod telemetry;
+mod test_data;
+mod text;
+mod third_party;
 mod timestamp;
+mod user_defined;
+#[cfg(feature = "std")]
+mod validity;
 mod via;
+mod weather;
 
+#[cfg(feature = "std")]
+pub use beacon::{BeaconScheduler, ProportionalPath};
+pub use builder::{
+    AprsItemBuilder, AprsMessageBuilder, AprsObjectBuilder, AprsPositionBuilder, AprsStatusBuilder,
+    PacketBuilder,
+};
 pub use callsign::Callsign;
+pub use capabilities::AprsCapabilities;
 pub use compressed_cs::{AprsAltitude, AprsCompressedCs, AprsCourseSpeed, AprsRadioRange};
-pub use compression_type::AprsCompressionType;
-pub use error::{DecodeError, EncodeError};
+pub use compression_type::{AprsCompressionType, GpsFix, NmeaSource, Origin};
+pub use dao::Dao;
+#[cfg(feature = "std")]
+pub use dedupe::DedupeCache;
+pub use device::{DeviceClass, DeviceInfo};
+pub use digipeater::Digipeater;
+pub use error::{CallsignError, DecodeError, EncodeError, ErrorLocation};
+pub use extension::{CourseSpeed, DataExtension, DfBearing, Dfs, Phg};
+#[cfg(feature = "std")]
+pub use filter::{Filter, FilterTerm};
+pub use frequency::{FrequencyInfo, FrequencyRange, Tone};
+#[cfg(feature = "std")]
+pub use igate::IGate;
+#[cfg(feature = "std")]
+pub use influx::{FieldValue, Measurement};
+pub use item::AprsItem;
+pub use json::{JsonValue, JSON_SCHEMA_VERSION};
 pub use lonlat::{Latitude, Longitude};
-pub use message::AprsMessage;
-pub use mic_e::AprsMicE;
-pub use packet::{AprsData, AprsPacket};
+pub use message::{AprsMessage, Bulletin, MessageKind};
+#[cfg(feature = "std")]
+pub use messaging::{MessageBuilder, MessageIdGenerator};
+pub use mic_e::{AprsMicE, MicERadio, MicEStatus};
+pub use nmea::{AprsNmeaPosition, NmeaWaypoint};
+pub use nws::{NwsBulletin, NwsProduct};
+pub use object::AprsObject;
+pub use options::ParseOptions;
+#[cfg(feature = "std")]
+pub use ownership::{ObjectOwnershipTracker, OwnershipChange};
+pub use packet::{AprsData, AprsPacket, PacketKind, TelemetryDefinitionSource};
+pub use packet_ref::{AprsMessageRef, AprsPacketRef, CallsignRef, ViaRef};
+pub use path::{Hops, PathAnalyzer, PathIssue, PathRecommendation, Reception};
 pub use position::{AprsCst, AprsPosition, Precision};
+pub use query::{AprsQuery, DirectedQuery, QueryFootprint, QueryType};
+#[cfg(feature = "std")]
+pub use rate_limit::{RateLimitKey, RateLimiter, TokenBucket};
+#[cfg(feature = "std")]
+pub use reader::{read_packets, PacketReader};
+#[cfg(feature = "std")]
+pub use scrub::Scrubber;
+pub use stats::PacketStatistics;
 pub use status::AprsStatus;
-pub use timestamp::{DhmTimestamp, Timestamp};
-pub use via::{QConstruct, Via};
+pub use storm::{StormData, StormType};
+pub use symbol::{SpriteIndex, Symbol, SymbolCategory};
+#[cfg(feature = "std")]
+pub use tactical::{Alias, AliasMap};
+pub use telemetry::{
+    AprsTelemetry, ScaledBit, ScaledValue, SequenceEvent, SequenceTracker, TelemetryBuilder,
+    TelemetryDefinition, TelemetryScaling,
+};
+pub use test_data::AprsTestData;
+pub use text::{display_text, display_text_latin1, strip_non_printable};
+pub use third_party::AprsThirdParty;
+#[cfg(feature = "std")]
+pub use timestamp::FutureTimestampPolicy;
+pub use timestamp::{DhmTimestamp, MdhmTimestamp, Timestamp};
+pub use user_defined::AprsUserDefined;
+#[cfg(feature = "std")]
+pub use validity::{PositionValidator, PositionWarning};
+pub use via::{PathToken, QConstruct, Via};
+pub use weather::{AprsRawWeather, AprsWeather, RawWeatherFormat, WeatherData};
 
 #[cfg(test)]
 mod tests {
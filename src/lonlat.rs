@@ -1,15 +1,42 @@
-use std::io::Write;
-use std::ops::Deref;
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
 
 use base91;
 use bytes::parse_bytes;
+#[cfg(not(any(feature = "std", test)))]
+use float::F64Ext;
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
 use DecodeError;
 use EncodeError;
 use Precision;
 
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub struct Latitude(f64);
 
+// The value is never NaN, so equality and ordering are total.
+impl Eq for Latitude {}
+
+impl PartialOrd for Latitude {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Latitude {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl Hash for Latitude {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_f64(self.0, state);
+    }
+}
+
 impl Deref for Latitude {
     type Target = f64;
 
@@ -49,9 +76,7 @@ impl Latitude {
             (false, -lat)
         };
 
-        let deg = lat as u32;
-        let min = ((lat - (deg as f64)) * 60.0) as u32;
-        let hundredths = ((lat - (deg as f64) - (min as f64 / 60.0)) * 6000.0).round() as u32;
+        let (deg, min, hundredths) = split_dmh(lat);
 
         (deg, min, hundredths, dir)
     }
@@ -63,13 +88,13 @@ impl Latitude {
 
     pub(crate) fn parse_uncompressed(b: &[u8]) -> Result<(Self, Precision), DecodeError> {
         if b.len() != 8 || b[4] != b'.' {
-            return Err(DecodeError::InvalidLatitude(b.to_owned()));
+            return Err(DecodeError::InvalidLatitude(b.to_owned(), 0));
         }
 
         let north = match b[7] {
             b'N' => true,
             b'S' => false,
-            _ => return Err(DecodeError::InvalidLatitude(b.to_owned())),
+            _ => return Err(DecodeError::InvalidLatitude(b.to_owned(), 0)),
         };
 
         // Some APRS lats have trailing spaces
@@ -77,20 +102,20 @@ impl Latitude {
         // Once we encounter a space, the remainder must be spaces
         let mut total_spaces = 0;
         let (deg, num_spaces) = parse_bytes_trailing_spaces(&[b[0], b[1]], false)
-            .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned(), 0))?;
         total_spaces += num_spaces;
         let (min, num_spaces) = parse_bytes_trailing_spaces(&[b[2], b[3]], num_spaces > 0)
-            .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned(), 0))?;
         total_spaces += num_spaces;
         let (min_frac, num_spaces) = parse_bytes_trailing_spaces(&[b[5], b[6]], num_spaces > 0)
-            .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned(), 0))?;
         total_spaces += num_spaces;
 
         let precision = Precision::from_num_digits(total_spaces)
-            .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned(), 0))?;
 
         let lat = Self::from_dmh(deg, min, min_frac, north)
-            .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned(), 0))?;
 
         Ok((lat, precision))
     }
@@ -98,10 +123,10 @@ impl Latitude {
     pub(crate) fn parse_compressed(b: &[u8]) -> Result<Self, DecodeError> {
         let value = 90.0
             - (base91::decode_ascii(b)
-                .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned()))?
+                .ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned(), 0))?
                 / 380926.0);
 
-        Self::new(value).ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned()))
+        Self::new(value).ok_or_else(|| DecodeError::InvalidLatitude(b.to_owned(), 0))
     }
 
     pub(crate) fn encode_compressed<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
@@ -109,18 +134,26 @@ impl Latitude {
         base91::encode_ascii(value, buf, 4)
     }
 
+    // The latitude that is encoded at the given precision
+    #[cfg(feature = "std")]
+    pub(crate) fn rounded(&self, precision: Precision) -> Self {
+        let (deg, min, hundredths) = split_dmh_rounded(self.0.abs(), precision);
+
+        Self::from_dmh(deg, min, hundredths, self.0 >= 0.0).unwrap_or(*self)
+    }
+
     pub(crate) fn encode_uncompressed<W: Write>(
         &self,
         buf: &mut W,
         precision: Precision,
     ) -> Result<(), EncodeError> {
-        let (deg, min, min_frac, is_north) = self.dmh();
-        let dir = if is_north { 'N' } else { 'S' };
+        let (deg, min, min_frac) = split_dmh_rounded(self.0.abs(), precision);
+        let dir = if self.0 >= 0.0 { 'N' } else { 'S' };
 
-        // zero out fields as required for precision
-        // Ideally we would be doing some clever rounding here
-        // E.g. if last 2 digits were blanked,
-        // 4905.83 would become 4906.__
+        // blank out fields as required for precision,
+        // after rounding to the last digit that is kept
+        // E.g. if last 2 digits are blanked,
+        // 4905.83 becomes 4906.__
         let mut digit_buffer = [b' '; 6];
         let blank_index = 6 - precision.num_digits() as usize;
 
@@ -141,9 +174,30 @@ impl Latitude {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Default)]
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
 pub struct Longitude(f64);
 
+// The value is never NaN, so equality and ordering are total.
+impl Eq for Longitude {}
+
+impl PartialOrd for Longitude {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Longitude {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl Hash for Longitude {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_f64(self.0, state);
+    }
+}
+
 impl Deref for Longitude {
     type Target = f64;
 
@@ -183,9 +237,7 @@ impl Longitude {
             (false, -lon)
         };
 
-        let deg = lon as u32;
-        let min = ((lon - (deg as f64)) * 60.0) as u32;
-        let hundredths = ((lon - (deg as f64) - (min as f64 / 60.0)) * 6000.0).round() as u32;
+        let (deg, min, hundredths) = split_dmh(lon);
 
         (deg, min, hundredths, dir)
     }
@@ -198,13 +250,13 @@ impl Longitude {
     /// Precision is needed so we know how many digits to ignore
     pub(crate) fn parse_uncompressed(b: &[u8], precision: Precision) -> Result<Self, DecodeError> {
         if b.len() != 9 || b[5] != b'.' {
-            return Err(DecodeError::InvalidLongitude(b.to_owned()));
+            return Err(DecodeError::InvalidLongitude(b.to_owned(), 0));
         }
 
         let east = match b[8] {
             b'E' => true,
             b'W' => false,
-            _ => return Err(DecodeError::InvalidLongitude(b.to_owned())),
+            _ => return Err(DecodeError::InvalidLongitude(b.to_owned(), 0)),
         };
 
         let mut digit_buffer = [0; 7];
@@ -217,23 +269,23 @@ impl Longitude {
         }
 
         let deg = parse_bytes::<u32>(&digit_buffer[0..3])
-            .ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned(), 0))?;
         let min = parse_bytes::<u32>(&digit_buffer[3..5])
-            .ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned(), 0))?;
         let min_frac = parse_bytes::<u32>(&digit_buffer[5..7])
-            .ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned(), 0))?;
 
         Self::from_dmh(deg, min, min_frac, east)
-            .ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned()))
+            .ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned(), 0))
     }
 
     pub(crate) fn parse_compressed(b: &[u8]) -> Result<Self, DecodeError> {
         let value = (base91::decode_ascii(b)
-            .ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned()))?
+            .ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned(), 0))?
             / 190463.0)
             - 180.0;
 
-        Self::new(value).ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned()))
+        Self::new(value).ok_or_else(|| DecodeError::InvalidLongitude(b.to_owned(), 0))
     }
 
     pub(crate) fn encode_compressed<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
@@ -241,20 +293,74 @@ impl Longitude {
         base91::encode_ascii(value, buf, 4)
     }
 
-    pub(crate) fn encode_uncompressed<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
-        let (deg, min, min_frac, is_east) = self.dmh();
-        let dir = if is_east { 'E' } else { 'W' };
+    /// The digits that are ambiguous aren't blanked, but they are rounded
+    /// like the latitude, since decoders ignore them.
+    // The longitude that is encoded at the given precision
+    #[cfg(feature = "std")]
+    pub(crate) fn rounded(&self, precision: Precision) -> Self {
+        let (deg, min, hundredths) = split_dmh_rounded(self.0.abs(), precision);
+
+        Self::from_dmh(deg, min, hundredths, self.0 >= 0.0).unwrap_or(*self)
+    }
+
+    pub(crate) fn encode_uncompressed<W: Write>(
+        &self,
+        buf: &mut W,
+        precision: Precision,
+    ) -> Result<(), EncodeError> {
+        let (deg, min, min_frac) = split_dmh_rounded(self.0.abs(), precision);
+        let dir = if self.0 >= 0.0 { 'E' } else { 'W' };
 
         write!(buf, "{:03}{:02}.{:02}{}", deg, min, min_frac, dir)?;
         Ok(())
     }
 }
 
+// 0.0 and -0.0 are equal, so they must hash the same
+fn hash_f64<H: Hasher>(value: f64, state: &mut H) {
+    let value = if value == 0.0 { 0.0 } else { value };
+    value.to_bits().hash(state);
+}
+
+// Rounds to the last digit kept at the given precision, carrying into the
+// minutes and degrees, e.g. 49°59.99' becomes 50°00' at one minute precision
+fn split_dmh_rounded(value: f64, precision: Precision) -> (u32, u32, u32) {
+    // in hundredths of a minute
+    const UNITS: [u32; 6] = [1, 10, 100, 1_000, 6_000, 60_000];
+
+    let unit = UNITS[usize::from(precision.num_digits())];
+    let total = (value * 6000.0 / f64::from(unit)).round() as u32 * unit;
+
+    (total / 6000, (total % 6000) / 100, total % 100)
+}
+
+// Rounds to the nearest hundredth of a minute first,
+// so that e.g. 49.05 doesn't come out as 49°02.100'
+fn split_dmh(value: f64) -> (u32, u32, u32) {
+    let total = (value * 6000.0).round() as u32;
+
+    (total / 6000, (total % 6000) / 100, total % 100)
+}
+
+/// Great-circle distance in kilometres between two points, using the haversine formula.
+#[cfg(feature = "std")]
+pub(crate) fn distance_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0;
+
+    let d_lat = (lat2 - lat1).to_radians();
+    let d_lon = (lon2 - lon1).to_radians();
+
+    let a = (d_lat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (d_lon / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
 // if only_spaces is true, requires that b is only spaces
 // returns the parsed value as well as the number of spaces we found
 fn parse_bytes_trailing_spaces(b: &[u8; 2], only_spaces: bool) -> Option<(u32, u8)> {
     if only_spaces {
-        if b == &[b' ', b' '] {
+        if b == b"  " {
             return Some((0, 2));
         } else {
             return None;
@@ -334,31 +440,31 @@ mod tests {
         );
         assert_eq!(
             Latitude::parse_uncompressed(&b"    .  S"[..]),
-            Err(DecodeError::InvalidLatitude(b"    .  S".to_vec()))
+            Err(DecodeError::InvalidLatitude(b"    .  S".to_vec(), 0))
         );
         assert_eq!(
             Latitude::parse_uncompressed(&b"49 3.50W"[..]),
-            Err(DecodeError::InvalidLatitude(b"49 3.50W".to_vec()))
+            Err(DecodeError::InvalidLatitude(b"49 3.50W".to_vec(), 0))
         );
         assert_eq!(
             Latitude::parse_uncompressed(&b"490 .50W"[..]),
-            Err(DecodeError::InvalidLatitude(b"490 .50W".to_vec()))
+            Err(DecodeError::InvalidLatitude(b"490 .50W".to_vec(), 0))
         );
         assert_eq!(
             Latitude::parse_uncompressed(&b"49  . 0W"[..]),
-            Err(DecodeError::InvalidLatitude(b"49  . 0W".to_vec()))
+            Err(DecodeError::InvalidLatitude(b"49  . 0W".to_vec(), 0))
         );
         assert_eq!(
             Latitude::parse_uncompressed(&b"4903.50W"[..]),
-            Err(DecodeError::InvalidLatitude(b"4903.50W".to_vec()))
+            Err(DecodeError::InvalidLatitude(b"4903.50W".to_vec(), 0))
         );
         assert_eq!(
             Latitude::parse_uncompressed(&b"4903.50E"[..]),
-            Err(DecodeError::InvalidLatitude(b"4903.50E".to_vec()))
+            Err(DecodeError::InvalidLatitude(b"4903.50E".to_vec(), 0))
         );
         assert_eq!(
             Latitude::parse_uncompressed(&b"9903.50N"[..]),
-            Err(DecodeError::InvalidLatitude(b"9903.50N".to_vec()))
+            Err(DecodeError::InvalidLatitude(b"9903.50N".to_vec(), 0))
         );
         assert_eq!(
             Latitude::parse_uncompressed(&b"0000.00N"[..]).unwrap(),
@@ -382,15 +488,15 @@ mod tests {
         );
         assert_eq!(
             Longitude::parse_uncompressed(&b"04903.50N"[..], Precision::default()),
-            Err(DecodeError::InvalidLongitude(b"04903.50N".to_vec()))
+            Err(DecodeError::InvalidLongitude(b"04903.50N".to_vec(), 0))
         );
         assert_eq!(
             Longitude::parse_uncompressed(&b"04903.50S"[..], Precision::default()),
-            Err(DecodeError::InvalidLongitude(b"04903.50S".to_vec()))
+            Err(DecodeError::InvalidLongitude(b"04903.50S".to_vec(), 0))
         );
         assert_eq!(
             Longitude::parse_uncompressed(&b"18903.50E"[..], Precision::default()),
-            Err(DecodeError::InvalidLongitude(b"18903.50E".to_vec()))
+            Err(DecodeError::InvalidLongitude(b"18903.50E".to_vec(), 0))
         );
         assert_relative_eq!(
             *Longitude::parse_uncompressed(&b"00000.00E"[..], Precision::default()).unwrap(),
@@ -446,19 +552,107 @@ mod tests {
         let mut buf = vec![];
         Longitude::new(129.05833)
             .unwrap()
-            .encode_uncompressed(&mut buf)
+            .encode_uncompressed(&mut buf, Precision::default())
             .unwrap();
         assert_eq!(buf, &b"12903.50E"[..]);
 
         let mut buf = vec![];
         Longitude::new(-49.0583)
             .unwrap()
-            .encode_uncompressed(&mut buf)
+            .encode_uncompressed(&mut buf, Precision::default())
             .unwrap();
         assert_eq!(buf, &b"04903.50W"[..]);
 
         let mut buf = vec![];
-        Longitude(0.0).encode_uncompressed(&mut buf).unwrap();
+        Longitude(0.0)
+            .encode_uncompressed(&mut buf, Precision::default())
+            .unwrap();
         assert_eq!(buf, &b"00000.00E"[..]);
     }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_distance_km() {
+        assert_relative_eq!(0.0, distance_km(49.0, -72.0, 49.0, -72.0));
+        // one degree of latitude is roughly 111 km
+        assert_relative_eq!(111.19, distance_km(0.0, 0.0, 1.0, 0.0), epsilon = 0.01);
+        assert_relative_eq!(
+            distance_km(48.0, 12.0, 49.0, 13.0),
+            distance_km(49.0, 13.0, 48.0, 12.0)
+        );
+    }
+
+    #[test]
+    fn test_dmh_whole_minutes() {
+        assert_eq!(
+            (49, 3, 0, true),
+            Latitude::from_dmh(49, 3, 0, true).unwrap().dmh()
+        );
+        assert_eq!(
+            (72, 1, 0, false),
+            Longitude::from_dmh(72, 1, 0, false).unwrap().dmh()
+        );
+    }
+
+    #[test]
+    fn test_encode_uncompressed_rounds_ambiguous_digits() {
+        let encode_lat = |lat: f64, precision: Precision| {
+            let mut buf = vec![];
+            Latitude::new(lat)
+                .unwrap()
+                .encode_uncompressed(&mut buf, precision)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        let encode_lon = |lon: f64, precision: Precision| {
+            let mut buf = vec![];
+            Longitude::new(lon)
+                .unwrap()
+                .encode_uncompressed(&mut buf, precision)
+                .unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+
+        // 49°05.83'
+        let lat = 49.0 + 5.83 / 60.0;
+        assert_eq!("4905.8 N", encode_lat(lat, Precision::TenthMinute));
+        assert_eq!("4906.  N", encode_lat(lat, Precision::OneMinute));
+        assert_eq!("491 .  N", encode_lat(lat, Precision::TenMinute));
+        assert_eq!("49  .  N", encode_lat(lat, Precision::OneDegree));
+        assert_eq!("4905.83S", encode_lat(-lat, Precision::HundredthMinute));
+
+        // carries into the degrees
+        let lat = 49.0 + 59.99 / 60.0;
+        assert_eq!("5000.0 N", encode_lat(lat, Precision::TenthMinute));
+        assert_eq!("5000.  S", encode_lat(-lat, Precision::OneMinute));
+        assert_eq!("500 .  N", encode_lat(lat, Precision::TenMinute));
+        assert_eq!("5   .  N", encode_lat(lat, Precision::TenDegree));
+        assert_eq!("9000.  N", encode_lat(89.999, Precision::OneMinute));
+
+        let lon = 72.0 + 59.99 / 60.0;
+        assert_eq!("07300.00W", encode_lon(-lon, Precision::OneMinute));
+        assert_eq!("07259.99E", encode_lon(lon, Precision::HundredthMinute));
+        assert_eq!("18000.00E", encode_lon(179.9999, Precision::TenthMinute));
+    }
+
+    #[test]
+    fn total_order() {
+        use std::collections::HashSet;
+
+        let mut lats = [
+            Latitude::new(10.0).unwrap(),
+            Latitude::new(-0.0).unwrap(),
+            Latitude::new(-45.5).unwrap(),
+        ];
+        lats.sort();
+        assert_eq!(
+            vec![-45.5, 0.0, 10.0],
+            lats.iter().map(|l| l.value()).collect::<Vec<_>>()
+        );
+
+        let set: HashSet<_> = vec![Longitude::new(0.0).unwrap(), Longitude::new(-0.0).unwrap()]
+            .into_iter()
+            .collect();
+        assert_eq!(1, set.len());
+    }
 }
@@ -1,32 +1,391 @@
-use std::io::Write;
-
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsData;
+use AprsPacket;
 use Callsign;
 use DecodeError;
+use DirectedQuery;
 use EncodeError;
+use NwsBulletin;
+use TelemetryDefinition;
+
+/// Maximum length of a message's text, excluding the message ID.
+pub(crate) const MAX_TEXT_LEN: usize = 67;
+
+/// Maximum length of a message ID.
+pub(crate) const MAX_ID_LEN: usize = 5;
+
+const EMAIL_ADDRESSEE: &[u8] = b"EMAIL";
+
+/// What a message is for, based on its text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MessageKind {
+    Message,
+    /// Acknowledges the message with this ID
+    Ack(Vec<u8>),
+    /// Rejects the message with this ID
+    Rej(Vec<u8>),
+}
+
+/// The addressee of a bulletin: `BLN` followed by a digit for general bulletins
+/// or a letter for announcements. General bulletins can be sent to a group,
+/// whose name of up to 5 characters follows the digit, e.g. `BLN4WX`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bulletin {
+    /// `0`-`9`, or `A`-`Z` for announcements
+    pub id: u8,
+    pub group: Option<Vec<u8>>,
+}
+
+impl Bulletin {
+    pub fn decode(addressee: &[u8]) -> Option<Self> {
+        let rest = addressee.strip_prefix(b"BLN")?;
+        let (&id, group) = rest.split_first()?;
+
+        let bulletin = Self {
+            id,
+            group: Some(group.to_owned()).filter(|g| !g.is_empty()),
+        };
+        if bulletin.is_valid() {
+            Some(bulletin)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_announcement(&self) -> bool {
+        self.id.is_ascii_uppercase()
+    }
+
+    fn is_valid(&self) -> bool {
+        match &self.group {
+            None => self.id.is_ascii_digit() || self.id.is_ascii_uppercase(),
+            Some(group) => {
+                self.id.is_ascii_digit()
+                    && (1..=5).contains(&group.len())
+                    && group.iter().all(u8::is_ascii_alphanumeric)
+            }
+        }
+    }
+
+    fn addressee(&self) -> Vec<u8> {
+        let mut addressee = b"BLN".to_vec();
+        addressee.push(self.id);
+        if let Some(group) = &self.group {
+            addressee.extend_from_slice(group);
+        }
+
+        addressee
+    }
+}
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AprsMessage {
     pub to: Callsign,
     pub data_type_identifier: u8,
 
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
     pub addressee: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
     pub text: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text_option"))]
     pub id: Option<Vec<u8>>,
 }
 
 impl AprsMessage {
+    /// Builds a message, checking that it can be encoded and understood by receivers:
+    /// the addressee is at most 9 characters, the text is at most 67 characters without
+    /// `|`, `~` or `{`, and the ID is 1 to 5 alphanumeric characters.
+    pub fn new(
+        to: Callsign,
+        addressee: &[u8],
+        text: &[u8],
+        id: Option<&[u8]>,
+    ) -> Result<Self, EncodeError> {
+        if addressee.is_empty() || addressee.len() > 9 || addressee.contains(&b':') {
+            return Err(EncodeError::InvalidMessageAddressee(addressee.to_owned()));
+        }
+
+        if text.len() > MAX_TEXT_LEN || text.iter().any(|&b| is_forbidden(b)) {
+            return Err(EncodeError::InvalidMessageText(text.to_owned()));
+        }
+
+        if let Some(id) = id {
+            if !is_valid_id(id) {
+                return Err(EncodeError::InvalidMessageId(id.to_owned()));
+            }
+        }
+
+        Ok(Self {
+            to,
+            data_type_identifier: b':',
+            addressee: addressee.to_owned(),
+            text: text.to_owned(),
+            id: id.map(<[u8]>::to_vec),
+        })
+    }
+
+    /// Recognizes acknowledgements (`ack12`) and rejections (`rej12`).
+    /// With the reply-ack scheme these can be followed by `}`, and another ID that is also acked.
+    pub fn kind(&self) -> MessageKind {
+        let (prefix, id) = match (self.text.get(..3), self.text.get(3..)) {
+            (Some(prefix), Some(id)) if self.id.is_none() => (prefix, split_reply_ack(id).0),
+            _ => return MessageKind::Message,
+        };
+        if !is_valid_id(id) {
+            return MessageKind::Message;
+        }
+
+        if prefix.eq_ignore_ascii_case(b"ack") {
+            MessageKind::Ack(id.to_owned())
+        } else if prefix.eq_ignore_ascii_case(b"rej") {
+            MessageKind::Rej(id.to_owned())
+        } else {
+            MessageKind::Message
+        }
+    }
+
+    /// The ID of this message. With the reply-ack scheme, the ID field is `{MM}AA`,
+    /// and this returns `MM`.
+    pub fn message_id(&self) -> Option<&[u8]> {
+        self.id.as_deref().map(|id| split_reply_ack(id).0)
+    }
+
+    /// With the reply-ack scheme, the ID of a message from the addressee that
+    /// this message also acknowledges (`AA` in `{MM}AA`).
+    pub fn reply_ack(&self) -> Option<&[u8]> {
+        self.id
+            .as_deref()
+            .and_then(|id| split_reply_ack(id).1)
+            .filter(|ack| !ack.is_empty())
+    }
+
+    /// Whether the sender supports the reply-ack scheme, which it signals
+    /// with a `}` after the message ID even if there's nothing to ack.
+    pub fn supports_reply_ack(&self) -> bool {
+        self.id
+            .as_deref()
+            .map_or(false, |id| split_reply_ack(id).1.is_some())
+    }
+
+    /// Uses the reply-ack scheme for this message, acknowledging `ack` if given.
+    /// The message must have an ID.
+    pub fn set_reply_ack(&mut self, ack: Option<&[u8]>) -> Result<(), EncodeError> {
+        let mut id = self
+            .message_id()
+            .filter(|id| is_valid_id(id))
+            .ok_or(EncodeError::InvalidMessageId(vec![]))?
+            .to_vec();
+
+        id.push(b'}');
+        if let Some(ack) = ack {
+            if !is_valid_id(ack) {
+                return Err(EncodeError::InvalidMessageId(ack.to_owned()));
+            }
+            id.extend_from_slice(ack);
+        }
+        self.id = Some(id);
+
+        Ok(())
+    }
+
+    /// Builds the acknowledgement for a received message, addressed to its sender.
+    /// `to` is the destination of our own packets, e.g. our software's tocall.
+    /// Returns `None` if the packet isn't a message with an ID, or is itself an ack or rej.
+    /// Messages using the reply-ack scheme get `ackMM}`, which tells the sender we support it too.
+    pub fn ack_for(to: Callsign, packet: &AprsPacket) -> Option<Self> {
+        Self::reply_for(to, packet, b"ack")
+    }
+
+    /// Builds the rejection for a received message, addressed to its sender.
+    /// `to` is the destination of our own packets, e.g. our software's tocall.
+    /// Returns `None` if the packet isn't a message with an ID, or is itself an ack or rej.
+    pub fn rej_for(to: Callsign, packet: &AprsPacket) -> Option<Self> {
+        Self::reply_for(to, packet, b"rej")
+    }
+
+    fn reply_for(to: Callsign, packet: &AprsPacket, prefix: &[u8]) -> Option<Self> {
+        let msg = match &packet.data {
+            AprsData::Message(m) if m.kind() == MessageKind::Message => m,
+            _ => return None,
+        };
+        let id = msg.message_id()?;
+
+        let mut text = prefix.to_vec();
+        text.extend_from_slice(id);
+        if msg.supports_reply_ack() {
+            text.push(b'}');
+        }
+
+        Some(Self {
+            to,
+            data_type_identifier: b':',
+            addressee: packet.from.to_string().into_bytes(),
+            text,
+            id: None,
+        })
+    }
+
+    /// The directed query in the text, e.g. `?APRSP`.
+    pub fn directed_query(&self) -> Option<DirectedQuery> {
+        DirectedQuery::decode(&self.text)
+    }
+
+    /// The bulletin this message is part of, if it's addressed to one.
+    pub fn bulletin(&self) -> Option<Bulletin> {
+        Bulletin::decode(&self.addressee)
+    }
+
+    /// Builds a bulletin or announcement. Bulletins don't have a message ID,
+    /// since they aren't acked.
+    pub fn new_bulletin(
+        to: Callsign,
+        bulletin: &Bulletin,
+        text: &[u8],
+    ) -> Result<Self, EncodeError> {
+        if !bulletin.is_valid() {
+            return Err(EncodeError::InvalidMessageAddressee(bulletin.addressee()));
+        }
+
+        Self::new(to, &bulletin.addressee(), text, None)
+    }
+
+    /// Decodes the message as a weather service bulletin, if it's addressed to one.
+    pub fn nws_bulletin(&self) -> Option<NwsBulletin> {
+        NwsBulletin::decode(&self.addressee, &self.text)
+    }
+
+    /// Whether the message text is a telemetry definition (`PARM.`, `UNIT.`, `EQNS.` or `BITS.`).
+    /// The definitions apply to the station named in the addressee.
+    pub fn is_telemetry_definition(&self) -> bool {
+        [&b"PARM."[..], b"UNIT.", b"EQNS.", b"BITS."]
+            .iter()
+            .any(|prefix| self.text.starts_with(prefix))
+    }
+
+    /// Decodes the telemetry definition carried by this message, if any.
+    pub fn telemetry_definition(&self) -> Option<TelemetryDefinition> {
+        TelemetryDefinition::decode(&self.text)
+    }
+
+    /// Builds a message publishing `definition` for the telemetry of `station`.
+    pub fn new_telemetry_definition(
+        to: Callsign,
+        station: &Callsign,
+        definition: &TelemetryDefinition,
+    ) -> Result<Self, EncodeError> {
+        let mut text = vec![];
+        definition.encode(&mut text)?;
+        if text.len() > MAX_TEXT_LEN {
+            return Err(EncodeError::InvalidMessageText(text));
+        }
+
+        Ok(Self {
+            to,
+            data_type_identifier: b':',
+            addressee: station.to_string().into_bytes(),
+            text,
+            id: None,
+        })
+    }
+
+    /// Builds messages for the APRS email gateway, which forwards messages
+    /// addressed to `EMAIL` whose text is the email address followed by the body.
+    /// Bodies that don't fit into a single message are split across several,
+    /// each suffixed with its number, e.g. ` (1/3)`.
+    pub fn new_email(to: Callsign, address: &str, body: &str) -> Result<Vec<Self>, EncodeError> {
+        if address.is_empty()
+            || !address.contains('@')
+            || address
+                .bytes()
+                .any(|b| !b.is_ascii_graphic() || is_forbidden(b))
+        {
+            return Err(EncodeError::InvalidEmailAddress(address.to_owned()));
+        }
+
+        if let Some(b) = body.bytes().find(|b| is_forbidden(*b)) {
+            return Err(EncodeError::InvalidMessageText(vec![b]));
+        }
+
+        let single_len = MAX_TEXT_LEN.saturating_sub(address.len() + 1);
+        if single_len == 0 {
+            return Err(EncodeError::InvalidEmailAddress(address.to_owned()));
+        }
+
+        let chunks = if body.len() <= single_len {
+            vec![body]
+        } else {
+            // the suffix length depends on the number of chunks, so retry
+            // until the number of chunks fits in the suffix we reserved space for
+            let mut total = 2;
+            loop {
+                let suffix_len = format!(" ({}/{})", total, total).len();
+                let chunk_len = single_len
+                    .checked_sub(suffix_len)
+                    .filter(|l| *l > 0)
+                    .ok_or_else(|| EncodeError::InvalidEmailAddress(address.to_owned()))?;
+
+                let chunks = split_at_char_boundaries(body, chunk_len);
+                if chunks.len() <= total {
+                    break chunks;
+                }
+                total = chunks.len();
+            }
+        };
+
+        let total = chunks.len();
+        Ok(chunks
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| {
+                let text = if total == 1 {
+                    format!("{} {}", address, chunk)
+                } else {
+                    format!("{} {} ({}/{})", address, chunk, i + 1, total)
+                };
+
+                Self {
+                    to: to.clone(),
+                    data_type_identifier: b':',
+                    addressee: EMAIL_ADDRESSEE.to_vec(),
+                    text: text.into_bytes(),
+                    id: None,
+                }
+            })
+            .collect())
+    }
+
+    /// If this message is addressed to the email gateway,
+    /// returns the email address and the body.
+    pub fn email(&self) -> Option<(&[u8], &[u8])> {
+        if self.addressee != EMAIL_ADDRESSEE && self.addressee != b"EMAIL-2" {
+            return None;
+        }
+
+        let mut splitter = self.text.splitn(2, |x| *x == b' ');
+        let address = splitter.next()?;
+        let body = splitter.next().unwrap_or(&[]);
+
+        Some((address, body))
+    }
+
     pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
         let mut splitter = b.splitn(2, |x| *x == b':');
 
         let mut addressee = match splitter.next() {
             Some(x) => x.to_vec(),
             None => {
-                return Err(DecodeError::InvalidMessageDestination(vec![]));
+                return Err(DecodeError::InvalidMessageDestination(vec![], 0));
             }
         };
 
         if addressee.len() != 9 {
-            return Err(DecodeError::InvalidMessageDestination(addressee.to_owned()));
+            return Err(DecodeError::InvalidMessageDestination(
+                addressee.to_owned(),
+                0,
+            ));
         }
 
         trim_spaces_end(&mut addressee);
@@ -52,8 +411,13 @@ impl AprsMessage {
                 self.addressee.to_owned(),
             ));
         }
+        if self.data_type_identifier != b':' {
+            return Err(EncodeError::InvalidDataTypeIdentifier(
+                self.data_type_identifier,
+            ));
+        }
 
-        buf.write_all(b":")?;
+        buf.write_all(&[self.data_type_identifier])?;
         buf.write_all(&self.addressee)?;
         for _ in self.addressee.len()..9 {
             buf.write_all(b" ")?;
@@ -71,6 +435,49 @@ impl AprsMessage {
     }
 }
 
+// these have special meanings in messages
+fn is_forbidden(b: u8) -> bool {
+    b == b'|' || b == b'~' || b == b'{'
+}
+
+// Splits `MM}AA` into `MM` and `AA`
+fn split_reply_ack(id: &[u8]) -> (&[u8], Option<&[u8]>) {
+    match id.iter().position(|&c| c == b'}') {
+        Some(i) => (&id[..i], Some(&id[i + 1..])),
+        None => (id, None),
+    }
+}
+
+pub(crate) fn is_valid_id(id: &[u8]) -> bool {
+    !id.is_empty() && id.len() <= MAX_ID_LEN && id.iter().all(u8::is_ascii_alphanumeric)
+}
+
+fn split_at_char_boundaries(s: &str, max_len: usize) -> Vec<&str> {
+    let mut chunks = vec![];
+    let mut rest = s;
+
+    while !rest.is_empty() {
+        let mut end = max_len.min(rest.len());
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        // a single character wider than max_len
+        if end == 0 {
+            end = rest
+                .chars()
+                .next()
+                .map(char::len_utf8)
+                .unwrap_or(rest.len());
+        }
+
+        let (chunk, remainder) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = remainder;
+    }
+
+    chunks
+}
+
 fn trim_spaces_end(arr: &mut Vec<u8>) {
     let space_count = arr.iter().rev().take_while(|&&b| b == b' ').count();
 
@@ -95,7 +502,10 @@ mod tests {
 
         assert_eq!(
             result,
-            Err(DecodeError::InvalidMessageDestination(b"DEST  ".to_vec()))
+            Err(DecodeError::InvalidMessageDestination(
+                b"DEST  ".to_vec(),
+                0
+            ))
         );
     }
 
@@ -155,4 +565,303 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn new_message() {
+        let msg = AprsMessage::new(default_callsign(), b"N0CALL-7", b"Hello", Some(b"42")).unwrap();
+
+        let mut buf = vec![];
+        msg.encode(&mut buf).unwrap();
+        assert_eq!(&b":N0CALL-7 :Hello{42"[..], buf);
+        assert_eq!(
+            msg,
+            AprsMessage::decode(&buf[1..], default_callsign()).unwrap()
+        );
+
+        assert!(matches!(
+            AprsMessage::new(default_callsign(), b"TOOLONGCALL", b"Hi", None),
+            Err(EncodeError::InvalidMessageAddressee(_))
+        ));
+        assert!(matches!(
+            AprsMessage::new(default_callsign(), b"N0CALL", b"a{b", None),
+            Err(EncodeError::InvalidMessageText(_))
+        ));
+        assert!(matches!(
+            AprsMessage::new(default_callsign(), b"N0CALL", b"Hi", Some(b"123456")),
+            Err(EncodeError::InvalidMessageId(_))
+        ));
+        assert!(matches!(
+            AprsMessage::new(default_callsign(), b"N0CALL", b"Hi", Some(b"1 2")),
+            Err(EncodeError::InvalidMessageId(_))
+        ));
+
+        let mut msg = msg;
+        msg.data_type_identifier = b'>';
+        assert!(matches!(
+            msg.encode(&mut vec![]),
+            Err(EncodeError::InvalidDataTypeIdentifier(b'>'))
+        ));
+    }
+
+    #[test]
+    fn kind() {
+        for (text, kind) in [
+            (&b":N0CALL   :ack12"[..], MessageKind::Ack(b"12".to_vec())),
+            (b":N0CALL   :rejAB3", MessageKind::Rej(b"AB3".to_vec())),
+            (b":N0CALL   :ackAB}", MessageKind::Ack(b"AB".to_vec())),
+            (b":N0CALL   :ackAB}CD", MessageKind::Ack(b"AB".to_vec())),
+            (b":N0CALL   :ack", MessageKind::Message),
+            (b":N0CALL   :acknowledged", MessageKind::Message),
+            (b":N0CALL   :ack1{2", MessageKind::Message),
+            (b":N0CALL   :Hello{1", MessageKind::Message),
+        ] {
+            let msg = AprsMessage::decode(&text[1..], default_callsign()).unwrap();
+            assert_eq!(kind, msg.kind());
+        }
+    }
+
+    #[test]
+    fn reply_ack() {
+        let msg = AprsMessage::decode(&b"N0CALL   :Hello{AB}CD"[..], default_callsign()).unwrap();
+        assert_eq!(Some(&b"AB"[..]), msg.message_id());
+        assert_eq!(Some(&b"CD"[..]), msg.reply_ack());
+        assert!(msg.supports_reply_ack());
+
+        let msg = AprsMessage::decode(&b"N0CALL   :Hello{AB}"[..], default_callsign()).unwrap();
+        assert_eq!(Some(&b"AB"[..]), msg.message_id());
+        assert_eq!(None, msg.reply_ack());
+        assert!(msg.supports_reply_ack());
+
+        let msg = AprsMessage::decode(&b"N0CALL   :Hello{AB"[..], default_callsign()).unwrap();
+        assert_eq!(Some(&b"AB"[..]), msg.message_id());
+        assert_eq!(None, msg.reply_ack());
+        assert!(!msg.supports_reply_ack());
+
+        let packet =
+            AprsPacket::decode_textual(&b"N0CALL-7>APRS::N1CALL   :Hello{AB}CD"[..]).unwrap();
+        assert_eq!(
+            b"ackAB}",
+            &AprsMessage::ack_for(default_callsign(), &packet)
+                .unwrap()
+                .text[..]
+        );
+    }
+
+    #[test]
+    fn reply_with_ack() {
+        let mut msg =
+            AprsMessage::new(default_callsign(), b"N0CALL-7", b"Hi there", Some(b"EF")).unwrap();
+        msg.set_reply_ack(Some(b"AB")).unwrap();
+
+        let mut buf = vec![];
+        msg.encode(&mut buf).unwrap();
+        assert_eq!(&b":N0CALL-7 :Hi there{EF}AB"[..], buf);
+
+        // replacing the ack keeps the message ID
+        msg.set_reply_ack(None).unwrap();
+        assert_eq!(Some(&b"EF}"[..]), msg.id.as_deref());
+
+        assert!(msg.set_reply_ack(Some(b"A B")).is_err());
+        msg.id = None;
+        assert!(msg.set_reply_ack(None).is_err());
+    }
+
+    #[test]
+    fn ack_and_rej() {
+        let packet =
+            AprsPacket::decode_textual(&b"N0CALL-7>APRS,WIDE2-1::N1CALL   :Hello{42"[..]).unwrap();
+
+        let to = Callsign::new_no_ssid("APZ123");
+
+        let ack = AprsMessage::ack_for(to.clone(), &packet).unwrap();
+        assert_eq!(to, ack.to);
+        let mut buf = vec![];
+        ack.encode(&mut buf).unwrap();
+        assert_eq!(&b":N0CALL-7 :ack42"[..], buf);
+
+        let rej = AprsMessage::rej_for(to.clone(), &packet).unwrap();
+        assert_eq!(to, rej.to);
+        let mut buf = vec![];
+        rej.encode(&mut buf).unwrap();
+        assert_eq!(&b":N0CALL-7 :rej42"[..], buf);
+
+        // no ID, and acks aren't acked
+        for line in [
+            &b"N0CALL-7>APRS::N1CALL   :Hello"[..],
+            b"N0CALL-7>APRS::N1CALL   :ack42",
+            b"N0CALL-7>APRS:>Status",
+        ] {
+            let packet = AprsPacket::decode_textual(line).unwrap();
+            assert_eq!(None, AprsMessage::ack_for(to.clone(), &packet));
+        }
+    }
+
+    #[test]
+    fn bulletins() {
+        for (addressee, bulletin) in [
+            (&b"BLN3"[..], Some((b'3', None))),
+            (b"BLNA", Some((b'A', None))),
+            (b"BLN4WX", Some((b'4', Some(b"WX".to_vec())))),
+            (b"BLN1ARES5", Some((b'1', Some(b"ARES5".to_vec())))),
+            (b"BLNAWX", None),
+            (b"BLN4W-X", None),
+            (b"BLN", None),
+            (b"N0CALL", None),
+        ] {
+            let msg = AprsMessage {
+                to: default_callsign(),
+                data_type_identifier: b':',
+                addressee: addressee.to_vec(),
+                text: b"Net tonight".to_vec(),
+                id: None,
+            };
+            assert_eq!(
+                bulletin.map(|(id, group)| Bulletin { id, group }),
+                msg.bulletin()
+            );
+        }
+    }
+
+    #[test]
+    fn new_bulletin() {
+        let bulletin = Bulletin {
+            id: b'4',
+            group: Some(b"WX".to_vec()),
+        };
+        let msg =
+            AprsMessage::new_bulletin(default_callsign(), &bulletin, b"Storm at 8pm").unwrap();
+
+        let mut buf = vec![];
+        msg.encode(&mut buf).unwrap();
+        assert_eq!(&b":BLN4WX   :Storm at 8pm"[..], buf);
+        assert!(!msg.bulletin().unwrap().is_announcement());
+
+        let bulletin = Bulletin {
+            id: b'4',
+            group: Some(b"TOOLONG".to_vec()),
+        };
+        assert!(matches!(
+            AprsMessage::new_bulletin(default_callsign(), &bulletin, b"Hi"),
+            Err(EncodeError::InvalidMessageAddressee(_))
+        ));
+    }
+
+    #[test]
+    fn nws_bulletin() {
+        let msg = AprsMessage::decode(
+            &b"NWS-WARN :092010z,THUNDER_STORM,AR_ASHLEY{S9JbA"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        let bulletin = msg.nws_bulletin().unwrap();
+        assert_eq!(b"THUNDER_STORM", &bulletin.event[..]);
+        assert_eq!(vec![b"AR_ASHLEY".to_vec()], bulletin.zones);
+
+        let msg = AprsMessage::decode(&b"N0CALL   :Hello"[..], default_callsign()).unwrap();
+        assert_eq!(None, msg.nws_bulletin());
+    }
+
+    #[test]
+    fn telemetry_definition() {
+        let mut msg =
+            AprsMessage::decode(&b"N0CALL   :PARM.Battery,Temp"[..], default_callsign()).unwrap();
+        assert!(msg.is_telemetry_definition());
+
+        msg.text = b"BITS.11111111,Project".to_vec();
+        assert!(msg.is_telemetry_definition());
+
+        msg.text = b"Parm.Battery".to_vec();
+        assert!(!msg.is_telemetry_definition());
+    }
+
+    #[test]
+    fn new_email_single() {
+        let msgs =
+            AprsMessage::new_email(default_callsign(), "ve9@example.com", "Hello there").unwrap();
+
+        assert_eq!(1, msgs.len());
+        assert_eq!(b"EMAIL", &msgs[0].addressee[..]);
+        assert_eq!(&b"ve9@example.com Hello there"[..], &msgs[0].text[..]);
+        assert_eq!(
+            Some((&b"ve9@example.com"[..], &b"Hello there"[..])),
+            msgs[0].email()
+        );
+    }
+
+    #[test]
+    fn new_email_split() {
+        let body = "x".repeat(100);
+        let msgs = AprsMessage::new_email(default_callsign(), "ve9@example.com", &body).unwrap();
+
+        assert_eq!(3, msgs.len());
+        for (i, m) in msgs.iter().enumerate() {
+            assert!(m.text.len() <= MAX_TEXT_LEN);
+            assert!(m.text.ends_with(format!(" ({}/3)", i + 1).as_bytes()));
+        }
+
+        let rebuilt: Vec<u8> = msgs
+            .iter()
+            .flat_map(|m| {
+                let (_, body) = m.email().unwrap();
+                body[..body.len() - 6].to_vec()
+            })
+            .collect();
+        assert_eq!(body.as_bytes(), &rebuilt[..]);
+    }
+
+    #[test]
+    fn new_email_invalid() {
+        assert!(matches!(
+            AprsMessage::new_email(default_callsign(), "not an address", "Hi"),
+            Err(EncodeError::InvalidEmailAddress(_))
+        ));
+        assert!(matches!(
+            AprsMessage::new_email(default_callsign(), "ve9@example.com", "Hi {1"),
+            Err(EncodeError::InvalidMessageText(_))
+        ));
+        assert!(matches!(
+            AprsMessage::new_email(
+                default_callsign(),
+                &format!("{}@x.com", "a".repeat(70)),
+                "Hi"
+            ),
+            Err(EncodeError::InvalidEmailAddress(_))
+        ));
+    }
+
+    #[test]
+    fn email_not_addressed_to_gateway() {
+        let msg =
+            AprsMessage::decode(&b"DEST     :ve9@example.com Hi"[..], default_callsign()).unwrap();
+        assert_eq!(None, msg.email());
+    }
+
+    #[test]
+    fn new_telemetry_definition() {
+        let station = Callsign::new("N0CALL-11").unwrap();
+        let msg = AprsMessage::new_telemetry_definition(
+            default_callsign(),
+            &station,
+            &TelemetryDefinition::Units(vec![b"Volts".to_vec(), b"deg.C".to_vec()]),
+        )
+        .unwrap();
+
+        let mut buf = vec![];
+        msg.encode(&mut buf).unwrap();
+        assert_eq!(&b":N0CALL-11:UNIT.Volts,deg.C"[..], buf);
+        assert_eq!(
+            Some(TelemetryDefinition::Units(vec![
+                b"Volts".to_vec(),
+                b"deg.C".to_vec()
+            ])),
+            msg.telemetry_definition()
+        );
+
+        assert!(AprsMessage::new_telemetry_definition(
+            default_callsign(),
+            &station,
+            &TelemetryDefinition::Units(vec![b"a,b".to_vec()]),
+        )
+        .is_err());
+    }
 }
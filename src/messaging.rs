@@ -0,0 +1,274 @@
+//! State for sending messages: unique message IDs, and tracking which
+//! outgoing messages have been acknowledged or rejected by their addressee.
+
+use std::collections::HashMap;
+#[cfg(feature = "serde")]
+use std::convert::TryFrom;
+
+use AprsData;
+use AprsMessage;
+use AprsPacket;
+use Callsign;
+use EncodeError;
+use MessageKind;
+
+const DIGITS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// Generates message IDs from a counter, which wraps around once every ID has been used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "MessageIdGeneratorFields"))]
+pub struct MessageIdGenerator {
+    base: u64,
+    len: usize,
+    next: u64,
+}
+
+impl MessageIdGenerator {
+    /// Generates IDs of `len` decimal digits, e.g. `001`.
+    /// Returns `None` unless `len` is 1 to 5.
+    pub fn numeric(len: usize) -> Option<Self> {
+        Self::new(10, len)
+    }
+
+    /// Generates IDs of `len` digits and upper case letters, e.g. `00A`.
+    /// Returns `None` unless `len` is 1 to 5.
+    pub fn alphanumeric(len: usize) -> Option<Self> {
+        Self::new(36, len)
+    }
+
+    fn new(base: u64, len: usize) -> Option<Self> {
+        if !(1..=5).contains(&len) {
+            return None;
+        }
+
+        Some(Self { base, len, next: 0 })
+    }
+
+    pub fn next_id(&mut self) -> Vec<u8> {
+        let mut n = self.next;
+        self.next = (self.next + 1) % self.base.pow(self.len as u32);
+
+        let mut id = vec![b'0'; self.len];
+        for digit in id.iter_mut().rev() {
+            *digit = DIGITS[(n % self.base) as usize];
+            n /= self.base;
+        }
+
+        id
+    }
+}
+
+// The counter has to fit the ID length when deserializing
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "MessageIdGenerator")]
+struct MessageIdGeneratorFields {
+    base: u64,
+    len: usize,
+    next: u64,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<MessageIdGeneratorFields> for MessageIdGenerator {
+    type Error = &'static str;
+
+    fn try_from(f: MessageIdGeneratorFields) -> Result<Self, Self::Error> {
+        let mut ids = match f.base {
+            10 | 36 => Self::new(f.base, f.len),
+            _ => None,
+        }
+        .ok_or("invalid message ID format")?;
+        if f.next >= f.base.pow(f.len as u32) {
+            return Err("message ID counter out of range");
+        }
+        ids.next = f.next;
+
+        Ok(ids)
+    }
+}
+
+/// Builds outgoing messages with unique IDs, and keeps track of the ones
+/// still waiting for an ack.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MessageBuilder {
+    to: Callsign,
+    ids: MessageIdGenerator,
+    // message ID -> addressee
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text_map"))]
+    pending: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl MessageBuilder {
+    /// `to` is the destination field of the built messages.
+    pub fn new(to: Callsign, ids: MessageIdGenerator) -> Self {
+        Self {
+            to,
+            ids,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Builds a message with the next ID, which is pending until it's acked or rejected.
+    pub fn build(&mut self, addressee: &[u8], text: &[u8]) -> Result<AprsMessage, EncodeError> {
+        let id = self.ids.next_id();
+        let message = AprsMessage::new(self.to.clone(), addressee, text, Some(&id))?;
+        self.pending.insert(id, addressee.to_owned());
+
+        Ok(message)
+    }
+
+    /// Whether the message with this ID is still waiting for an ack.
+    pub fn is_pending(&self, id: &[u8]) -> bool {
+        self.pending.contains_key(id)
+    }
+
+    /// IDs of the messages still waiting for an ack.
+    pub fn pending(&self) -> impl Iterator<Item = &[u8]> {
+        self.pending.keys().map(Vec::as_slice)
+    }
+
+    /// Processes a received packet. Acks and rejections from the addressee of a
+    /// pending message, including reply-acks in its messages, are returned and
+    /// the message stops being pending.
+    pub fn observe(&mut self, packet: &AprsPacket) -> Vec<MessageKind> {
+        let msg = match &packet.data {
+            AprsData::Message(m) => m,
+            _ => return vec![],
+        };
+
+        let mut answers = vec![];
+        match msg.kind() {
+            MessageKind::Message => {}
+            kind @ MessageKind::Ack(_) | kind @ MessageKind::Rej(_) => answers.push(kind),
+        }
+        if let Some(id) = msg.reply_ack() {
+            answers.push(MessageKind::Ack(id.to_owned()));
+        }
+
+        let from = packet.from.to_string().into_bytes();
+        answers.retain(|answer| {
+            let id = match answer {
+                MessageKind::Ack(id) | MessageKind::Rej(id) => id,
+                MessageKind::Message => return false,
+            };
+            match self.pending.get(id) {
+                Some(addressee) if addressee.eq_ignore_ascii_case(&from) => {
+                    self.pending.remove(id);
+                    true
+                }
+                _ => false,
+            }
+        });
+
+        answers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn received(line: &str) -> AprsPacket {
+        AprsPacket::decode_textual(line.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn ids_wrap_around() {
+        let mut ids = MessageIdGenerator::numeric(2).unwrap();
+        assert_eq!(b"00", &ids.next_id()[..]);
+        assert_eq!(b"01", &ids.next_id()[..]);
+        for _ in 2..100 {
+            ids.next_id();
+        }
+        assert_eq!(b"00", &ids.next_id()[..]);
+
+        let mut ids = MessageIdGenerator::alphanumeric(3).unwrap();
+        for _ in 0..35 {
+            ids.next_id();
+        }
+        assert_eq!(b"00Z", &ids.next_id()[..]);
+        assert_eq!(b"010", &ids.next_id()[..]);
+
+        assert!(MessageIdGenerator::numeric(0).is_none());
+        assert!(MessageIdGenerator::alphanumeric(6).is_none());
+    }
+
+    #[test]
+    fn tracks_acks() {
+        let mut builder = MessageBuilder::new(
+            Callsign::new_no_ssid("APRS"),
+            MessageIdGenerator::numeric(3).unwrap(),
+        );
+
+        let first = builder.build(b"N1CALL", b"Hello").unwrap();
+        let second = builder.build(b"N1CALL", b"Still there?").unwrap();
+        assert_eq!(Some(&b"000"[..]), first.id.as_deref());
+        assert_eq!(Some(&b"001"[..]), second.id.as_deref());
+        assert!(builder.is_pending(b"000"));
+
+        // acks from other stations don't count
+        assert!(builder
+            .observe(&received("N2CALL>APRS::N0CALL   :ack000"))
+            .is_empty());
+        assert_eq!(
+            vec![MessageKind::Ack(b"000".to_vec())],
+            builder.observe(&received("N1CALL>APRS::N0CALL   :ack000"))
+        );
+        assert!(!builder.is_pending(b"000"));
+
+        // reply-ack in a message
+        assert_eq!(
+            vec![MessageKind::Ack(b"001".to_vec())],
+            builder.observe(&received("N1CALL>APRS::N0CALL   :Yes{AB}001"))
+        );
+        assert_eq!(0, builder.pending().count());
+    }
+
+    #[test]
+    fn tracks_rejections() {
+        let mut builder = MessageBuilder::new(
+            Callsign::new_no_ssid("APRS"),
+            MessageIdGenerator::alphanumeric(2).unwrap(),
+        );
+        builder.build(b"N1CALL", b"Hello").unwrap();
+
+        assert_eq!(
+            vec![MessageKind::Rej(b"00".to_vec())],
+            builder.observe(&received("N1CALL>APRS::N0CALL   :rej00"))
+        );
+        assert!(builder
+            .observe(&received("N1CALL>APRS::N0CALL   :rej00"))
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        use serde_test::{assert_de_tokens_error, assert_tokens, Token};
+
+        let mut ids = MessageIdGenerator::numeric(2).unwrap();
+        ids.next_id();
+        let tokens = |next| {
+            vec![
+                Token::Struct {
+                    name: "MessageIdGenerator",
+                    len: 3,
+                },
+                Token::Str("base"),
+                Token::U64(10),
+                Token::Str("len"),
+                Token::U64(2),
+                Token::Str("next"),
+                Token::U64(next),
+                Token::StructEnd,
+            ]
+        };
+        assert_tokens(&ids, &tokens(1));
+        assert_de_tokens_error::<MessageIdGenerator>(
+            &tokens(100),
+            "message ID counter out of range",
+        );
+    }
+}
@@ -1,15 +1,22 @@
-use std::convert::TryInto;
-use std::io::Write;
+use alloc::borrow::Cow;
+use core::convert::TryInto;
 
+use base91;
+use io::Write;
+use text;
 use Callsign;
 use DecodeError;
 use EncodeError;
 use Latitude;
 use Precision;
+use Symbol;
 
 use crate::Longitude;
+#[cfg(not(feature = "std"))]
+use prelude::*;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Message {
     M0,
     M1,
@@ -94,6 +101,116 @@ impl Message {
     }
 }
 
+/// The meaning of a Mic-E [`Message`], as defined by the spec.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MicEStatus {
+    OffDuty,
+    EnRoute,
+    InService,
+    Returning,
+    Committed,
+    Special,
+    Priority,
+    /// One of the custom messages, numbered 0 to 6.
+    Custom(u8),
+    Emergency,
+    Unknown,
+}
+
+impl From<Message> for MicEStatus {
+    fn from(message: Message) -> Self {
+        match message {
+            Message::M0 => MicEStatus::OffDuty,
+            Message::M1 => MicEStatus::EnRoute,
+            Message::M2 => MicEStatus::InService,
+            Message::M3 => MicEStatus::Returning,
+            Message::M4 => MicEStatus::Committed,
+            Message::M5 => MicEStatus::Special,
+            Message::M6 => MicEStatus::Priority,
+            Message::C0 => MicEStatus::Custom(0),
+            Message::C1 => MicEStatus::Custom(1),
+            Message::C2 => MicEStatus::Custom(2),
+            Message::C3 => MicEStatus::Custom(3),
+            Message::C4 => MicEStatus::Custom(4),
+            Message::C5 => MicEStatus::Custom(5),
+            Message::C6 => MicEStatus::Custom(6),
+            Message::Emergency => MicEStatus::Emergency,
+            Message::Unknown => MicEStatus::Unknown,
+        }
+    }
+}
+
+impl From<MicEStatus> for Message {
+    /// Custom messages above 6 become `Message::Unknown`.
+    fn from(status: MicEStatus) -> Self {
+        match status {
+            MicEStatus::OffDuty => Message::M0,
+            MicEStatus::EnRoute => Message::M1,
+            MicEStatus::InService => Message::M2,
+            MicEStatus::Returning => Message::M3,
+            MicEStatus::Committed => Message::M4,
+            MicEStatus::Special => Message::M5,
+            MicEStatus::Priority => Message::M6,
+            MicEStatus::Custom(0) => Message::C0,
+            MicEStatus::Custom(1) => Message::C1,
+            MicEStatus::Custom(2) => Message::C2,
+            MicEStatus::Custom(3) => Message::C3,
+            MicEStatus::Custom(4) => Message::C4,
+            MicEStatus::Custom(5) => Message::C5,
+            MicEStatus::Custom(6) => Message::C6,
+            MicEStatus::Emergency => Message::Emergency,
+            MicEStatus::Custom(_) | MicEStatus::Unknown => Message::Unknown,
+        }
+    }
+}
+
+/// The radio or tracker that sent a Mic-E packet,
+/// identified by the type codes it adds around the comment.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MicERadio {
+    KenwoodThD7a,
+    KenwoodThD72,
+    KenwoodThD74,
+    KenwoodThD75,
+    KenwoodTmD700,
+    KenwoodTmD710,
+    YaesuVx8,
+    YaesuVx8g,
+    YaesuFt1d,
+    YaesuFt2d,
+    YaesuFt3d,
+    YaesuFt5d,
+    YaesuFtm100d,
+    YaesuFtm300d,
+    YaesuFtm350,
+    YaesuFtm400dr,
+    ByonicsTinyTrak3,
+    ByonicsTinyTrak4,
+}
+
+// The type code at the start of the comment, and the suffix at the end.
+// Entries with a suffix come before the same prefix without one.
+const RADIOS: &[(u8, &[u8], MicERadio)] = &[
+    (b'>', b"=", MicERadio::KenwoodThD72),
+    (b'>', b"^", MicERadio::KenwoodThD74),
+    (b'>', b"&", MicERadio::KenwoodThD75),
+    (b'>', b"", MicERadio::KenwoodThD7a),
+    (b']', b"=", MicERadio::KenwoodTmD710),
+    (b']', b"", MicERadio::KenwoodTmD700),
+    (b'`', b"_ ", MicERadio::YaesuVx8),
+    (b'`', b"_\"", MicERadio::YaesuFtm350),
+    (b'`', b"_#", MicERadio::YaesuVx8g),
+    (b'`', b"_$", MicERadio::YaesuFt1d),
+    (b'`', b"_%", MicERadio::YaesuFtm400dr),
+    (b'`', b"_)", MicERadio::YaesuFtm100d),
+    (b'`', b"_(", MicERadio::YaesuFt2d),
+    (b'`', b"_0", MicERadio::YaesuFt3d),
+    (b'`', b"_1", MicERadio::YaesuFtm300d),
+    (b'`', b"_3", MicERadio::YaesuFt5d),
+    (b'\'', b"|3", MicERadio::ByonicsTinyTrak3),
+    (b'\'', b"|4", MicERadio::ByonicsTinyTrak4),
+];
+
 /// A speed. Valid values range from 0 to 799 knots.
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub struct Speed(u32);
@@ -137,6 +254,7 @@ impl Course {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AprsMicE {
     pub latitude: Latitude,
     pub longitude: Longitude,
@@ -145,8 +263,8 @@ pub struct AprsMicE {
     pub message: Message,
     pub speed: Speed,
     pub course: Course,
-    pub symbol_table: u8,
-    pub symbol_code: u8,
+    pub symbol: Symbol,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
     pub comment: Vec<u8>,
 
     pub current: bool,
@@ -161,19 +279,18 @@ impl AprsMicE {
         current: bool,
     ) -> Result<Self, DecodeError> {
         let (latitude, precision, message, long_offset, long_dir) =
-            decode_callsign(&to).ok_or(DecodeError::InvalidMicEDestination(to))?;
+            decode_callsign(&to).ok_or(DecodeError::InvalidMicEDestination(to, 0))?;
 
         let info = b
             .get(0..8)
-            .ok_or_else(|| DecodeError::InvalidMicEInformation(b.to_vec()))?;
+            .ok_or_else(|| DecodeError::InvalidMicEInformation(b.to_vec(), 0))?;
         let comment = b.get(8..).unwrap_or(&[]).to_vec();
 
         let longitude = decode_longitude(&info[0..3], long_offset, long_dir)
-            .ok_or_else(|| DecodeError::InvalidMicEInformation(b.to_vec()))?;
+            .ok_or_else(|| DecodeError::InvalidMicEInformation(b.to_vec(), 0))?;
         let (speed, course) = decode_speed_and_course(&info[3..6])
-            .ok_or_else(|| DecodeError::InvalidMicEInformation(b.to_vec()))?;
-        let symbol_code = info[6];
-        let symbol_table = info[7];
+            .ok_or_else(|| DecodeError::InvalidMicEInformation(b.to_vec(), 0))?;
+        let symbol = Symbol::from_bytes(info[7], info[6]);
 
         Ok(Self {
             data_type_identifier,
@@ -184,30 +301,125 @@ impl AprsMicE {
             message,
             speed,
             course,
-            symbol_table,
-            symbol_code,
+            symbol,
             comment,
 
             current,
         })
     }
 
-    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
-        if self.current {
-            buf.write_all(&[b'`'])?;
-        } else {
-            buf.write_all(&[b'\''])?;
+    /// Decodes the hex telemetry at the start of the comment:
+    /// five channels after a `` ` ``, or channels 1 and 3 after a `'`.
+    pub fn telemetry(&self) -> Option<Vec<u8>> {
+        self.split_telemetry().0
+    }
+
+    /// Decodes the altitude in meters from the base-91 `xxx}` field,
+    /// which follows any telemetry and may be preceded by a one-byte radio type code.
+    pub fn altitude_meters(&self) -> Option<i32> {
+        let rest = self.split_telemetry().1;
+        let offset = altitude_offset(rest)?;
+
+        Some(base91::decode_ascii(&rest[offset..offset + 3])? as i32 - 10000)
+    }
+
+    // Splits off the telemetry at the start of the comment, if there is any
+    fn split_telemetry(&self) -> (Option<Vec<u8>>, &[u8]) {
+        let channels = match self.comment.first() {
+            Some(b'`') => 5,
+            Some(b'\'') => 2,
+            _ => return (None, &self.comment),
+        };
+
+        let hex = match self.comment.get(1..1 + channels * 2) {
+            Some(hex) if hex.iter().all(u8::is_ascii_hexdigit) => hex,
+            _ => return (None, &self.comment),
+        };
+
+        let values = hex
+            .chunks(2)
+            .map(|c| (hex_digit(c[0]) << 4) | hex_digit(c[1]))
+            .collect();
+
+        (Some(values), &self.comment[1 + channels * 2..])
+    }
+
+    /// Identifies the radio from the type codes around the comment.
+    pub fn radio(&self) -> Option<MicERadio> {
+        self.split_radio().0
+    }
+
+    /// The comment as UTF-8, with invalid sequences replaced by `�`.
+    /// See [`display_text_latin1`](crate::display_text_latin1) for a Latin-1 fallback.
+    pub fn comment_display(&self) -> Cow<'_, str> {
+        text::display_text(&self.comment)
+    }
+
+    /// The comment without any telemetry, radio type codes or altitude.
+    pub fn comment_text(&self) -> &[u8] {
+        let rest = match self.split_telemetry() {
+            (Some(_), rest) => rest,
+            (None, _) => self.split_radio().1,
+        };
+
+        match altitude_offset(rest) {
+            Some(offset) => &rest[offset + 4..],
+            None => rest,
         }
+    }
+
+    // Splits off the radio type code at the start of the comment, and the suffix at the end
+    fn split_radio(&self) -> (Option<MicERadio>, &[u8]) {
+        if self.split_telemetry().0.is_some() {
+            return (None, &self.comment);
+        }
+
+        let (&first, rest) = match self.comment.split_first() {
+            Some(c) => c,
+            None => return (None, &self.comment),
+        };
+
+        RADIOS
+            .iter()
+            .find(|(prefix, suffix, _)| *prefix == first && rest.ends_with(suffix))
+            .map_or((None, &self.comment), |(_, suffix, radio)| {
+                (Some(*radio), &rest[..rest.len() - suffix.len()])
+            })
+    }
+
+    pub fn status(&self) -> MicEStatus {
+        self.message.into()
+    }
+
+    /// Sets the message bits sent in the destination address.
+    pub fn set_status(&mut self, status: MicEStatus) {
+        self.message = status.into();
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_all(&[self.identifier()])?;
 
         self.encode_longitude(buf)?;
         self.encode_speed_and_course(buf)?;
 
-        buf.write_all(&[self.symbol_code, self.symbol_table])?;
+        buf.write_all(&[self.symbol.code_byte(), self.symbol.table_byte()])?;
         buf.write_all(&self.comment)?;
 
         Ok(())
     }
 
+    // Prefer the identifier we were decoded from (e.g. the old 0x1c/0x1d ones),
+    // as long as it still agrees with `current`
+    fn identifier(&self) -> u8 {
+        match (self.data_type_identifier, self.current) {
+            (0x1c, true) | (b'`', true) | (0x1d, false) | (b'\'', false) => {
+                self.data_type_identifier
+            }
+            (_, true) => b'`',
+            (_, false) => b'\'',
+        }
+    }
+
     pub fn encode_destination(&self) -> Callsign {
         let mut encoded_lat = vec![];
         // safe to do
@@ -298,6 +510,15 @@ impl AprsMicE {
     }
 }
 
+// Where the `xxx}` altitude is: at the start, or after a type code that isn't known
+fn altitude_offset(rest: &[u8]) -> Option<usize> {
+    (0..=1).find(|&offset| {
+        rest.get(offset..offset + 4).map_or(false, |field| {
+            field[3] == b'}' && base91::decode_ascii(&field[0..3]).is_some()
+        })
+    })
+}
+
 enum MessageBit {
     Zero,
     CustomOne,
@@ -369,6 +590,15 @@ impl LongDir {
     }
 }
 
+// only valid for ASCII hex digits
+fn hex_digit(c: u8) -> u8 {
+    match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'f' => c - b'a' + 10,
+        _ => c - b'A' + 10,
+    }
+}
+
 // returns the ASCII value, since we can have spaces
 fn decode_latitude_digit(c: u8) -> Option<u8> {
     match c {
@@ -587,8 +817,7 @@ mod tests {
                 message: Message::M0,
                 speed: Speed::new(20).unwrap(),
                 course: Course::new(251).unwrap(),
-                symbol_table: b'/',
-                symbol_code: b'j',
+                symbol: Symbol::new('/', 'j').unwrap(),
                 comment: b"Hello world!".to_vec(),
                 current: true
             },
@@ -612,4 +841,118 @@ mod tests {
 
         assert_eq!(to, data.encode_destination());
     }
+
+    #[test]
+    fn encode_preserves_identifier() {
+        let information = &br#"(_fn"Oj/Hello world!"#[..];
+        let to = Callsign::new_no_ssid("PPPPPP");
+
+        for (identifier, current) in [(b'`', true), (0x1c, true), (b'\'', false), (0x1d, false)] {
+            let data = AprsMicE::decode(identifier, information, to.clone(), current).unwrap();
+
+            let mut buf = vec![];
+            data.encode(&mut buf).unwrap();
+            assert_eq!(identifier, buf[0]);
+        }
+    }
+
+    #[test]
+    fn encode_identifier_follows_current() {
+        let information = &br#"(_fn"Oj/Hello world!"#[..];
+        let to = Callsign::new_no_ssid("PPPPPP");
+
+        let mut data = AprsMicE::decode(0x1c, information, to, true).unwrap();
+        data.current = false;
+
+        let mut buf = vec![];
+        data.encode(&mut buf).unwrap();
+        assert_eq!(b'\'', buf[0]);
+    }
+
+    #[test]
+    fn status_round_trip() {
+        let information = &br#"(_fn"Oj/Hello world!"#[..];
+        let to = Callsign::new_no_ssid("PPPPPP");
+
+        let mut data = AprsMicE::decode(b'`', information, to, true).unwrap();
+        assert_eq!(MicEStatus::OffDuty, data.status());
+
+        for status in [
+            MicEStatus::Emergency,
+            MicEStatus::Priority,
+            MicEStatus::Custom(3),
+        ] {
+            data.set_status(status);
+
+            let decoded =
+                AprsMicE::decode(b'`', information, data.encode_destination(), true).unwrap();
+            assert_eq!(status, decoded.status());
+        }
+
+        data.set_status(MicEStatus::Custom(7));
+        assert_eq!(Message::Unknown, data.message);
+    }
+
+    #[test]
+    fn altitude_and_telemetry() {
+        let to = Callsign::new_no_ssid("PPPPPP");
+        let decode = |information: &[u8]| AprsMicE::decode(b'`', information, to.clone(), true);
+
+        // 61 m, with a Kenwood type code in front
+        let data = decode(&br#"(_fn"Oj/]"4T}Hello"#[..]).unwrap();
+        assert_eq!(Some(61), data.altitude_meters());
+        assert_eq!(None, data.telemetry());
+        assert_eq!(b"Hello", data.comment_text());
+
+        // a type code that isn't known is skipped as well
+        let data = decode(&br#"(_fn"Oj/ "4T}Hello"#[..]).unwrap();
+        assert_eq!(None, data.radio());
+        assert_eq!(Some(61), data.altitude_meters());
+        assert_eq!(b"Hello", data.comment_text());
+
+        let data = decode(&br#"(_fn"Oj/`0A1B2C3D4E"4T}"#[..]).unwrap();
+        assert_eq!(Some(vec![0x0a, 0x1b, 0x2c, 0x3d, 0x4e]), data.telemetry());
+        assert_eq!(Some(61), data.altitude_meters());
+
+        let data = decode(&br#"(_fn"Oj/'FF00"#[..]).unwrap();
+        assert_eq!(Some(vec![0xff, 0x00]), data.telemetry());
+        assert_eq!(None, data.altitude_meters());
+
+        let data = decode(&br#"(_fn"Oj/Hello world!"#[..]).unwrap();
+        assert_eq!(None, data.telemetry());
+        assert_eq!(None, data.altitude_meters());
+    }
+
+    #[test]
+    fn radio_type_codes() {
+        let to = Callsign::new_no_ssid("PPPPPP");
+        let decode =
+            |information: &[u8]| AprsMicE::decode(b'`', information, to.clone(), true).unwrap();
+
+        let data = decode(&br#"(_fn"Oj/]"4T}Hello="#[..]);
+        assert_eq!(Some(MicERadio::KenwoodTmD710), data.radio());
+        assert_eq!(Some(61), data.altitude_meters());
+        assert_eq!(b"Hello", data.comment_text());
+
+        let data = decode(&br#"(_fn"Oj/>Hello"#[..]);
+        assert_eq!(Some(MicERadio::KenwoodThD7a), data.radio());
+        assert_eq!(b"Hello", data.comment_text());
+
+        let data = decode(&br#"(_fn"Oj/`Hello_%"#[..]);
+        assert_eq!(Some(MicERadio::YaesuFtm400dr), data.radio());
+        assert_eq!(b"Hello", data.comment_text());
+
+        let data = decode(&br#"(_fn"Oj/'Hello|3"#[..]);
+        assert_eq!(Some(MicERadio::ByonicsTinyTrak3), data.radio());
+        assert_eq!(b"Hello", data.comment_text());
+
+        // telemetry rather than a type code
+        let data = decode(&br#"(_fn"Oj/'FF00Hello"#[..]);
+        assert_eq!(None, data.radio());
+        assert_eq!(b"Hello", data.comment_text());
+
+        let data = decode(&br#"(_fn"Oj/Hello world!"#[..]);
+        assert_eq!(None, data.radio());
+        assert_eq!(b"Hello world!", data.comment_text());
+    }
 }
@@ -0,0 +1,492 @@
+//! Raw NMEA sentences from a GPS receiver, sent as is by trackers. These start
+//! with the '$' APRS Data Type Identifier, which is also the start of the sentence.
+//! Positions are decoded from RMC and GGA sentences, from any talker.
+//!
+//! Examples:
+//! - "$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A"
+//! - "$GPGGA,123519,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,*47"
+//!
+//! Stations can also be sent to a GPS or a Kenwood radio as waypoints, with
+//! `$GPWPL` or `$PKWDWPL` sentences.
+
+use bytes::parse_bytes;
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsData;
+use AprsPacket;
+use Callsign;
+use DecodeError;
+use EncodeError;
+use Latitude;
+use Longitude;
+use NmeaSource;
+use Symbol;
+use Timestamp;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AprsNmeaPosition {
+    pub to: Callsign,
+    /// `Rmc` or `Gga`
+    pub source: NmeaSource,
+    /// Time of the fix in UTC, always `Timestamp::HHMMSS`
+    pub timestamp: Option<Timestamp>,
+    /// `false` if the receiver reports that it doesn't have a fix
+    pub valid: bool,
+    pub latitude: Latitude,
+    pub longitude: Longitude,
+    /// Only in RMC sentences
+    pub speed_knots: Option<f64>,
+    /// Only in RMC sentences
+    pub course: Option<f64>,
+    /// Altitude above mean sea level in meters, only in GGA sentences
+    pub altitude_m: Option<f64>,
+
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
+    raw: Vec<u8>,
+}
+
+impl AprsNmeaPosition {
+    /// Decodes the whole information field, including the `$`.
+    /// The checksum is verified if there is one.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        let err = || DecodeError::InvalidNmea(b.to_owned(), 0);
+
+        let sentence = b.strip_prefix(b"$").ok_or_else(err)?;
+        let sentence = sentence
+            .strip_suffix(b"\r\n")
+            .or_else(|| sentence.strip_suffix(b"\r"))
+            .unwrap_or(sentence);
+        let sentence = match sentence.iter().rposition(|&c| c == b'*') {
+            Some(i) => {
+                let expected = core::str::from_utf8(&sentence[i + 1..])
+                    .ok()
+                    .and_then(|c| u8::from_str_radix(c, 16).ok())
+                    .ok_or_else(err)?;
+                if checksum(&sentence[..i]) != expected {
+                    return Err(err());
+                }
+                &sentence[..i]
+            }
+            None => sentence,
+        };
+
+        let fields: Vec<_> = sentence.split(|&c| c == b',').collect();
+        let field = |i: usize| fields.get(i).copied().filter(|f| !f.is_empty());
+        let number = |i: usize| field(i).and_then(parse_bytes::<f64>);
+
+        let (source, time, valid, position, speed_knots, course, altitude_m) =
+            match sentence_type(sentence) {
+                Some(b"RMC") => (
+                    NmeaSource::Rmc,
+                    field(1),
+                    field(2) == Some(b"A"),
+                    3,
+                    number(7),
+                    number(8),
+                    None,
+                ),
+                Some(b"GGA") => (
+                    NmeaSource::Gga,
+                    field(1),
+                    field(6).map_or(false, |q| q != b"0"),
+                    2,
+                    None,
+                    None,
+                    number(9).filter(|_| field(10) == Some(b"M")),
+                ),
+                _ => return Err(err()),
+            };
+
+        let latitude = decode_coordinate(field(position), field(position + 1), 2, b'N', b'S')
+            .and_then(Latitude::new)
+            .ok_or_else(err)?;
+        let longitude = decode_coordinate(field(position + 2), field(position + 3), 3, b'E', b'W')
+            .and_then(Longitude::new)
+            .ok_or_else(err)?;
+
+        Ok(Self {
+            to,
+            source,
+            timestamp: time.and_then(decode_time),
+            valid,
+            latitude,
+            longitude,
+            speed_knots,
+            course,
+            altitude_m,
+            raw: b.to_owned(),
+        })
+    }
+
+    /// The sentence as it was received.
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+
+    /// Replaces the position in the sentence, keeping the other fields.
+    /// Used when scrubbing, since the sentence is encoded as received.
+    #[cfg(feature = "std")]
+    pub(crate) fn set_position(&mut self, latitude: Latitude, longitude: Longitude) {
+        let (deg, min, hundredths, north) = latitude.dmh();
+        let lat = format!("{:02}{:02}.{:02}", deg, min, hundredths);
+        let ns = if north { "N" } else { "S" };
+        let (deg, min, hundredths, east) = longitude.dmh();
+        let lon = format!("{:03}{:02}.{:02}", deg, min, hundredths);
+        let ew = if east { "E" } else { "W" };
+
+        let start = match self.source {
+            NmeaSource::Rmc => 3,
+            _ => 2,
+        };
+        let sentence = self.raw[1..]
+            .split(|&c| c == b'*')
+            .next()
+            .unwrap_or_default();
+        let mut fields: Vec<&[u8]> = sentence.split(|&c| c == b',').collect();
+        for (i, value) in [lat.as_bytes(), ns.as_bytes(), lon.as_bytes(), ew.as_bytes()]
+            .iter()
+            .enumerate()
+        {
+            fields[start + i] = value;
+        }
+        let sentence = fields.join(&b',');
+
+        let mut raw = b"$".to_vec();
+        raw.extend_from_slice(&sentence);
+        if self.raw.contains(&b'*') {
+            raw.extend_from_slice(format!("*{:02X}", checksum(&sentence)).as_bytes());
+        }
+
+        self.raw = raw;
+        self.latitude = latitude;
+        self.longitude = longitude;
+    }
+
+    /// Encodes the sentence as it was received.
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_all(&self.raw)?;
+
+        Ok(())
+    }
+}
+
+/// A station or object, to be sent to a GPS or radio as a waypoint.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NmeaWaypoint {
+    pub name: Vec<u8>,
+    pub latitude: Latitude,
+    pub longitude: Longitude,
+    /// Shown by Kenwood radios
+    pub symbol: Option<Symbol>,
+}
+
+impl NmeaWaypoint {
+    /// The waypoint for a packet with a position. Objects and items are named
+    /// after themselves, anything else after the sender.
+    /// Third-party packets give the waypoint of the packet they carry.
+    pub fn from_packet(packet: &AprsPacket) -> Option<Self> {
+        if let AprsData::ThirdParty(t) = &packet.data {
+            return Self::from_packet(&t.packet);
+        }
+
+        let (latitude, longitude) = packet.data.coordinates()?;
+        let (name, symbol) = match &packet.data {
+            AprsData::Object(o) => (o.name.clone(), Some(o.position.symbol)),
+            AprsData::Item(i) => (i.name.clone(), Some(i.position.symbol)),
+            AprsData::Position(p) => (callsign_name(packet), Some(p.symbol)),
+            AprsData::MicE(m) => (callsign_name(packet), Some(m.symbol)),
+            _ => (callsign_name(packet), None),
+        };
+
+        Some(Self {
+            name,
+            latitude,
+            longitude,
+            symbol,
+        })
+    }
+
+    /// Encodes a `$GPWPL` sentence, with checksum and line ending.
+    pub fn encode_wpl<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        let mut sentence = b"GPWPL,".to_vec();
+        self.encode_position(&mut sentence)?;
+        sentence.push(b',');
+        sentence.extend_from_slice(self.valid_name()?);
+
+        encode_sentence(&sentence, buf)
+    }
+
+    /// Encodes a Kenwood `$PKWDWPL` sentence, with checksum and line ending.
+    /// Time, speed, course and altitude are left empty.
+    pub fn encode_kenwood<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        let mut sentence = b"PKWDWPL,,V,".to_vec();
+        self.encode_position(&mut sentence)?;
+        sentence.extend_from_slice(b",,,,,");
+        sentence.extend_from_slice(self.valid_name()?);
+        sentence.push(b',');
+        if let Some(symbol) = self.symbol {
+            sentence.extend_from_slice(&[symbol.table_byte(), symbol.code_byte()]);
+        }
+
+        encode_sentence(&sentence, buf)
+    }
+
+    fn valid_name(&self) -> Result<&[u8], EncodeError> {
+        let valid = !self.name.is_empty()
+            && self
+                .name
+                .iter()
+                .all(|c| (b' '..=b'~').contains(c) && !b",*$".contains(c));
+        if !valid {
+            return Err(EncodeError::InvalidData);
+        }
+
+        Ok(&self.name)
+    }
+
+    fn encode_position(&self, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+        let (deg, min, hundredths, north) = self.latitude.dmh();
+        write!(
+            buf,
+            "{:02}{:02}.{:02},{},",
+            deg,
+            min,
+            hundredths,
+            if north { 'N' } else { 'S' }
+        )?;
+        let (deg, min, hundredths, east) = self.longitude.dmh();
+        write!(
+            buf,
+            "{:03}{:02}.{:02},{}",
+            deg,
+            min,
+            hundredths,
+            if east { 'E' } else { 'W' }
+        )?;
+
+        Ok(())
+    }
+}
+
+fn callsign_name(packet: &AprsPacket) -> Vec<u8> {
+    packet.from.to_string().into_bytes()
+}
+
+fn encode_sentence<W: Write>(sentence: &[u8], buf: &mut W) -> Result<(), EncodeError> {
+    buf.write_all(b"$")?;
+    buf.write_all(sentence)?;
+    write!(buf, "*{:02X}\r\n", checksum(sentence))?;
+
+    Ok(())
+}
+
+/// Whether the information field is an NMEA sentence with a position.
+// Receivers without a fix leave the coordinates empty, e.g. `$GPRMC,123519,V,,,,,,,230394,,`.
+// These aren't positions, and decode as unknown packets.
+pub(crate) fn is_position_sentence(b: &[u8]) -> bool {
+    let sentence = match b.strip_prefix(b"$") {
+        Some(s) => s,
+        None => return false,
+    };
+    let position = match sentence_type(sentence) {
+        Some(b"RMC") => 3,
+        Some(b"GGA") => 2,
+        _ => return false,
+    };
+
+    let fields: Vec<_> = sentence.split(|&c| c == b',').collect();
+    let empty = |i: usize| fields.get(i).map_or(false, |f| f.is_empty());
+    !empty(position) && !empty(position + 2)
+}
+
+// The address field is a two character talker ID, e.g. `GP` or `GN`, and the sentence type
+fn sentence_type(sentence: &[u8]) -> Option<&[u8]> {
+    match sentence.get(..6) {
+        Some([_, _, t @ .., b',']) => Some(t),
+        _ => None,
+    }
+}
+
+/// XOR of all characters between the `$` and the `*`.
+pub(crate) fn checksum(sentence: &[u8]) -> u8 {
+    sentence.iter().fold(0, |acc, c| acc ^ c)
+}
+
+// `4807.038` is 48 degrees and 7.038 minutes
+fn decode_coordinate(
+    value: Option<&[u8]>,
+    hemisphere: Option<&[u8]>,
+    degree_digits: usize,
+    positive: u8,
+    negative: u8,
+) -> Option<f64> {
+    let value = value?;
+    let point = value.iter().position(|&c| c == b'.').unwrap_or(value.len());
+    if point != degree_digits + 2 {
+        return None;
+    }
+
+    let degrees: f64 = parse_bytes(&value[..degree_digits])?;
+    let minutes: f64 = parse_bytes(&value[degree_digits..])?;
+    if minutes >= 60.0 {
+        return None;
+    }
+    let coordinate = degrees + minutes / 60.0;
+
+    match hemisphere? {
+        [c] if *c == positive => Some(coordinate),
+        [c] if *c == negative => Some(-coordinate),
+        _ => None,
+    }
+}
+
+// `123519` or `123519.00`
+fn decode_time(b: &[u8]) -> Option<Timestamp> {
+    let digits = b.get(..6)?;
+    if !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    let hour = parse_bytes(&digits[0..2])?;
+    let minute = parse_bytes(&digits[2..4])?;
+    let second = parse_bytes(&digits[4..6])?;
+
+    Some(Timestamp::HHMMSS(hour, minute, second))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use PacketKind;
+
+    fn default_callsign() -> Callsign {
+        Callsign::new_no_ssid("APRS")
+    }
+
+    #[test]
+    fn rmc() {
+        let original = &b"$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A"[..];
+        let nmea = AprsNmeaPosition::decode(original, default_callsign()).unwrap();
+
+        assert_eq!(NmeaSource::Rmc, nmea.source);
+        assert_eq!(Some(Timestamp::HHMMSS(12, 35, 19)), nmea.timestamp);
+        assert!(nmea.valid);
+        assert_relative_eq!(48.1173, *nmea.latitude);
+        assert_relative_eq!(11.516666, *nmea.longitude, epsilon = 0.00001);
+        assert_eq!(Some(22.4), nmea.speed_knots);
+        assert_eq!(Some(84.4), nmea.course);
+        assert_eq!(None, nmea.altitude_m);
+
+        let mut buf = vec![];
+        nmea.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn gga() {
+        let original = &b"$GNGGA,123519.00,4807.038,S,01131.000,W,1,08,0.9,545.4,M,46.9,M,,"[..];
+        let nmea = AprsNmeaPosition::decode(original, default_callsign()).unwrap();
+
+        assert_eq!(NmeaSource::Gga, nmea.source);
+        assert_eq!(Some(Timestamp::HHMMSS(12, 35, 19)), nmea.timestamp);
+        assert!(nmea.valid);
+        assert_relative_eq!(-48.1173, *nmea.latitude);
+        assert_relative_eq!(-11.516666, *nmea.longitude, epsilon = 0.00001);
+        assert_eq!(None, nmea.speed_knots);
+        assert_eq!(Some(545.4), nmea.altitude_m);
+    }
+
+    #[test]
+    fn no_fix() {
+        let nmea = AprsNmeaPosition::decode(
+            b"$GPRMC,123519,V,4807.038,N,01131.000,E,,,230394,,",
+            default_callsign(),
+        )
+        .unwrap();
+        assert!(!nmea.valid);
+        assert_eq!(None, nmea.speed_knots);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        for b in [
+            // wrong checksum
+            &b"$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6B"[..],
+            b"$GPRMC,123519,A,,,,,,,230394,,",
+            b"$GPRMC,123519,A,4807.038,X,01131.000,E,,,230394,,",
+            b"$GPGSV,3,1,11,03,03,111,00",
+        ] {
+            assert_eq!(
+                Err(DecodeError::InvalidNmea(b.to_vec(), 0)),
+                AprsNmeaPosition::decode(b, default_callsign())
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn set_position() {
+        let mut nmea = AprsNmeaPosition::decode(
+            b"$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A",
+            default_callsign(),
+        )
+        .unwrap();
+        nmea.set_position(
+            Latitude::from_dmh(48, 7, 0, true).unwrap(),
+            Longitude::from_dmh(11, 31, 0, true).unwrap(),
+        );
+
+        let reencoded = AprsNmeaPosition::decode(nmea.raw(), default_callsign()).unwrap();
+        assert_eq!(nmea, reencoded);
+        assert_eq!(
+            &b"$GPRMC,123519,A,4807.00,N,01131.00,E,022.4,084.4,230394,003.1,W*61"[..],
+            nmea.raw()
+        );
+    }
+
+    #[test]
+    fn waypoints() {
+        let packet = AprsPacket::decode_textual(
+            b"N0CALL-9>APRS:;LEADER   *092345z4903.50N/07201.75W>088/036",
+        )
+        .unwrap();
+        let waypoint = NmeaWaypoint::from_packet(&packet).unwrap();
+        assert_eq!(b"LEADER", &waypoint.name[..]);
+
+        let mut buf = vec![];
+        waypoint.encode_wpl(&mut buf).unwrap();
+        assert_eq!(&b"$GPWPL,4903.50,N,07201.75,W,LEADER*4F\r\n"[..], buf);
+
+        let mut buf = vec![];
+        waypoint.encode_kenwood(&mut buf).unwrap();
+        assert_eq!(
+            &b"$PKWDWPL,,V,4903.50,N,07201.75,W,,,,,LEADER,/>*3B\r\n"[..],
+            buf
+        );
+
+        let packet = AprsPacket::decode_textual(b"N0CALL-9>APRS:>Status").unwrap();
+        assert_eq!(None, NmeaWaypoint::from_packet(&packet));
+    }
+
+    #[test]
+    fn position_sentences() {
+        assert!(is_position_sentence(b"$GPRMC,123519,A"));
+        assert!(is_position_sentence(b"$GNGGA,123519"));
+        assert!(!is_position_sentence(b"$GPGSV,3,1,11"));
+        assert!(!is_position_sentence(b"$ULTW0000"));
+        assert!(!is_position_sentence(b"$GPRMC,123519,V,,,,,,,230394,,"));
+        assert!(!is_position_sentence(b"$GPGGA,123519,,,,,0,00,,,M,,M,,"));
+    }
+
+    #[test]
+    fn no_fix_without_coordinates() {
+        let raw = b"N0CALL>APRS:$GPRMC,123519,V,,,,,,,230394,,";
+        assert_eq!(
+            Ok(AprsData::Unknown(default_callsign())),
+            AprsPacket::decode_textual(raw).map(|p| p.data)
+        );
+        assert_eq!(Some(PacketKind::Unknown), AprsPacket::peek_kind(raw));
+    }
+}
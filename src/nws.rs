@@ -0,0 +1,187 @@
+//! National Weather Service bulletins, which are messages addressed to
+//! `NWS-` followed by the product type, e.g.
+//! `:NWS-WARN :092010z,THUNDER_STORM,AR_ASHLEY,{S9JbA`.
+//!
+//! The text holds the expiry time, the event and the list of affected zones or
+//! counties. Zones may be abbreviated: `OKZ019>022` is a range, and in
+//! `TXZ001-003` the second zone reuses the prefix of the first.
+
+use core::convert::TryFrom;
+
+use bytes::parse_digits;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use Timestamp;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NwsProduct {
+    /// `NWS-WARN`
+    Warning,
+    /// `NWS-WATCH`
+    Watch,
+    /// `NWS-ADVIS`
+    Advisory,
+    /// `NWS-CANCL`
+    Cancellation,
+    /// Addressees starting with `SKY`, for spotter networks
+    Skywarn,
+    /// Any other `NWS-` addressee, with the product type
+    Other(Vec<u8>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NwsBulletin {
+    pub product: NwsProduct,
+    /// When the bulletin expires
+    pub expires: Option<Timestamp>,
+    /// Type of event, with underscores for spaces, e.g. `THUNDER_STORM`
+    pub event: Vec<u8>,
+    /// Affected zones or counties, with ranges and abbreviations expanded
+    pub zones: Vec<Vec<u8>>,
+}
+
+impl NwsBulletin {
+    /// Decodes a bulletin from the addressee and text of a message.
+    /// Returns `None` if the addressee isn't a weather service one.
+    pub fn decode(addressee: &[u8], text: &[u8]) -> Option<Self> {
+        let product = if let Some(kind) = addressee
+            .strip_prefix(b"NWS-")
+            .or_else(|| addressee.strip_prefix(b"NWS_"))
+        {
+            match kind {
+                b"WARN" => NwsProduct::Warning,
+                b"WATCH" => NwsProduct::Watch,
+                b"ADVIS" => NwsProduct::Advisory,
+                b"CANCL" => NwsProduct::Cancellation,
+                _ => NwsProduct::Other(kind.to_owned()),
+            }
+        } else if addressee.starts_with(b"SKY") {
+            NwsProduct::Skywarn
+        } else {
+            return None;
+        };
+
+        let mut fields = text.split(|&c| c == b',');
+        let mut first = fields.next().unwrap_or_default();
+        let expires = Timestamp::try_from(first).ok();
+        if expires.is_some() {
+            first = fields.next().unwrap_or_default();
+        }
+
+        let mut zones = vec![];
+        for field in fields {
+            expand_zones(field, &mut zones);
+        }
+
+        Some(Self {
+            product,
+            expires,
+            event: first.to_owned(),
+            zones,
+        })
+    }
+}
+
+// Zones look like `OKZ019`: a prefix followed by a number.
+// Abbreviated zones only have the number, and take the prefix of the one before.
+fn expand_zones(field: &[u8], zones: &mut Vec<Vec<u8>>) {
+    // county names can contain dashes too
+    let abbreviated = field
+        .split(|&c| c == b'-')
+        .skip(1)
+        .all(|p| p.iter().all(|c| c.is_ascii_digit() || *c == b'>'));
+    let parts: Vec<&[u8]> = if abbreviated {
+        field
+            .split(|&c| c == b'-')
+            .filter(|p| !p.is_empty())
+            .collect()
+    } else {
+        vec![field]
+    };
+
+    let mut prefix: &[u8] = &[];
+    for part in parts {
+        let mut range = part.splitn(2, |&c| c == b'>');
+        let start = range.next().unwrap_or_default();
+        let end = range.next();
+
+        let digits = start
+            .iter()
+            .rev()
+            .take_while(|c| c.is_ascii_digit())
+            .count();
+        if digits < start.len() {
+            prefix = &start[..start.len() - digits];
+        }
+        let number = &start[start.len() - digits..];
+
+        let range = match (parse_digits::<u32>(number), end.and_then(parse_digits)) {
+            (Some(first), Some(last)) if first <= last && last - first < 1000 => first..=last,
+            _ => {
+                zones.push([prefix, number].concat());
+                continue;
+            }
+        };
+        for n in range {
+            zones.push([prefix, format!("{:0width$}", n, width = digits).as_bytes()].concat());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warning() {
+        let bulletin =
+            NwsBulletin::decode(b"NWS-WARN", b"092010z,THUNDER_STORM,AR_ASHLEY").unwrap();
+
+        assert_eq!(
+            NwsBulletin {
+                product: NwsProduct::Warning,
+                expires: Some(Timestamp::DDHHMM(9, 20, 10)),
+                event: b"THUNDER_STORM".to_vec(),
+                zones: vec![b"AR_ASHLEY".to_vec()],
+            },
+            bulletin
+        );
+    }
+
+    #[test]
+    fn zone_ranges() {
+        let bulletin = NwsBulletin::decode(
+            b"NWS_ADVIS",
+            b"101800z,WIND,OKZ019>022,TXZ001-003,LA_ST-TAMMANY",
+        )
+        .unwrap();
+
+        assert_eq!(NwsProduct::Advisory, bulletin.product);
+        assert_eq!(
+            vec![
+                b"OKZ019".to_vec(),
+                b"OKZ020".to_vec(),
+                b"OKZ021".to_vec(),
+                b"OKZ022".to_vec(),
+                b"TXZ001".to_vec(),
+                b"TXZ003".to_vec(),
+                b"LA_ST-TAMMANY".to_vec(),
+            ],
+            bulletin.zones
+        );
+    }
+
+    #[test]
+    fn other_addressees() {
+        let bulletin = NwsBulletin::decode(b"SKYCSTL", b"SPOTTERS ACTIVATED").unwrap();
+        assert_eq!(NwsProduct::Skywarn, bulletin.product);
+        assert_eq!(None, bulletin.expires);
+        assert_eq!(b"SPOTTERS ACTIVATED", &bulletin.event[..]);
+
+        assert_eq!(
+            NwsProduct::Other(b"TSTRM".to_vec()),
+            NwsBulletin::decode(b"NWS-TSTRM", b"").unwrap().product
+        );
+        assert_eq!(None, NwsBulletin::decode(b"N0CALL", b"092010z,WIND"));
+    }
+}
@@ -0,0 +1,306 @@
+//! An Object Report describes something other than the sending station, such as
+//! a net, an event or a repeater. It starts with the ';' APRS Data Type Identifier,
+//! followed by a fixed nine character name, a live (`*`) or killed (`_`) flag,
+//! a timestamp and a position in the same formats as position reports.
+//!
+//! Example:
+//! - ";LEADER   *092345z4903.50N/07201.75W>088/036"
+
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsPosition;
+use Callsign;
+use DataExtension;
+use DecodeError;
+use EncodeError;
+use Latitude;
+use Longitude;
+use StormData;
+use Timestamp;
+
+pub(crate) const NAME_LEN: usize = 9;
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AprsObject {
+    /// Name of the object, without the padding
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
+    pub name: Vec<u8>,
+    /// `false` if the object has been killed
+    pub live: bool,
+    pub timestamp: Timestamp,
+    /// The position, symbol, data extension and comment of the object.
+    /// Its timestamp is always `None`.
+    pub position: AprsPosition,
+}
+
+impl AprsObject {
+    /// Decodes the information field after the `;`.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        let err = || DecodeError::InvalidObject(b.to_owned(), 0);
+
+        let name = b.get(0..NAME_LEN).ok_or_else(err)?;
+        let live = match b.get(NAME_LEN) {
+            Some(b'*') => true,
+            Some(b'_') => false,
+            _ => return Err(err()),
+        };
+        let timestamp =
+            Timestamp::try_from(b.get(10..17).ok_or_else(err)?).map_err(|e| e.offset_by(10))?;
+        let position = AprsPosition::decode_position(b'!', &b[17..], to, None, false)
+            .map_err(|e| e.offset_by(17))?;
+
+        let mut name = name.to_owned();
+        while name.last() == Some(&b' ') {
+            name.pop();
+        }
+        if name.is_empty() {
+            return Err(err());
+        }
+
+        Ok(Self {
+            name,
+            live,
+            timestamp,
+            position,
+        })
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.live
+    }
+
+    /// Returns the report that kills this object, stamped with `timestamp`.
+    /// It keeps the name, position and symbol, so that receivers can match it
+    /// to the object they are showing.
+    pub fn killed(&self, timestamp: Timestamp) -> Self {
+        Self {
+            live: false,
+            timestamp,
+            ..self.clone()
+        }
+    }
+
+    pub fn to(&self) -> &Callsign {
+        &self.position.to
+    }
+
+    pub fn latitude(&self) -> Latitude {
+        self.position.latitude
+    }
+
+    pub fn longitude(&self) -> Longitude {
+        self.position.longitude
+    }
+
+    pub fn comment(&self) -> &[u8] {
+        &self.position.comment
+    }
+
+    pub fn comment_display(&self) -> Cow<'_, str> {
+        self.position.comment_display()
+    }
+
+    /// Decodes the data extension at the start of the comment, e.g. course and speed.
+    pub fn extension(&self) -> Option<DataExtension> {
+        self.position.extension()
+    }
+
+    /// Sets the data extension at the start of the comment, e.g. the range of a repeater.
+    pub fn set_extension(&mut self, extension: Option<DataExtension>) -> Result<(), EncodeError> {
+        self.position.set_extension(extension)
+    }
+
+    /// Returns the text of a signpost with the `\m` symbol, e.g. a speed limit.
+    pub fn signpost(&self) -> Option<&[u8]> {
+        self.position.signpost()
+    }
+
+    pub fn set_signpost(&mut self, text: &[u8]) -> Result<(), EncodeError> {
+        self.position.set_signpost(text)
+    }
+
+    /// Decodes the storm data in the comment, for objects with the hurricane symbol `\@`.
+    pub fn storm(&self) -> Option<StormData> {
+        self.position.storm()
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        if !is_valid_name(&self.name, 1) {
+            return Err(EncodeError::InvalidObjectName(self.name.clone()));
+        }
+
+        buf.write_all(b";")?;
+        buf.write_all(&self.name)?;
+        for _ in self.name.len()..NAME_LEN {
+            buf.write_all(b" ")?;
+        }
+        buf.write_all(if self.live { b"*" } else { b"_" })?;
+        self.timestamp.encode(buf)?;
+        self.position.encode_position(buf)?;
+
+        Ok(())
+    }
+}
+
+// Names are printable ASCII
+pub(crate) fn is_valid_name(name: &[u8], min_len: usize) -> bool {
+    (min_len..=NAME_LEN).contains(&name.len()) && name.iter().all(|c| (b' '..=b'~').contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AprsCst;
+    use Precision;
+    use Symbol;
+
+    fn default_callsign() -> Callsign {
+        Callsign::new_no_ssid("APRS")
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        let original = &b";LEADER   *092345z4903.50N/07201.75W>088/036"[..];
+        let object = AprsObject::decode(&original[1..], default_callsign()).unwrap();
+
+        assert_eq!(b"LEADER", &object.name[..]);
+        assert!(object.live);
+        assert_eq!(Timestamp::DDHHMM(9, 23, 45), object.timestamp);
+        assert_relative_eq!(49.058334, *object.latitude(), epsilon = 0.00001);
+        assert_relative_eq!(-72.029166, *object.longitude(), epsilon = 0.00001);
+        assert_eq!(Precision::HundredthMinute, object.position.precision);
+        assert_eq!('>', object.position.symbol.code());
+        assert_eq!(b"088/036", object.comment());
+        assert!(matches!(
+            object.extension(),
+            Some(DataExtension::CourseSpeed(_))
+        ));
+
+        let mut buf = vec![];
+        object.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn killed_and_compressed() {
+        let original = &b";Net 8pm  _111111z/5L!!<*e7>7P[Weekly net"[..];
+        let object = AprsObject::decode(&original[1..], default_callsign()).unwrap();
+
+        assert_eq!(b"Net 8pm", &object.name[..]);
+        assert!(!object.live);
+        assert!(matches!(
+            object.position.cst,
+            AprsCst::CompressedSome { .. }
+        ));
+        assert_eq!(b"Weekly net", object.comment());
+
+        let mut buf = vec![];
+        object.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn kill() {
+        let object = AprsObject::decode(
+            &b"NET 8PM  *092345z4903.50N/07201.75W>Weekly net"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        assert!(object.is_alive());
+
+        let killed = object.killed(Timestamp::DDHHMM(10, 1, 0));
+        assert!(!killed.is_alive());
+
+        let mut buf = vec![];
+        killed.encode(&mut buf).unwrap();
+        assert_eq!(&b";NET 8PM  _100100z4903.50N/07201.75W>Weekly net"[..], buf);
+    }
+
+    #[test]
+    fn storm() {
+        let object = AprsObject::decode(
+            &br"BONNIE   *092345z2520.00N\08300.00W@090/012/HC^110/135/0963>040&120%200"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        assert_eq!(Some(110), object.storm().map(|s| s.sustained_wind));
+    }
+
+    #[test]
+    fn decode_invalid() {
+        for b in [
+            &b"LEADER"[..],
+            &b"LEADER   #092345z4903.50N/07201.75W>"[..],
+            &b"         *092345z4903.50N/07201.75W>"[..],
+        ] {
+            assert_eq!(
+                Err(DecodeError::InvalidObject(b.to_vec(), 0)),
+                AprsObject::decode(b, default_callsign())
+            );
+        }
+    }
+
+    #[test]
+    fn encode_invalid_name() {
+        let mut object = AprsObject::decode(
+            &b"LEADER   *092345z4903.50N/07201.75W>"[..],
+            default_callsign(),
+        )
+        .unwrap();
+
+        object.name = b"TOO LONG NAME".to_vec();
+        assert!(matches!(
+            object.encode(&mut vec![]),
+            Err(EncodeError::InvalidObjectName(_))
+        ));
+    }
+
+    #[test]
+    fn signpost() {
+        let original = &br";I-95 N   *092345z4903.50N\07201.75Wm{55}Speed limit"[..];
+        let mut object = AprsObject::decode(&original[1..], default_callsign()).unwrap();
+        assert_eq!(Some(&b"55"[..]), object.signpost());
+        assert_eq!(b"{55}Speed limit", object.comment());
+
+        object.set_signpost(b"65").unwrap();
+        let mut buf = vec![];
+        object.encode(&mut buf).unwrap();
+        assert_eq!(
+            &br";I-95 N   *092345z4903.50N\07201.75Wm{65}Speed limit"[..],
+            buf
+        );
+
+        assert!(object.set_signpost(b"1000").is_err());
+        assert!(object.set_signpost(b"").is_err());
+
+        // `/m` is a microwave tower, not a signpost
+        object.position.symbol = Symbol::new('/', 'm').unwrap();
+        assert_eq!(None, object.signpost());
+    }
+
+    #[test]
+    fn range() {
+        let mut object = AprsObject::decode(
+            &b"147.00-NC*092345z4903.50N/07201.75WrT100 -060"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        assert_eq!(None, object.position.range_miles());
+
+        object.set_extension(Some(DataExtension::Rng(25))).unwrap();
+        assert_eq!(Some(25), object.position.range_miles());
+        assert_relative_eq!(40.2336, object.position.range_km().unwrap());
+
+        let mut buf = vec![];
+        object.encode(&mut buf).unwrap();
+        assert_eq!(
+            &b";147.00-NC*092345z4903.50N/07201.75WrRNG0025T100 -060"[..],
+            buf
+        );
+    }
+}
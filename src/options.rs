@@ -0,0 +1,210 @@
+//! Tolerance knobs for decoding. Many stations send packets that bend the
+//! APRS spec, so by default the decoder accepts whatever it can make sense of.
+//! [`ParseOptions::strict`] rejects those packets instead, e.g. for validating
+//! the output of an encoder.
+//!
+//! ```rust
+//! use aprs_parser::{AprsPacket, ParseOptions};
+//!
+//! let raw = b"N0CALL>APRS:>092345hNet tonight";
+//! assert!(AprsPacket::decode_textual_with(raw, &ParseOptions::default()).is_ok());
+//! assert!(AprsPacket::decode_textual_with(raw, &ParseOptions::strict()).is_err());
+//! ```
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsData;
+use AprsPacket;
+use DecodeError;
+use Timestamp;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct ParseOptions {
+    /// Keep timestamps that don't parse, as [`Timestamp::Unsupported`] or as
+    /// part of a status report's text.
+    pub malformed_timestamps: bool,
+    /// Accept status reports with HHMMSS or local time timestamps.
+    /// The spec only allows zulu DDHHMM timestamps there.
+    pub noncompliant_status_timestamps: bool,
+    /// Accept control characters and bytes outside of ASCII in the packet.
+    /// Mic-E packets are exempt, as their information field is binary.
+    pub out_of_spec_characters: bool,
+}
+
+impl ParseOptions {
+    /// Accepts everything that [`AprsPacket::decode_textual`] does.
+    pub fn lenient() -> Self {
+        Self {
+            malformed_timestamps: true,
+            noncompliant_status_timestamps: true,
+            out_of_spec_characters: true,
+        }
+    }
+
+    /// Rejects packets that don't follow the spec.
+    pub fn strict() -> Self {
+        Self {
+            malformed_timestamps: false,
+            noncompliant_status_timestamps: false,
+            out_of_spec_characters: false,
+        }
+    }
+
+    pub fn with_malformed_timestamps(mut self, allow: bool) -> Self {
+        self.malformed_timestamps = allow;
+        self
+    }
+
+    pub fn with_noncompliant_status_timestamps(mut self, allow: bool) -> Self {
+        self.noncompliant_status_timestamps = allow;
+        self
+    }
+
+    pub fn with_out_of_spec_characters(mut self, allow: bool) -> Self {
+        self.out_of_spec_characters = allow;
+        self
+    }
+
+    // Checks a leniently decoded packet against the options
+    pub(crate) fn check(&self, raw: &[u8], packet: &AprsPacket) -> Result<(), DecodeError> {
+        if !self.out_of_spec_characters
+            && !is_mic_e(packet)
+            && raw.iter().any(|&c| !(b' '..=b'~').contains(&c))
+        {
+            return Err(DecodeError::InvalidPacket(raw.to_owned(), 0));
+        }
+
+        self.check_data(raw, &packet.data)
+    }
+
+    // `raw` is the textual packet `data` was decoded from
+    fn check_data(&self, raw: &[u8], data: &AprsData) -> Result<(), DecodeError> {
+        let info = raw
+            .iter()
+            .position(|&c| c == b':')
+            .map_or(raw.len(), |i| i + 1);
+
+        match data {
+            // after the data type identifier
+            AprsData::Position(p) => self.check_timestamp(p.timestamp.as_ref(), info + 1),
+            // after the data type identifier, name and live/killed flag
+            AprsData::Object(o) => self.check_timestamp(Some(&o.timestamp), info + 11),
+            AprsData::Status(s) => {
+                self.check_timestamp(s.timestamp(), info + 1)?;
+
+                if !self.noncompliant_status_timestamps && !s.is_timestamp_compliant() {
+                    let mut buf = vec![];
+                    if let Some(t) = s.timestamp() {
+                        t.encode(&mut buf).ok();
+                    }
+                    return Err(DecodeError::InvalidTimestamp(buf, info + 1));
+                }
+
+                // a timestamp that didn't parse was kept as text
+                let text = s.comment();
+                if !self.malformed_timestamps
+                    && s.timestamp().is_none()
+                    && looks_like_timestamp(text)
+                {
+                    return Err(DecodeError::InvalidTimestamp(
+                        text[..7].to_owned(),
+                        info + 1,
+                    ));
+                }
+
+                Ok(())
+            }
+            AprsData::ThirdParty(t) => {
+                let start = (info + 1).min(raw.len());
+                self.check_data(&raw[start..], &t.packet.data)
+                    .map_err(|e| e.offset_by(start))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_timestamp(
+        &self,
+        timestamp: Option<&Timestamp>,
+        offset: usize,
+    ) -> Result<(), DecodeError> {
+        match timestamp {
+            Some(Timestamp::Unsupported(b)) if !self.malformed_timestamps => {
+                Err(DecodeError::InvalidTimestamp(b.clone(), offset))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self::lenient()
+    }
+}
+
+fn is_mic_e(packet: &AprsPacket) -> bool {
+    match &packet.data {
+        AprsData::MicE(_) => true,
+        AprsData::ThirdParty(t) => is_mic_e(&t.packet),
+        _ => false,
+    }
+}
+
+// e.g. `0923x5z`
+fn looks_like_timestamp(b: &[u8]) -> bool {
+    b.len() >= 7 && b[0].is_ascii_digit() && matches!(b[6], b'z' | b'h' | b'/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(s: &[u8], options: &ParseOptions) -> Result<AprsPacket, DecodeError> {
+        AprsPacket::decode_textual_with(s, options)
+    }
+
+    #[test]
+    fn lenient_by_default() {
+        for s in &[
+            &b"N0CALL>APRS:>092345hNet tonight"[..],
+            b"N0CALL>APRS:>0923x5zNet tonight",
+            b"N0CALL>APRS:/0923x5/4903.50N/07201.75W-Test",
+            b"N0CALL>APRS:>Caf\xe9",
+        ] {
+            let lenient = decode(s, &ParseOptions::default()).unwrap();
+            assert_eq!(AprsPacket::decode_textual(s).unwrap(), lenient);
+            assert!(decode(s, &ParseOptions::strict()).is_err());
+        }
+    }
+
+    #[test]
+    fn strict() {
+        for s in &[
+            &b"N0CALL>APRS:>092345zNet tonight"[..],
+            b"N0CALL>APRS:>Net tonight",
+            b"N0CALL>APRS:@092345z4903.50N/07201.75W-Test",
+            b"N0CALL>PPPPPP:\x1c(_fn\"Oj/]Hello",
+        ] {
+            assert!(decode(s, &ParseOptions::strict()).is_ok());
+        }
+    }
+
+    #[test]
+    fn single_knob() {
+        let options = ParseOptions::strict().with_noncompliant_status_timestamps(true);
+        assert!(decode(b"N0CALL>APRS:>092345hNet tonight", &options).is_ok());
+        assert!(matches!(
+            decode(b"N0CALL>APRS:>0923x5zNet tonight", &options),
+            Err(DecodeError::InvalidTimestamp(..))
+        ));
+    }
+
+    #[test]
+    fn third_party() {
+        let s = b"N0CALL>APRS:}N1CALL>APRS,TCPIP,N0CALL*:>092345hNet tonight";
+        assert!(decode(s, &ParseOptions::default()).is_ok());
+        let e = decode(s, &ParseOptions::strict()).unwrap_err();
+        assert_eq!(&b"092345h"[..], &s[e.offset()..e.offset() + 7]);
+    }
+}
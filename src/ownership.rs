@@ -0,0 +1,93 @@
+//! Objects and items are identified by name alone, and any station may take one
+//! over by transmitting it with its own source callsign.
+//! [`ObjectOwnershipTracker`] remembers who last transmitted each name and
+//! reports when that changes.
+
+use std::collections::HashMap;
+
+use Callsign;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OwnershipChange {
+    /// The object name, without trailing padding.
+    pub name: Vec<u8>,
+    pub previous: Callsign,
+    pub new: Callsign,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ObjectOwnershipTracker {
+    owners: HashMap<Vec<u8>, Callsign>,
+}
+
+impl ObjectOwnershipTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records that `source` transmitted the object called `name`.
+    /// Returns the change of ownership if a different station transmitted it last.
+    /// Trailing spaces in `name` are ignored, so padded object names
+    /// match their unpadded form.
+    pub fn observe(&mut self, name: &[u8], source: &Callsign) -> Option<OwnershipChange> {
+        let name = trim_name(name);
+
+        match self.owners.get_mut(name) {
+            Some(owner) if owner == source => None,
+            Some(owner) => {
+                let previous = std::mem::replace(owner, source.clone());
+                Some(OwnershipChange {
+                    name: name.to_vec(),
+                    previous,
+                    new: source.clone(),
+                })
+            }
+            None => {
+                self.owners.insert(name.to_vec(), source.clone());
+                None
+            }
+        }
+    }
+
+    /// Returns the station that last transmitted the object called `name`.
+    pub fn owner(&self, name: &[u8]) -> Option<&Callsign> {
+        self.owners.get(trim_name(name))
+    }
+
+    /// Forgets the object called `name`, e.g. after it has been killed.
+    pub fn forget(&mut self, name: &[u8]) -> Option<Callsign> {
+        self.owners.remove(trim_name(name))
+    }
+}
+
+fn trim_name(name: &[u8]) -> &[u8] {
+    let end = name.iter().rposition(|&b| b != b' ').map_or(0, |i| i + 1);
+    &name[..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_takeover() {
+        let mut tracker = ObjectOwnershipTracker::new();
+        let a = Callsign::new_no_ssid("N0CALL");
+        let b = Callsign::new_with_ssid("N1CALL", "9");
+
+        assert_eq!(None, tracker.observe(b"LEADER   ", &a));
+        assert_eq!(None, tracker.observe(b"LEADER", &a));
+        assert_eq!(
+            Some(OwnershipChange {
+                name: b"LEADER".to_vec(),
+                previous: a.clone(),
+                new: b.clone(),
+            }),
+            tracker.observe(b"LEADER   ", &b)
+        );
+        assert_eq!(Some(&b), tracker.owner(b"LEADER"));
+
+        assert_eq!(Some(b), tracker.forget(b"LEADER"));
+        assert_eq!(None, tracker.observe(b"LEADER", &a));
+    }
+}
@@ -1,17 +1,43 @@
-use std::borrow::Cow;
-use std::io::Write;
+use alloc::borrow::Cow;
 
 use callsign::CallsignField;
+use io::Write;
+use json;
+use nmea;
+use path;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsCapabilities;
+use AprsCst;
+use AprsItem;
 use AprsMessage;
 use AprsMicE;
+use AprsNmeaPosition;
+use AprsObject;
 use AprsPosition;
+use AprsQuery;
+use AprsRawWeather;
 use AprsStatus;
+use AprsTelemetry;
+use AprsTestData;
+use AprsThirdParty;
+use AprsUserDefined;
+use AprsWeather;
 use Callsign;
 use DecodeError;
+use DeviceInfo;
 use EncodeError;
+use Hops;
+use JsonValue;
+use Latitude;
+use Longitude;
+use ParseOptions;
+use QConstruct;
+use Reception;
 use Via;
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AprsPacket {
     pub from: Callsign,
     pub via: Vec<Via>,
@@ -23,30 +49,36 @@ impl AprsPacket {
         let header_delimiter = s
             .iter()
             .position(|x| *x == b':')
-            .ok_or_else(|| DecodeError::InvalidPacket(s.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidPacket(s.to_owned(), 0))?;
         let (header, rest) = s.split_at(header_delimiter);
         let body = &rest[1..];
 
         let from_delimiter = header
             .iter()
             .position(|x| *x == b'>')
-            .ok_or_else(|| DecodeError::InvalidPacket(s.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidPacket(s.to_owned(), 0))?;
         let (from, rest) = header.split_at(from_delimiter);
         let (from, _) = Callsign::decode_textual(from)
-            .ok_or_else(|| DecodeError::InvalidCallsign(from.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidCallsign(from.to_owned(), 0))?;
 
         let to_and_via = &rest[1..];
         let mut to_and_via = to_and_via.split(|x| *x == b',');
+        let to_offset = from_delimiter + 1;
 
-        let to = to_and_via
+        let to_bytes = to_and_via
             .next()
-            .ok_or_else(|| DecodeError::InvalidPacket(s.to_owned()))?;
-        let (to, _) = Callsign::decode_textual(to)
-            .ok_or_else(|| DecodeError::InvalidCallsign(to.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidPacket(s.to_owned(), 0))?;
+        let (to, _) = Callsign::decode_textual(to_bytes)
+            .ok_or_else(|| DecodeError::InvalidCallsign(to_bytes.to_owned(), to_offset))?;
 
         let mut via = vec![];
+        let mut offset = to_offset + to_bytes.len() + 1;
         for v in to_and_via {
-            via.push(Via::decode_textual(v).ok_or_else(|| DecodeError::InvalidVia(v.to_owned()))?);
+            via.push(
+                Via::decode_textual(v)
+                    .ok_or_else(|| DecodeError::InvalidVia(v.to_owned(), offset))?,
+            );
+            offset += v.len() + 1;
         }
 
         // if our Via path looks like A,B,C*,D,E
@@ -62,17 +94,147 @@ impl AprsPacket {
             }
         }
 
-        let data = AprsData::decode(body, to)?;
+        let data = AprsData::decode(body, to)
+            .map_err(|e| info_error(e, header_delimiter + 1, to_offset))?;
 
         Ok(AprsPacket { from, via, data })
     }
 
+    /// Like [`decode_textual`](Self::decode_textual), but rejects packets that
+    /// `options` don't tolerate.
+    pub fn decode_textual_with(s: &[u8], options: &ParseOptions) -> Result<Self, DecodeError> {
+        let packet = Self::decode_textual(s)?;
+        options.check(s, &packet)?;
+
+        Ok(packet)
+    }
+
     pub fn to(&self) -> Option<&Callsign> {
         self.data.to()
     }
 
+    /// Classifies a textual packet from its data type identifier, without
+    /// decoding it or checking the callsigns. Returns `None` if there's no header.
+    /// Packets that are classified may still fail to decode.
+    pub fn peek_kind(s: &[u8]) -> Option<PacketKind> {
+        let header_delimiter = s.iter().position(|x| *x == b':')?;
+        if !s[..header_delimiter].contains(&b'>') {
+            return None;
+        }
+
+        Some(PacketKind::of_information(&s[header_delimiter + 1..]))
+    }
+
+    /// Identifies the device or software that sent this packet from its destination field.
+    pub fn device_info(&self) -> Option<DeviceInfo> {
+        self.to().and_then(DeviceInfo::from_tocall)
+    }
+
+    /// Classifies how this packet was received, based on the used digipeaters
+    /// and the q-construct in its path.
+    pub fn reception(&self) -> Reception {
+        path::reception(&self.via)
+    }
+
+    /// Whether an igate may transmit this packet, received from APRS-IS, on RF.
+    /// Packets from unverified stations (`TCPXX` or `qAX`) and packets marked
+    /// `NOGATE` or `RFONLY` mustn't be.
+    pub fn may_gate_to_rf(&self) -> bool {
+        path::may_gate_to_rf(&self.via)
+    }
+
+    /// Whether an igate may send this packet, heard on RF, to APRS-IS.
+    /// Packets that came from APRS-IS (`TCPIP` or `TCPXX`) and packets marked
+    /// `NOGATE` or `RFONLY` mustn't be, including when they're carried in a
+    /// third-party packet.
+    pub fn may_gate_to_is(&self) -> bool {
+        if let AprsData::ThirdParty(t) = &self.data {
+            if !t.packet.may_gate_to_is() {
+                return false;
+            }
+        }
+
+        path::may_gate_to_is(&self.via)
+    }
+
+    /// Index of the first path entry that hasn't been used yet, which is the one
+    /// a digipeater should act on. Entries after a q-construct aren't considered.
+    pub fn next_hop(&self) -> Option<usize> {
+        path::next_hop(&self.via)
+    }
+
+    /// Marks the path entry at `index` as used, along with the entries before it.
+    /// Returns `false` if there's no such entry in the over-the-air path.
+    pub fn mark_hop_used(&mut self, index: usize) -> bool {
+        path::mark_used(&mut self.via, index)
+    }
+
+    /// Decrements the `WIDEn-N` alias at `index`, marking it used once no hops remain.
+    /// Returns `false` if the entry isn't an alias with hops remaining.
+    pub fn decrement_hop(&mut self, index: usize) -> bool {
+        path::decrement(&mut self.via, index)
+    }
+
+    /// Inserts a digipeater's own callsign before the entry at `index`, marked as used.
+    /// Returns `false` if `index` is past the over-the-air path.
+    pub fn insert_digipeater(&mut self, index: usize, callsign: Callsign) -> bool {
+        path::insert_digipeater(&mut self.via, index, callsign)
+    }
+
+    /// Returns the q-construct added by APRS-IS, with the server or igate
+    /// callsign that follows it.
+    pub fn q_construct(&self) -> Option<(QConstruct, Option<&Callsign>)> {
+        let (i, q) = self.via.iter().enumerate().find_map(|(i, v)| match v {
+            Via::QConstruct(q) => Some((i, *q)),
+            Via::Callsign(..) => None,
+        })?;
+
+        Some((
+            q,
+            self.via.get(i + 1).and_then(Via::callsign).map(|(c, _)| c),
+        ))
+    }
+
+    /// Returns the digipeaters that have repeated this packet, in order.
+    /// Generic aliases such as `WIDE1` are skipped.
+    pub fn digipeaters(&self) -> Vec<&Callsign> {
+        path::digipeaters(&self.via)
+    }
+
+    /// Counts the hops requested in, and used from, the over-the-air part of the path.
+    pub fn hops(&self) -> Hops {
+        path::hops(&self.via)
+    }
+
+    /// For messages carrying telemetry definitions, returns whether the sender is
+    /// defining its own telemetry or relaying definitions for the addressed station.
+    pub fn telemetry_definition_source(&self) -> Option<TelemetryDefinitionSource> {
+        let msg = match &self.data {
+            AprsData::Message(m) if m.is_telemetry_definition() => m,
+            _ => return None,
+        };
+
+        if msg
+            .addressee
+            .eq_ignore_ascii_case(self.from.to_string().as_bytes())
+        {
+            Some(TelemetryDefinitionSource::Own)
+        } else {
+            Some(TelemetryDefinitionSource::Relayed)
+        }
+    }
+
+    /// The packet in the stable JSON representation described in the `json` module.
+    pub fn to_json_value(&self) -> JsonValue {
+        json::packet(self)
+    }
+
     /// Used for encoding a packet into ASCII for transmission on the internet (APRS-IS)
+    /// The part of the path before any q-construct must be valid for AX.25:
+    /// at most 8 entries, each a valid AX.25 address.
     pub fn encode_textual<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        path::validate(&self.via)?;
+
         // logic to clear extraneous asterisks
         let mut via = self.via.clone();
         let mut heard = false;
@@ -99,35 +261,68 @@ impl AprsPacket {
         Ok(())
     }
 
+    /// Encodes the packet with [`encode_textual`](Self::encode_textual) into a new buffer.
+    pub fn encode_to_vec(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::with_capacity(self.encoded_len_hint());
+        self.encode_textual(&mut buf)?;
+
+        Ok(buf)
+    }
+
+    /// Encodes the packet with [`encode_textual`](Self::encode_textual).
+    /// Text that isn't valid UTF-8, e.g. a Latin-1 comment, is replaced by `�`.
+    pub fn encode_to_string(&self) -> Result<String, EncodeError> {
+        Ok(into_string(self.encode_to_vec()?))
+    }
+
+    /// An estimate of the length of the textual encoding, for sizing buffers.
+    pub fn encoded_len_hint(&self) -> usize {
+        let via: usize = self
+            .via
+            .iter()
+            .map(|v| match v {
+                Via::Callsign(c, _) => callsign_len(c) + 2,
+                Via::QConstruct(_) => 4,
+            })
+            .sum();
+
+        callsign_len(&self.from)
+            + 1
+            + callsign_len(&self.data.dest_field())
+            + via
+            + 1
+            + self.data.encoded_len_hint()
+    }
+
     /// Used for decoding a packet received over the air (via KISS or otherwise)
     pub fn decode_ax25(data: &[u8]) -> Result<Self, DecodeError> {
         let dest_bytes = data
             .get(0..7)
-            .ok_or_else(|| DecodeError::InvalidPacket(data.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidPacket(data.to_owned(), 0))?;
         let (to, _, has_more) = Callsign::decode_ax25(dest_bytes)
-            .ok_or_else(|| DecodeError::InvalidCallsign(dest_bytes.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidCallsign(dest_bytes.to_owned(), 0))?;
 
         if !has_more {
-            return Err(DecodeError::InvalidPacket(data.to_owned()));
+            return Err(DecodeError::InvalidPacket(data.to_owned(), 0));
         }
 
         let src_bytes = data
             .get(7..14)
-            .ok_or_else(|| DecodeError::InvalidPacket(data.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidPacket(data.to_owned(), 0))?;
         let (from, _, mut has_more) = Callsign::decode_ax25(src_bytes)
-            .ok_or_else(|| DecodeError::InvalidCallsign(src_bytes.to_owned()))?;
+            .ok_or_else(|| DecodeError::InvalidCallsign(src_bytes.to_owned(), 7))?;
 
         let mut i = 14;
         let mut via = vec![];
         while has_more {
             let v_bytes = data
                 .get(i..(i + 7))
-                .ok_or_else(|| DecodeError::InvalidPacket(data.to_owned()))?;
+                .ok_or_else(|| DecodeError::InvalidPacket(data.to_owned(), 0))?;
 
             // vias received over AX.25 are going to be callsigns only
             // no Q-constructs
             let (v, heard, more) = Callsign::decode_ax25(v_bytes)
-                .ok_or_else(|| DecodeError::InvalidCallsign(v_bytes.to_owned()))?;
+                .ok_or_else(|| DecodeError::InvalidCallsign(v_bytes.to_owned(), i))?;
 
             via.push(Via::Callsign(v, heard));
             has_more = more;
@@ -136,12 +331,13 @@ impl AprsPacket {
 
         // verify control field and protocol id
         if data.get(i..(i + 2)) != Some(&[0x03, 0xf0]) {
-            return Err(DecodeError::InvalidPacket(data.to_owned()));
+            return Err(DecodeError::InvalidPacket(data.to_owned(), 0));
         }
         i += 2;
 
         // remainder is the information field
-        let data = AprsData::decode(data.get(i..).unwrap_or(&[]), to)?;
+        let data =
+            AprsData::decode(data.get(i..).unwrap_or(&[]), to).map_err(|e| info_error(e, i, 0))?;
 
         Ok(Self { data, from, via })
     }
@@ -154,6 +350,9 @@ impl AprsPacket {
             .encode_ax25(buf, CallsignField::Destination, true)?;
 
         let via_calls: Vec<_> = self.via.iter().filter_map(|v| v.callsign()).collect();
+        if via_calls.len() > path::MAX_VIAS {
+            return Err(EncodeError::TooManyVias(via_calls.len()));
+        }
 
         // Source address
         let has_more = !via_calls.is_empty();
@@ -180,12 +379,105 @@ impl AprsPacket {
     }
 }
 
+fn callsign_len(c: &Callsign) -> usize {
+    c.call().len() + c.ssid().map_or(0, |ssid| ssid.len() + 1)
+}
+
+// Errors from the information field are relative to its start, except for a
+// Mic-E destination, which is in the header
+fn info_error(e: DecodeError, info_offset: usize, to_offset: usize) -> DecodeError {
+    match e {
+        DecodeError::InvalidMicEDestination(to, _) => {
+            DecodeError::InvalidMicEDestination(to, to_offset)
+        }
+        e => e.offset_by(info_offset),
+    }
+}
+
+fn into_string(buf: Vec<u8>) -> String {
+    String::from_utf8(buf).unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+}
+
+/// The type of a packet, as far as it can be told from its data type identifier.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PacketKind {
+    /// Includes positions with the weather symbol
+    Position,
+    Message,
+    Status,
+    MicE,
+    Telemetry,
+    Object,
+    Item,
+    Query,
+    /// Weather reports without a position
+    Weather,
+    RawWeather,
+    Capabilities,
+    UserDefined,
+    ThirdParty,
+    Nmea,
+    TestData,
+    Unknown,
+}
+
+impl PacketKind {
+    // Classifies the information field, the same way as `AprsData::decode`
+    fn of_information(s: &[u8]) -> Self {
+        if s.starts_with(b"$ULTW") || s.starts_with(b"!!") {
+            return PacketKind::RawWeather;
+        }
+        if nmea::is_position_sentence(s) {
+            return PacketKind::Nmea;
+        }
+
+        match s.first() {
+            Some(b':') => PacketKind::Message,
+            Some(b'!') | Some(b'/') | Some(b'=') | Some(b'@') => PacketKind::Position,
+            Some(b'>') => PacketKind::Status,
+            Some(0x1c) | Some(b'`') | Some(0x1d) | Some(b'\'') => PacketKind::MicE,
+            Some(b'T') if s.get(1) == Some(&b'#') => PacketKind::Telemetry,
+            Some(b';') => PacketKind::Object,
+            Some(b')') => PacketKind::Item,
+            Some(b'?') => PacketKind::Query,
+            Some(b'_') => PacketKind::Weather,
+            Some(b'<') => PacketKind::Capabilities,
+            Some(b'{') => PacketKind::UserDefined,
+            Some(b'}') => PacketKind::ThirdParty,
+            Some(b',') => PacketKind::TestData,
+            _ => PacketKind::Unknown,
+        }
+    }
+}
+
+/// Who a telemetry definition message describes.
+#[derive(PartialEq, Eq, Debug, Copy, Clone)]
+pub enum TelemetryDefinitionSource {
+    /// The addressee is the sender itself.
+    Own,
+    /// The sender is publishing definitions on behalf of the addressee.
+    Relayed,
+}
+
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AprsData {
     Position(AprsPosition),
     Message(AprsMessage),
     Status(AprsStatus),
     MicE(AprsMicE),
+    Telemetry(AprsTelemetry),
+    Object(AprsObject),
+    Item(AprsItem),
+    Query(AprsQuery),
+    Weather(AprsWeather),
+    RawWeather(AprsRawWeather),
+    Capabilities(AprsCapabilities),
+    UserDefined(AprsUserDefined),
+    ThirdParty(AprsThirdParty),
+    Nmea(AprsNmeaPosition),
+    TestData(AprsTestData),
     Unknown(Callsign),
 }
 
@@ -196,38 +488,194 @@ impl AprsData {
             AprsData::Message(m) => Some(&m.to),
             AprsData::Status(s) => Some(&s.to),
             AprsData::MicE(_) => None,
+            AprsData::Telemetry(t) => Some(&t.to),
+            AprsData::Object(o) => Some(o.to()),
+            AprsData::Item(i) => Some(i.to()),
+            AprsData::Query(q) => Some(&q.to),
+            AprsData::Weather(w) => Some(&w.to),
+            AprsData::RawWeather(w) => Some(&w.to),
+            AprsData::Capabilities(x) => Some(&x.to),
+            AprsData::UserDefined(x) => Some(&x.to),
+            AprsData::ThirdParty(x) => Some(&x.to),
+            AprsData::Nmea(x) => Some(&x.to),
+            AprsData::TestData(x) => Some(&x.to),
             AprsData::Unknown(to) => Some(to),
         }
     }
+
+    /// Returns the coordinates carried by this payload, if it has any.
+    pub fn coordinates(&self) -> Option<(Latitude, Longitude)> {
+        match self {
+            AprsData::Position(p) => Some((p.latitude, p.longitude)),
+            AprsData::MicE(m) => Some((m.latitude, m.longitude)),
+            AprsData::Object(o) => Some((o.latitude(), o.longitude())),
+            AprsData::Item(i) => Some((i.latitude(), i.longitude())),
+            AprsData::Nmea(n) => Some((n.latitude, n.longitude)),
+            AprsData::ThirdParty(t) => t.packet.data.coordinates(),
+            AprsData::Message(_)
+            | AprsData::Status(_)
+            | AprsData::Telemetry(_)
+            | AprsData::Weather(_)
+            | AprsData::RawWeather(_)
+            | AprsData::Query(_)
+            | AprsData::Capabilities(_)
+            | AprsData::UserDefined(_)
+            | AprsData::TestData(_)
+            | AprsData::Unknown(_) => None,
+        }
+    }
+
     pub fn data_type_identifier(&self) -> u8 {
         match self {
             AprsData::Position(p) => p.data_type_identifier,
             AprsData::MicE(m) => m.data_type_identifier,
             AprsData::Message(m) => m.data_type_identifier,
             AprsData::Status(s) => s.data_type_identifier,
+            AprsData::Telemetry(_) => b'T',
+            AprsData::Object(_) => b';',
+            AprsData::Item(_) => b')',
+            AprsData::Query(_) => b'?',
+            AprsData::Weather(_) => b'_',
+            AprsData::RawWeather(w) => w.format.data_type_identifier(),
+            AprsData::Capabilities(_) => b'<',
+            AprsData::UserDefined(_) => b'{',
+            AprsData::ThirdParty(_) => b'}',
+            AprsData::Nmea(_) => b'$',
+            AprsData::TestData(_) => b',',
             AprsData::Unknown(_) => 0x00,
         }
     }
 
-    fn dest_field(&self) -> Cow<Callsign> {
+    pub(crate) fn dest_field(&self) -> Cow<'_, Callsign> {
         match self {
             AprsData::Position(p) => Cow::Borrowed(&p.to),
             AprsData::Message(m) => Cow::Borrowed(&m.to),
             AprsData::Status(s) => Cow::Borrowed(&s.to),
             AprsData::MicE(m) => Cow::Owned(m.encode_destination()),
+            AprsData::Telemetry(t) => Cow::Borrowed(&t.to),
+            AprsData::Object(o) => Cow::Borrowed(o.to()),
+            AprsData::Item(i) => Cow::Borrowed(i.to()),
+            AprsData::Query(q) => Cow::Borrowed(&q.to),
+            AprsData::Weather(w) => Cow::Borrowed(&w.to),
+            AprsData::RawWeather(w) => Cow::Borrowed(&w.to),
+            AprsData::Capabilities(x) => Cow::Borrowed(&x.to),
+            AprsData::UserDefined(x) => Cow::Borrowed(&x.to),
+            AprsData::ThirdParty(x) => Cow::Borrowed(&x.to),
+            AprsData::Nmea(x) => Cow::Borrowed(&x.to),
+            AprsData::TestData(x) => Cow::Borrowed(&x.to),
             AprsData::Unknown(to) => Cow::Borrowed(to),
         }
     }
 
+    pub fn kind(&self) -> PacketKind {
+        match self {
+            AprsData::Position(_) => PacketKind::Position,
+            AprsData::Message(_) => PacketKind::Message,
+            AprsData::Status(_) => PacketKind::Status,
+            AprsData::MicE(_) => PacketKind::MicE,
+            AprsData::Telemetry(_) => PacketKind::Telemetry,
+            AprsData::Object(_) => PacketKind::Object,
+            AprsData::Item(_) => PacketKind::Item,
+            AprsData::Query(_) => PacketKind::Query,
+            AprsData::Weather(_) => PacketKind::Weather,
+            AprsData::RawWeather(_) => PacketKind::RawWeather,
+            AprsData::Capabilities(_) => PacketKind::Capabilities,
+            AprsData::UserDefined(_) => PacketKind::UserDefined,
+            AprsData::ThirdParty(_) => PacketKind::ThirdParty,
+            AprsData::Nmea(_) => PacketKind::Nmea,
+            AprsData::TestData(_) => PacketKind::TestData,
+            AprsData::Unknown(_) => PacketKind::Unknown,
+        }
+    }
+
+    /// Encodes the information field, starting with the data type identifier.
+    pub fn encode_to_vec(&self) -> Result<Vec<u8>, EncodeError> {
+        let mut buf = Vec::with_capacity(self.encoded_len_hint());
+        self.encode(&mut buf)?;
+
+        Ok(buf)
+    }
+
+    /// Encodes the information field, starting with the data type identifier.
+    /// Text that isn't valid UTF-8, e.g. a Latin-1 comment, is replaced by `�`.
+    pub fn encode_to_string(&self) -> Result<String, EncodeError> {
+        Ok(into_string(self.encode_to_vec()?))
+    }
+
+    /// An estimate of the length of the information field, for sizing buffers.
+    pub fn encoded_len_hint(&self) -> usize {
+        // data type identifier, timestamp and position
+        fn position_len(p: &AprsPosition) -> usize {
+            let position = match p.cst {
+                AprsCst::Uncompressed => 19,
+                _ => 13,
+            };
+            1 + p.timestamp.as_ref().map_or(0, |_| 7) + position + p.comment.len()
+        }
+
+        match self {
+            AprsData::Position(p) => position_len(p),
+            AprsData::Message(m) => 11 + m.text.len() + m.id.as_ref().map_or(0, |id| id.len() + 1),
+            AprsData::Status(s) => 1 + s.timestamp().map_or(0, |_| 7) + s.comment().len(),
+            AprsData::MicE(m) => 9 + m.comment.len(),
+            AprsData::Telemetry(t) => 32 + t.comment.len(),
+            AprsData::Object(o) => 11 + position_len(&o.position) + 7,
+            AprsData::Item(i) => 2 + i.name.len() + position_len(&i.position),
+            AprsData::ThirdParty(t) => 1 + t.packet.encoded_len_hint(),
+            _ => 64,
+        }
+    }
+
     fn decode(s: &[u8], to: Callsign) -> Result<Self, DecodeError> {
         let identifier = s.first().unwrap_or(&0);
-        Ok(match identifier {
-            b':' => AprsData::Message(AprsMessage::decode(&s[1..], to)?),
-            b'!' | b'/' | b'=' | b'@' => AprsData::Position(AprsPosition::decode(s, to)?),
-            b'>' => AprsData::Status(AprsStatus::decode(&s[1..], to)?),
-            0x1c | b'`' => AprsData::MicE(AprsMicE::decode(*identifier, &s[1..], to, true)?),
-            0x1d | b'\'' => AprsData::MicE(AprsMicE::decode(*identifier, &s[1..], to, false)?),
-            _ => AprsData::Unknown(to),
+        // most decoders are given what follows the data type identifier
+        let after_identifier = |e: DecodeError| e.offset_by(1);
+
+        Ok(match PacketKind::of_information(s) {
+            PacketKind::RawWeather => AprsData::RawWeather(AprsRawWeather::decode(s, to)?),
+            PacketKind::Nmea => AprsData::Nmea(AprsNmeaPosition::decode(s, to)?),
+            PacketKind::Message => {
+                AprsData::Message(AprsMessage::decode(&s[1..], to).map_err(after_identifier)?)
+            }
+            PacketKind::Position => AprsData::Position(AprsPosition::decode(s, to)?),
+            PacketKind::Status => {
+                AprsData::Status(AprsStatus::decode(&s[1..], to).map_err(after_identifier)?)
+            }
+            PacketKind::MicE => {
+                let current = matches!(identifier, 0x1c | b'`');
+                AprsData::MicE(
+                    AprsMicE::decode(*identifier, &s[1..], to, current)
+                        .map_err(after_identifier)?,
+                )
+            }
+            PacketKind::Telemetry => {
+                AprsData::Telemetry(AprsTelemetry::decode(&s[1..], to).map_err(after_identifier)?)
+            }
+            PacketKind::Object => {
+                AprsData::Object(AprsObject::decode(&s[1..], to).map_err(after_identifier)?)
+            }
+            PacketKind::Item => {
+                AprsData::Item(AprsItem::decode(&s[1..], to).map_err(after_identifier)?)
+            }
+            PacketKind::Query => {
+                AprsData::Query(AprsQuery::decode(&s[1..], to).map_err(after_identifier)?)
+            }
+            PacketKind::Weather => {
+                AprsData::Weather(AprsWeather::decode(&s[1..], to).map_err(after_identifier)?)
+            }
+            PacketKind::Capabilities => AprsData::Capabilities(
+                AprsCapabilities::decode(&s[1..], to).map_err(after_identifier)?,
+            ),
+            PacketKind::UserDefined => AprsData::UserDefined(
+                AprsUserDefined::decode(&s[1..], to).map_err(after_identifier)?,
+            ),
+            PacketKind::ThirdParty => {
+                AprsData::ThirdParty(AprsThirdParty::decode(&s[1..], to).map_err(after_identifier)?)
+            }
+            PacketKind::TestData => {
+                AprsData::TestData(AprsTestData::decode(&s[1..], to).map_err(after_identifier)?)
+            }
+            PacketKind::Unknown => AprsData::Unknown(to),
         })
     }
 
@@ -245,6 +693,39 @@ impl AprsData {
             Self::MicE(m) => {
                 m.encode(buf)?;
             }
+            Self::Telemetry(t) => {
+                t.encode(buf)?;
+            }
+            Self::Object(o) => {
+                o.encode(buf)?;
+            }
+            Self::Item(i) => {
+                i.encode(buf)?;
+            }
+            Self::Query(q) => {
+                q.encode(buf)?;
+            }
+            Self::Weather(w) => {
+                w.encode(buf)?;
+            }
+            Self::RawWeather(w) => {
+                w.encode(buf)?;
+            }
+            Self::Capabilities(x) => {
+                x.encode(buf)?;
+            }
+            Self::UserDefined(x) => {
+                x.encode(buf)?;
+            }
+            Self::ThirdParty(x) => {
+                x.encode(buf)?;
+            }
+            Self::Nmea(x) => {
+                x.encode(buf)?;
+            }
+            Self::TestData(x) => {
+                x.encode(buf)?;
+            }
             Self::Unknown(_) => return Err(EncodeError::InvalidData),
         }
 
@@ -256,10 +737,9 @@ impl AprsData {
 mod tests {
     use super::*;
     use mic_e::{Course, Message, Speed};
-    use Latitude;
-    use Longitude;
     use Precision;
     use QConstruct;
+    use Symbol;
     use Timestamp;
 
     #[test]
@@ -279,7 +759,8 @@ mod tests {
             AprsData::Position(position) => {
                 assert_eq!(position.timestamp, Some(Timestamp::HHMMSS(7, 48, 49)));
                 assert_eq!(position.latitude.value(), 48.36016666666667);
-                assert_eq!(position.longitude.value(), 12.408166666666666);
+                // 01224.49E plus 0.009 minutes from the !W09! DAO extension
+                assert_eq!(position.longitude.value(), 12.408316666666666);
                 assert_eq!(
                     position.comment,
                     b"322/103/A=003054 !W09! id213D17F2 -039fpm +0.0rot 2.5dB 3e -0.0kHz gps1x1"
@@ -395,8 +876,7 @@ mod tests {
                     message: Message::M1,
                     speed: Speed::new(64).unwrap(),
                     course: Course::new(35).unwrap(),
-                    symbol_table: b'/',
-                    symbol_code: b'>',
+                    symbol: Symbol::new('/', '>').unwrap(),
                     comment: br#">"4z}="#.to_vec(),
                     current: true
                 })
@@ -419,6 +899,20 @@ mod tests {
             r"ICA3F2>APRS,qAS,DL4MEA:>184050hAlso with HMS format...",
             "VE9MP-12>T5RX8P,VE9GFI-2,WIDE1*,WIDE2-1,qAR,VE9QLE-10:`]Q\x1cl|ok/'\"4<}Nick - Monitoring IRG|!\"&7'M|!wTD!|3",
             r#"DF1CHB-9>UQ0RT6,ARISS,APRSAT,WIDE1-1,qAU,DB0KOE-1:`|9g\"H?>/>\"4z}="#,
+            "VE9MP-12>T5RX8P,WIDE2-1,qAR,VE9QLE-10:\x1c]Q\x1cl|ok/'\"4<}Nick",
+            "VE9MP-12>T5RX8P,WIDE2-1,qAR,VE9QLE-10:']Q\x1cl|ok/'\"4<}Nick",
+            "VE9MP-12>T5RX8P,WIDE2-1,qAR,VE9QLE-10:\x1d]Q\x1cl|ok/'\"4<}Nick",
+            r"N0CALL-11>APRS,WIDE2-1,qAR,VE9QLE-10:T#005,199,000,255,073,123,01101001",
+            r"N0CALL-13>APRS,WIDE2-1,qAR,VE9QLE-10:;LEADER   *092345z4903.50N/07201.75W>088/036",
+            r"N0CALL-13>APRS,WIDE2-1,qAR,VE9QLE-10:)AID #2!4903.50N/07201.75WA",
+            r"N0CALL-13>APRS,WIDE2-1,qAR,VE9QLE-10:?APRS? 34.02,-117.15,0200",
+            r"N0CALL-13>APRS,WIDE2-1,qAR,VE9QLE-10:<IGATE,MSG_CNT=35,LOC_CNT=14",
+            r"N0CALL-13>APRS,WIDE2-1,qAR,VE9QLE-10:{Q1qwerty",
+            r"N0CALL-13>APRS,WIDE2-1,qAR,VE9QLE-10:,Test packet, please ignore",
+            r"N0CALL-13>APRS,WIDE2-1,qAR,VE9QLE-10:$GPRMC,123519,A,4807.038,N,01131.000,E,022.4,084.4,230394,003.1,W*6A",
+            r"N0CALL-13>APRS,WIDE2-1,qAR,VE9QLE-10:}N1CALL>APRS,TCPIP,N0CALL-13*:>Gated status",
+            r"N0CALL-13>APRS,WIDE2-1,qAR,VE9QLE-10:_10090556c220s004g005t077r000p000P000h50b09900wRSW",
+            r"N0CALL-13>APRS,WIDE2-1,qAR,VE9QLE-10:$ULTW0031003702CE0069----000086A0008603E80146031600CB0008",
         ];
 
         for v in valids {
@@ -521,4 +1015,138 @@ mod tests {
         decoded.encode_textual(&mut buf).unwrap();
         assert_eq!(buf, original);
     }
+
+    #[test]
+    fn telemetry_definition_source() {
+        let own =
+            AprsPacket::decode_textual(&b"N0CALL-1>APRS::N0CALL-1 :PARM.Battery"[..]).unwrap();
+        assert_eq!(
+            Some(TelemetryDefinitionSource::Own),
+            own.telemetry_definition_source()
+        );
+
+        let relayed =
+            AprsPacket::decode_textual(&b"N0CALL>APRS::N0CALL-1 :UNIT.Volts"[..]).unwrap();
+        assert_eq!(
+            Some(TelemetryDefinitionSource::Relayed),
+            relayed.telemetry_definition_source()
+        );
+
+        let plain = AprsPacket::decode_textual(&b"N0CALL>APRS::N0CALL-1 :Hello"[..]).unwrap();
+        assert_eq!(None, plain.telemetry_definition_source());
+    }
+
+    #[test]
+    fn q_construct() {
+        let packet =
+            AprsPacket::decode_textual(&b"N0CALL>APRS,WIDE1-1*,qAR,IGATE-1:>Hi"[..]).unwrap();
+        let (q, gate) = packet.q_construct().unwrap();
+        assert_eq!(QConstruct::AR, q);
+        assert_eq!(Some(&Callsign::new_with_ssid("IGATE", "1")), gate);
+        assert!(q.is_verified());
+        assert!(q.is_rf_gated());
+
+        let packet = AprsPacket::decode_textual(&b"N0CALL>APRS,TCPIP*,qAX:>Hi"[..]).unwrap();
+        let (q, server) = packet.q_construct().unwrap();
+        assert!(!q.is_verified());
+        assert!(!q.is_rf_gated());
+        assert_eq!(None, server);
+
+        let packet = AprsPacket::decode_textual(&b"N0CALL>APRS,WIDE1-1:>Hi"[..]).unwrap();
+        assert_eq!(None, packet.q_construct());
+    }
+
+    #[test]
+    fn via_limits() {
+        let mut packet = AprsPacket::decode_textual(&b"N0CALL>APRS,WIDE1-1:>Hi"[..]).unwrap();
+        packet.via = (1..=9)
+            .map(|i| Via::Callsign(Callsign::new_with_ssid("DIGI", i.to_string()), false))
+            .collect();
+        assert!(matches!(
+            packet.encode_textual(&mut vec![]),
+            Err(EncodeError::TooManyVias(9))
+        ));
+        assert!(matches!(
+            packet.encode_ax25(&mut vec![]),
+            Err(EncodeError::TooManyVias(9))
+        ));
+
+        packet.via.truncate(8);
+        assert!(packet.encode_textual(&mut vec![]).is_ok());
+
+        packet.via[3] = Via::Callsign(Callsign::new_no_ssid("TOOLONGCALL"), false);
+        match packet.encode_textual(&mut vec![]) {
+            Err(EncodeError::InvalidVia(c)) => assert_eq!(Callsign::new_no_ssid("TOOLONGCALL"), c),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+
+        // APRS-IS entries after the q-construct aren't limited
+        let packet =
+            AprsPacket::decode_textual(&b"N0CALL>APRS,WIDE1-1,qAR,IGATE-LONGSSID:>Hi"[..]).unwrap();
+        assert!(packet.encode_textual(&mut vec![]).is_ok());
+    }
+
+    #[test]
+    fn peek_kind() {
+        for (raw, kind) in &[
+            (
+                &b"N0CALL>APRS:!4903.50N/07201.75W-"[..],
+                PacketKind::Position,
+            ),
+            (b"N0CALL>APRS,WIDE1-1::N1CALL   :Hi", PacketKind::Message),
+            (
+                b"N0CALL>APRS:_10090556c220s004g005t077",
+                PacketKind::Weather,
+            ),
+            (b"N0CALL>APRS:!!00000066013D000", PacketKind::RawWeather),
+            (b"N0CALL>APRS:$GPRMC,123519,A", PacketKind::Nmea),
+            (b"N0CALL>APRS:`(_fn\"Oj/", PacketKind::MicE),
+            (b"N0CALL>APRS:T#005,199", PacketKind::Telemetry),
+            (b"N0CALL>APRS:Tlemetry? not", PacketKind::Unknown),
+            (b"N0CALL>APRS:", PacketKind::Unknown),
+        ] {
+            assert_eq!(Some(*kind), AprsPacket::peek_kind(raw));
+        }
+
+        assert_eq!(None, AprsPacket::peek_kind(b"N0CALL:>Hi"));
+
+        let raw = b"N0CALL>APRS:>Hi";
+        assert_eq!(
+            Some(AprsPacket::decode_textual(raw).unwrap().data.kind()),
+            AprsPacket::peek_kind(raw)
+        );
+    }
+
+    #[test]
+    fn t_without_hash_is_unknown() {
+        assert_eq!(
+            Ok(AprsData::Unknown(Callsign::new_no_ssid("APRS"))),
+            AprsPacket::decode_textual(b"N0CALL>APRS:Tlemetry? not").map(|p| p.data)
+        );
+    }
+
+    #[test]
+    fn encode_to_vec() {
+        for raw in &[
+            &b"N0CALL>APRS,WIDE1-1,qAR,N1CALL:!4903.50N/07201.75W-Test"[..],
+            b"N0CALL>APRS::N1CALL   :Hi{42",
+            b"N0CALL>APRS:}N1CALL>APRS,TCPIP,N0CALL*:>Hi",
+            b"N0CALL>APRS:>Caf\xe9",
+        ] {
+            let packet = AprsPacket::decode_textual(raw).unwrap();
+            let buf = packet.encode_to_vec().unwrap();
+            assert_eq!(*raw, &buf[..]);
+            assert!(packet.encoded_len_hint() >= buf.len());
+
+            let info = &raw[raw.iter().position(|&c| c == b':').unwrap() + 1..];
+            assert_eq!(info, &packet.data.encode_to_vec().unwrap()[..]);
+        }
+
+        let packet = AprsPacket::decode_textual(b"N0CALL>APRS:>Caf\xe9").unwrap();
+        assert_eq!(
+            "N0CALL>APRS:>Caf\u{fffd}",
+            packet.encode_to_string().unwrap()
+        );
+        assert_eq!(">Caf\u{fffd}", packet.data.encode_to_string().unwrap());
+    }
 }
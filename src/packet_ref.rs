@@ -0,0 +1,312 @@
+//! Borrowed views of textual packets, for consumers of busy feeds such as the
+//! full APRS-IS stream. Decoding one only splits the header, and the
+//! callsigns, path and information field borrow from the input.
+//! Messages and comments are also available without allocating, and
+//! [`AprsPacketRef::to_owned`] does the full decode when it's needed.
+//!
+//! ```rust
+//! use aprs_parser::AprsPacketRef;
+//!
+//! let packet = AprsPacketRef::decode_textual(b"N0CALL>APRS,WIDE1-1::N1CALL   :Hello{42").unwrap();
+//! assert_eq!("N0CALL", packet.from.to_string());
+//!
+//! let message = packet.message().unwrap();
+//! assert_eq!(&b"N1CALL"[..], message.addressee);
+//! assert_eq!(&b"Hello"[..], message.text);
+//! ```
+
+use core::fmt::{Display, Formatter};
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsPacket;
+use Callsign;
+use DecodeError;
+use QConstruct;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CallsignRef<'a> {
+    call: &'a str,
+    ssid: Option<&'a str>,
+}
+
+impl<'a> CallsignRef<'a> {
+    /// Splits off the SSID, with the same rules as [`Callsign::new`].
+    pub fn new(s: &'a str) -> Option<Self> {
+        match s.split_once('-') {
+            Some((call, ssid)) if call.is_empty() || ssid.is_empty() => None,
+            Some((call, ssid)) => Some(Self {
+                call,
+                ssid: Some(ssid),
+            }),
+            None => Some(Self {
+                call: s,
+                ssid: None,
+            }),
+        }
+    }
+
+    // e.g. `N0CALL-9*`
+    fn decode_textual(bytes: &'a [u8]) -> Option<(Self, bool)> {
+        let (bytes, heard) = match bytes.strip_suffix(b"*") {
+            Some(bytes) => (bytes, true),
+            None => (bytes, false),
+        };
+
+        Self::new(core::str::from_utf8(bytes).ok()?).map(|c| (c, heard))
+    }
+
+    pub fn call(&self) -> &'a str {
+        self.call
+    }
+
+    pub fn ssid(&self) -> Option<&'a str> {
+        self.ssid
+    }
+
+    pub fn to_owned(&self) -> Callsign {
+        match self.ssid {
+            Some(ssid) => Callsign::new_with_ssid(self.call, ssid),
+            None => Callsign::new_no_ssid(self.call),
+        }
+    }
+}
+
+impl Display for CallsignRef<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.call)?;
+        if let Some(ssid) = self.ssid {
+            write!(f, "-{}", ssid)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ViaRef<'a> {
+    /// A callsign, and whether it's marked as used with `*`.
+    /// Unlike [`AprsPacket::via`], the flag isn't copied to the entries before it.
+    Callsign(CallsignRef<'a>, bool),
+    QConstruct(QConstruct),
+}
+
+impl<'a> ViaRef<'a> {
+    fn decode_textual(bytes: &'a [u8]) -> Option<Self> {
+        if let Some(q) = QConstruct::decode_textual(bytes) {
+            return Some(Self::QConstruct(q));
+        }
+
+        CallsignRef::decode_textual(bytes).map(|(c, heard)| Self::Callsign(c, heard))
+    }
+}
+
+/// A message, with the padding of the addressee removed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AprsMessageRef<'a> {
+    pub addressee: &'a [u8],
+    pub text: &'a [u8],
+    pub id: Option<&'a [u8]>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AprsPacketRef<'a> {
+    pub from: CallsignRef<'a>,
+    pub to: CallsignRef<'a>,
+    /// The information field, starting with the data type identifier.
+    pub info: &'a [u8],
+    path: &'a [u8],
+    raw: &'a [u8],
+}
+
+impl<'a> AprsPacketRef<'a> {
+    /// Splits and checks the header. The information field isn't decoded.
+    pub fn decode_textual(s: &'a [u8]) -> Result<Self, DecodeError> {
+        let header_delimiter = s
+            .iter()
+            .position(|x| *x == b':')
+            .ok_or_else(|| DecodeError::InvalidPacket(s.to_owned(), 0))?;
+        let (header, info) = (&s[..header_delimiter], &s[header_delimiter + 1..]);
+
+        let from_delimiter = header
+            .iter()
+            .position(|x| *x == b'>')
+            .ok_or_else(|| DecodeError::InvalidPacket(s.to_owned(), 0))?;
+        let (from, to_and_via) = (&header[..from_delimiter], &header[from_delimiter + 1..]);
+        let (from, _) = CallsignRef::decode_textual(from)
+            .ok_or_else(|| DecodeError::InvalidCallsign(from.to_owned(), 0))?;
+
+        let (to, path) = match to_and_via.iter().position(|x| *x == b',') {
+            Some(i) => (&to_and_via[..i], &to_and_via[i + 1..]),
+            None => (to_and_via, &[][..]),
+        };
+        let (to, _) = CallsignRef::decode_textual(to)
+            .ok_or_else(|| DecodeError::InvalidCallsign(to.to_owned(), from_delimiter + 1))?;
+
+        if !path.is_empty() {
+            if let Some(v) = path
+                .split(|x| *x == b',')
+                .find(|v| ViaRef::decode_textual(v).is_none())
+            {
+                let offset = v.as_ptr() as usize - s.as_ptr() as usize;
+                return Err(DecodeError::InvalidVia(v.to_owned(), offset));
+            }
+        }
+
+        Ok(Self {
+            from,
+            to,
+            info,
+            path,
+            raw: s,
+        })
+    }
+
+    pub fn via(&self) -> impl Iterator<Item = ViaRef<'a>> {
+        let path = self.path;
+        path.split(|x| *x == b',')
+            .filter(move |_| !path.is_empty())
+            .filter_map(ViaRef::decode_textual)
+    }
+
+    pub fn data_type_identifier(&self) -> Option<u8> {
+        self.info.first().copied()
+    }
+
+    /// The message in the information field, if it's one.
+    pub fn message(&self) -> Option<AprsMessageRef<'a>> {
+        let b = self.info.strip_prefix(b":")?;
+        let addressee = b.get(..9)?;
+        let text = b[9..].strip_prefix(b":")?;
+
+        let addressee = match addressee.iter().rposition(|&c| c != b' ') {
+            Some(i) => &addressee[..=i],
+            None => &[],
+        };
+        let (text, id) = match text.iter().position(|&c| c == b'{') {
+            Some(i) => (&text[..i], Some(&text[i + 1..])),
+            None => (text, None),
+        };
+
+        Some(AprsMessageRef {
+            addressee,
+            text,
+            id,
+        })
+    }
+
+    /// The free-form text of positions, objects, items and status reports.
+    /// Data extensions at the start of a comment are included.
+    pub fn comment(&self) -> Option<&'a [u8]> {
+        let (&identifier, b) = self.info.split_first()?;
+        let position = match identifier {
+            b'!' | b'=' => b,
+            b'/' | b'@' => b.get(7..)?,
+            // name, live flag and timestamp
+            b';' => b.get(17..)?,
+            b')' => {
+                let flag = b.iter().take(10).position(|&c| c == b'!' || c == b'_')?;
+                &b[flag + 1..]
+            }
+            b'>' => {
+                let has_timestamp = b.len() >= 7
+                    && b[..6].iter().all(u8::is_ascii_digit)
+                    && matches!(b[6], b'z' | b'/' | b'h');
+                return Some(if has_timestamp { &b[7..] } else { b });
+            }
+            _ => return None,
+        };
+
+        let len = if position.first()?.is_ascii_digit() {
+            19
+        } else {
+            13
+        };
+        position.get(len..)
+    }
+
+    /// Fully decodes the packet.
+    pub fn to_owned(&self) -> Result<AprsPacket, DecodeError> {
+        AprsPacket::decode_textual(self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AprsData;
+
+    #[test]
+    fn header() {
+        let packet =
+            AprsPacketRef::decode_textual(b"N0CALL-9>APRS,N1CALL*,WIDE2-1,qAR,N2CALL:>Hello")
+                .unwrap();
+        assert_eq!("N0CALL", packet.from.call());
+        assert_eq!(Some("9"), packet.from.ssid());
+        assert_eq!(Callsign::new_no_ssid("APRS"), packet.to.to_owned());
+        assert_eq!(Some(b'>'), packet.data_type_identifier());
+        assert_eq!(
+            vec![
+                ViaRef::Callsign(CallsignRef::new("N1CALL").unwrap(), true),
+                ViaRef::Callsign(CallsignRef::new("WIDE2-1").unwrap(), false),
+                ViaRef::QConstruct(QConstruct::AR),
+                ViaRef::Callsign(CallsignRef::new("N2CALL").unwrap(), false),
+            ],
+            packet.via().collect::<Vec<_>>()
+        );
+
+        let packet = AprsPacketRef::decode_textual(b"N0CALL>APRS:>Hello").unwrap();
+        assert_eq!(0, packet.via().count());
+
+        assert!(AprsPacketRef::decode_textual(b"N0CALL>APRS,-1:>Hello").is_err());
+        assert!(AprsPacketRef::decode_textual(b"N0CALL:>Hello").is_err());
+    }
+
+    #[test]
+    fn message() {
+        let packet = AprsPacketRef::decode_textual(b"N0CALL>APRS::N1CALL   :Hi{42").unwrap();
+        assert_eq!(
+            Some(AprsMessageRef {
+                addressee: b"N1CALL",
+                text: b"Hi",
+                id: Some(b"42"),
+            }),
+            packet.message()
+        );
+
+        let packet = AprsPacketRef::decode_textual(b"N0CALL>APRS:>Hello").unwrap();
+        assert_eq!(None, packet.message());
+    }
+
+    #[test]
+    fn comments() {
+        for (packet, comment) in &[
+            (&b"N0CALL>APRS:!4903.50N/07201.75W-Home"[..], &b"Home"[..]),
+            (b"N0CALL>APRS:@092345z4903.50N/07201.75W-Home", b"Home"),
+            (b"N0CALL>APRS:=/5L!!<*e7> sTMobile", b"Mobile"),
+            (
+                b"N0CALL>APRS:;LEADER   *092345z4903.50N/07201.75W>Car",
+                b"Car",
+            ),
+            (
+                b"N0CALL>APRS:)AID #2!4903.50N/07201.75WAFirst aid",
+                b"First aid",
+            ),
+            (b"N0CALL>APRS:>092345zNet tonight", b"Net tonight"),
+            (b"N0CALL>APRS:>Net tonight", b"Net tonight"),
+        ] {
+            let packet = AprsPacketRef::decode_textual(packet).unwrap();
+            assert_eq!(Some(*comment), packet.comment());
+
+            // the same as the fully decoded comment
+            let owned = match packet.to_owned().unwrap().data {
+                AprsData::Position(p) => p.comment,
+                AprsData::Object(o) => o.position.comment,
+                AprsData::Item(i) => i.position.comment,
+                AprsData::Status(s) => s.comment().to_vec(),
+                _ => panic!("Unexpected data"),
+            };
+            assert_eq!(*comment, &owned[..]);
+        }
+    }
+}
@@ -0,0 +1,584 @@
+//! Helpers for working out how a packet travelled, based on its via path.
+//!
+//! Digipeaters mark the path entries they have used with the `heard` flag (`*`),
+//! and APRS-IS servers append a q-construct describing how the packet entered
+//! the internet. Hops through old digipeaters that decrement an `n-N` alias
+//! without marking it can't be detected.
+
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsPacket;
+use Callsign;
+use EncodeError;
+use PathToken;
+use QConstruct;
+use Via;
+
+/// How a packet reached the receiving station.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Reception {
+    /// Heard directly from the sender.
+    Direct,
+    /// Repeated by this many digipeaters.
+    Digipeated(usize),
+    /// Originated on APRS-IS rather than over the air.
+    Internet,
+}
+
+/// Hop counts of a packet's over-the-air path.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Hops {
+    /// Hops the sender asked for, including those already used.
+    pub requested: usize,
+    /// Hops that digipeaters have used so far.
+    pub consumed: usize,
+}
+
+/// A problem found in a requested path by [`PathAnalyzer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathIssue {
+    /// An alias that is no longer supported by the network, such as `RELAY`, `WIDE` or `TRACEn-N`.
+    ObsoleteAlias(Callsign),
+    /// An `n-N` alias asking for more hops than any single alias should, such as `WIDE7-7`.
+    OversizedAlias(Callsign),
+    /// More hops are requested in total than the configured maximum.
+    TooManyHops { requested: usize, max: usize },
+    /// `WIDE1-1` comes after another alias, where fill-in digipeaters won't act on it.
+    Wide1NotFirst,
+}
+
+/// What to do about a [`PathIssue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathRecommendation {
+    /// Replace the first entry with the second.
+    Replace(Callsign, Callsign),
+    /// Reduce the path to at most this many hops.
+    ReduceHops(usize),
+    /// Move `WIDE1-1` to the start of the path.
+    MoveWide1First,
+}
+
+impl PathIssue {
+    pub fn recommendation(&self) -> PathRecommendation {
+        match self {
+            PathIssue::ObsoleteAlias(c) => {
+                let replacement = match alias(c) {
+                    Some((n, remaining)) if c.call().starts_with("TRACE") && remaining > 0 => {
+                        Callsign::new_with_ssid(format!("WIDE{}", n), remaining.to_string())
+                    }
+                    _ => Callsign::new_with_ssid("WIDE1", "1"),
+                };
+
+                PathRecommendation::Replace(c.clone(), replacement)
+            }
+            PathIssue::OversizedAlias(c) => {
+                PathRecommendation::Replace(c.clone(), Callsign::new_with_ssid("WIDE2", "2"))
+            }
+            PathIssue::TooManyHops { max, .. } => PathRecommendation::ReduceHops(*max),
+            PathIssue::Wide1NotFirst => PathRecommendation::MoveWide1First,
+        }
+    }
+}
+
+/// Checks requested paths against current network practice.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PathAnalyzer {
+    max_hops: usize,
+}
+
+impl PathAnalyzer {
+    /// Creates an analyzer allowing up to 3 hops in total.
+    pub fn new() -> Self {
+        Self { max_hops: 3 }
+    }
+
+    pub fn with_max_hops(mut self, max_hops: usize) -> Self {
+        self.max_hops = max_hops;
+        self
+    }
+
+    /// Returns the problems with the over-the-air part of the packet's path.
+    pub fn analyze(&self, packet: &AprsPacket) -> Vec<PathIssue> {
+        let mut issues = vec![];
+        let mut seen_alias = false;
+
+        for (c, _) in rf_path(&packet.via).iter().filter_map(Via::callsign) {
+            let requested = match alias(c) {
+                Some((requested, _)) => requested,
+                None => continue,
+            };
+
+            if matches!(c.call(), "RELAY" | "WIDE" | "GATE" | "ECHO")
+                || c.call().starts_with("TRACE")
+            {
+                issues.push(PathIssue::ObsoleteAlias(c.clone()));
+            } else if requested > 3 {
+                issues.push(PathIssue::OversizedAlias(c.clone()));
+            }
+
+            if c.call() == "WIDE1" && seen_alias {
+                issues.push(PathIssue::Wide1NotFirst);
+            }
+            seen_alias = true;
+        }
+
+        let requested = hops(&packet.via).requested;
+        if requested > self.max_hops {
+            issues.push(PathIssue::TooManyHops {
+                requested,
+                max: self.max_hops,
+            });
+        }
+
+        issues
+    }
+}
+
+impl Default for PathAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn reception(via: &[Via]) -> Reception {
+    let rf = rf_path(via);
+
+    if let Some(Via::QConstruct(q)) = via.get(rf.len()) {
+        if is_internet_origin(*q) {
+            return Reception::Internet;
+        }
+    }
+
+    if rf
+        .iter()
+        .filter_map(Via::callsign)
+        .any(|(c, _)| is_internet_marker(c))
+    {
+        return Reception::Internet;
+    }
+
+    match hops(via).consumed {
+        0 => Reception::Direct,
+        n => Reception::Digipeated(n),
+    }
+}
+
+pub(crate) fn hops(via: &[Via]) -> Hops {
+    let mut stations = 0;
+    let mut alias_hops = 0;
+    let mut outstanding = 0;
+    for (c, heard) in rf_path(via).iter().filter_map(Via::callsign) {
+        if is_internet_marker(c) {
+            continue;
+        }
+
+        match (alias(c), heard) {
+            (Some((requested, remaining)), true) => {
+                alias_hops += requested.saturating_sub(remaining)
+            }
+            (Some((_, remaining)), false) => outstanding += remaining,
+            (None, true) => stations += 1,
+            (None, false) => outstanding += 1,
+        }
+    }
+
+    // Tracing digipeaters insert their own callsign as well as consuming the alias,
+    // so the two counts describe the same hops.
+    let consumed = stations.max(alias_hops);
+
+    Hops {
+        requested: consumed + outstanding,
+        consumed,
+    }
+}
+
+pub(crate) fn digipeaters(via: &[Via]) -> Vec<&Callsign> {
+    rf_path(via)
+        .iter()
+        .filter_map(Via::callsign)
+        .filter(|(c, heard)| *heard && alias(c).is_none() && !is_internet_marker(c))
+        .map(|(c, _)| c)
+        .collect()
+}
+
+// The first entry after the last used one, if it's still part of the over-the-air path.
+pub(crate) fn next_hop(via: &[Via]) -> Option<usize> {
+    let rf = rf_path(via);
+    let next = rf
+        .iter()
+        .rposition(|v| matches!(v, Via::Callsign(_, true)))
+        .map_or(0, |i| i + 1);
+
+    Some(next).filter(|i| *i < rf.len())
+}
+
+// Like the `*` in `A,B,C*`, which marks all of A, B and C as used
+pub(crate) fn mark_used(via: &mut [Via], i: usize) -> bool {
+    let len = rf_path(via).len();
+    if via[..len].get(i).and_then(Via::callsign).is_none() {
+        return false;
+    }
+
+    for (_, heard) in via[..=i].iter_mut().filter_map(Via::callsign_mut) {
+        *heard = true;
+    }
+    true
+}
+
+// `WIDE2-2` becomes `WIDE2-1`, and `WIDE2-1` becomes a used `WIDE2`.
+pub(crate) fn decrement(via: &mut [Via], i: usize) -> bool {
+    let len = rf_path(via).len();
+    let (c, heard) = match via[..len].get_mut(i).and_then(Via::callsign_mut) {
+        Some(entry) => entry,
+        None => return false,
+    };
+
+    match alias(c) {
+        Some((_, remaining)) if remaining > 1 => {
+            *c = Callsign::new_with_ssid(c.call(), (remaining - 1).to_string());
+        }
+        Some((_, 1)) => {
+            *c = Callsign::new_no_ssid(c.call());
+            *heard = true;
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+pub(crate) fn insert_digipeater(via: &mut Vec<Via>, i: usize, callsign: Callsign) -> bool {
+    if i > rf_path(via).len() {
+        return false;
+    }
+
+    via.insert(i, Via::Callsign(callsign, true));
+    true
+}
+
+// Replaces the unused entries from `i` on with the digipeater's callsign, marked as used,
+// so that the packet goes no further.
+pub(crate) fn trap(via: &mut Vec<Via>, i: usize, callsign: Callsign) -> bool {
+    let len = rf_path(via).len();
+    if i >= len {
+        return false;
+    }
+
+    via.splice(i..len, Some(Via::Callsign(callsign, true)));
+    true
+}
+
+/// The most digipeater addresses an AX.25 frame can have.
+pub(crate) const MAX_VIAS: usize = 8;
+
+// Checks that the over-the-air path fits in an AX.25 frame
+pub(crate) fn validate(via: &[Via]) -> Result<(), EncodeError> {
+    let rf = rf_path(via);
+    if rf.len() > MAX_VIAS {
+        return Err(EncodeError::TooManyVias(rf.len()));
+    }
+
+    match rf
+        .iter()
+        .filter_map(Via::callsign)
+        .find(|(c, _)| c.validate().is_err() && alias(c).is_none())
+    {
+        Some((c, _)) => Err(EncodeError::InvalidVia(c.clone())),
+        None => Ok(()),
+    }
+}
+
+// The part of the path added over the air, before any q-construct and the igate after it.
+fn rf_path(via: &[Via]) -> &[Via] {
+    let end = via
+        .iter()
+        .position(|v| matches!(v, Via::QConstruct(_)))
+        .unwrap_or(via.len());
+
+    &via[..end]
+}
+
+fn is_internet_origin(q: QConstruct) -> bool {
+    match q {
+        QConstruct::AC | QConstruct::AX | QConstruct::AU | QConstruct::AS | QConstruct::AZ => true,
+        QConstruct::AR | QConstruct::Ar | QConstruct::AO | QConstruct::Ao | QConstruct::AI => false,
+    }
+}
+
+pub(crate) fn is_internet_marker(c: &Callsign) -> bool {
+    matches!(
+        PathToken::from_callsign(c),
+        Some(PathToken::TcpIp) | Some(PathToken::TcpXx)
+    )
+}
+
+pub(crate) fn may_gate_to_rf(via: &[Via]) -> bool {
+    let unverified = via
+        .iter()
+        .any(|v| matches!(v, Via::QConstruct(QConstruct::AX)));
+    let prohibited = via.iter().any(|v| {
+        matches!(
+            v.token(),
+            Some(PathToken::TcpXx) | Some(PathToken::NoGate) | Some(PathToken::RfOnly)
+        )
+    });
+
+    !unverified && !prohibited
+}
+
+pub(crate) fn may_gate_to_is(via: &[Via]) -> bool {
+    !via.iter().any(|v| v.token().is_some())
+}
+
+// Parses generic digipeater aliases, returning the requested and remaining hop counts.
+// `WIDE2-1` is (2, 1), a fully used `WIDE2` is (2, 0) and `RELAY` is (1, 0).
+pub(crate) fn alias(c: &Callsign) -> Option<(usize, usize)> {
+    if c.ssid().is_none() && matches!(c.call(), "RELAY" | "WIDE" | "TRACE" | "GATE" | "ECHO") {
+        return Some((1, 0));
+    }
+
+    let call = c.call().as_bytes();
+    let (&n, prefix) = call.split_last()?;
+    if prefix.len() < 2 || !prefix.iter().all(u8::is_ascii_uppercase) || !(b'1'..=b'7').contains(&n)
+    {
+        return None;
+    }
+    let requested = usize::from(n - b'0');
+
+    let remaining = match c.ssid() {
+        None => 0,
+        Some(ssid) => match ssid.parse::<usize>() {
+            Ok(remaining) if remaining <= requested => remaining,
+            _ => return None,
+        },
+    };
+
+    Some((requested, remaining))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use AprsPacket;
+
+    fn reception_of(s: &str) -> Reception {
+        AprsPacket::decode_textual(s.as_bytes())
+            .unwrap()
+            .reception()
+    }
+
+    #[test]
+    fn direct() {
+        assert_eq!(Reception::Direct, reception_of("N0CALL>APRS:>Hi"));
+        assert_eq!(
+            Reception::Direct,
+            reception_of("N0CALL>APRS,WIDE1-1,WIDE2-1,qAR,IGATE:>Hi")
+        );
+    }
+
+    #[test]
+    fn digipeated() {
+        assert_eq!(
+            Reception::Digipeated(1),
+            reception_of("N0CALL>APRS,KD8ABC-2*,WIDE1*,WIDE2-1:>Hi")
+        );
+        assert_eq!(
+            Reception::Digipeated(3),
+            reception_of("N0CALL>APRS,KD8ABC-2,WIDE1,W1XYZ,VE3DEF-3*,WIDE2*,qAR,IGATE:>Hi")
+        );
+        // untraced digipeaters only leave the used alias behind
+        assert_eq!(
+            Reception::Digipeated(1),
+            reception_of("N0CALL>APRS,WIDE1*,WIDE2-2:>Hi")
+        );
+    }
+
+    #[test]
+    fn internet() {
+        assert_eq!(
+            Reception::Internet,
+            reception_of("N0CALL>APRS,TCPIP*,qAC,T2TEST:>Hi")
+        );
+        assert_eq!(
+            Reception::Internet,
+            reception_of("N0CALL>APRS,qAS,T2TEST:>Hi")
+        );
+    }
+
+    #[test]
+    fn hop_counts() {
+        let packet =
+            AprsPacket::decode_textual(&b"N0CALL>APRS,KD8ABC-2,WIDE1,W1XYZ*,WIDE2-1:>Hi"[..])
+                .unwrap();
+        assert_eq!(
+            Hops {
+                requested: 3,
+                consumed: 2
+            },
+            packet.hops()
+        );
+        assert_eq!(
+            vec![
+                &Callsign::new_with_ssid("KD8ABC", "2"),
+                &Callsign::new_no_ssid("W1XYZ")
+            ],
+            packet.digipeaters()
+        );
+
+        // explicitly routed, not yet digipeated
+        let packet =
+            AprsPacket::decode_textual(&b"N0CALL>APRS,W1XYZ,VE3DEF-3,qAR,IGATE:>Hi"[..]).unwrap();
+        assert_eq!(
+            Hops {
+                requested: 2,
+                consumed: 0
+            },
+            packet.hops()
+        );
+        assert!(packet.digipeaters().is_empty());
+    }
+
+    #[test]
+    fn analyze() {
+        let analyze = |s: &str| {
+            PathAnalyzer::new().analyze(&AprsPacket::decode_textual(s.as_bytes()).unwrap())
+        };
+
+        assert!(analyze("N0CALL>APRS,WIDE1-1,WIDE2-1:>Hi").is_empty());
+        assert!(analyze("N0CALL>APRS,KD8ABC-2,WIDE1*,WIDE2-1:>Hi").is_empty());
+
+        let relay = Callsign::new_no_ssid("RELAY");
+        assert_eq!(
+            vec![
+                PathIssue::ObsoleteAlias(relay.clone()),
+                PathIssue::Wide1NotFirst
+            ],
+            analyze("N0CALL>APRS,RELAY,WIDE1-1:>Hi")
+        );
+        assert_eq!(
+            PathRecommendation::Replace(relay, Callsign::new_with_ssid("WIDE1", "1")),
+            PathIssue::ObsoleteAlias(Callsign::new_no_ssid("RELAY")).recommendation()
+        );
+
+        let wide7 = Callsign::new_with_ssid("WIDE7", "7");
+        assert_eq!(
+            vec![
+                PathIssue::OversizedAlias(wide7),
+                PathIssue::TooManyHops {
+                    requested: 7,
+                    max: 3
+                }
+            ],
+            analyze("N0CALL>APRS,WIDE7-7:>Hi")
+        );
+
+        let trace = Callsign::new_with_ssid("TRACE3", "3");
+        let issues = analyze("N0CALL>APRS,TRACE3-3:>Hi");
+        assert_eq!(
+            vec![PathRecommendation::Replace(
+                trace,
+                Callsign::new_with_ssid("WIDE3", "3")
+            )],
+            issues
+                .iter()
+                .map(PathIssue::recommendation)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn aliases() {
+        assert_eq!(Some((2, 1)), alias(&Callsign::new_with_ssid("WIDE2", "1")));
+        assert_eq!(Some((2, 0)), alias(&Callsign::new_no_ssid("WIDE2")));
+        assert_eq!(Some((1, 0)), alias(&Callsign::new_no_ssid("RELAY")));
+        assert_eq!(None, alias(&Callsign::new_with_ssid("WIDE2", "9")));
+        assert_eq!(None, alias(&Callsign::new_no_ssid("N0CALL")));
+        assert_eq!(None, alias(&Callsign::new_with_ssid("KD8ABC", "2")));
+    }
+
+    #[test]
+    fn digipeating() {
+        let mut packet = AprsPacket::decode_textual(b"N0CALL>APRS,WIDE1-1,WIDE2-2:>Hi").unwrap();
+        let path = |packet: &AprsPacket| {
+            let mut buf = vec![];
+            packet.encode_textual(&mut buf).unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+
+        // a fill-in digipeater
+        assert_eq!(Some(0), packet.next_hop());
+        assert!(packet.decrement_hop(0));
+        assert!(packet.insert_digipeater(0, Callsign::new_with_ssid("N1DIG", "1")));
+        assert_eq!("N0CALL>APRS,N1DIG-1,WIDE1*,WIDE2-2:>Hi", path(&packet));
+
+        // a wide digipeater
+        assert_eq!(Some(2), packet.next_hop());
+        assert!(packet.decrement_hop(2));
+        assert!(packet.insert_digipeater(2, Callsign::new_no_ssid("N2DIG")));
+        assert_eq!(
+            "N0CALL>APRS,N1DIG-1,WIDE1,N2DIG*,WIDE2-1:>Hi",
+            path(&packet)
+        );
+
+        assert_eq!(Some(3), packet.next_hop());
+        assert!(packet.decrement_hop(3));
+        assert_eq!(None, packet.next_hop());
+        assert!(!packet.decrement_hop(3));
+
+        let mut packet = AprsPacket::decode_textual(b"N0CALL>APRS,N1DIG,qAR,IGATE:>Hi").unwrap();
+        assert!(!packet.decrement_hop(0));
+        assert!(packet.mark_hop_used(0));
+        assert_eq!(None, packet.next_hop());
+        assert!(!packet.mark_hop_used(1));
+        assert!(!packet.insert_digipeater(3, Callsign::new_no_ssid("N2DIG")));
+        assert_eq!("N0CALL>APRS,N1DIG*,qAR,IGATE:>Hi", path(&packet));
+    }
+
+    #[test]
+    fn mark_used_like_decoded() {
+        let mut packet = AprsPacket::decode_textual(b"N0CALL>APRS,AA1AA,BB1BB,CC1CC:>Hi").unwrap();
+        let decoded = AprsPacket::decode_textual(b"N0CALL>APRS,AA1AA,BB1BB,CC1CC*:>Hi").unwrap();
+
+        assert!(packet.mark_hop_used(2));
+        assert_eq!(decoded, packet);
+        assert_eq!(decoded.digipeaters(), packet.digipeaters());
+        assert_eq!(3, packet.digipeaters().len());
+        assert_eq!(decoded.hops().consumed, packet.hops().consumed);
+        assert_eq!(3, packet.hops().consumed);
+        assert_eq!(
+            &b"N0CALL>APRS,AA1AA,BB1BB,CC1CC*:>Hi"[..],
+            &packet.encode_to_vec().unwrap()[..]
+        );
+    }
+
+    #[test]
+    fn gating() {
+        let decode = |s: &str| AprsPacket::decode_textual(s.as_bytes()).unwrap();
+
+        let packet = decode("N0CALL>APRS,TCPIP*,qAC,T2TEST:>Hi");
+        assert_eq!(Some(PathToken::TcpIp), packet.via[0].token());
+        assert!(packet.may_gate_to_rf());
+        assert!(!packet.may_gate_to_is());
+
+        let packet = decode("N0CALL>APRS,TCPXX*,qAX,T2TEST:>Hi");
+        assert!(!packet.may_gate_to_rf());
+        assert!(!packet.may_gate_to_is());
+
+        let packet = decode("N0CALL>APRS,RFONLY,WIDE1-1:>Hi");
+        assert!(!packet.may_gate_to_rf());
+        assert!(!packet.may_gate_to_is());
+
+        let packet = decode("N0CALL>APRS,WIDE1-1,NOGATE:>Hi");
+        assert_eq!(Some(PathToken::NoGate), packet.via[1].token());
+        assert!(!packet.may_gate_to_is());
+
+        let packet = decode("N0CALL>APRS,WIDE1-1,WIDE2-1:>Hi");
+        assert_eq!(None, packet.via[0].token());
+        assert!(packet.may_gate_to_rf());
+        assert!(packet.may_gate_to_is());
+
+        // gated from APRS-IS, so it mustn't go back
+        let packet = decode("IGATE>APRS,WIDE2-1:}N0CALL>APRS,TCPIP,IGATE*:>Hi");
+        assert!(!packet.may_gate_to_is());
+    }
+}
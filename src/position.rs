@@ -1,16 +1,35 @@
-use std::convert::TryFrom;
-use std::io::Write;
-use std::ops::RangeInclusive;
-
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
+use core::ops::RangeInclusive;
+
+use dao::DAO_LEN;
+use extension::EXTENSION_LEN;
+#[cfg(not(any(feature = "std", test)))]
+use float::F64Ext;
+use io::Write;
 use lonlat::{Latitude, Longitude};
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use text;
 use AprsCompressedCs;
 use AprsCompressionType;
 use Callsign;
+use CourseSpeed;
+use Dao;
+use DataExtension;
 use DecodeError;
+use DfBearing;
+use Dfs;
 use EncodeError;
+use FrequencyInfo;
+use Phg;
+use StormData;
+use Symbol;
 use Timestamp;
+use WeatherData;
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AprsCst {
     CompressedSome {
         cs: AprsCompressedCs,
@@ -20,7 +39,8 @@ pub enum AprsCst {
     Uncompressed,
 }
 
-#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Ord, Eq)]
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Ord, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Precision {
     TenDegree,
     OneDegree,
@@ -83,6 +103,7 @@ impl Default for Precision {
 }
 
 #[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AprsPosition {
     pub to: Callsign,
     pub data_type_identifier: u8,
@@ -95,8 +116,8 @@ pub struct AprsPosition {
     /// Longitudes aren't specified precisely in APRS and have ambiguity built in. This value stores the center, but you can also call `AprsPosition::longitude_bounding()` to get the entire range that the actual longitude could be in.
     pub longitude: Longitude,
     pub precision: Precision,
-    pub symbol_table: char,
-    pub symbol_code: char,
+    pub symbol: Symbol,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
     pub comment: Vec<u8>,
     pub cst: AprsCst,
 }
@@ -112,31 +133,315 @@ impl AprsPosition {
         self.precision.range(self.longitude.value())
     }
 
+    /// Decodes the frequency information at the start of the comment, if any.
+    pub fn frequency(&self) -> Option<FrequencyInfo> {
+        FrequencyInfo::decode(&self.comment).map(|(info, _)| info)
+    }
+
+    /// Decodes the storm data in the comment, for positions with the hurricane symbol `\@`.
+    pub fn storm(&self) -> Option<StormData> {
+        if self.symbol.code() != '@' || self.symbol.is_primary() {
+            return None;
+        }
+
+        StormData::decode(&self.comment).map(|(storm, _)| storm)
+    }
+
+    /// Returns the text of a signpost, which is one to three characters in braces
+    /// at the start of the comment, e.g. `{55}` for a speed limit.
+    /// Only positions with the signpost symbol `\m` have one.
+    pub fn signpost(&self) -> Option<&[u8]> {
+        if self.symbol.code() != 'm' || self.symbol.is_primary() {
+            return None;
+        }
+
+        signpost_len(&self.comment).map(|len| &self.comment[1..=len])
+    }
+
+    /// Sets the signpost text at the start of the comment, replacing any existing one.
+    /// The text must be one to three characters, and the symbol should be `\m`.
+    pub fn set_signpost(&mut self, text: &[u8]) -> Result<(), EncodeError> {
+        if text.is_empty() || text.len() > 3 || text.iter().any(|&c| c == b'{' || c == b'}') {
+            return Err(EncodeError::InvalidSignpost(text.to_owned()));
+        }
+
+        let existing = signpost_len(&self.comment).map(|len| len + 2).unwrap_or(0);
+        let mut comment = vec![b'{'];
+        comment.extend_from_slice(text);
+        comment.push(b'}');
+        comment.extend_from_slice(&self.comment[existing..]);
+        self.comment = comment;
+
+        Ok(())
+    }
+
+    /// The GPS fix, NMEA source and origin from the T byte of a compressed position.
+    /// Positions without course, speed, range or altitude don't have one.
+    pub fn compression_type(&self) -> Option<AprsCompressionType> {
+        match self.cst {
+            AprsCst::CompressedSome { t, .. } => Some(t),
+            _ => None,
+        }
+    }
+
+    /// Sets the T byte of a compressed position that has course, speed, range or altitude.
+    pub fn set_compression_type(&mut self, t: AprsCompressionType) -> Result<(), EncodeError> {
+        match &mut self.cst {
+            AprsCst::CompressedSome { t: existing, .. } => {
+                *existing = t;
+                Ok(())
+            }
+            _ => Err(EncodeError::InvalidData),
+        }
+    }
+
+    /// Decodes the data extension at the start of the comment.
+    /// Only uncompressed positions have one, since compressed ones carry it in the cs bytes.
+    pub fn extension(&self) -> Option<DataExtension> {
+        match self.cst {
+            AprsCst::Uncompressed => DataExtension::decode(&self.comment),
+            _ => None,
+        }
+    }
+
+    /// Decodes the course and speed extension, e.g. `088/036`.
+    pub fn course_speed(&self) -> Option<CourseSpeed> {
+        match self.extension()? {
+            DataExtension::CourseSpeed(cs) => Some(cs),
+            _ => None,
+        }
+    }
+
+    /// Decodes the power, height, gain and directivity extension, e.g. `PHG5132`.
+    pub fn phg(&self) -> Option<Phg> {
+        match self.extension()? {
+            DataExtension::Phg(phg) => Some(phg),
+            _ => None,
+        }
+    }
+
+    /// Decodes the pre-calculated radio range extension, e.g. `RNG0050`.
+    pub fn range_miles(&self) -> Option<u16> {
+        match self.extension()? {
+            DataExtension::Rng(range) => Some(range),
+            _ => None,
+        }
+    }
+
+    /// The radio range extension, converted to kilometers.
+    pub fn range_km(&self) -> Option<f64> {
+        self.range_miles().map(|miles| f64::from(miles) * 1.609344)
+    }
+
+    /// Decodes the omni-DF signal strength extension, e.g. `DFS2360`.
+    pub fn dfs(&self) -> Option<Dfs> {
+        match self.extension()? {
+            DataExtension::Dfs(dfs) => Some(dfs),
+            _ => None,
+        }
+    }
+
+    /// Decodes the bearing of a DF report, which follows the course and speed
+    /// of positions with the `/\` symbol, e.g. `088/036/270/729`.
+    pub fn df_bearing(&self) -> Option<DfBearing> {
+        if !self.symbol.is_primary() || self.symbol.code() != '\\' {
+            return None;
+        }
+        self.course_speed()?;
+
+        DfBearing::decode(&self.comment[EXTENSION_LEN..])
+    }
+
+    /// Sets the bearing of a DF report after the course and speed extension,
+    /// replacing any existing one. `None` removes it.
+    /// The position needs the `/\` symbol and a course and speed.
+    pub fn set_df_bearing(&mut self, bearing: Option<DfBearing>) -> Result<(), EncodeError> {
+        if !self.symbol.is_primary() || self.symbol.code() != '\\' || self.course_speed().is_none()
+        {
+            return Err(EncodeError::InvalidData);
+        }
+
+        let existing = match self.df_bearing() {
+            Some(_) => DfBearing::LEN,
+            None => 0,
+        };
+        let mut comment = self.comment[..EXTENSION_LEN].to_vec();
+        if let Some(bearing) = bearing {
+            bearing.encode(&mut comment)?;
+        }
+        comment.extend_from_slice(&self.comment[EXTENSION_LEN + existing..]);
+        self.comment = comment;
+
+        Ok(())
+    }
+
+    /// Sets the data extension at the start of the comment, replacing any existing one.
+    /// `None` removes it. Compressed positions can't have one.
+    pub fn set_extension(&mut self, extension: Option<DataExtension>) -> Result<(), EncodeError> {
+        if self.cst != AprsCst::Uncompressed {
+            return Err(EncodeError::InvalidData);
+        }
+
+        let mut comment = vec![];
+        if let Some(extension) = extension {
+            extension.encode(&mut comment)?;
+        }
+        comment.extend_from_slice(self.after_extension());
+        self.comment = comment;
+
+        Ok(())
+    }
+
+    /// The style of the DAO extension in the comment, which adds precision to the position.
+    pub fn dao(&self) -> Option<Dao> {
+        Dao::find(&self.comment).map(|(_, dao, _, _)| dao)
+    }
+
+    /// Adds a DAO field with the given style to the end of the comment, replacing
+    /// any existing one. `None` removes it. It's filled in when encoding uncompressed
+    /// positions with full precision.
+    pub fn set_dao(&mut self, dao: Option<Dao>) {
+        if let Some((start, _, _, _)) = Dao::find(&self.comment) {
+            self.comment.drain(start..start + DAO_LEN);
+        }
+
+        if let Some(dao) = dao {
+            let (_, lat) = dao.split(self.latitude.abs());
+            let (_, lon) = dao.split(self.longitude.abs());
+            self.comment
+                .extend_from_slice(&[b'!', dao.datum(), lat, lon, b'!']);
+        }
+    }
+
+    /// Decodes the `/A=001234` altitude field, which can be anywhere in the comment.
+    pub fn altitude_feet(&self) -> Option<i32> {
+        find_altitude(&self.comment).map(|(_, feet)| feet)
+    }
+
+    pub fn altitude_meters(&self) -> Option<f64> {
+        self.altitude_feet().map(|feet| f64::from(feet) * 0.3048)
+    }
+
+    /// Sets the altitude field, replacing any existing one. New fields are put after
+    /// the data extension. `None` removes it.
+    /// The altitude must be from -99999 to 999999 feet.
+    pub fn set_altitude_feet(&mut self, feet: Option<i32>) -> Result<(), EncodeError> {
+        let mut field = vec![];
+        if let Some(feet) = feet {
+            if !(-99999..=999999).contains(&feet) {
+                return Err(EncodeError::InvalidData);
+            }
+            write!(field, "/A={:06}", feet)?;
+        }
+
+        let (start, end) = match find_altitude(&self.comment) {
+            Some((start, _)) => (start, start + ALTITUDE_LEN),
+            None => {
+                let start = self.comment.len() - self.after_extension().len();
+                (start, start)
+            }
+        };
+        self.comment.splice(start..end, field);
+
+        Ok(())
+    }
+
+    /// The comment as UTF-8, with invalid sequences replaced by `�`.
+    /// See [`display_text_latin1`](crate::display_text_latin1) for a Latin-1 fallback.
+    pub fn comment_display(&self) -> Cow<'_, str> {
+        text::display_text(&self.comment)
+    }
+
+    /// The comment without the data extension and altitude.
+    pub fn comment_text(&self) -> Cow<'_, [u8]> {
+        let text = self.after_extension();
+
+        match find_altitude(text) {
+            Some((start, _)) => {
+                let mut text = text.to_vec();
+                text.drain(start..start + ALTITUDE_LEN);
+                Cow::Owned(text)
+            }
+            None => Cow::Borrowed(text),
+        }
+    }
+
+    fn after_extension(&self) -> &[u8] {
+        match self.extension() {
+            Some(_) => &self.comment[EXTENSION_LEN..],
+            None => &self.comment,
+        }
+    }
+
+    /// Decodes the weather data in the comment of a complete weather report,
+    /// which is a position report with the `_` symbol.
+    /// For compressed positions, the wind comes from the course and speed field.
+    pub fn weather(&self) -> Option<WeatherData> {
+        if self.symbol.code() != '_' {
+            return None;
+        }
+
+        let (mut weather, _) = WeatherData::decode_position_comment(&self.comment);
+        if let AprsCst::CompressedSome {
+            cs: AprsCompressedCs::CourseSpeed(cs),
+            ..
+        } = &self.cst
+        {
+            weather.wind_direction = weather.wind_direction.or(Some(cs.course_degrees()));
+            weather.wind_speed = weather
+                .wind_speed
+                .or(Some((cs.speed_knots() * 1.150779).round() as u16));
+        }
+
+        if weather == WeatherData::default() {
+            None
+        } else {
+            Some(weather)
+        }
+    }
+
     pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
         let first = *b
             .first()
-            .ok_or_else(|| DecodeError::InvalidPosition(vec![]))?;
+            .ok_or_else(|| DecodeError::InvalidPosition(vec![], 0))?;
         let messaging_supported = first == b'=' || first == b'@';
 
         // parse timestamp if necessary
         let has_timestamp = first == b'@' || first == b'/';
         let timestamp = if has_timestamp {
-            Some(Timestamp::try_from(
-                b.get(1..8)
-                    .ok_or_else(|| DecodeError::InvalidPosition(b.to_vec()))?,
-            )?)
+            Some(
+                Timestamp::try_from(
+                    b.get(1..8)
+                        .ok_or_else(|| DecodeError::InvalidPosition(b.to_vec(), 0))?,
+                )
+                .map_err(|e| e.offset_by(1))?,
+            )
         } else {
             None
         };
 
         // strip leading type symbol and potential timestamp
-        let b = if has_timestamp { &b[8..] } else { &b[1..] };
+        let start = if has_timestamp { 8 } else { 1 };
 
+        Self::decode_position(first, &b[start..], to, timestamp, messaging_supported)
+            .map_err(|e| e.offset_by(start))
+    }
+
+    // Decodes everything from the position onwards, which is shared with objects and items
+    pub(crate) fn decode_position(
+        type_identifier: u8,
+        b: &[u8],
+        to: Callsign,
+        timestamp: Option<Timestamp>,
+        messaging_supported: bool,
+    ) -> Result<Self, DecodeError> {
         // check for compressed position format
         let is_uncompressed_position = (*b.first().unwrap_or(&0) as char).is_numeric();
         match is_uncompressed_position {
-            true => Self::parse_uncompressed(first, b, to, timestamp, messaging_supported),
-            false => Self::parse_compressed(first, b, to, timestamp, messaging_supported),
+            true => {
+                Self::parse_uncompressed(type_identifier, b, to, timestamp, messaging_supported)
+            }
+            false => Self::parse_compressed(type_identifier, b, to, timestamp, messaging_supported),
         }
     }
 
@@ -148,18 +453,17 @@ impl AprsPosition {
         messaging_supported: bool,
     ) -> Result<Self, DecodeError> {
         if b.len() < 13 {
-            return Err(DecodeError::InvalidPosition(b.to_owned()));
+            return Err(DecodeError::InvalidPosition(b.to_owned(), 0));
         }
 
-        let symbol_table = b[0] as char;
+        let symbol = Symbol::from_compressed_bytes(b[0], b[9]);
         let comp_lat = &b[1..5];
         let comp_lon = &b[5..9];
-        let symbol_code = b[9] as char;
         let course_speed = &b[10..12];
         let comp_type = b[12];
 
-        let latitude = Latitude::parse_compressed(comp_lat)?;
-        let longitude = Longitude::parse_compressed(comp_lon)?;
+        let latitude = Latitude::parse_compressed(comp_lat).map_err(|e| e.offset_by(1))?;
+        let longitude = Longitude::parse_compressed(comp_lon).map_err(|e| e.offset_by(5))?;
 
         // From the APRS spec - if the c value is a space,
         // the csT doesn't matter
@@ -168,9 +472,10 @@ impl AprsPosition {
             _ => {
                 let t = comp_type
                     .checked_sub(33)
-                    .ok_or_else(|| DecodeError::InvalidPosition(b.to_owned()))?
+                    .ok_or_else(|| DecodeError::InvalidPosition(b.to_owned(), 0))?
                     .into();
-                let cs = AprsCompressedCs::parse(course_speed[0], course_speed[1], t)?;
+                let cs = AprsCompressedCs::parse(course_speed[0], course_speed[1], t)
+                    .map_err(|e| e.offset_by(10))?;
                 AprsCst::CompressedSome { cs, t }
             }
         };
@@ -185,8 +490,7 @@ impl AprsPosition {
             latitude,
             longitude,
             precision: Precision::default(),
-            symbol_table,
-            symbol_code,
+            symbol,
             comment,
             cst,
         })
@@ -200,18 +504,27 @@ impl AprsPosition {
         messaging_supported: bool,
     ) -> Result<Self, DecodeError> {
         if b.len() < 19 {
-            return Err(DecodeError::InvalidPosition(b.to_owned()));
+            return Err(DecodeError::InvalidPosition(b.to_owned(), 0));
         }
 
         // parse position
-        let (latitude, precision) = Latitude::parse_uncompressed(&b[0..8])?;
-        let longitude = Longitude::parse_uncompressed(&b[9..18], precision)?;
+        let (mut latitude, precision) = Latitude::parse_uncompressed(&b[0..8])?;
+        let mut longitude =
+            Longitude::parse_uncompressed(&b[9..18], precision).map_err(|e| e.offset_by(9))?;
 
-        let symbol_table = b[8] as char;
-        let symbol_code = b[18] as char;
+        let symbol = Symbol::from_bytes(b[8], b[18]);
 
         let comment = b[19..].to_owned();
 
+        // the DAO extension adds to the full precision position
+        if let (Some((_, _, lat, lon)), Precision::HundredthMinute) =
+            (Dao::find(&comment), precision)
+        {
+            let add = |value: f64, minutes: f64| value + value.signum() * minutes / 60.0;
+            latitude = Latitude::new(add(*latitude, lat)).unwrap_or(latitude);
+            longitude = Longitude::new(add(*longitude, lon)).unwrap_or(longitude);
+        }
+
         Ok(Self {
             data_type_identifier: type_identifier,
             to,
@@ -220,27 +533,61 @@ impl AprsPosition {
             latitude,
             longitude,
             precision,
-            symbol_table,
-            symbol_code,
+            symbol,
             comment,
             cst: AprsCst::Uncompressed,
         })
     }
 
+    /// The data type identifier implied by the timestamp and messaging capability.
+    pub fn default_identifier(&self) -> u8 {
+        match (self.timestamp.is_some(), self.messaging_supported) {
+            (true, true) => b'@',
+            (true, false) => b'/',
+            (false, true) => b'=',
+            (false, false) => b'!',
+        }
+    }
+
+    /// Encodes the position using `data_type_identifier`, as long as it's consistent with
+    /// the presence of a timestamp. Otherwise `default_identifier()` is used.
     pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
-        let sym = match (self.timestamp.is_some(), self.messaging_supported) {
-            (true, true) => '@',
-            (true, false) => '/',
-            (false, true) => '=',
-            (false, false) => '!',
+        let identifier = match (self.data_type_identifier, self.timestamp.is_some()) {
+            (b'!' | b'=', false) | (b'/' | b'@', true) => self.data_type_identifier,
+            _ => self.default_identifier(),
+        };
+
+        self.encode_with_identifier(buf, identifier)
+    }
+
+    /// Encodes the position using the given data type identifier, which must be one of
+    /// `!`, `=`, `/` or `@`. The timestamp is omitted for `!` and `=`,
+    /// and is required for `/` and `@`.
+    pub fn encode_with_identifier<W: Write>(
+        &self,
+        buf: &mut W,
+        identifier: u8,
+    ) -> Result<(), EncodeError> {
+        let has_timestamp = match identifier {
+            b'!' | b'=' => false,
+            b'/' | b'@' => true,
+            _ => return Err(EncodeError::InvalidDataTypeIdentifier(identifier)),
         };
 
-        write!(buf, "{}", sym)?;
+        buf.write_all(&[identifier])?;
 
-        if let Some(ts) = &self.timestamp {
-            ts.encode(buf)?;
+        if has_timestamp {
+            self.timestamp
+                .as_ref()
+                .ok_or(EncodeError::MissingTimestamp)?
+                .encode(buf)?;
         }
 
+        self.encode_position(buf)
+    }
+
+    // Encodes everything from the position onwards, which is shared with objects and items
+    pub(crate) fn encode_position<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
         match self.cst {
             AprsCst::Uncompressed => self.encode_uncompressed(buf),
             AprsCst::CompressedSome { cs, t } => self.encode_compressed(buf, Some((cs, t))),
@@ -248,13 +595,44 @@ impl AprsPosition {
         }
     }
 
+    /// Encodes the position as uncompressed. If the comment has a DAO field,
+    /// it's updated with the precision that doesn't fit in the position.
     pub fn encode_uncompressed<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
-        self.latitude.encode_uncompressed(buf, self.precision)?;
-        write!(buf, "{}", self.symbol_table)?;
-        self.longitude.encode_uncompressed(buf)?;
-        write!(buf, "{}", self.symbol_code)?;
+        let dao = Dao::find(&self.comment).filter(|_| self.precision == Precision::HundredthMinute);
+        let (latitude, longitude, comment) = match dao {
+            Some((start, dao, _, _)) => {
+                let (lat, lat_extra) = dao.split(self.latitude.abs());
+                let (lon, lon_extra) = dao.split(self.longitude.abs());
+                let latitude = Latitude::from_dmh(
+                    lat / 6000,
+                    (lat % 6000) / 100,
+                    lat % 100,
+                    *self.latitude >= 0.0,
+                )
+                .ok_or(EncodeError::InvalidLatitude(*self.latitude))?;
+                let longitude = Longitude::from_dmh(
+                    lon / 6000,
+                    (lon % 6000) / 100,
+                    lon % 100,
+                    *self.longitude >= 0.0,
+                )
+                .ok_or(EncodeError::InvalidLongitude(*self.longitude))?;
+
+                let mut comment = self.comment.clone();
+                comment[start + 2] = lat_extra;
+                comment[start + 3] = lon_extra;
+
+                (latitude, longitude, Cow::Owned(comment))
+            }
+            None => (self.latitude, self.longitude, Cow::Borrowed(&self.comment)),
+        };
 
-        buf.write_all(&self.comment)?;
+        latitude.encode_uncompressed(buf, self.precision)?;
+        buf.write_all(&[self.symbol.table_byte()])?;
+        longitude.encode_uncompressed(buf, self.precision)?;
+        buf.write_all(&[self.symbol.code_byte()])?;
+
+        buf.write_all(&comment)?;
 
         Ok(())
     }
@@ -264,12 +642,12 @@ impl AprsPosition {
         buf: &mut W,
         extra: Option<(AprsCompressedCs, AprsCompressionType)>,
     ) -> Result<(), EncodeError> {
-        write!(buf, "{}", self.symbol_table)?;
+        buf.write_all(&[self.symbol.compressed_table_byte()])?;
 
         self.latitude.encode_compressed(buf)?;
         self.longitude.encode_compressed(buf)?;
 
-        write!(buf, "{}", self.symbol_code)?;
+        buf.write_all(&[self.symbol.code_byte()])?;
 
         match extra {
             Some((cs, t)) => {
@@ -284,6 +662,41 @@ impl AprsPosition {
     }
 }
 
+const ALTITUDE_LEN: usize = 9;
+
+// Finds the start of a `/A=001234` or `/A=-00123` altitude field in feet
+fn find_altitude(comment: &[u8]) -> Option<(usize, i32)> {
+    comment
+        .windows(ALTITUDE_LEN)
+        .enumerate()
+        .find_map(|(i, w)| {
+            let digits = w.strip_prefix(b"/A=")?;
+            let valid = match digits.split_first()? {
+                (b'-', rest) => rest.iter().all(u8::is_ascii_digit),
+                _ => digits.iter().all(u8::is_ascii_digit),
+            };
+            if !valid {
+                return None;
+            }
+
+            Some((i, core::str::from_utf8(digits).ok()?.parse().ok()?))
+        })
+}
+
+// Length of the text of a `{xxx}` signpost at the start of `comment`
+fn signpost_len(comment: &[u8]) -> Option<usize> {
+    if comment.first() != Some(&b'{') {
+        return None;
+    }
+
+    comment
+        .iter()
+        .take(5)
+        .position(|&c| c == b'}')
+        .filter(|&end| end >= 2)
+        .map(|end| end - 1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -312,8 +725,8 @@ mod tests {
         assert!(!result.messaging_supported);
         assert_relative_eq!(*result.latitude, 25.97004667573229);
         assert_relative_eq!(*result.longitude, -171.95429033460567);
-        assert_eq!(result.symbol_table, '/');
-        assert_eq!(result.symbol_code, '-');
+        assert_eq!(result.symbol.table(), '/');
+        assert_eq!(result.symbol.code(), '-');
         assert_eq!(result.comment, []);
         assert_eq!(result.cst, AprsCst::CompressedNone);
     }
@@ -327,8 +740,8 @@ mod tests {
         assert_eq!(result.timestamp, None);
         assert_relative_eq!(*result.latitude, 25.97004667573229);
         assert_relative_eq!(*result.longitude, -171.95429033460567);
-        assert_eq!(result.symbol_table, '/');
-        assert_eq!(result.symbol_code, '-');
+        assert_eq!(result.symbol.table(), '/');
+        assert_eq!(result.symbol.code(), '-');
         assert_eq!(result.comment, b"Hello/A=001000");
         assert_eq!(
             result.cst,
@@ -355,8 +768,8 @@ mod tests {
         assert!(!result.messaging_supported);
         assert_relative_eq!(*result.latitude, 25.97004667573229);
         assert_relative_eq!(*result.longitude, -171.95429033460567);
-        assert_eq!(result.symbol_table, '\\');
-        assert_eq!(result.symbol_code, '^');
+        assert_eq!(result.symbol.table(), '\\');
+        assert_eq!(result.symbol.code(), '^');
         assert_eq!(result.comment, b"322/103/A=003054");
         assert_eq!(
             result.cst,
@@ -380,8 +793,8 @@ mod tests {
         assert!(result.messaging_supported);
         assert_relative_eq!(*result.latitude, 25.97004667573229);
         assert_relative_eq!(*result.longitude, -171.95429033460567);
-        assert_eq!(result.symbol_table, '/');
-        assert_eq!(result.symbol_code, '-');
+        assert_eq!(result.symbol.table(), '/');
+        assert_eq!(result.symbol.code(), '-');
         assert_eq!(result.comment, []);
         assert_eq!(
             result.cst,
@@ -409,8 +822,8 @@ mod tests {
         assert!(result.messaging_supported);
         assert_relative_eq!(*result.latitude, 25.97004667573229);
         assert_relative_eq!(*result.longitude, -171.95429033460567);
-        assert_eq!(result.symbol_table, '\\');
-        assert_eq!(result.symbol_code, '^');
+        assert_eq!(result.symbol.table(), '\\');
+        assert_eq!(result.symbol.code(), '^');
         assert_eq!(result.comment, b"322/103/A=003054");
         assert_eq!(result.cst, AprsCst::CompressedNone);
     }
@@ -425,8 +838,8 @@ mod tests {
         assert!(!result.messaging_supported);
         assert_relative_eq!(*result.latitude, 49.05833333333333);
         assert_relative_eq!(*result.longitude, -72.02916666666667);
-        assert_eq!(result.symbol_table, '/');
-        assert_eq!(result.symbol_code, '-');
+        assert_eq!(result.symbol.table(), '/');
+        assert_eq!(result.symbol.code(), '-');
         assert_eq!(result.comment, []);
         assert_eq!(result.cst, AprsCst::Uncompressed);
     }
@@ -446,8 +859,8 @@ mod tests {
         assert_eq!(Precision::TenthMinute, result.precision);
         assert_eq!(49.0575..=49.05916666666666, result.latitude_bounding());
         assert_eq!(-72.02916666666667..=-72.0275, result.longitude_bounding());
-        assert_eq!(result.symbol_table, '/');
-        assert_eq!(result.symbol_code, '-');
+        assert_eq!(result.symbol.table(), '/');
+        assert_eq!(result.symbol.code(), '-');
         assert_eq!(result.comment, b"Hello/A=001000");
         assert_eq!(result.cst, AprsCst::Uncompressed);
     }
@@ -465,8 +878,8 @@ mod tests {
         assert!(!result.messaging_supported);
         assert_relative_eq!(*result.latitude, 48.36016666666667);
         assert_relative_eq!(*result.longitude, 12.408166666666666);
-        assert_eq!(result.symbol_table, '\\');
-        assert_eq!(result.symbol_code, '^');
+        assert_eq!(result.symbol.table(), '\\');
+        assert_eq!(result.symbol.code(), '^');
         assert_eq!(result.comment, b"322/103/A=003054");
         assert_eq!(result.cst, AprsCst::Uncompressed);
     }
@@ -481,8 +894,8 @@ mod tests {
         assert!(result.messaging_supported);
         assert_relative_eq!(*result.latitude, 49.05833333333333);
         assert_relative_eq!(*result.longitude, -72.02916666666667);
-        assert_eq!(result.symbol_table, '/');
-        assert_eq!(result.symbol_code, '-');
+        assert_eq!(result.symbol.table(), '/');
+        assert_eq!(result.symbol.code(), '-');
         assert_eq!(result.comment, []);
         assert_eq!(result.cst, AprsCst::Uncompressed);
     }
@@ -500,8 +913,8 @@ mod tests {
         assert!(result.messaging_supported);
         assert_relative_eq!(*result.latitude, 48.36016666666667);
         assert_relative_eq!(*result.longitude, 12.408166666666666);
-        assert_eq!(result.symbol_table, '\\');
-        assert_eq!(result.symbol_code, '^');
+        assert_eq!(result.symbol.table(), '\\');
+        assert_eq!(result.symbol.code(), '^');
         assert_eq!(result.comment, b"322/103/A=003054");
         assert_eq!(result.cst, AprsCst::Uncompressed);
     }
@@ -536,4 +949,309 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn encode_honors_data_type_identifier() {
+        let mut pos =
+            AprsPosition::decode(&b"=4903.50N/07201.75W-"[..], default_callsign()).unwrap();
+        assert_eq!(b'=', pos.default_identifier());
+
+        pos.data_type_identifier = b'!';
+        let mut buf = vec![];
+        pos.encode(&mut buf).unwrap();
+        assert_eq!(&b"!4903.50N/07201.75W-"[..], buf);
+
+        // inconsistent with the lack of a timestamp
+        pos.data_type_identifier = b'@';
+        let mut buf = vec![];
+        pos.encode(&mut buf).unwrap();
+        assert_eq!(&b"=4903.50N/07201.75W-"[..], buf);
+    }
+
+    #[test]
+    fn encode_with_identifier() {
+        let pos = AprsPosition::decode(
+            &br"@074849h4821.61N\01224.49E^322/103/A=003054"[..],
+            default_callsign(),
+        )
+        .unwrap();
+
+        let mut buf = vec![];
+        pos.encode_with_identifier(&mut buf, b'/').unwrap();
+        assert_eq!(&br"/074849h4821.61N\01224.49E^322/103/A=003054"[..], buf);
+
+        let mut buf = vec![];
+        pos.encode_with_identifier(&mut buf, b'!').unwrap();
+        assert_eq!(&br"!4821.61N\01224.49E^322/103/A=003054"[..], buf);
+
+        let mut buf = vec![];
+        assert!(matches!(
+            pos.encode_with_identifier(&mut buf, b'>'),
+            Err(EncodeError::InvalidDataTypeIdentifier(b'>'))
+        ));
+    }
+
+    #[test]
+    fn encode_with_identifier_requires_timestamp() {
+        let pos = AprsPosition::decode(&b"!4903.50N/07201.75W-"[..], default_callsign()).unwrap();
+
+        let mut buf = vec![];
+        assert!(matches!(
+            pos.encode_with_identifier(&mut buf, b'@'),
+            Err(EncodeError::MissingTimestamp)
+        ));
+    }
+
+    #[test]
+    fn course_speed_extension() {
+        let mut position = AprsPosition::decode(
+            &b"!4903.50N/07201.75W>088/036/A=001234 Moving"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        assert_eq!(
+            Some(CourseSpeed {
+                course: 88,
+                speed_knots: 36
+            }),
+            position.course_speed()
+        );
+        assert_eq!(b" Moving", &position.comment_text()[..]);
+
+        position
+            .set_extension(Some(DataExtension::CourseSpeed(CourseSpeed {
+                course: 270,
+                speed_knots: 5,
+            })))
+            .unwrap();
+        let mut buf = vec![];
+        position.encode(&mut buf).unwrap();
+        assert_eq!(&b"!4903.50N/07201.75W>270/005/A=001234 Moving"[..], buf);
+
+        position.set_extension(None).unwrap();
+        assert_eq!(None, position.extension());
+        assert_eq!(b"/A=001234 Moving", &position.comment[..]);
+
+        // compressed positions carry course and speed in the cs bytes
+        let compressed =
+            AprsPosition::decode(&b"!/5L!!<*e7>7P[088/036"[..], default_callsign()).unwrap();
+        assert_eq!(None, compressed.extension());
+    }
+
+    #[test]
+    fn frequency_in_comment() {
+        let result = AprsPosition::decode(
+            &b"!4903.50N/07201.75Wr146.940MHz T100 -060"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        assert_eq!(Some(146_940), result.frequency().map(|f| f.frequency_khz));
+
+        let result =
+            AprsPosition::decode(&b"!4903.50N/07201.75W-Hello"[..], default_callsign()).unwrap();
+        assert_eq!(None, result.frequency());
+    }
+
+    #[test]
+    fn weather_in_comment() {
+        let result = AprsPosition::decode(
+            &b"@092345z4903.50N/07201.75W_220/004g005t077r000p000P000h50b09900wRSW"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        let wx = result.weather().unwrap();
+        assert_eq!(Some(220), wx.wind_direction);
+        assert_eq!(Some(4), wx.wind_speed);
+        assert_eq!(Some(77), wx.temperature);
+        assert_eq!(Some(9900), wx.barometric_pressure);
+
+        // wind from the compressed course and speed
+        let result = AprsPosition::decode(
+            &b"=/5L!!<*e7_7P[g005t077r000p000P000h50b09900wRSW"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        let wx = result.weather().unwrap();
+        assert_eq!(Some(88), wx.wind_direction);
+        assert_eq!(Some(42), wx.wind_speed);
+        assert_eq!(Some(5), wx.wind_gust);
+
+        // not the weather symbol
+        let result = AprsPosition::decode(
+            &b"!4903.50N/07201.75W-220/004g005t077"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        assert_eq!(None, result.weather());
+    }
+
+    #[test]
+    fn storm_in_comment() {
+        let result = AprsPosition::decode(
+            &br"!2520.00N\08300.00W@090/012/HC^110/135/0963>040&120%200"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        assert_eq!(Some(963), result.storm().map(|s| s.central_pressure));
+
+        let result = AprsPosition::decode(
+            &b"!2520.00N/08300.00W@090/012/HC^110/135/0963>040&120%200"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        assert_eq!(None, result.storm());
+    }
+
+    #[test]
+    fn phg_extension() {
+        let mut position =
+            AprsPosition::decode(&b"!4903.50N/07201.75W#PHG5132"[..], default_callsign()).unwrap();
+        assert_eq!(Some(3), position.phg().map(|phg| phg.gain_db()));
+        assert_eq!(None, position.course_speed());
+
+        position
+            .set_extension(Some(DataExtension::Phg(Phg {
+                power: 2,
+                height: 3,
+                gain: 6,
+                directivity: 0,
+            })))
+            .unwrap();
+        let mut buf = vec![];
+        position.encode(&mut buf).unwrap();
+        assert_eq!(&b"!4903.50N/07201.75W#PHG2360"[..], buf);
+    }
+
+    #[test]
+    fn df_report() {
+        let mut position = AprsPosition::decode(
+            &br"!4903.50N/07201.75W\088/036/270/729 Fox"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        let bearing = position.df_bearing().unwrap();
+        assert_eq!(270, bearing.bearing);
+        assert_eq!(Some(88), position.course_speed().map(|cs| cs.course));
+
+        position
+            .set_df_bearing(Some(DfBearing {
+                bearing: 90,
+                ..bearing
+            }))
+            .unwrap();
+        let mut buf = vec![];
+        position.encode(&mut buf).unwrap();
+        assert_eq!(&br"!4903.50N/07201.75W\088/036/090/729 Fox"[..], buf);
+
+        position.set_df_bearing(None).unwrap();
+        assert_eq!(None, position.df_bearing());
+        assert_eq!(b"088/036 Fox", &position.comment[..]);
+
+        let other = AprsPosition::decode(
+            &b"!4903.50N/07201.75W>088/036/270/729"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        assert_eq!(None, other.df_bearing());
+    }
+
+    #[test]
+    fn altitude() {
+        let mut position = AprsPosition::decode(
+            &b"!4903.50N/07201.75WO088/036 Balloon /A=012345 up"[..],
+            default_callsign(),
+        )
+        .unwrap();
+        assert_eq!(Some(12345), position.altitude_feet());
+        assert_relative_eq!(3762.756, position.altitude_meters().unwrap());
+        assert_eq!(b" Balloon  up", &position.comment_text()[..]);
+
+        position.set_altitude_feet(Some(-12)).unwrap();
+        assert_eq!(b"088/036 Balloon /A=-00012 up", &position.comment[..]);
+
+        position.set_altitude_feet(None).unwrap();
+        assert_eq!(None, position.altitude_feet());
+        assert_eq!(b"088/036 Balloon  up", &position.comment[..]);
+
+        position.set_altitude_feet(Some(150)).unwrap();
+        let mut buf = vec![];
+        position.encode(&mut buf).unwrap();
+        assert_eq!(
+            &b"!4903.50N/07201.75WO088/036/A=000150 Balloon  up"[..],
+            buf
+        );
+
+        assert!(position.set_altitude_feet(Some(1_000_000)).is_err());
+    }
+
+    #[test]
+    fn dao() {
+        let original = &b"!4903.50N/07201.75W-Home !W52!"[..];
+        let mut position = AprsPosition::decode(original, default_callsign()).unwrap();
+        assert_eq!(Some(Dao::HumanReadable), position.dao());
+        assert_relative_eq!(49.0 + 3.505 / 60.0, *position.latitude);
+        assert_relative_eq!(-(72.0 + 1.752 / 60.0), *position.longitude);
+
+        let mut buf = vec![];
+        position.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+
+        position.set_dao(Some(Dao::Base91));
+        assert_eq!(Some(Dao::Base91), position.dao());
+        let mut buf = vec![];
+        position.encode(&mut buf).unwrap();
+        let reencoded = AprsPosition::decode(&buf, default_callsign()).unwrap();
+        assert_relative_eq!(*position.latitude, *reencoded.latitude, epsilon = 0.000001);
+        assert_relative_eq!(
+            *position.longitude,
+            *reencoded.longitude,
+            epsilon = 0.000001
+        );
+
+        position.set_dao(None);
+        assert_eq!(b"Home ", &position.comment[..]);
+    }
+
+    #[test]
+    fn compression_type() {
+        let mut position =
+            AprsPosition::decode(&b"!/ABQ\\S;.Ub{Z["[..], default_callsign()).unwrap();
+        let t = position.compression_type().unwrap();
+        assert_eq!(GpsFix::Current, t.gps_fix);
+        assert_eq!(NmeaSource::Rmc, t.nmea_source);
+        assert_eq!(Origin::Software, t.origin);
+
+        position
+            .set_compression_type(AprsCompressionType {
+                gps_fix: GpsFix::Old,
+                ..t
+            })
+            .unwrap();
+        let mut buf = vec![];
+        position.encode(&mut buf).unwrap();
+        assert_eq!(&b"!/ABQ\\S;.Ub{Z;"[..], buf);
+
+        let mut uncompressed =
+            AprsPosition::decode(&b"!4903.50N/07201.75W-"[..], default_callsign()).unwrap();
+        assert_eq!(None, uncompressed.compression_type());
+        assert!(uncompressed.set_compression_type(t).is_err());
+    }
+
+    #[test]
+    fn compressed_overlay() {
+        let result = AprsPosition::decode(&b"=c5L!!<*e7>7P["[..], default_callsign()).unwrap();
+        assert_eq!(Symbol::new('2', '>').unwrap(), result.symbol);
+        assert_eq!(Some('2'), result.symbol.overlay());
+
+        let mut buf = vec![];
+        result.encode(&mut buf).unwrap();
+        assert_eq!(b"=c5L!!<*e7>7P[", &buf[..]);
+
+        // uncompressed positions have the digit itself
+        let mut position = result;
+        position.cst = AprsCst::Uncompressed;
+        let mut buf = vec![];
+        position.encode(&mut buf).unwrap();
+        assert_eq!(b"=4930.00N207245.00W>", &buf[..]);
+    }
 }
@@ -0,0 +1,7 @@
+//! What the std prelude would otherwise bring into scope, for `no_std`
+//! builds. Modules import it with `use prelude::*;`.
+
+pub use alloc::borrow::ToOwned;
+pub use alloc::boxed::Box;
+pub use alloc::string::{String, ToString};
+pub use alloc::vec::Vec;
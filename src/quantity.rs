@@ -0,0 +1,211 @@
+//! Accessors returning uom quantities, behind the `uom` feature.
+//! Each is named like the plain accessor or field it wraps, without the unit,
+//! e.g. [`AprsPosition::altitude`] for [`AprsPosition::altitude_feet`].
+
+use uom::si::f64::{Length, Pressure, ThermodynamicTemperature, Velocity};
+use uom::si::length::{foot, inch, meter, mile};
+use uom::si::pressure::millibar;
+use uom::si::thermodynamic_temperature::degree_fahrenheit;
+use uom::si::velocity::{knot, mile_per_hour};
+
+use AprsAltitude;
+use AprsCourseSpeed;
+use AprsMicE;
+use AprsNmeaPosition;
+use AprsPosition;
+use AprsRadioRange;
+use CourseSpeed;
+use DfBearing;
+use Dfs;
+use Phg;
+use WeatherData;
+
+impl AprsPosition {
+    /// The `/A=001234` altitude field.
+    pub fn altitude(&self) -> Option<Length> {
+        self.altitude_feet()
+            .map(|feet| Length::new::<foot>(f64::from(feet)))
+    }
+
+    /// The pre-calculated radio range extension.
+    pub fn range(&self) -> Option<Length> {
+        self.range_miles()
+            .map(|miles| Length::new::<mile>(f64::from(miles)))
+    }
+}
+
+impl AprsCourseSpeed {
+    pub fn speed(&self) -> Velocity {
+        Velocity::new::<knot>(self.speed_knots())
+    }
+}
+
+impl AprsRadioRange {
+    pub fn range(&self) -> Length {
+        Length::new::<mile>(self.range_miles())
+    }
+}
+
+impl AprsAltitude {
+    pub fn altitude(&self) -> Length {
+        Length::new::<foot>(self.altitude_feet())
+    }
+}
+
+impl CourseSpeed {
+    pub fn speed(&self) -> Velocity {
+        Velocity::new::<knot>(f64::from(self.speed_knots))
+    }
+}
+
+impl Phg {
+    /// Antenna height above average terrain.
+    pub fn height(&self) -> Option<Length> {
+        self.height_feet()
+            .map(|feet| Length::new::<foot>(f64::from(feet)))
+    }
+}
+
+impl Dfs {
+    /// Antenna height above average terrain.
+    pub fn height(&self) -> Option<Length> {
+        self.height_feet()
+            .map(|feet| Length::new::<foot>(f64::from(feet)))
+    }
+}
+
+impl DfBearing {
+    pub fn range(&self) -> Option<Length> {
+        self.range_miles()
+            .map(|miles| Length::new::<mile>(f64::from(miles)))
+    }
+}
+
+impl AprsMicE {
+    pub fn speed(&self) -> Velocity {
+        Velocity::new::<knot>(f64::from(self.speed.knots()))
+    }
+
+    /// The base-91 `xxx}` altitude field.
+    pub fn altitude(&self) -> Option<Length> {
+        self.altitude_meters()
+            .map(|meters| Length::new::<meter>(f64::from(meters)))
+    }
+}
+
+impl AprsNmeaPosition {
+    pub fn speed(&self) -> Option<Velocity> {
+        self.speed_knots.map(Velocity::new::<knot>)
+    }
+
+    /// Altitude above mean sea level, only in GGA sentences.
+    pub fn altitude(&self) -> Option<Length> {
+        self.altitude_m.map(Length::new::<meter>)
+    }
+}
+
+impl WeatherData {
+    pub fn temperature(&self) -> Option<ThermodynamicTemperature> {
+        self.temperature
+            .map(|t| ThermodynamicTemperature::new::<degree_fahrenheit>(f64::from(t)))
+    }
+
+    /// Sustained wind speed.
+    pub fn wind_speed(&self) -> Option<Velocity> {
+        self.wind_speed
+            .map(|s| Velocity::new::<mile_per_hour>(f64::from(s)))
+    }
+
+    /// Peak wind speed.
+    pub fn wind_gust(&self) -> Option<Velocity> {
+        self.wind_gust
+            .map(|s| Velocity::new::<mile_per_hour>(f64::from(s)))
+    }
+
+    pub fn rain_last_hour(&self) -> Option<Length> {
+        self.rain_last_hour.map(hundredths_inch)
+    }
+
+    pub fn rain_last_24_hours(&self) -> Option<Length> {
+        self.rain_last_24_hours.map(hundredths_inch)
+    }
+
+    pub fn rain_since_midnight(&self) -> Option<Length> {
+        self.rain_since_midnight.map(hundredths_inch)
+    }
+
+    pub fn barometric_pressure(&self) -> Option<Pressure> {
+        self.barometric_pressure
+            .map(|p| Pressure::new::<millibar>(f64::from(p) / 10.0))
+    }
+}
+
+fn hundredths_inch(rain: u16) -> Length {
+    Length::new::<inch>(f64::from(rain) / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uom::si::length::millimeter;
+    use uom::si::pressure::hectopascal;
+    use uom::si::thermodynamic_temperature::degree_celsius;
+    use uom::si::velocity::meter_per_second;
+    use AprsData;
+    use AprsPacket;
+
+    fn position(raw: &[u8]) -> AprsPosition {
+        match AprsPacket::decode_textual(raw).unwrap().data {
+            AprsData::Position(p) => p,
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn position_quantities() {
+        let p = position(br"N0CALL>APRS:!4903.50N/07201.75W-RNG0050/A=001234");
+        assert_relative_eq!(
+            376.1232,
+            p.altitude().unwrap().get::<meter>(),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(50.0, p.range().unwrap().get::<mile>(), epsilon = 1e-9);
+
+        let p = position(br"N0CALL>APRS:!4903.50N/07201.75W-Test");
+        assert_eq!(None, p.altitude());
+        assert_eq!(None, p.range());
+    }
+
+    #[test]
+    fn weather_quantities() {
+        let data = WeatherData {
+            temperature: Some(77),
+            wind_speed: Some(10),
+            rain_last_hour: Some(10),
+            barometric_pressure: Some(10132),
+            ..WeatherData::default()
+        };
+
+        assert_relative_eq!(
+            25.0,
+            data.temperature().unwrap().get::<degree_celsius>(),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            4.4704,
+            data.wind_speed().unwrap().get::<meter_per_second>(),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            2.54,
+            data.rain_last_hour().unwrap().get::<millimeter>(),
+            epsilon = 1e-9
+        );
+        assert_relative_eq!(
+            1013.2,
+            data.barometric_pressure().unwrap().get::<hectopascal>(),
+            epsilon = 1e-9
+        );
+        assert_eq!(None, data.wind_gust());
+    }
+}
@@ -0,0 +1,386 @@
+//! A General Query asks all stations, or only those within a footprint, to
+//! respond. It starts with the '?' APRS Data Type Identifier, followed by the
+//! query type terminated by another '?'.
+//!
+//! The footprint is a latitude and longitude in decimal degrees and a radius in miles.
+//!
+//! Examples:
+//! - "?APRS?"                        (all stations report their position)
+//! - "?IGATE?"                       (igates report their capabilities)
+//! - "?APRS? 34.02,-117.15,0200"     (stations within 200 miles)
+//!
+//! Directed queries are instead sent as messages to a single station, with
+//! texts like `?APRSP` (position) or `?APRST` (trace).
+
+use bytes::parse_bytes;
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsData;
+use AprsMessage;
+use AprsPacket;
+use AprsPosition;
+use Callsign;
+use DecodeError;
+use EncodeError;
+use Latitude;
+use Longitude;
+use Via;
+
+// Heard lists are cut to fit in a message
+const MAX_REPLY_LEN: usize = 67;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QueryType {
+    /// `?APRS?`: stations report their position and status
+    Aprs,
+    /// `?IGATE?`: igates report their capabilities
+    IGate,
+    /// `?WX?`: weather stations report their weather
+    Weather,
+    Other(#[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))] Vec<u8>),
+}
+
+impl QueryType {
+    fn as_bytes(&self) -> &[u8] {
+        match self {
+            Self::Aprs => b"APRS",
+            Self::IGate => b"IGATE",
+            Self::Weather => b"WX",
+            Self::Other(q) => q,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueryFootprint {
+    pub latitude: Latitude,
+    pub longitude: Longitude,
+    pub radius_miles: u16,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AprsQuery {
+    pub to: Callsign,
+    pub query: QueryType,
+    pub footprint: Option<QueryFootprint>,
+}
+
+impl AprsQuery {
+    /// Decodes the information field after the `?`.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        let err = || DecodeError::InvalidQuery(b.to_owned(), 0);
+
+        let end = b.iter().position(|&c| c == b'?').ok_or_else(err)?;
+        let query = match &b[..end] {
+            b"APRS" => QueryType::Aprs,
+            b"IGATE" => QueryType::IGate,
+            b"WX" => QueryType::Weather,
+            q if !q.is_empty() && q.iter().all(u8::is_ascii_alphanumeric) => {
+                QueryType::Other(q.to_owned())
+            }
+            _ => return Err(err()),
+        };
+
+        let rest = &b[end + 1..];
+        let footprint = match rest.strip_prefix(b" ") {
+            Some(f) if !f.is_empty() => Some(decode_footprint(f).ok_or_else(err)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            to,
+            query,
+            footprint,
+        })
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        let query = self.query.as_bytes();
+        if query.is_empty() || query.contains(&b'?') {
+            return Err(EncodeError::InvalidData);
+        }
+        if matches!(&self.footprint, Some(f) if f.radius_miles > 9999) {
+            return Err(EncodeError::InvalidData);
+        }
+
+        buf.write_all(b"?")?;
+        buf.write_all(query)?;
+        buf.write_all(b"?")?;
+
+        if let Some(f) = &self.footprint {
+            write!(
+                buf,
+                " {},{},{:04}",
+                f.latitude.value(),
+                f.longitude.value(),
+                f.radius_miles
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A query sent as a message to a single station.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DirectedQuery {
+    /// `?APRSD`: stations heard directly
+    Heard,
+    /// `?APRSH CALL`: how often the station was heard
+    HeardStation(Vec<u8>),
+    /// `?APRSM`: messages waiting to be acked
+    Messages,
+    /// `?APRSO`: objects and items
+    Objects,
+    /// `?APRSP`: the station's position
+    Position,
+    /// `?APRSS`: the station's status
+    Status,
+    /// `?APRST` or `?PING?`: the path the query took
+    Trace,
+    /// Any other query, without the leading `?`
+    Other(Vec<u8>),
+}
+
+impl DirectedQuery {
+    /// Decodes the text of a message. Returns `None` if it isn't a query.
+    pub fn decode(text: &[u8]) -> Option<Self> {
+        let query = text.strip_prefix(b"?")?;
+
+        Some(match query {
+            b"APRSD" => Self::Heard,
+            b"APRSM" => Self::Messages,
+            b"APRSO" => Self::Objects,
+            b"APRSP" => Self::Position,
+            b"APRSS" => Self::Status,
+            b"APRST" | b"PING?" => Self::Trace,
+            _ => match query.strip_prefix(b"APRSH ") {
+                Some(call) if !call.is_empty() => Self::HeardStation(call.to_owned()),
+                _ if !query.is_empty() => Self::Other(query.to_owned()),
+                _ => return None,
+            },
+        })
+    }
+
+    /// The query sent in a received packet, if it's a message.
+    pub fn from_packet(packet: &AprsPacket) -> Option<Self> {
+        match &packet.data {
+            AprsData::Message(m) => m.directed_query(),
+            _ => None,
+        }
+    }
+
+    /// Answers a `?APRSP` query with the station's position report.
+    /// Returns `None` if the packet isn't a position query.
+    pub fn position_reply(query: &AprsPacket, position: &AprsPosition) -> Option<AprsData> {
+        match Self::from_packet(query)? {
+            Self::Position => Some(AprsData::Position(position.clone())),
+            _ => None,
+        }
+    }
+
+    /// Answers a `?APRST` or `?PING?` query with a message holding the path it
+    /// was received through, e.g. `N0CALL>APRS,DIGI1*,WIDE2*`.
+    /// The path stops at the first q construct, since the rest is on APRS-IS.
+    pub fn trace_reply(query: &AprsPacket) -> Option<AprsMessage> {
+        let msg = match (Self::from_packet(query)?, &query.data) {
+            (Self::Trace, AprsData::Message(m)) => m,
+            _ => return None,
+        };
+
+        let mut text = vec![];
+        query.from.encode_textual(false, &mut text).ok()?;
+        text.push(b'>');
+        msg.to.encode_textual(false, &mut text).ok()?;
+        for via in &query.via {
+            if let Via::QConstruct(_) = via {
+                break;
+            }
+            text.push(b',');
+            via.encode_textual(&mut text).ok()?;
+        }
+
+        Self::reply(query, msg, &text)
+    }
+
+    /// Answers a `?APRSD` query with a message listing the stations heard
+    /// directly, e.g. `Directs= N1CALL N2CALL-7`. Stations that don't fit in
+    /// the message are left out.
+    pub fn heard_reply(query: &AprsPacket, heard: &[Callsign]) -> Option<AprsMessage> {
+        let msg = match (Self::from_packet(query)?, &query.data) {
+            (Self::Heard, AprsData::Message(m)) => m,
+            _ => return None,
+        };
+
+        let mut text = b"Directs=".to_vec();
+        for call in heard {
+            let call = call.to_string();
+            if text.len() + 1 + call.len() > MAX_REPLY_LEN {
+                break;
+            }
+            text.push(b' ');
+            text.extend_from_slice(call.as_bytes());
+        }
+
+        Self::reply(query, msg, &text)
+    }
+
+    fn reply(query: &AprsPacket, msg: &AprsMessage, text: &[u8]) -> Option<AprsMessage> {
+        AprsMessage::new(
+            msg.to.clone(),
+            query.from.to_string().as_bytes(),
+            text,
+            None,
+        )
+        .ok()
+    }
+}
+
+fn decode_footprint(b: &[u8]) -> Option<QueryFootprint> {
+    let mut fields = b.split(|&c| c == b',');
+    let latitude = Latitude::new(parse_bytes(fields.next()?)?)?;
+    let longitude = Longitude::new(parse_bytes(fields.next()?)?)?;
+    let radius = fields.next()?;
+    if fields.next().is_some() || !radius.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    Some(QueryFootprint {
+        latitude,
+        longitude,
+        radius_miles: parse_bytes(radius)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_callsign() -> Callsign {
+        Callsign::new_no_ssid("APRS")
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        for (original, query) in [
+            (&b"?APRS?"[..], QueryType::Aprs),
+            (b"?IGATE?", QueryType::IGate),
+            (b"?WX?", QueryType::Weather),
+            (b"?PING?", QueryType::Other(b"PING".to_vec())),
+        ] {
+            let decoded = AprsQuery::decode(&original[1..], default_callsign()).unwrap();
+            assert_eq!(query, decoded.query);
+            assert_eq!(None, decoded.footprint);
+
+            let mut buf = vec![];
+            decoded.encode(&mut buf).unwrap();
+            assert_eq!(original, buf);
+        }
+    }
+
+    #[test]
+    fn footprint() {
+        let original = &b"?APRS? 34.02,-117.15,0200"[..];
+        let query = AprsQuery::decode(&original[1..], default_callsign()).unwrap();
+
+        let footprint = query.footprint.unwrap();
+        assert_relative_eq!(34.02, *footprint.latitude);
+        assert_relative_eq!(-117.15, *footprint.longitude);
+        assert_eq!(200, footprint.radius_miles);
+
+        let mut buf = vec![];
+        query.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+
+        // nothing is written if the radius doesn't fit
+        let mut query = query;
+        query.footprint.as_mut().unwrap().radius_miles = 10000;
+        let mut buf = vec![];
+        assert!(matches!(
+            query.encode(&mut buf),
+            Err(EncodeError::InvalidData)
+        ));
+        assert!(buf.is_empty());
+    }
+
+    fn received(line: &str) -> AprsPacket {
+        AprsPacket::decode_textual(line.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn directed_queries() {
+        for (text, query) in [
+            (&b"?APRSD"[..], DirectedQuery::Heard),
+            (
+                b"?APRSH N1CALL-9",
+                DirectedQuery::HeardStation(b"N1CALL-9".to_vec()),
+            ),
+            (b"?APRSP", DirectedQuery::Position),
+            (b"?APRST", DirectedQuery::Trace),
+            (b"?PING?", DirectedQuery::Trace),
+            (b"?VER", DirectedQuery::Other(b"VER".to_vec())),
+        ] {
+            assert_eq!(Some(query), DirectedQuery::decode(text));
+        }
+        assert_eq!(None, DirectedQuery::decode(b"APRSP"));
+        assert_eq!(None, DirectedQuery::decode(b"?"));
+    }
+
+    #[test]
+    fn trace_reply() {
+        let query = received("N1CALL>APRS,DIGI1*,WIDE2*,qAR,IGATE::N0CALL   :?APRST");
+        let reply = DirectedQuery::trace_reply(&query).unwrap();
+
+        let mut buf = vec![];
+        reply.encode(&mut buf).unwrap();
+        assert_eq!(&b":N1CALL   :N1CALL>APRS,DIGI1*,WIDE2*"[..], buf);
+
+        let other = received("N1CALL>APRS::N0CALL   :?APRSD");
+        assert_eq!(None, DirectedQuery::trace_reply(&other));
+    }
+
+    #[test]
+    fn heard_reply() {
+        let query = received("N1CALL>APRS::N0CALL   :?APRSD");
+        let heard: Vec<_> = (0..10)
+            .map(|i| Callsign::new_with_ssid("N2CALL", i.to_string()))
+            .collect();
+        let reply = DirectedQuery::heard_reply(&query, &heard).unwrap();
+
+        assert_eq!(b"N1CALL", &reply.addressee[..]);
+        assert!(reply.text.starts_with(b"Directs= N2CALL-0 N2CALL-1 "));
+        assert_eq!(62, reply.text.len());
+    }
+
+    #[test]
+    fn position_reply() {
+        let position = received("N0CALL>APRS:!4903.50N/07201.75W-");
+        let position = match position.data {
+            AprsData::Position(p) => p,
+            _ => unreachable!(),
+        };
+
+        let query = received("N1CALL>APRS::N0CALL   :?APRSP");
+        assert_eq!(
+            Some(AprsData::Position(position.clone())),
+            DirectedQuery::position_reply(&query, &position)
+        );
+
+        let query = received("N1CALL>APRS::N0CALL   :?APRST");
+        assert_eq!(None, DirectedQuery::position_reply(&query, &position));
+    }
+
+    #[test]
+    fn decode_invalid() {
+        for b in [&b"APRS"[..], b"?", b"APRS? 34.02,-117.15", b"APRS? 94,0,10"] {
+            assert_eq!(
+                Err(DecodeError::InvalidQuery(b.to_vec(), 0)),
+                AprsQuery::decode(b, default_callsign())
+            );
+        }
+    }
+}
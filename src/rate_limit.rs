@@ -0,0 +1,194 @@
+//! Rate limiting for transmitted packets, to respect channel-sharing conventions
+//! on RF and the limits enforced by APRS-IS servers.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use AprsData;
+use AprsPacket;
+
+/// A token bucket holding up to `capacity` tokens, refilled with one token per `interval`.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    capacity: u32,
+    interval: Duration,
+    tokens: u32,
+    last_refill: Option<Instant>,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket. Allows bursts of up to `capacity` packets,
+    /// and one packet per `interval` after that.
+    /// Returns `None` if `capacity` or `interval` is zero.
+    pub fn new(capacity: u32, interval: Duration) -> Option<Self> {
+        if capacity == 0 || interval == Duration::ZERO {
+            return None;
+        }
+
+        Some(Self {
+            capacity,
+            interval,
+            tokens: capacity,
+            last_refill: None,
+        })
+    }
+
+    /// Allows one packet per `interval`, with no bursts.
+    /// Returns `None` if `interval` is zero.
+    pub fn min_interval(interval: Duration) -> Option<Self> {
+        Self::new(1, interval)
+    }
+
+    /// Takes a token if one is available at `now`.
+    pub fn try_acquire(&mut self, now: Instant) -> bool {
+        self.refill(now);
+
+        if self.tokens == 0 {
+            return false;
+        }
+
+        self.tokens -= 1;
+        true
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let last = match self.last_refill {
+            Some(last) if self.tokens < self.capacity => last,
+            _ => {
+                self.last_refill = Some(now);
+                return;
+            }
+        };
+
+        let elapsed = now.saturating_duration_since(last);
+        let earned = elapsed.as_nanos() / self.interval.as_nanos();
+        if earned == 0 {
+            return;
+        }
+
+        if earned >= u128::from(self.capacity - self.tokens) {
+            self.tokens = self.capacity;
+            self.last_refill = Some(now);
+        } else {
+            // keep the remainder, so that frequent checks don't lose time
+            self.tokens += earned as u32;
+            self.last_refill = Some(last + self.interval * earned as u32);
+        }
+    }
+}
+
+/// What a [`RateLimiter`] keeps separate buckets for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RateLimitKey {
+    pub data_type_identifier: u8,
+    /// The addressee, for messages.
+    pub addressee: Option<Vec<u8>>,
+}
+
+impl RateLimitKey {
+    pub fn of(packet: &AprsPacket) -> Self {
+        let addressee = match &packet.data {
+            AprsData::Message(m) => Some(m.addressee.clone()),
+            _ => None,
+        };
+
+        Self {
+            data_type_identifier: packet.data.data_type_identifier(),
+            addressee,
+        }
+    }
+}
+
+/// Keeps a [`TokenBucket`] per packet type and addressee.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    // copied for each new key
+    full: TokenBucket,
+    buckets: HashMap<RateLimitKey, TokenBucket>,
+}
+
+impl RateLimiter {
+    /// Each packet type and addressee gets its own bucket,
+    /// as created by [`TokenBucket::new`].
+    /// Returns `None` if `capacity` or `interval` is zero.
+    pub fn new(capacity: u32, interval: Duration) -> Option<Self> {
+        Some(Self {
+            full: TokenBucket::new(capacity, interval)?,
+            buckets: HashMap::new(),
+        })
+    }
+
+    /// Returns whether `packet` may be sent at `now`, using up a token if so.
+    pub fn try_acquire(&mut self, packet: &AprsPacket, now: Instant) -> bool {
+        let full = &self.full;
+
+        self.buckets
+            .entry(RateLimitKey::of(packet))
+            .or_insert_with(|| full.clone())
+            .try_acquire(now)
+    }
+
+    /// Drops all buckets, allowing full bursts again.
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_bucket() {
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let mut bucket = TokenBucket::new(2, Duration::from_secs(10)).unwrap();
+
+        assert!(bucket.try_acquire(start));
+        assert!(bucket.try_acquire(start + second));
+        assert!(!bucket.try_acquire(start + second * 2));
+
+        assert!(bucket.try_acquire(start + second * 10));
+        assert!(!bucket.try_acquire(start + second * 15));
+        assert!(bucket.try_acquire(start + second * 20));
+
+        // refills to capacity, not beyond
+        assert!(bucket.try_acquire(start + second * 100));
+        assert!(bucket.try_acquire(start + second * 100));
+        assert!(!bucket.try_acquire(start + second * 100));
+    }
+
+    #[test]
+    fn min_interval() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::min_interval(Duration::from_secs(30)).unwrap();
+
+        assert!(bucket.try_acquire(start));
+        assert!(!bucket.try_acquire(start + Duration::from_secs(29)));
+        assert!(bucket.try_acquire(start + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn zero_capacity_or_interval() {
+        assert!(TokenBucket::new(0, Duration::from_secs(10)).is_none());
+        assert!(TokenBucket::min_interval(Duration::ZERO).is_none());
+        assert!(RateLimiter::new(0, Duration::from_secs(10)).is_none());
+        assert!(RateLimiter::new(1, Duration::ZERO).is_none());
+    }
+
+    #[test]
+    fn keyed_by_type_and_addressee() {
+        let start = Instant::now();
+        let mut limiter = RateLimiter::new(1, Duration::from_secs(60)).unwrap();
+
+        let msg_a = AprsPacket::decode_textual(&b"N0CALL>APRS::N1CALL   :Hi"[..]).unwrap();
+        let msg_b = AprsPacket::decode_textual(&b"N0CALL>APRS::N2CALL   :Hi"[..]).unwrap();
+        let status = AprsPacket::decode_textual(&b"N0CALL>APRS:>Hi"[..]).unwrap();
+
+        assert!(limiter.try_acquire(&msg_a, start));
+        assert!(!limiter.try_acquire(&msg_a, start));
+        assert!(limiter.try_acquire(&msg_b, start));
+        assert!(limiter.try_acquire(&status, start));
+        assert!(!limiter.try_acquire(&status, start));
+    }
+}
@@ -0,0 +1,134 @@
+//! Reading packets from TNC2 text, e.g. log files or a recorded APRS-IS feed.
+//!
+//! ```rust
+//! let log = &b"# recorded feed\r\nN0CALL>APRS:>Hello\r\nN1CALL>APRS:>World\n"[..];
+//!
+//! for packet in aprs_parser::read_packets(log) {
+//!     println!("{:?}", packet);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+use AprsPacket;
+use DecodeError;
+
+/// Returns an iterator over the packets in `reader`, one per line.
+/// Lines can end in LF, CRLF or CR. Empty lines and comments starting with `#`
+/// are skipped.
+pub fn read_packets<R: BufRead>(reader: R) -> PacketReader<R> {
+    PacketReader {
+        reader,
+        lines: VecDeque::new(),
+        error: None,
+    }
+}
+
+pub struct PacketReader<R> {
+    reader: R,
+    lines: VecDeque<Vec<u8>>,
+    error: Option<io::Error>,
+}
+
+impl<R> PacketReader<R> {
+    /// Iteration stops at the first I/O error, which is kept here.
+    pub fn io_error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: BufRead> PacketReader<R> {
+    fn next_line(&mut self) -> Option<Vec<u8>> {
+        while self.lines.is_empty() {
+            if self.error.is_some() {
+                return None;
+            }
+
+            let mut buf = vec![];
+            match self.reader.read_until(b'\n', &mut buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.error = Some(e);
+                    return None;
+                }
+            }
+
+            // a lone CR ends a line too
+            self.lines.extend(
+                buf.split(|&b| b == b'\r' || b == b'\n')
+                    .filter(|l| !l.is_empty() && !l.starts_with(b"#"))
+                    .map(<[u8]>::to_vec),
+            );
+        }
+
+        self.lines.pop_front()
+    }
+}
+
+impl<R: BufRead> Iterator for PacketReader<R> {
+    type Item = Result<AprsPacket, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_line()
+            .map(|line| AprsPacket::decode_textual(&line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Read};
+    use Callsign;
+
+    #[test]
+    fn line_endings() {
+        let log = &b"N0CALL>APRS:>1\nN1CALL>APRS:>2\r\nN2CALL>APRS:>3\rN3CALL>APRS:>4"[..];
+        let from: Vec<_> = read_packets(log).map(|p| p.unwrap().from).collect();
+        assert_eq!(
+            vec![
+                Callsign::new_no_ssid("N0CALL"),
+                Callsign::new_no_ssid("N1CALL"),
+                Callsign::new_no_ssid("N2CALL"),
+                Callsign::new_no_ssid("N3CALL"),
+            ],
+            from
+        );
+    }
+
+    #[test]
+    fn skips_comments() {
+        let log = &b"# aprsc 2.1.14\r\n\r\nN0CALL>APRS:>1\r\nInvalid\r\n# keepalive\r\n"[..];
+        let mut packets = read_packets(log);
+        assert!(packets.next().unwrap().is_ok());
+        assert!(matches!(
+            packets.next(),
+            Some(Err(DecodeError::InvalidPacket(..)))
+        ));
+        assert!(packets.next().is_none());
+        assert!(packets.io_error().is_none());
+    }
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::new(io::ErrorKind::Other, "disconnected"))
+        }
+    }
+
+    #[test]
+    fn io_error() {
+        let reader = (&b"N0CALL>APRS:>1\n"[..]).chain(FailingReader);
+        let mut packets = read_packets(BufReader::new(reader));
+        assert!(packets.next().unwrap().is_ok());
+        assert!(packets.next().is_none());
+        assert_eq!(io::ErrorKind::Other, packets.io_error().unwrap().kind());
+    }
+}
@@ -0,0 +1,226 @@
+//! Rewriting of packets so they can be shared safely, e.g. in bug reports.
+//!
+//! Scrubbed packets remain decodable: callsigns are replaced with consistent
+//! pseudonyms, positions are truncated to a chosen ambiguity and message bodies
+//! are removed.
+
+use std::collections::HashMap;
+
+use path;
+use AprsData;
+use AprsPacket;
+use AprsPosition;
+use Callsign;
+use Precision;
+use Via;
+
+#[derive(Debug, Clone)]
+pub struct Scrubber {
+    precision: Precision,
+    strip_messages: bool,
+    pseudonyms: HashMap<String, String>,
+}
+
+impl Scrubber {
+    /// Creates a scrubber that reduces positions to the nearest minute
+    /// and removes message bodies.
+    pub fn new() -> Self {
+        Self {
+            precision: Precision::OneMinute,
+            strip_messages: true,
+            pseudonyms: HashMap::new(),
+        }
+    }
+
+    /// Sets the precision positions are reduced to.
+    /// Positions that are already less precise are left alone.
+    pub fn with_precision(mut self, precision: Precision) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn with_strip_messages(mut self, strip_messages: bool) -> Self {
+        self.strip_messages = strip_messages;
+        self
+    }
+
+    /// Returns a scrubbed copy of `packet`.
+    /// The same station is given the same pseudonym for the lifetime of the scrubber.
+    /// SSIDs, generic path aliases and the destination field are kept.
+    pub fn scrub(&mut self, packet: &AprsPacket) -> AprsPacket {
+        let mut packet = packet.clone();
+
+        packet.from = self.pseudonym(&packet.from);
+
+        for v in &mut packet.via {
+            if let Via::Callsign(c, _) = v {
+                if path::alias(c).is_none() && !path::is_internet_marker(c) {
+                    *c = self.pseudonym(c);
+                }
+            }
+        }
+
+        match &mut packet.data {
+            AprsData::Position(p) => self.scrub_position(p),
+            AprsData::Object(o) => self.scrub_position(&mut o.position),
+            AprsData::Item(i) => self.scrub_position(&mut i.position),
+            AprsData::MicE(m) => {
+                m.precision = m.precision.min(self.precision);
+                m.latitude = m.latitude.rounded(m.precision);
+                m.longitude = m.longitude.rounded(m.precision);
+            }
+            AprsData::Nmea(n) => {
+                let latitude = n.latitude.rounded(self.precision);
+                let longitude = n.longitude.rounded(self.precision);
+                n.set_position(latitude, longitude);
+            }
+            AprsData::Message(m) => {
+                if let Some(addressee) = self.scrub_addressee(&m.addressee) {
+                    m.addressee = addressee;
+                }
+                if self.strip_messages {
+                    m.text.clear();
+                }
+            }
+            AprsData::ThirdParty(t) => {
+                let inner = self.scrub(&t.packet);
+                *t.packet = inner;
+            }
+            AprsData::Status(_)
+            | AprsData::Telemetry(_)
+            | AprsData::Weather(_)
+            | AprsData::RawWeather(_)
+            | AprsData::Query(_)
+            | AprsData::Capabilities(_)
+            | AprsData::UserDefined(_)
+            | AprsData::TestData(_)
+            | AprsData::Unknown(_) => {}
+        }
+
+        packet
+    }
+
+    fn scrub_position(&self, p: &mut AprsPosition) {
+        p.precision = p.precision.min(self.precision);
+        p.latitude = p.latitude.rounded(p.precision);
+        p.longitude = p.longitude.rounded(p.precision);
+    }
+
+    // Pseudonyms are X followed by five base-36 digits, which is still a valid AX.25 callsign.
+    fn pseudonym(&mut self, c: &Callsign) -> Callsign {
+        let next = self.pseudonyms.len();
+        let call = self
+            .pseudonyms
+            .entry(c.call().to_ascii_uppercase())
+            .or_insert_with(|| format!("X{:0>5}", to_base36(next)))
+            .clone();
+
+        match c.ssid() {
+            Some(ssid) => Callsign::new_with_ssid(call, ssid),
+            None => Callsign::new_no_ssid(call),
+        }
+    }
+
+    // Bulletins, announcements and service addressees aren't stations
+    fn scrub_addressee(&mut self, addressee: &[u8]) -> Option<Vec<u8>> {
+        let addressee = std::str::from_utf8(addressee).ok()?;
+        if ["BLN", "NWS", "EMAIL"]
+            .iter()
+            .any(|prefix| addressee.starts_with(prefix))
+        {
+            return None;
+        }
+
+        let c = self.pseudonym(&Callsign::new(addressee)?);
+        Some(c.to_string().into_bytes())
+    }
+}
+
+impl Default for Scrubber {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn to_base36(mut n: usize) -> String {
+    let mut digits = vec![];
+    loop {
+        digits.push(
+            std::char::from_digit((n % 36) as u32, 36)
+                .unwrap()
+                .to_ascii_uppercase(),
+        );
+        n /= 36;
+        if n == 0 {
+            break;
+        }
+    }
+
+    digits.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrub(scrubber: &mut Scrubber, s: &str) -> String {
+        let packet = AprsPacket::decode_textual(s.as_bytes()).unwrap();
+
+        let mut buf = vec![];
+        scrubber.scrub(&packet).encode_textual(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn pseudonyms_are_consistent() {
+        let mut scrubber = Scrubber::new();
+
+        assert_eq!(
+            "X00000-9>APRS,X00001-2,WIDE1*,WIDE2-1,qAR,X00002::X00003-7 :{01",
+            scrub(
+                &mut scrubber,
+                "N0CALL-9>APRS,KD8ABC-2*,WIDE1*,WIDE2-1,qAR,IGATE::N1CALL-7 :Meet at home{01"
+            )
+        );
+        assert_eq!(
+            "X00003>APRS,TCPIP*,qAC,X00004::X00000-9 :",
+            scrub(&mut scrubber, "N1CALL>APRS,TCPIP*,qAC,T2TEST::N0CALL-9 :Hi")
+        );
+        assert_eq!(
+            "X00003>APRS::BLN1     :",
+            scrub(&mut scrubber, "N1CALL>APRS::BLN1     :Net tonight")
+        );
+    }
+
+    #[test]
+    fn positions_are_truncated() {
+        let mut scrubber = Scrubber::new();
+
+        let scrubbed = scrub(&mut scrubber, "N0CALL>APRS:!4903.57N/07201.75W-Hello");
+        assert_eq!("X00000>APRS:!4904.  N/07202.00W-Hello", scrubbed);
+
+        let decoded = AprsPacket::decode_textual(scrubbed.as_bytes()).unwrap();
+        match decoded.data {
+            AprsData::Position(p) => {
+                assert_eq!(Precision::OneMinute, p.precision);
+                assert_relative_eq!(49.066666, *p.latitude, epsilon = 0.00001);
+                assert_relative_eq!(-72.033333, *p.longitude, epsilon = 0.00001);
+            }
+            _ => panic!("Unexpected data"),
+        }
+
+        // already less precise than requested
+        let mut scrubber = Scrubber::new().with_precision(Precision::HundredthMinute);
+        assert_eq!(
+            "X00000>APRS:!49  .  N/07200.00W-",
+            scrub(&mut scrubber, "N0CALL>APRS:!49  .  N/07201.75W-")
+        );
+    }
+
+    #[test]
+    fn base36() {
+        assert_eq!("0", to_base36(0));
+        assert_eq!("Z", to_base36(35));
+        assert_eq!("10", to_base36(36));
+    }
+}
@@ -0,0 +1,245 @@
+//! Serde support for the packet types, behind the `serde` feature.
+//!
+//! Raw byte fields like comments are serialized as strings by human-readable
+//! formats like JSON, falling back to an array of bytes if they aren't UTF-8.
+//! Binary formats get the bytes as they are.
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, Serializer};
+
+use mic_e::{Course, Speed};
+use Latitude;
+use Longitude;
+
+struct Text<'a>(&'a [u8]);
+
+impl Serialize for Text<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match std::str::from_utf8(self.0) {
+            Ok(s) if serializer.is_human_readable() => serializer.serialize_str(s),
+            _ => serializer.serialize_bytes(self.0),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Hash)]
+struct TextBuf(Vec<u8>);
+
+impl<'de> Deserialize<'de> for TextBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_byte_buf(TextVisitor).map(TextBuf)
+    }
+}
+
+struct TextVisitor;
+
+impl<'de> Visitor<'de> for TextVisitor {
+    type Value = Vec<u8>;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a string or bytes")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Vec<u8>, E> {
+        Ok(v.as_bytes().to_vec())
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Vec<u8>, E> {
+        Ok(v.into_bytes())
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Vec<u8>, E> {
+        Ok(v.to_vec())
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Vec<u8>, E> {
+        Ok(v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+        while let Some(b) = seq.next_element()? {
+            bytes.push(b);
+        }
+        Ok(bytes)
+    }
+}
+
+// For `#[serde(with = "::serde_support::text")]` on `Vec<u8>` fields
+pub mod text {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        Text(bytes).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        TextBuf::deserialize(deserializer).map(|t| t.0)
+    }
+}
+
+// For `Option<Vec<u8>>` fields
+pub mod text_option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        bytes: &Option<Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        bytes.as_deref().map(Text).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Vec<u8>>, D::Error> {
+        Ok(Option::<TextBuf>::deserialize(deserializer)?.map(|t| t.0))
+    }
+}
+
+// For the key/value pairs of capabilities
+pub mod text_pairs {
+    use super::*;
+
+    #[allow(clippy::type_complexity)]
+    pub fn serialize<S: Serializer>(
+        pairs: &[(Vec<u8>, Option<Vec<u8>>)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            pairs
+                .iter()
+                .map(|(key, value)| (Text(key), value.as_deref().map(Text))),
+        )
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(Vec<u8>, Option<Vec<u8>>)>, D::Error> {
+        let pairs = Vec::<(TextBuf, Option<TextBuf>)>::deserialize(deserializer)?;
+        Ok(pairs
+            .into_iter()
+            .map(|(key, value)| (key.0, value.map(|v| v.0)))
+            .collect())
+    }
+}
+
+// For maps of byte strings, like the pending messages of a `MessageBuilder`
+pub mod text_map {
+    use super::*;
+    use std::collections::HashMap;
+
+    pub fn serialize<S: Serializer>(
+        map: &HashMap<Vec<u8>, Vec<u8>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(key, value)| (Text(key), Text(value))))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<Vec<u8>, Vec<u8>>, D::Error> {
+        let map = HashMap::<TextBuf, TextBuf>::deserialize(deserializer)?;
+        Ok(map
+            .into_iter()
+            .map(|(key, value)| (key.0, value.0))
+            .collect())
+    }
+}
+
+// Validated newtypes are serialized as their value, and checked again on the way in
+macro_rules! validated {
+    ($ty:ty, $inner:ty, $get:expr, $expecting:expr) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let get: fn(&$ty) -> $inner = $get;
+                get(self).serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = <$inner>::deserialize(deserializer)?;
+                <$ty>::new(value).ok_or_else(|| {
+                    de::Error::invalid_value(
+                        de::Unexpected::Other("out of range value"),
+                        &$expecting,
+                    )
+                })
+            }
+        }
+    };
+}
+
+validated!(Latitude, f64, |l| **l, "a latitude from -90 to 90");
+validated!(Longitude, f64, |l| **l, "a longitude from -180 to 180");
+validated!(Speed, u32, Speed::knots, "a speed of at most 799 knots");
+validated!(
+    Course,
+    u32,
+    Course::degrees,
+    "a course of at most 360 degrees"
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_test::{assert_de_tokens_error, assert_tokens, Configure, Token};
+    use AprsStatus;
+    use Callsign;
+
+    fn status_tokens(comment: Token) -> Vec<Token> {
+        vec![
+            Token::Struct {
+                name: "AprsStatus",
+                len: 4,
+            },
+            Token::Str("to"),
+            Token::Struct {
+                name: "Callsign",
+                len: 2,
+            },
+            Token::Str("call"),
+            Token::Str("APRS"),
+            Token::Str("ssid"),
+            Token::None,
+            Token::StructEnd,
+            Token::Str("data_type_identifier"),
+            Token::U8(b'>'),
+            Token::Str("timestamp"),
+            Token::None,
+            Token::Str("comment"),
+            comment,
+            Token::StructEnd,
+        ]
+    }
+
+    #[test]
+    fn text() {
+        let status = AprsStatus::new(Callsign::new_no_ssid("APRS"), None, b"Hello".to_vec());
+        assert_tokens(
+            &status.clone().readable(),
+            &status_tokens(Token::Str("Hello")),
+        );
+        assert_tokens(&status.compact(), &status_tokens(Token::Bytes(b"Hello")));
+
+        let status = AprsStatus::new(Callsign::new_no_ssid("APRS"), None, b"Caf\xe9".to_vec());
+        assert_tokens(&status.readable(), &status_tokens(Token::Bytes(b"Caf\xe9")));
+    }
+
+    #[test]
+    fn validated() {
+        assert_tokens(&Latitude::new(49.5).unwrap(), &[Token::F64(49.5)]);
+        assert_de_tokens_error::<Latitude>(
+            &[Token::F64(90.5)],
+            "invalid value: out of range value, expected a latitude from -90 to 90",
+        );
+        assert_tokens(&Speed::new(20).unwrap(), &[Token::U32(20)]);
+        assert_de_tokens_error::<Speed>(
+            &[Token::U32(800)],
+            "invalid value: out of range value, expected a speed of at most 799 knots",
+        );
+    }
+}
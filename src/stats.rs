@@ -0,0 +1,156 @@
+//! Counters describing parser and traffic health, with output in the
+//! [Prometheus text exposition format] for scraping.
+//!
+//! [Prometheus text exposition format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+
+use alloc::collections::BTreeMap;
+
+use io::Write;
+use AprsData;
+use AprsPacket;
+use DecodeError;
+use EncodeError;
+use Reception;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PacketStatistics {
+    decoded: BTreeMap<&'static str, u64>,
+    received: BTreeMap<&'static str, u64>,
+    errors: u64,
+}
+
+impl PacketStatistics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the outcome of decoding one packet.
+    pub fn record(&mut self, result: &Result<AprsPacket, DecodeError>) {
+        let packet = match result {
+            Ok(p) => p,
+            Err(_) => {
+                self.errors += 1;
+                return;
+            }
+        };
+
+        let kind = match packet.data {
+            AprsData::Position(_) => "position",
+            AprsData::Message(_) => "message",
+            AprsData::Status(_) => "status",
+            AprsData::MicE(_) => "mic_e",
+            AprsData::Telemetry(_) => "telemetry",
+            AprsData::Object(_) => "object",
+            AprsData::Item(_) => "item",
+            AprsData::Query(_) => "query",
+            AprsData::Weather(_) | AprsData::RawWeather(_) => "weather",
+            AprsData::Capabilities(_) => "capabilities",
+            AprsData::UserDefined(_) => "user_defined",
+            AprsData::ThirdParty(_) => "third_party",
+            AprsData::Nmea(_) => "nmea",
+            AprsData::TestData(_) => "test",
+            AprsData::Unknown(_) => "unknown",
+        };
+        *self.decoded.entry(kind).or_default() += 1;
+
+        let reception = match packet.reception() {
+            Reception::Direct => "direct",
+            Reception::Digipeated(_) => "digipeated",
+            Reception::Internet => "internet",
+        };
+        *self.received.entry(reception).or_default() += 1;
+    }
+
+    /// Total number of successfully decoded packets.
+    pub fn decoded(&self) -> u64 {
+        self.decoded.values().sum()
+    }
+
+    /// Total number of packets that failed to decode.
+    pub fn errors(&self) -> u64 {
+        self.errors
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Writes all counters in the Prometheus text exposition format.
+    pub fn encode_prometheus<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        writeln!(
+            buf,
+            "# HELP aprs_packets_decoded_total Packets decoded successfully, by payload type."
+        )?;
+        writeln!(buf, "# TYPE aprs_packets_decoded_total counter")?;
+        for (kind, count) in &self.decoded {
+            writeln!(
+                buf,
+                "aprs_packets_decoded_total{{type=\"{}\"}} {}",
+                kind, count
+            )?;
+        }
+
+        writeln!(
+            buf,
+            "# HELP aprs_packets_received_total Packets decoded successfully, by how they were received."
+        )?;
+        writeln!(buf, "# TYPE aprs_packets_received_total counter")?;
+        for (reception, count) in &self.received {
+            writeln!(
+                buf,
+                "aprs_packets_received_total{{reception=\"{}\"}} {}",
+                reception, count
+            )?;
+        }
+
+        writeln!(
+            buf,
+            "# HELP aprs_decode_errors_total Packets that failed to decode."
+        )?;
+        writeln!(buf, "# TYPE aprs_decode_errors_total counter")?;
+        writeln!(buf, "aprs_decode_errors_total {}", self.errors)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "std"))]
+    use prelude::*;
+
+    #[test]
+    fn prometheus() {
+        let mut stats = PacketStatistics::new();
+        for line in [
+            &b"N0CALL>APRS,TCPIP*,qAC,T2TEST:>Hi"[..],
+            &b"N0CALL>APRS,KD8ABC-2*,WIDE1*:!4903.50N/07201.75W-"[..],
+            &b"N0CALL>APRS:!4903.50N/07201.75W-"[..],
+            &b"N0CALL"[..],
+        ] {
+            stats.record(&AprsPacket::decode_textual(line));
+        }
+
+        assert_eq!(3, stats.decoded());
+        assert_eq!(1, stats.errors());
+
+        let mut buf = vec![];
+        stats.encode_prometheus(&mut buf).unwrap();
+        assert_eq!(
+            "# HELP aprs_packets_decoded_total Packets decoded successfully, by payload type.\n\
+             # TYPE aprs_packets_decoded_total counter\n\
+             aprs_packets_decoded_total{type=\"position\"} 2\n\
+             aprs_packets_decoded_total{type=\"status\"} 1\n\
+             # HELP aprs_packets_received_total Packets decoded successfully, by how they were received.\n\
+             # TYPE aprs_packets_received_total counter\n\
+             aprs_packets_received_total{reception=\"digipeated\"} 1\n\
+             aprs_packets_received_total{reception=\"direct\"} 1\n\
+             aprs_packets_received_total{reception=\"internet\"} 1\n\
+             # HELP aprs_decode_errors_total Packets that failed to decode.\n\
+             # TYPE aprs_decode_errors_total counter\n\
+             aprs_decode_errors_total 1\n",
+            String::from_utf8(buf).unwrap()
+        );
+    }
+}
@@ -7,21 +7,29 @@
 //! - ">120503hFatal error"             (report with timestamp in HMS format)
 //! - ">281205zSystem will shutdown"    (report with timestamp in DHM format)
 
-use std::convert::TryFrom;
-use std::io::Write;
+use alloc::borrow::Cow;
+use core::convert::TryFrom;
 
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use text;
 use Callsign;
+use DataExtension;
 use DecodeError;
 use DhmTimestamp;
 use EncodeError;
+use Phg;
 use Timestamp;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AprsStatus {
     pub to: Callsign,
     pub data_type_identifier: u8,
 
     timestamp: Option<Timestamp>,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
     comment: Vec<u8>,
 }
 
@@ -48,6 +56,8 @@ impl AprsStatus {
         }
     }
 
+    /// Only zulu DDHHMM timestamps are compliant.
+    /// Local time and HHMMSS timestamps are only legal in position and object reports.
     pub fn is_timestamp_compliant(&self) -> bool {
         self.timestamp
             .as_ref()
@@ -63,6 +73,21 @@ impl AprsStatus {
         &self.comment
     }
 
+    /// The comment as UTF-8, with invalid sequences replaced by `�`.
+    /// See [`display_text_latin1`](crate::display_text_latin1) for a Latin-1 fallback.
+    pub fn comment_display(&self) -> Cow<'_, str> {
+        text::display_text(&self.comment)
+    }
+
+    /// Decodes the power, height, gain and directivity extension at the
+    /// start of the comment, e.g. `PHG5132`.
+    pub fn phg(&self) -> Option<Phg> {
+        match DataExtension::decode(&self.comment)? {
+            DataExtension::Phg(phg) => Some(phg),
+            _ => None,
+        }
+    }
+
     pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
         // Interpret the first 7 bytes as a timestamp, if valid.
         // Otherwise the whole field is the comment.
@@ -78,7 +103,13 @@ impl AprsStatus {
     }
 
     pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
-        write!(buf, ">")?;
+        if self.data_type_identifier != b'>' {
+            return Err(EncodeError::InvalidDataTypeIdentifier(
+                self.data_type_identifier,
+            ));
+        }
+
+        buf.write_all(&[self.data_type_identifier])?;
 
         if let Some(ts) = &self.timestamp {
             ts.encode(buf)?;
@@ -162,4 +193,37 @@ mod tests {
         assert_eq!(result.timestamp, None);
         assert!(result.is_timestamp_compliant());
     }
+
+    #[test]
+    fn local_time_is_not_compliant() {
+        let result =
+            AprsStatus::decode(r"312359/Hi there!".as_bytes(), default_callsign()).unwrap();
+
+        assert_eq!(result.timestamp, Some(Timestamp::DDHHMMLocal(31, 23, 59)));
+        assert!(!result.is_timestamp_compliant());
+    }
+
+    #[test]
+    fn phg() {
+        let result = AprsStatus::decode(&b"PHG5132 Digi"[..], default_callsign()).unwrap();
+        assert_eq!(Some(25), result.phg().map(|phg| phg.power_watts()));
+
+        let result = AprsStatus::decode(&b"Hi there!"[..], default_callsign()).unwrap();
+        assert_eq!(None, result.phg());
+    }
+
+    #[test]
+    fn encode() {
+        let mut status =
+            AprsStatus::decode(r"312359zHi there!".as_bytes(), default_callsign()).unwrap();
+        let mut buf = vec![];
+        status.encode(&mut buf).unwrap();
+        assert_eq!(&b">312359zHi there!"[..], buf);
+
+        status.data_type_identifier = b':';
+        assert!(matches!(
+            status.encode(&mut vec![]),
+            Err(EncodeError::InvalidDataTypeIdentifier(b':'))
+        ));
+    }
 }
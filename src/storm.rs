@@ -0,0 +1,185 @@
+//! Storm data, sent by stations tracking hurricanes and tropical storms,
+//! usually in object reports with the `\@` symbol, e.g.
+//! `090/012/HC^110/135/0963>040&120%200`.
+//!
+//! The fields are the storm's direction and speed of travel, its type,
+//! sustained winds and peak gusts, the central pressure, and the radii of
+//! hurricane, tropical storm and whole gale winds. Speeds are in knots,
+//! pressure in millibars and radii in nautical miles.
+
+use bytes::parse_digits;
+use io::Write;
+use EncodeError;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StormType {
+    /// `TS`
+    TropicalStorm,
+    /// `HC`
+    Hurricane,
+    /// `TD`
+    TropicalDepression,
+    /// Any other two-letter code
+    Other([u8; 2]),
+}
+
+impl StormType {
+    fn code(&self) -> [u8; 2] {
+        match self {
+            Self::TropicalStorm => *b"TS",
+            Self::Hurricane => *b"HC",
+            Self::TropicalDepression => *b"TD",
+            Self::Other(code) => *code,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StormData {
+    /// Direction the storm is moving in, in degrees
+    pub direction: u16,
+    /// Speed of the storm in knots
+    pub speed: u16,
+    pub storm_type: StormType,
+    /// Sustained wind speed in knots
+    pub sustained_wind: u16,
+    /// Peak wind gusts in knots
+    pub peak_gusts: u16,
+    /// Central pressure in millibars
+    pub central_pressure: u16,
+    /// Radius of hurricane force winds in nautical miles
+    pub radius_hurricane_winds: u16,
+    /// Radius of tropical storm force winds in nautical miles
+    pub radius_tropical_storm_winds: u16,
+    /// Radius of whole gale force winds in nautical miles, which is optional
+    pub radius_whole_gale: Option<u16>,
+}
+
+impl StormData {
+    /// Decodes the storm data at the start of `b`.
+    /// Returns the decoded data and the rest of `b`.
+    pub fn decode(b: &[u8]) -> Option<(Self, &[u8])> {
+        let fields = b.get(0..31)?;
+        if fields[3] != b'/'
+            || fields[7] != b'/'
+            || fields[10] != b'^'
+            || fields[14] != b'/'
+            || fields[18] != b'/'
+            || fields[23] != b'>'
+            || fields[27] != b'&'
+        {
+            return None;
+        }
+
+        let storm_type = match &fields[8..10] {
+            b"TS" => StormType::TropicalStorm,
+            b"HC" => StormType::Hurricane,
+            b"TD" => StormType::TropicalDepression,
+            &[a, b] if a.is_ascii_uppercase() && b.is_ascii_uppercase() => StormType::Other([a, b]),
+            _ => return None,
+        };
+
+        let mut storm = Self {
+            direction: parse_digits(&fields[0..3])?,
+            speed: parse_digits(&fields[4..7])?,
+            storm_type,
+            sustained_wind: parse_digits(&fields[11..14])?,
+            peak_gusts: parse_digits(&fields[15..18])?,
+            central_pressure: parse_digits(&fields[19..23])?,
+            radius_hurricane_winds: parse_digits(&fields[24..27])?,
+            radius_tropical_storm_winds: parse_digits(&fields[28..31])?,
+            radius_whole_gale: None,
+        };
+
+        match (b.get(31), b.get(32..35).and_then(parse_digits)) {
+            (Some(b'%'), Some(r)) => {
+                storm.radius_whole_gale = Some(r);
+                Some((storm, &b[35..]))
+            }
+            _ => Some((storm, &b[31..])),
+        }
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        let three_digits = [
+            self.direction,
+            self.speed,
+            self.sustained_wind,
+            self.peak_gusts,
+            self.radius_hurricane_winds,
+            self.radius_tropical_storm_winds,
+            self.radius_whole_gale.unwrap_or(0),
+        ];
+        if three_digits.iter().any(|&v| v > 999) || self.central_pressure > 9999 {
+            return Err(EncodeError::InvalidData);
+        }
+
+        write!(buf, "{:03}/{:03}/", self.direction, self.speed)?;
+        buf.write_all(&self.storm_type.code())?;
+        write!(
+            buf,
+            "^{:03}/{:03}/{:04}>{:03}&{:03}",
+            self.sustained_wind,
+            self.peak_gusts,
+            self.central_pressure,
+            self.radius_hurricane_winds,
+            self.radius_tropical_storm_winds
+        )?;
+        if let Some(r) = self.radius_whole_gale {
+            write!(buf, "%{:03}", r)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_and_encode() {
+        let original = &b"090/012/HC^110/135/0963>040&120%200 Hurricane Test"[..];
+        let (storm, rest) = StormData::decode(original).unwrap();
+
+        assert_eq!(
+            StormData {
+                direction: 90,
+                speed: 12,
+                storm_type: StormType::Hurricane,
+                sustained_wind: 110,
+                peak_gusts: 135,
+                central_pressure: 963,
+                radius_hurricane_winds: 40,
+                radius_tropical_storm_winds: 120,
+                radius_whole_gale: Some(200),
+            },
+            storm
+        );
+        assert_eq!(b" Hurricane Test", rest);
+
+        let mut buf = vec![];
+        storm.encode(&mut buf).unwrap();
+        assert_eq!(&original[..35], buf);
+    }
+
+    #[test]
+    fn without_whole_gale() {
+        let original = &b"270/005/TS^045/060/0998>000&050"[..];
+        let (storm, rest) = StormData::decode(original).unwrap();
+        assert_eq!(StormType::TropicalStorm, storm.storm_type);
+        assert_eq!(None, storm.radius_whole_gale);
+        assert!(rest.is_empty());
+
+        let mut buf = vec![];
+        storm.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(None, StormData::decode(b"090/012/HC^110/135/0963>040"));
+        assert_eq!(None, StormData::decode(b"090/012/hc^110/135/0963>040&120"));
+        assert_eq!(None, StormData::decode(b"090/012/HC^1+0/135/0963>040&120"));
+    }
+}
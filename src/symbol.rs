@@ -0,0 +1,617 @@
+//! Symbols are two characters: the table and the code within it.
+//! The table is `/` for the primary table, `\` for the alternate table,
+//! or an overlay character shown on top of an alternate table symbol.
+//!
+//! Examples:
+//! - "/>"                            (car)
+//! - "\\^"                           (aircraft, alternate table)
+//! - "S#"                            (digi with an `S` overlay)
+//!
+//! Compressed positions can't have a digit in the table position, so overlays
+//! `0-9` are sent as `a-j` instead.
+
+use core::fmt::{Display, Formatter};
+
+/// A symbol table and code, e.g. `/` and `j` for a jeep.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Symbol {
+    table: char,
+    code: char,
+}
+
+/// The position of an icon in the common aprs-symbols sprite sheets, where
+/// sheet 0 is the primary table, 1 the alternate table and 2 the overlay
+/// characters. Each sheet has 16 icons per row, from `!` to `~`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpriteIndex {
+    pub sheet: u8,
+    pub index: u8,
+}
+
+impl SpriteIndex {
+    pub const COLUMNS: u8 = 16;
+
+    pub fn column(&self) -> u8 {
+        self.index % Self::COLUMNS
+    }
+
+    pub fn row(&self) -> u8 {
+        self.index / Self::COLUMNS
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SymbolCategory {
+    Weather,
+    Vehicle,
+    /// Digipeaters, gateways, repeaters and other network stations
+    Infrastructure,
+    Other,
+}
+
+impl Symbol {
+    /// Creates a new `Symbol`, where the table can be an overlay.
+    /// Returns `None` if the table isn't `/`, `\`, `A-Z` or `0-9`,
+    /// the code isn't printable ASCII, or the code can't be overlaid.
+    pub fn new(table: char, code: char) -> Option<Self> {
+        Some(Self { table, code }).filter(Self::is_valid)
+    }
+
+    // Decoded symbols are kept as they are, so that packets with invalid ones
+    // are still encoded exactly as they were received
+    pub(crate) fn from_bytes(table: u8, code: u8) -> Self {
+        Self {
+            table: char::from(table),
+            code: char::from(code),
+        }
+    }
+
+    pub(crate) fn from_compressed_bytes(table: u8, code: u8) -> Self {
+        match table {
+            b'a'..=b'j' => Self::from_bytes(table - b'a' + b'0', code),
+            _ => Self::from_bytes(table, code),
+        }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        let valid_table = match self.overlay() {
+            Some(_) => OVERLAYABLE.contains(self.code),
+            None => matches!(self.table, '/' | '\\'),
+        };
+
+        valid_table && ('!'..='~').contains(&self.code)
+    }
+
+    pub fn table(&self) -> char {
+        self.table
+    }
+
+    pub fn code(&self) -> char {
+        self.code
+    }
+
+    pub fn table_byte(&self) -> u8 {
+        self.table as u8
+    }
+
+    pub fn code_byte(&self) -> u8 {
+        self.code as u8
+    }
+
+    /// The table as it's sent in compressed positions.
+    pub fn compressed_table_byte(&self) -> u8 {
+        match self.table_byte() {
+            t @ b'0'..=b'9' => t - b'0' + b'a',
+            t => t,
+        }
+    }
+
+    /// The overlay character shown on top of an alternate table symbol, if any.
+    pub fn overlay(&self) -> Option<char> {
+        match self.table {
+            'A'..='Z' | '0'..='9' => Some(self.table),
+            _ => None,
+        }
+    }
+
+    /// The same symbol from the alternate table, without the overlay.
+    pub fn without_overlay(&self) -> Self {
+        match self.overlay() {
+            Some(_) => Self {
+                table: '\\',
+                code: self.code,
+            },
+            None => *self,
+        }
+    }
+
+    /// Whether the code is one of the alternate table symbols that can be overlaid.
+    pub fn can_overlay(code: char) -> bool {
+        OVERLAYABLE.contains(code)
+    }
+
+    pub fn is_primary(&self) -> bool {
+        self.table == '/'
+    }
+
+    /// Whether the symbol is from the alternate table, with or without an overlay.
+    pub fn is_alternate(&self) -> bool {
+        !self.is_primary()
+    }
+
+    /// A short description, e.g. "Jeep" for `/j`.
+    /// Returns `None` for codes that aren't assigned.
+    pub fn description(&self) -> Option<&'static str> {
+        let table = if self.is_primary() {
+            &PRIMARY
+        } else {
+            &ALTERNATE
+        };
+
+        table.get(self.index()?).filter(|d| !d.is_empty()).copied()
+    }
+
+    pub fn category(&self) -> SymbolCategory {
+        let (weather, vehicle, infrastructure) = if self.is_primary() {
+            ("@W_", "'()*<=>CFOPRSUXYabfgjkpsuv^", "#&BIm]nr`")
+        } else {
+            ("(*:<@BDFGHIJTUW[_`efgptwy{", ">Oksuv^", "#&8")
+        };
+
+        if weather.contains(self.code) {
+            SymbolCategory::Weather
+        } else if vehicle.contains(self.code) {
+            SymbolCategory::Vehicle
+        } else if infrastructure.contains(self.code) {
+            SymbolCategory::Infrastructure
+        } else {
+            SymbolCategory::Other
+        }
+    }
+
+    /// The icon of the symbol in the sprite sheets. Overlaid symbols use the
+    /// alternate table icon, with the overlay from `overlay_sprite()` on top.
+    pub fn sprite(&self) -> Option<SpriteIndex> {
+        Some(SpriteIndex {
+            sheet: if self.is_primary() { 0 } else { 1 },
+            index: self.index()? as u8,
+        })
+    }
+
+    /// The icon of the overlay character in the sprite sheets, if any.
+    pub fn overlay_sprite(&self) -> Option<SpriteIndex> {
+        self.index()?;
+
+        Some(SpriteIndex {
+            sheet: 2,
+            index: self.overlay()? as u8 - b'!',
+        })
+    }
+
+    /// A representative emoji, for the symbols that have a close enough one.
+    /// Overlays are ignored.
+    pub fn emoji(&self) -> Option<&'static str> {
+        self.index()?;
+
+        let emoji = if self.is_primary() {
+            match self.code {
+                '$' => "☎️",
+                '\'' => "🛩️",
+                ')' => "♿",
+                '-' => "🏠",
+                '.' => "❌",
+                '/' => "🔴",
+                ':' => "🔥",
+                ';' => "🏕️",
+                '<' => "🏍️",
+                '=' => "🚂",
+                '>' => "🚗",
+                'A' => "⛑️",
+                'C' => "🛶",
+                'E' => "👁️",
+                'F' => "🚜",
+                'H' => "🏨",
+                'K' => "🏫",
+                'L' | 'l' => "💻",
+                'O' => "🎈",
+                'P' => "🚓",
+                'R' | 'v' => "🚐",
+                'S' => "🚀",
+                'U' => "🚌",
+                'X' => "🚁",
+                'Y' => "⛵",
+                '[' => "🏃",
+                '^' => "✈️",
+                '_' => "🌦️",
+                '`' | 'y' => "📡",
+                'a' => "🚑",
+                'b' => "🚲",
+                'd' | 'f' => "🚒",
+                'e' => "🐎",
+                'h' => "🏥",
+                'j' => "🚙",
+                'k' => "🛻",
+                's' => "🚢",
+                'u' => "🚛",
+                'w' => "💧",
+                _ => return None,
+            }
+        } else {
+            match self.code {
+                '!' => "🚨",
+                '$' => "🏦",
+                '%' => "🏭",
+                '\'' => "💥",
+                '(' => "☁️",
+                '*' => "❄️",
+                '+' => "⛪",
+                '-' => "🏠",
+                '9' => "⛽",
+                ':' | 'F' | 'G' | 'e' => "🌨️",
+                ';' => "🏞️",
+                '<' => "⚠️",
+                '>' => "🚗",
+                '?' => "ℹ️",
+                '@' => "🌀",
+                'B' => "🌬️",
+                'D' | '`' => "🌧️",
+                'E' => "💨",
+                'H' | '{' => "🌫️",
+                'I' => "🌦️",
+                'J' => "🌩️",
+                'O' => "🚀",
+                'P' => "🅿️",
+                'R' => "🍴",
+                'S' => "🛰️",
+                'T' => "⛈️",
+                'U' => "☀️",
+                'X' => "💊",
+                '^' => "✈️",
+                'f' | 't' => "🌪️",
+                'g' => "🚩",
+                'h' => "🏬",
+                'j' => "🚧",
+                'k' => "🚙",
+                'p' => "⛅",
+                'r' => "🚻",
+                's' => "🚢",
+                'u' => "🚛",
+                'v' => "🚐",
+                'w' => "🌊",
+                'z' => "⛺",
+                _ => return None,
+            }
+        };
+
+        Some(emoji)
+    }
+
+    // Position of the code in the tables, from `!` to `~`
+    fn index(&self) -> Option<usize> {
+        if !self.is_valid() {
+            return None;
+        }
+
+        Some(usize::from(self.code_byte() - b'!'))
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}{}", self.table, self.code)
+    }
+}
+
+// Alternate table codes that take overlays, per the APRS 1.1 symbol revisions
+const OVERLAYABLE: &str = "!#$%&'(-089;<>ADEHORWY[^achiknsuvwyz";
+
+const PRIMARY: [&str; 94] = [
+    "Police station",
+    "",
+    "Digi",
+    "Phone",
+    "DX cluster",
+    "HF gateway",
+    "Small aircraft",
+    "Mobile satellite station",
+    "Wheelchair",
+    "Snowmobile",
+    "Red Cross",
+    "Boy Scouts",
+    "House",
+    "X",
+    "Red dot",
+    "Circle 0",
+    "Circle 1",
+    "Circle 2",
+    "Circle 3",
+    "Circle 4",
+    "Circle 5",
+    "Circle 6",
+    "Circle 7",
+    "Circle 8",
+    "Circle 9",
+    "Fire",
+    "Campground",
+    "Motorcycle",
+    "Railroad engine",
+    "Car",
+    "File server",
+    "Hurricane future prediction",
+    "Aid station",
+    "BBS",
+    "Canoe",
+    "",
+    "Eyeball",
+    "Farm vehicle",
+    "Grid square",
+    "Hotel",
+    "TCP/IP",
+    "",
+    "School",
+    "PC user",
+    "MacAPRS",
+    "NTS station",
+    "Balloon",
+    "Police",
+    "",
+    "Recreational vehicle",
+    "Space shuttle",
+    "SSTV",
+    "Bus",
+    "ATV",
+    "National Weather Service site",
+    "Helicopter",
+    "Yacht",
+    "WinAPRS",
+    "Jogger",
+    "DF station",
+    "PBBS",
+    "Large aircraft",
+    "Weather station",
+    "Dish antenna",
+    "Ambulance",
+    "Bicycle",
+    "Incident command post",
+    "Fire department",
+    "Horse",
+    "Fire truck",
+    "Glider",
+    "Hospital",
+    "IOTA",
+    "Jeep",
+    "Truck",
+    "Laptop",
+    "Mic-E repeater",
+    "Node",
+    "Emergency operations center",
+    "Rover",
+    "Grid square",
+    "Repeater",
+    "Ship",
+    "Truck stop",
+    "Semi-trailer truck",
+    "Van",
+    "Water station",
+    "xAPRS",
+    "Yagi at QTH",
+    "",
+    "",
+    "TNC stream switch",
+    "",
+    "TNC stream switch",
+];
+
+const ALTERNATE: [&str; 94] = [
+    "Emergency",
+    "",
+    "Digi",
+    "Bank",
+    "Power plant",
+    "Gateway",
+    "Crash site",
+    "Cloudy",
+    "Firenet MEO",
+    "Snow",
+    "Church",
+    "Girl Scouts",
+    "House",
+    "Ambiguous",
+    "Waypoint destination",
+    "Circle",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "",
+    "802.11 network node",
+    "Gas station",
+    "Hail",
+    "Park",
+    "Advisory",
+    "APRStt",
+    "Car",
+    "Info kiosk",
+    "Hurricane",
+    "Box",
+    "Blowing snow",
+    "Coast Guard",
+    "Drizzle",
+    "Smoke",
+    "Freezing rain",
+    "Snow shower",
+    "Haze",
+    "Rain shower",
+    "Lightning",
+    "Kenwood radio",
+    "Lighthouse",
+    "MARS",
+    "Navigation buoy",
+    "Rocket",
+    "Parking",
+    "Earthquake",
+    "Restaurant",
+    "Satellite",
+    "Thunderstorm",
+    "Sunny",
+    "VORTAC",
+    "NWS site",
+    "Pharmacy",
+    "Radios and devices",
+    "",
+    "Wall cloud",
+    "",
+    "",
+    "Aircraft",
+    "Weather site",
+    "Rain",
+    "ARRL or ARES",
+    "Blowing dust",
+    "Civil defense",
+    "DX spot",
+    "Sleet",
+    "Funnel cloud",
+    "Gale flags",
+    "Store",
+    "Point of interest",
+    "Work zone",
+    "SUV",
+    "Area",
+    "Value signpost",
+    "Triangle",
+    "Small circle",
+    "Partly cloudy",
+    "",
+    "Restrooms",
+    "Ship",
+    "Tornado",
+    "Truck",
+    "Van",
+    "Flooding",
+    "",
+    "Skywarn",
+    "Shelter",
+    "Fog",
+    "TNC stream switch",
+    "",
+    "TNC stream switch",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(not(feature = "std"))]
+    use prelude::*;
+
+    #[test]
+    fn validation() {
+        assert!(Symbol::new('/', '>').is_some());
+        assert!(Symbol::new('\\', '^').is_some());
+        assert!(Symbol::new('S', '#').is_some());
+        assert!(Symbol::new('5', '>').is_some());
+
+        assert_eq!(None, Symbol::new('x', '>'));
+        assert_eq!(None, Symbol::new('/', ' '));
+        assert_eq!(None, Symbol::new('/', 'é'));
+
+        // decoded symbols aren't validated
+        let symbol = Symbol::from_bytes(b'>', b'?');
+        assert!(!symbol.is_valid());
+        assert_eq!(None, symbol.description());
+    }
+
+    #[test]
+    fn description() {
+        assert_eq!(Some("Jeep"), Symbol::new('/', 'j').unwrap().description());
+        assert_eq!(Some("Digi"), Symbol::new('/', '#').unwrap().description());
+        assert_eq!(Some("Digi"), Symbol::new('S', '#').unwrap().description());
+        assert_eq!(
+            Some("TNC stream switch"),
+            Symbol::new('/', '~').unwrap().description()
+        );
+        assert_eq!(
+            Some("Hurricane"),
+            Symbol::new('\\', '@').unwrap().description()
+        );
+        assert_eq!(None, Symbol::new('/', 'z').unwrap().description());
+    }
+
+    #[test]
+    fn category() {
+        let category = |table, code| Symbol::new(table, code).unwrap().category();
+
+        assert_eq!(SymbolCategory::Weather, category('/', '_'));
+        assert_eq!(SymbolCategory::Weather, category('\\', 't'));
+        assert_eq!(SymbolCategory::Vehicle, category('/', '>'));
+        assert_eq!(SymbolCategory::Vehicle, category('\\', 'k'));
+        assert_eq!(SymbolCategory::Infrastructure, category('/', 'r'));
+        assert_eq!(SymbolCategory::Infrastructure, category('S', '#'));
+        assert_eq!(SymbolCategory::Other, category('/', '-'));
+    }
+
+    #[test]
+    fn overlay() {
+        let symbol = Symbol::new('S', '#').unwrap();
+        assert_eq!(Some('S'), symbol.overlay());
+        assert!(symbol.is_alternate());
+        assert_eq!(Symbol::new('\\', '#').unwrap(), symbol.without_overlay());
+        assert_eq!(b'S', symbol.compressed_table_byte());
+
+        let symbol = Symbol::new('3', '>').unwrap();
+        assert_eq!(Some('3'), symbol.overlay());
+        assert_eq!(b'd', symbol.compressed_table_byte());
+        assert_eq!(symbol, Symbol::from_compressed_bytes(b'd', b'>'));
+
+        assert_eq!(None, Symbol::new('/', '#').unwrap().overlay());
+        assert_eq!(b'/', Symbol::new('/', '#').unwrap().compressed_table_byte());
+
+        // not every symbol can be overlaid
+        assert!(Symbol::can_overlay('#'));
+        assert!(!Symbol::can_overlay('_'));
+        assert_eq!(None, Symbol::new('S', '_'));
+        assert_eq!(None, Symbol::new('d', '>'));
+    }
+
+    #[test]
+    fn sprite() {
+        let sprite = Symbol::new('/', '>').unwrap().sprite().unwrap();
+        assert_eq!(
+            SpriteIndex {
+                sheet: 0,
+                index: 29
+            },
+            sprite
+        );
+        assert_eq!(13, sprite.column());
+        assert_eq!(1, sprite.row());
+
+        let symbol = Symbol::new('S', '#').unwrap();
+        assert_eq!(Some(SpriteIndex { sheet: 1, index: 2 }), symbol.sprite());
+        assert_eq!(
+            Some(SpriteIndex {
+                sheet: 2,
+                index: 50
+            }),
+            symbol.overlay_sprite()
+        );
+
+        assert_eq!(None, Symbol::new('/', '>').unwrap().overlay_sprite());
+        assert_eq!(None, Symbol::from_bytes(b'/', b' ').sprite());
+    }
+
+    #[test]
+    fn emoji() {
+        assert_eq!(Some("🚙"), Symbol::new('/', 'j').unwrap().emoji());
+        assert_eq!(Some("🌀"), Symbol::new('\\', '@').unwrap().emoji());
+        assert_eq!(Some("🚗"), Symbol::new('5', '>').unwrap().emoji());
+        assert_eq!(None, Symbol::new('/', '#').unwrap().emoji());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("\\^", Symbol::new('\\', '^').unwrap().to_string());
+    }
+}
@@ -0,0 +1,247 @@
+//! Emergency nets and events often refer to stations by tactical callsigns,
+//! e.g. `EOC1` or `AID3`, instead of their real callsigns.
+//! [`AliasMap`] maps tactical callsigns to real ones, so that packets can be
+//! rewritten or annotated with the station behind them.
+//!
+//! The map can be populated by hand, or from `TACTICAL` messages, e.g.
+//! `:TACTICAL :KA1BCD=EOC1;KB2CDE=AID3`, where an empty alias removes one.
+
+use std::collections::HashMap;
+
+use AprsData;
+use AprsMessage;
+use AprsPacket;
+use Callsign;
+
+const TACTICAL_ADDRESSEE: &[u8] = b"TACTICAL";
+
+/// A tactical callsign found in a packet, with the real callsign behind it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alias {
+    pub tactical: Callsign,
+    pub real: Callsign,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AliasMap {
+    aliases: HashMap<String, Callsign>,
+}
+
+impl AliasMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps `tactical` to `real`, returning the real callsign it was mapped to before.
+    /// Tactical callsigns are matched case-insensitively.
+    pub fn insert(&mut self, tactical: &Callsign, real: Callsign) -> Option<Callsign> {
+        self.aliases.insert(key(tactical), real)
+    }
+
+    pub fn remove(&mut self, tactical: &Callsign) -> Option<Callsign> {
+        self.aliases.remove(&key(tactical))
+    }
+
+    pub fn resolve(&self, tactical: &Callsign) -> Option<&Callsign> {
+        self.aliases.get(&key(tactical))
+    }
+
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// Updates the map from a `TACTICAL` message.
+    /// Returns `false` if the message isn't one.
+    pub fn apply_message(&mut self, message: &AprsMessage) -> bool {
+        if message.addressee != TACTICAL_ADDRESSEE {
+            return false;
+        }
+
+        let text = String::from_utf8_lossy(&message.text);
+        for assignment in text.split(';') {
+            let (real, tactical) = match assignment.split_once('=') {
+                Some(parts) => parts,
+                None => continue,
+            };
+            let real = match Callsign::new(real.trim()) {
+                Some(real) => real,
+                None => continue,
+            };
+
+            match Callsign::new(tactical.trim()) {
+                Some(tactical) if !tactical.call().is_empty() => {
+                    // a station only has one tactical callsign at a time
+                    self.aliases.retain(|_, r| *r != real);
+                    self.insert(&tactical, real);
+                }
+                _ => self.aliases.retain(|_, r| *r != real),
+            }
+        }
+
+        true
+    }
+
+    /// Returns the tactical callsigns in a packet: its sender and, for
+    /// messages, the addressee.
+    pub fn annotate(&self, packet: &AprsPacket) -> Vec<Alias> {
+        let mut aliases = vec![];
+        let mut push = |tactical: &Callsign| {
+            if let Some(real) = self.resolve(tactical) {
+                aliases.push(Alias {
+                    tactical: tactical.clone(),
+                    real: real.clone(),
+                });
+            }
+        };
+
+        push(&packet.from);
+        if let Some(addressee) = message_addressee(packet) {
+            push(&addressee);
+        }
+
+        aliases
+    }
+
+    /// Returns a copy of `packet` with tactical callsigns replaced by real ones,
+    /// in the sender, the addressee of messages and third-party packets.
+    pub fn rewrite(&self, packet: &AprsPacket) -> AprsPacket {
+        let mut packet = packet.clone();
+
+        if let Some(real) = self.resolve(&packet.from) {
+            packet.from = real.clone();
+        }
+
+        let real_addressee = message_addressee(&packet)
+            .and_then(|a| self.resolve(&a))
+            .map(|real| real.to_string().into_bytes());
+        match &mut packet.data {
+            AprsData::Message(m) => {
+                if let Some(addressee) = real_addressee {
+                    m.addressee = addressee;
+                }
+            }
+            AprsData::ThirdParty(t) => {
+                let inner = self.rewrite(&t.packet);
+                *t.packet = inner;
+            }
+            _ => {}
+        }
+
+        packet
+    }
+}
+
+fn key(c: &Callsign) -> String {
+    c.to_string().to_ascii_uppercase()
+}
+
+fn message_addressee(packet: &AprsPacket) -> Option<Callsign> {
+    match &packet.data {
+        AprsData::Message(m) => Callsign::new(std::str::from_utf8(&m.addressee).ok()?.trim_end()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(s: &str) -> AprsPacket {
+        AprsPacket::decode_textual(s.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn resolve() {
+        let mut map = AliasMap::new();
+        let real = Callsign::new_with_ssid("KA1BCD", "7");
+        assert_eq!(
+            None,
+            map.insert(&Callsign::new_no_ssid("EOC1"), real.clone())
+        );
+
+        assert_eq!(Some(&real), map.resolve(&Callsign::new_no_ssid("eoc1")));
+        assert_eq!(None, map.resolve(&Callsign::new_no_ssid("EOC2")));
+
+        assert_eq!(Some(real), map.remove(&Callsign::new_no_ssid("EOC1")));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn tactical_message() {
+        let mut map = AliasMap::new();
+
+        let packet = decode("KA1BCD>APRS::TACTICAL :KA1BCD=EOC1;KB2CDE-9=AID3");
+        let message = match &packet.data {
+            AprsData::Message(m) => m,
+            _ => panic!("Unexpected data"),
+        };
+        assert!(map.apply_message(message));
+        assert_eq!(2, map.len());
+        assert_eq!(
+            Some(&Callsign::new_with_ssid("KB2CDE", "9")),
+            map.resolve(&Callsign::new_no_ssid("AID3"))
+        );
+
+        // reassigning and clearing
+        let packet = decode("KA1BCD>APRS::TACTICAL :KA1BCD=EOC2;KB2CDE-9=");
+        match &packet.data {
+            AprsData::Message(m) => assert!(map.apply_message(m)),
+            _ => panic!("Unexpected data"),
+        }
+        assert_eq!(1, map.len());
+        assert_eq!(None, map.resolve(&Callsign::new_no_ssid("EOC1")));
+        assert_eq!(
+            Some(&Callsign::new_no_ssid("KA1BCD")),
+            map.resolve(&Callsign::new_no_ssid("EOC2"))
+        );
+
+        let packet = decode("KA1BCD>APRS::N0CALL   :KA1BCD=EOC1");
+        match &packet.data {
+            AprsData::Message(m) => assert!(!map.apply_message(m)),
+            _ => panic!("Unexpected data"),
+        }
+    }
+
+    #[test]
+    fn rewrite_and_annotate() {
+        let mut map = AliasMap::new();
+        map.insert(
+            &Callsign::new_no_ssid("EOC1"),
+            Callsign::new_no_ssid("KA1BCD"),
+        );
+        map.insert(
+            &Callsign::new_no_ssid("AID3"),
+            Callsign::new_with_ssid("KB2CDE", "9"),
+        );
+
+        let packet = decode("AID3>APRS,WIDE1-1::EOC1     :Need water{12");
+        assert_eq!(
+            vec![
+                Alias {
+                    tactical: Callsign::new_no_ssid("AID3"),
+                    real: Callsign::new_with_ssid("KB2CDE", "9"),
+                },
+                Alias {
+                    tactical: Callsign::new_no_ssid("EOC1"),
+                    real: Callsign::new_no_ssid("KA1BCD"),
+                },
+            ],
+            map.annotate(&packet)
+        );
+
+        let mut buf = vec![];
+        map.rewrite(&packet).encode_textual(&mut buf).unwrap();
+        assert_eq!(
+            &b"KB2CDE-9>APRS,WIDE1-1::KA1BCD   :Need water{12"[..],
+            &buf[..]
+        );
+
+        let packet = decode("N0CALL>APRS:>Hi");
+        assert!(map.annotate(&packet).is_empty());
+        assert_eq!(packet, map.rewrite(&packet));
+    }
+}
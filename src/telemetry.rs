@@ -0,0 +1,776 @@
+//! Telemetry reports (`T#005,199,000,255,073,123,01101001`) carry a sequence
+//! number, five analog values and eight digital bits.
+//!
+//! The sequence number wraps around (from 999 to 000) and restarts whenever a
+//! station reboots. [`SequenceTracker`] turns these into indices that only ever
+//! increase, so that samples collected over a long period can be ordered correctly.
+
+use alloc::collections::BTreeMap;
+
+use bytes::parse_bytes;
+#[cfg(not(any(feature = "std", test)))]
+use float::F64Ext;
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use Callsign;
+use DecodeError;
+use EncodeError;
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AprsTelemetry {
+    pub to: Callsign,
+    /// `None` for the `MIC` sequence used by some Mic-E radios
+    pub sequence: Option<u16>,
+    pub analog: [f64; 5],
+    pub digital: [bool; 8],
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
+    pub comment: Vec<u8>,
+}
+
+impl AprsTelemetry {
+    /// Decodes the information field after the `T`.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        let err = || DecodeError::InvalidTelemetry(b.to_owned(), 0);
+
+        let mut fields = b
+            .strip_prefix(b"#")
+            .ok_or_else(err)?
+            .splitn(7, |&c| c == b',');
+
+        let sequence = match fields.next().ok_or_else(err)? {
+            b"MIC" => None,
+            s if !s.is_empty() && s.iter().all(u8::is_ascii_digit) => {
+                Some(parse_bytes(s).ok_or_else(err)?)
+            }
+            _ => return Err(err()),
+        };
+
+        let mut analog = [0.0; 5];
+        for a in &mut analog {
+            *a = fields
+                .next()
+                .and_then(parse_bytes::<f64>)
+                .filter(|v| v.is_finite())
+                .ok_or_else(err)?;
+        }
+
+        let rest = fields.next().ok_or_else(err)?;
+        let bits = rest.get(0..8).ok_or_else(err)?;
+        let mut digital = [false; 8];
+        for (d, bit) in digital.iter_mut().zip(bits) {
+            *d = match bit {
+                b'0' => false,
+                b'1' => true,
+                _ => return Err(err()),
+            };
+        }
+
+        Ok(Self {
+            to,
+            sequence,
+            analog,
+            digital,
+            comment: rest[8..].to_owned(),
+        })
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        match self.sequence {
+            Some(s) if s <= 999 => write!(buf, "T#{:03}", s)?,
+            Some(_) => return Err(EncodeError::InvalidData),
+            None => write!(buf, "T#MIC")?,
+        }
+
+        for a in &self.analog {
+            // whole numbers get the traditional three digits
+            if a.fract() == 0.0 && (0.0..=999.0).contains(a) {
+                write!(buf, ",{:03}", *a as u16)?;
+            } else if a.is_finite() {
+                write!(buf, ",{}", a)?;
+            } else {
+                return Err(EncodeError::InvalidData);
+            }
+        }
+
+        buf.write_all(b",")?;
+        for &d in &self.digital {
+            buf.write_all(if d { b"1" } else { b"0" })?;
+        }
+
+        buf.write_all(&self.comment)?;
+
+        Ok(())
+    }
+}
+
+/// Builds telemetry reports, checking the values against the limits of the format.
+///
+/// The original spec only allows analog values from 0 to 255. Most current
+/// software accepts anything that fits in three digits, which can be enabled
+/// with [`TelemetryBuilder::with_extended_range`].
+#[derive(Debug, Clone)]
+pub struct TelemetryBuilder {
+    to: Callsign,
+    sequence: u16,
+    analog: [f64; 5],
+    digital: [bool; 8],
+    comment: Vec<u8>,
+    max_analog: f64,
+}
+
+impl TelemetryBuilder {
+    pub fn new(to: Callsign) -> Self {
+        Self {
+            to,
+            sequence: 0,
+            analog: [0.0; 5],
+            digital: [false; 8],
+            comment: vec![],
+            max_analog: 255.0,
+        }
+    }
+
+    /// Allows analog values up to 999.
+    pub fn with_extended_range(mut self) -> Self {
+        self.max_analog = 999.0;
+        self
+    }
+
+    /// Sets the sequence number from a running counter, which wraps around after 999.
+    pub fn sequence(mut self, counter: u32) -> Self {
+        self.sequence = (counter % 1000) as u16;
+        self
+    }
+
+    pub fn analog(mut self, analog: [f64; 5]) -> Self {
+        self.analog = analog;
+        self
+    }
+
+    pub fn digital(mut self, digital: [bool; 8]) -> Self {
+        self.digital = digital;
+        self
+    }
+
+    pub fn comment(mut self, comment: impl Into<Vec<u8>>) -> Self {
+        self.comment = comment.into();
+        self
+    }
+
+    pub fn build(&self) -> Result<AprsTelemetry, EncodeError> {
+        if let Some(&a) = self
+            .analog
+            .iter()
+            .find(|a| !(0.0..=self.max_analog).contains(*a))
+        {
+            return Err(EncodeError::InvalidTelemetryValue(a));
+        }
+
+        Ok(AprsTelemetry {
+            to: self.to.clone(),
+            sequence: Some(self.sequence),
+            analog: self.analog,
+            digital: self.digital,
+            comment: self.comment.clone(),
+        })
+    }
+
+    /// Encodes the information field of the report, starting with `T#`.
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        self.build()?.encode(buf)
+    }
+}
+
+/// Telemetry definitions, sent as messages addressed to the station the telemetry belongs to.
+/// They describe the five analog channels and eight digital bits of its telemetry reports.
+#[derive(PartialEq, Debug, Clone)]
+pub enum TelemetryDefinition {
+    /// `PARM.`: names of the channels, analog first
+    Parameters(Vec<Vec<u8>>),
+    /// `UNIT.`: units or labels of the channels, analog first
+    Units(Vec<Vec<u8>>),
+    /// `EQNS.`: coefficients `a`, `b` and `c` of each analog channel,
+    /// whose value is `a * v² + b * v + c`
+    Equations([[f64; 3]; 5]),
+    /// `BITS.`: the value of each digital bit that means "on", and the project title
+    Bits { sense: [bool; 8], project: Vec<u8> },
+}
+
+impl TelemetryDefinition {
+    /// Decodes the text of a telemetry definition message.
+    /// Missing equation coefficients default to `0, 1, 0`, which leaves the value as is.
+    pub fn decode(text: &[u8]) -> Option<Self> {
+        let (kind, body) = (text.get(0..5)?, &text[5..]);
+        let fields = || body.split(|&c| c == b',').map(<[u8]>::to_vec);
+
+        match kind {
+            b"PARM." => Some(Self::Parameters(fields().collect())),
+            b"UNIT." => Some(Self::Units(fields().collect())),
+            b"EQNS." => {
+                let mut equations = [[0.0, 1.0, 0.0]; 5];
+                let mut values = body.split(|&c| c == b',').filter(|v| !v.is_empty());
+                for coefficient in equations.iter_mut().flat_map(|e| e.iter_mut()) {
+                    match values.next() {
+                        Some(v) => {
+                            *coefficient = parse_bytes::<f64>(v).filter(|v| v.is_finite())?
+                        }
+                        None => break,
+                    }
+                }
+
+                Some(Self::Equations(equations))
+            }
+            b"BITS." => {
+                let mut sense = [false; 8];
+                for (s, bit) in sense.iter_mut().zip(body.get(0..8)?) {
+                    *s = match bit {
+                        b'0' => false,
+                        b'1' => true,
+                        _ => return None,
+                    };
+                }
+
+                let project = match body.get(8) {
+                    Some(b',') => body[9..].to_vec(),
+                    Some(_) => return None,
+                    None => vec![],
+                };
+
+                Some(Self::Bits { sense, project })
+            }
+            _ => None,
+        }
+    }
+
+    /// Encodes the text of a telemetry definition message.
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        match self {
+            Self::Parameters(names) => {
+                buf.write_all(b"PARM.")?;
+                write_list(buf, names)?;
+            }
+            Self::Units(units) => {
+                buf.write_all(b"UNIT.")?;
+                write_list(buf, units)?;
+            }
+            Self::Equations(equations) => {
+                buf.write_all(b"EQNS.")?;
+                for (i, c) in equations.iter().flat_map(|e| e.iter()).enumerate() {
+                    if !c.is_finite() {
+                        return Err(EncodeError::InvalidData);
+                    }
+                    if i > 0 {
+                        buf.write_all(b",")?;
+                    }
+                    write!(buf, "{}", c)?;
+                }
+            }
+            Self::Bits { sense, project } => {
+                buf.write_all(b"BITS.")?;
+                for &s in sense {
+                    buf.write_all(if s { b"1" } else { b"0" })?;
+                }
+                if !project.is_empty() {
+                    buf.write_all(b",")?;
+                    buf.write_all(project)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_list<W: Write>(buf: &mut W, items: &[Vec<u8>]) -> Result<(), EncodeError> {
+    for (i, item) in items.iter().enumerate() {
+        if item.contains(&b',') {
+            return Err(EncodeError::InvalidMessageText(item.clone()));
+        }
+        if i > 0 {
+            buf.write_all(b",")?;
+        }
+        buf.write_all(item)?;
+    }
+
+    Ok(())
+}
+
+/// Everything a station has published about its telemetry,
+/// used to turn raw reports into engineering units.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TelemetryScaling {
+    pub parameters: Vec<Vec<u8>>,
+    pub units: Vec<Vec<u8>>,
+    pub equations: [[f64; 3]; 5],
+    pub sense: [bool; 8],
+    pub project: Vec<u8>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct ScaledValue {
+    /// Empty when the channel has no name
+    pub name: Vec<u8>,
+    pub unit: Vec<u8>,
+    pub value: f64,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+pub struct ScaledBit {
+    /// Empty when the bit has no name
+    pub name: Vec<u8>,
+    /// Label shown when the bit is on
+    pub label: Vec<u8>,
+    /// Whether the bit matches its sense from `BITS.`
+    pub on: bool,
+}
+
+impl TelemetryScaling {
+    /// Creates a scaling that leaves values unchanged, with every bit active high.
+    pub fn new() -> Self {
+        Self {
+            parameters: vec![],
+            units: vec![],
+            equations: [[0.0, 1.0, 0.0]; 5],
+            sense: [true; 8],
+            project: vec![],
+        }
+    }
+
+    /// Updates the scaling with a definition received from the station.
+    pub fn apply(&mut self, definition: &TelemetryDefinition) {
+        match definition {
+            TelemetryDefinition::Parameters(p) => self.parameters = p.clone(),
+            TelemetryDefinition::Units(u) => self.units = u.clone(),
+            TelemetryDefinition::Equations(e) => self.equations = *e,
+            TelemetryDefinition::Bits { sense, project } => {
+                self.sense = *sense;
+                self.project = project.clone();
+            }
+        }
+    }
+
+    /// Scales the analog values of `telemetry` with `a * v² + b * v + c`.
+    pub fn analog(&self, telemetry: &AprsTelemetry) -> Vec<ScaledValue> {
+        telemetry
+            .analog
+            .iter()
+            .zip(&self.equations)
+            .enumerate()
+            .map(|(i, (&v, &[a, b, c]))| ScaledValue {
+                name: label(&self.parameters, i),
+                unit: label(&self.units, i),
+                value: a * v * v + b * v + c,
+            })
+            .collect()
+    }
+
+    /// Labels the digital bits of `telemetry`. The bits come after the
+    /// analog channels in the `PARM.` and `UNIT.` lists.
+    pub fn digital(&self, telemetry: &AprsTelemetry) -> Vec<ScaledBit> {
+        telemetry
+            .digital
+            .iter()
+            .zip(&self.sense)
+            .enumerate()
+            .map(|(i, (&d, &sense))| ScaledBit {
+                name: label(&self.parameters, i + 5),
+                label: label(&self.units, i + 5),
+                on: d == sense,
+            })
+            .collect()
+    }
+}
+
+impl Default for TelemetryScaling {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn label(labels: &[Vec<u8>], i: usize) -> Vec<u8> {
+    labels.get(i).cloned().unwrap_or_default()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SequenceEvent {
+    /// The first sequence number seen from this station.
+    First,
+    /// The sequence number advanced, possibly skipping some lost reports.
+    Advanced,
+    /// The sequence number wrapped around to the start.
+    Rollover,
+    /// The sequence number went backwards, usually because the station restarted.
+    Reset,
+    /// The same sequence number was seen again.
+    Repeated,
+}
+
+#[derive(Debug, Clone)]
+pub struct SequenceTracker {
+    modulus: u32,
+    rollover_window: u32,
+    capacity: usize,
+    // counts observations, to find the station heard from least recently
+    clock: u64,
+    stations: BTreeMap<Callsign, Station>,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Station {
+    sequence: u32,
+    index: u64,
+    last_observed: u64,
+}
+
+impl SequenceTracker {
+    /// Creates a tracker for sequence numbers from 0 to 999.
+    pub fn new() -> Self {
+        Self {
+            modulus: 1000,
+            rollover_window: 100,
+            capacity: 10_000,
+            clock: 0,
+            stations: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a tracker for sequence numbers from 0 to `modulus - 1`.
+    /// A drop from the last tenth of the range into the first tenth
+    /// is treated as a rollover, anything else going backwards as a reset.
+    /// Returns `None` if `modulus` is zero.
+    pub fn with_modulus(modulus: u32) -> Option<Self> {
+        if modulus == 0 {
+            return None;
+        }
+
+        Some(Self {
+            modulus,
+            rollover_window: (modulus / 10).max(1),
+            ..Self::new()
+        })
+    }
+
+    /// Sets how many stations are tracked, 10000 by default. Beyond that, the
+    /// station heard from least recently is forgotten, and starts over if heard again.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Records a sequence number received from `station`, and returns
+    /// its monotonic sample index along with what happened to the sequence.
+    pub fn observe(&mut self, station: &Callsign, sequence: u32) -> (u64, SequenceEvent) {
+        let sequence = sequence % self.modulus;
+        self.clock += 1;
+
+        let (last, index) = match self.stations.get(station) {
+            Some(s) => (s.sequence, s.index),
+            None => {
+                self.insert(station, sequence, 0);
+                return (0, SequenceEvent::First);
+            }
+        };
+
+        let (index, event) = if sequence == last {
+            (index, SequenceEvent::Repeated)
+        } else if sequence > last {
+            (index + u64::from(sequence - last), SequenceEvent::Advanced)
+        } else if last >= self.modulus - self.rollover_window && sequence < self.rollover_window {
+            let delta = self.modulus - last + sequence;
+            (index + u64::from(delta), SequenceEvent::Rollover)
+        } else {
+            (index + 1, SequenceEvent::Reset)
+        };
+
+        self.insert(station, sequence, index);
+        (index, event)
+    }
+
+    /// Forgets everything known about `station`.
+    pub fn forget(&mut self, station: &Callsign) {
+        self.stations.remove(station);
+    }
+
+    fn insert(&mut self, station: &Callsign, sequence: u32, index: u64) {
+        if !self.stations.contains_key(station) {
+            if self.capacity == 0 {
+                return;
+            }
+            while self.stations.len() >= self.capacity {
+                let oldest = self
+                    .stations
+                    .iter()
+                    .min_by_key(|(_, s)| s.last_observed)
+                    .map(|(c, _)| c.clone());
+                match oldest {
+                    Some(c) => self.stations.remove(&c),
+                    None => break,
+                };
+            }
+        }
+
+        self.stations.insert(
+            station.clone(),
+            Station {
+                sequence,
+                index,
+                last_observed: self.clock,
+            },
+        );
+    }
+}
+
+impl Default for SequenceTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_and_encode() {
+        let original = &b"T#005,199,000,255,073,123,01101001 Battery"[..];
+        let telemetry =
+            AprsTelemetry::decode(&original[1..], Callsign::new_no_ssid("APRS")).unwrap();
+
+        assert_eq!(Some(5), telemetry.sequence);
+        assert_eq!([199.0, 0.0, 255.0, 73.0, 123.0], telemetry.analog);
+        assert_eq!(
+            [false, true, true, false, true, false, false, true],
+            telemetry.digital
+        );
+        assert_eq!(b" Battery", &telemetry.comment[..]);
+
+        let mut buf = vec![];
+        telemetry.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn decode_mic_and_decimals() {
+        let original = &b"T#MIC,1.5,-2,255,73,123,00000000"[..];
+        let telemetry =
+            AprsTelemetry::decode(&original[1..], Callsign::new_no_ssid("APRS")).unwrap();
+
+        assert_eq!(None, telemetry.sequence);
+        assert_eq!([1.5, -2.0, 255.0, 73.0, 123.0], telemetry.analog);
+
+        let mut buf = vec![];
+        telemetry.encode(&mut buf).unwrap();
+        assert_eq!(&b"T#MIC,1.5,-2,255,073,123,00000000"[..], buf);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        let to = Callsign::new_no_ssid("APRS");
+
+        for b in [
+            &b"005,199,000,255,073,123,01101001"[..],
+            &b"#005,199,000,255,073,01101001"[..],
+            &b"#005,199,000,255,073,123,0110100"[..],
+            &b"#005,199,000,255,073,123,01101002"[..],
+            &b"#,199,000,255,073,123,01101001"[..],
+        ] {
+            assert_eq!(
+                Err(DecodeError::InvalidTelemetry(b.to_vec(), 0)),
+                AprsTelemetry::decode(b, to.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn builder() {
+        let builder = TelemetryBuilder::new(Callsign::new("APRS").unwrap())
+            .sequence(1005)
+            .analog([199.0, 0.0, 255.0, 73.0, 12.5])
+            .digital([false, true, true, false, true, false, false, true])
+            .comment("Balloon");
+
+        let mut buf = vec![];
+        builder.encode(&mut buf).unwrap();
+        assert_eq!(&b"T#005,199,000,255,073,12.5,01101001Balloon"[..], buf);
+
+        let builder = builder.analog([999.0, 0.0, 0.0, 0.0, 0.0]);
+        assert!(matches!(
+            builder.build(),
+            Err(EncodeError::InvalidTelemetryValue(v)) if v == 999.0
+        ));
+        assert_eq!(
+            [999.0, 0.0, 0.0, 0.0, 0.0],
+            builder.with_extended_range().build().unwrap().analog
+        );
+
+        let builder = TelemetryBuilder::new(Callsign::new("APRS").unwrap());
+        assert!(builder.clone().analog([-1.0; 5]).build().is_err());
+        assert!(builder.analog([f64::NAN; 5]).build().is_err());
+    }
+
+    #[test]
+    fn scaling() {
+        let telemetry = AprsTelemetry::decode(
+            &b"#005,199,000,255,073,123,01101001"[..],
+            Callsign::new_no_ssid("APRS"),
+        )
+        .unwrap();
+
+        let mut scaling = TelemetryScaling::new();
+        for text in [
+            &b"PARM.Battery,Btemp,,Light,Temp,Door"[..],
+            &b"UNIT.v/100,deg.F,,,,Open"[..],
+            &b"EQNS.0,0.075,0,0,10,0,0,10,0,0,1,0,0.001,0.5,-40"[..],
+            &b"BITS.00110000,Balloon"[..],
+        ] {
+            scaling.apply(&TelemetryDefinition::decode(text).unwrap());
+        }
+
+        let analog = scaling.analog(&telemetry);
+        assert_eq!(b"Battery", &analog[0].name[..]);
+        assert_eq!(b"v/100", &analog[0].unit[..]);
+        assert_relative_eq!(14.925, analog[0].value);
+        assert_relative_eq!(0.0, analog[1].value);
+        assert!(analog[2].name.is_empty());
+        assert_relative_eq!(2550.0, analog[2].value);
+        assert_relative_eq!(73.0, analog[3].value);
+        assert_relative_eq!(36.629, analog[4].value);
+
+        let digital = scaling.digital(&telemetry);
+        assert_eq!(b"Door", &digital[0].name[..]);
+        assert_eq!(b"Open", &digital[0].label[..]);
+        assert_eq!(
+            vec![true, false, true, false, false, true, true, false],
+            digital.iter().map(|d| d.on).collect::<Vec<_>>()
+        );
+        assert_eq!(b"Balloon", &scaling.project[..]);
+    }
+
+    #[test]
+    fn definitions() {
+        for (text, definition) in [
+            (
+                &b"PARM.Battery,Btemp,,Light,Temp,Door,,Open"[..],
+                TelemetryDefinition::Parameters(vec![
+                    b"Battery".to_vec(),
+                    b"Btemp".to_vec(),
+                    vec![],
+                    b"Light".to_vec(),
+                    b"Temp".to_vec(),
+                    b"Door".to_vec(),
+                    vec![],
+                    b"Open".to_vec(),
+                ]),
+            ),
+            (
+                &b"UNIT.v/100,deg.F"[..],
+                TelemetryDefinition::Units(vec![b"v/100".to_vec(), b"deg.F".to_vec()]),
+            ),
+            (
+                &b"EQNS.0,0.075,0,0,10,0,0,10,0,0,1,0,0,0.5,-40"[..],
+                TelemetryDefinition::Equations([
+                    [0.0, 0.075, 0.0],
+                    [0.0, 10.0, 0.0],
+                    [0.0, 10.0, 0.0],
+                    [0.0, 1.0, 0.0],
+                    [0.0, 0.5, -40.0],
+                ]),
+            ),
+            (
+                &b"BITS.10110000,N0CALL's Balloon"[..],
+                TelemetryDefinition::Bits {
+                    sense: [true, false, true, true, false, false, false, false],
+                    project: b"N0CALL's Balloon".to_vec(),
+                },
+            ),
+        ] {
+            assert_eq!(Some(definition.clone()), TelemetryDefinition::decode(text));
+
+            let mut buf = vec![];
+            definition.encode(&mut buf).unwrap();
+            assert_eq!(text, buf);
+        }
+    }
+
+    #[test]
+    fn partial_equations() {
+        assert_eq!(
+            Some(TelemetryDefinition::Equations([
+                [0.0, 2.0, 1.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ])),
+            TelemetryDefinition::decode(b"EQNS.0,2,1")
+        );
+        assert_eq!(None, TelemetryDefinition::decode(b"EQNS.0,x,1"));
+        assert_eq!(None, TelemetryDefinition::decode(b"BITS.1011"));
+        assert_eq!(None, TelemetryDefinition::decode(b"Hello"));
+    }
+
+    #[test]
+    fn advances_and_repeats() {
+        let station = Callsign::new_no_ssid("N0CALL");
+        let mut tracker = SequenceTracker::new();
+
+        assert_eq!((0, SequenceEvent::First), tracker.observe(&station, 5));
+        assert_eq!((1, SequenceEvent::Advanced), tracker.observe(&station, 6));
+        assert_eq!((1, SequenceEvent::Repeated), tracker.observe(&station, 6));
+        // lost a few
+        assert_eq!((4, SequenceEvent::Advanced), tracker.observe(&station, 9));
+    }
+
+    #[test]
+    fn rollover() {
+        let station = Callsign::new_no_ssid("N0CALL");
+        let mut tracker = SequenceTracker::new();
+
+        tracker.observe(&station, 998);
+        assert_eq!((1, SequenceEvent::Advanced), tracker.observe(&station, 999));
+        assert_eq!((2, SequenceEvent::Rollover), tracker.observe(&station, 0));
+        assert_eq!((4, SequenceEvent::Advanced), tracker.observe(&station, 2));
+    }
+
+    #[test]
+    fn reset() {
+        let station = Callsign::new_no_ssid("N0CALL");
+        let mut tracker = SequenceTracker::new();
+
+        tracker.observe(&station, 500);
+        assert_eq!((1, SequenceEvent::Reset), tracker.observe(&station, 0));
+        assert_eq!((2, SequenceEvent::Advanced), tracker.observe(&station, 1));
+    }
+
+    #[test]
+    fn stations_are_independent() {
+        let a = Callsign::new_no_ssid("N0CALL");
+        let b = Callsign::new_with_ssid("N0CALL", "1");
+        let mut tracker = SequenceTracker::with_modulus(8281).unwrap();
+
+        tracker.observe(&a, 10);
+        assert_eq!((0, SequenceEvent::First), tracker.observe(&b, 8280));
+        assert_eq!((1, SequenceEvent::Rollover), tracker.observe(&b, 0));
+        assert_eq!((1, SequenceEvent::Advanced), tracker.observe(&a, 11));
+
+        assert!(SequenceTracker::with_modulus(0).is_none());
+    }
+
+    #[test]
+    fn bounded() {
+        let a = Callsign::new_no_ssid("N0CALL");
+        let b = Callsign::new_no_ssid("N1CALL");
+        let c = Callsign::new_no_ssid("N2CALL");
+        let mut tracker = SequenceTracker::new().with_capacity(2);
+
+        tracker.observe(&a, 10);
+        tracker.observe(&b, 20);
+        tracker.observe(&a, 11);
+        // b was heard from least recently
+        tracker.observe(&c, 30);
+        assert_eq!((2, SequenceEvent::Advanced), tracker.observe(&a, 12));
+        assert_eq!((0, SequenceEvent::First), tracker.observe(&b, 21));
+    }
+}
@@ -0,0 +1,54 @@
+//! Invalid data or test data is sent with the ',' APRS Data Type Identifier,
+//! and shouldn't be interpreted. The rest of the information field is kept as is.
+//!
+//! Example:
+//! - ",191146,V,4214.2466,N,07303.5181,W,000.0,000.0,260210,013.7,W*74"
+
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use Callsign;
+use DecodeError;
+use EncodeError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AprsTestData {
+    pub to: Callsign,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
+    pub data: Vec<u8>,
+}
+
+impl AprsTestData {
+    pub fn new(to: Callsign, data: Vec<u8>) -> Self {
+        Self { to, data }
+    }
+
+    /// Decodes the information field after the `,`.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        Ok(Self::new(to, b.to_owned()))
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_all(b",")?;
+        buf.write_all(&self.data)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_and_encode() {
+        let original = &b",Test packet, please ignore"[..];
+        let data = AprsTestData::decode(&original[1..], Callsign::new_no_ssid("APRS")).unwrap();
+        assert_eq!(b"Test packet, please ignore", &data.data[..]);
+
+        let mut buf = vec![];
+        data.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+}
@@ -0,0 +1,55 @@
+//! Converting the free-form text of packets for display. APRS doesn't specify
+//! a character set: most stations send ASCII or UTF-8, but some older radios
+//! and software send Latin-1.
+
+use alloc::borrow::Cow;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+
+/// Decodes `b` as UTF-8, with invalid sequences replaced by `�`.
+pub fn display_text(b: &[u8]) -> Cow<'_, str> {
+    String::from_utf8_lossy(b)
+}
+
+/// Decodes `b` as UTF-8, or if it isn't valid UTF-8, as Latin-1.
+pub fn display_text_latin1(b: &[u8]) -> Cow<'_, str> {
+    match core::str::from_utf8(b) {
+        Ok(s) => Cow::Borrowed(s),
+        Err(_) => Cow::Owned(b.iter().map(|&c| char::from(c)).collect()),
+    }
+}
+
+/// Removes control characters, e.g. stray carriage returns and NULs.
+pub fn strip_non_printable(s: &str) -> Cow<'_, str> {
+    if s.chars().any(char::is_control) {
+        Cow::Owned(s.chars().filter(|c| !c.is_control()).collect())
+    } else {
+        Cow::Borrowed(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utf8() {
+        assert_eq!("Café", display_text("Café".as_bytes()));
+        assert_eq!("Caf\u{fffd}", display_text(b"Caf\xe9"));
+    }
+
+    #[test]
+    fn latin1() {
+        assert_eq!("Café", display_text_latin1("Café".as_bytes()));
+        assert_eq!("Café", display_text_latin1(b"Caf\xe9"));
+    }
+
+    #[test]
+    fn non_printable() {
+        assert!(matches!(
+            strip_non_printable("Hello"),
+            Cow::Borrowed("Hello")
+        ));
+        assert_eq!("Hello", strip_non_printable("He\0llo\r"));
+    }
+}
@@ -0,0 +1,154 @@
+//! Third-party traffic carries a packet from another network, e.g. a packet
+//! gated from APRS-IS to RF. It starts with the '}' APRS Data Type Identifier,
+//! followed by the complete inner packet in textual form.
+//!
+//! Example:
+//! - "}N1CALL>APRS,TCPIP,N0CALL*:!4903.50N/07201.75W-"
+//!
+//! IGates gating a packet to RF replace its path with `TCPIP,` followed by
+//! their own callsign, marked as used.
+
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use AprsData;
+use AprsPacket;
+use Callsign;
+use DecodeError;
+use EncodeError;
+use Via;
+
+#[derive(PartialEq, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AprsThirdParty {
+    pub to: Callsign,
+    pub packet: Box<AprsPacket>,
+}
+
+impl AprsThirdParty {
+    pub fn new(to: Callsign, packet: AprsPacket) -> Self {
+        Self {
+            to,
+            packet: Box::new(packet),
+        }
+    }
+
+    /// Decodes the information field after the `}`.
+    /// Third-party packets nested in each other aren't supported.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        let nested = b
+            .iter()
+            .position(|&c| c == b':')
+            .map_or(false, |i| b.get(i + 1) == Some(&b'}'));
+        if nested {
+            return Err(DecodeError::InvalidThirdParty(b.to_owned(), 0));
+        }
+
+        let packet = AprsPacket::decode_textual(b)?;
+
+        Ok(Self::new(to, packet))
+    }
+
+    /// Encapsulates a packet received from APRS-IS for transmission on RF by
+    /// the IGate `gate`. The outer packet is sent to `to`, usually the tocall of
+    /// the IGate software, through `via`.
+    /// Returns `None` for packets that are already third-party traffic.
+    pub fn gate_to_rf(
+        packet: &AprsPacket,
+        gate: &Callsign,
+        to: Callsign,
+        via: Vec<Via>,
+    ) -> Option<AprsPacket> {
+        if let AprsData::ThirdParty(_) = packet.data {
+            return None;
+        }
+
+        let mut inner = packet.clone();
+        inner.via = vec![
+            Via::Callsign(Callsign::new_no_ssid("TCPIP"), false),
+            Via::Callsign(gate.clone(), true),
+        ];
+
+        Some(AprsPacket {
+            from: gate.clone(),
+            via,
+            data: AprsData::ThirdParty(Self::new(to, inner)),
+        })
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_all(b"}")?;
+        self.packet.encode_textual(buf)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_callsign() -> Callsign {
+        Callsign::new_no_ssid("APRS")
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        let original = &b"}N1CALL>APRS,TCPIP,N0CALL*:!4903.50N/07201.75W-"[..];
+        let third_party = AprsThirdParty::decode(&original[1..], default_callsign()).unwrap();
+
+        assert_eq!(Callsign::new_no_ssid("N1CALL"), third_party.packet.from);
+        assert!(matches!(third_party.packet.data, AprsData::Position(_)));
+
+        let mut buf = vec![];
+        third_party.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn gate_to_rf() {
+        let packet = AprsPacket::decode_textual(
+            b"N1CALL-9>APRS,WIDE2-1,qAR,N2CALL:!4903.50N/07201.75W-Mobile",
+        )
+        .unwrap();
+        let gated = AprsThirdParty::gate_to_rf(
+            &packet,
+            &Callsign::new_with_ssid("N0CALL", "10"),
+            Callsign::new_no_ssid("APRS"),
+            vec![Via::Callsign(Callsign::new_with_ssid("WIDE1", "1"), false)],
+        )
+        .unwrap();
+
+        let mut buf = vec![];
+        gated.encode_textual(&mut buf).unwrap();
+        assert_eq!(
+            &b"N0CALL-10>APRS,WIDE1-1:}N1CALL-9>APRS,TCPIP,N0CALL-10*:!4903.50N/07201.75W-Mobile"[..],
+            buf
+        );
+
+        assert_eq!(
+            None,
+            AprsThirdParty::gate_to_rf(
+                &gated,
+                &Callsign::new_no_ssid("N0CALL"),
+                Callsign::new_no_ssid("APRS"),
+                vec![]
+            )
+        );
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert!(matches!(
+            AprsThirdParty::decode(b"N1CALL", default_callsign()),
+            Err(DecodeError::InvalidPacket(..))
+        ));
+        assert_eq!(
+            Err(DecodeError::InvalidThirdParty(
+                b"N1CALL>APRS:}N2CALL>APRS:>Hi".to_vec(),
+                0
+            )),
+            AprsThirdParty::decode(b"N1CALL>APRS:}N2CALL>APRS:>Hi", default_callsign())
+        );
+    }
+}
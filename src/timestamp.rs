@@ -1,11 +1,20 @@
-use bytes::parse_bytes;
-use std::convert::TryFrom;
-use std::io::Write;
-
+use bytes::parse_digits;
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use core::convert::TryFrom;
+#[cfg(feature = "std")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+#[cfg(feature = "time")]
+use time::{OffsetDateTime, UtcOffset};
+
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
 use DecodeError;
 use EncodeError;
 
-#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct DhmTimestamp(u8, u8, u8);
 
 /// Day of month, Hour and Minute in UTC
@@ -34,14 +43,76 @@ impl TryFrom<Timestamp> for DhmTimestamp {
     }
 }
 
-#[derive(Eq, PartialEq, Debug, Clone)]
+/// Month, Day of month, Hour and Minute in UTC, as used by positionless weather reports
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MdhmTimestamp(u8, u8, u8, u8);
+
+impl MdhmTimestamp {
+    pub fn new(month: u8, d: u8, h: u8, m: u8) -> Option<Self> {
+        if (1..=12).contains(&month) && (1..=31).contains(&d) && h <= 23 && m <= 59 {
+            Some(Self(month, d, h, m))
+        } else {
+            None
+        }
+    }
+
+    pub fn month(&self) -> u8 {
+        self.0
+    }
+
+    pub fn day(&self) -> u8 {
+        self.1
+    }
+
+    pub fn hour(&self) -> u8 {
+        self.2
+    }
+
+    pub fn minute(&self) -> u8 {
+        self.3
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        write!(buf, "{:02}{:02}{:02}{:02}", self.0, self.1, self.2, self.3)?;
+
+        Ok(())
+    }
+}
+
+impl TryFrom<&[u8]> for MdhmTimestamp {
+    type Error = DecodeError;
+
+    fn try_from(b: &[u8]) -> Result<Self, Self::Error> {
+        let err = || DecodeError::InvalidTimestamp(b.to_owned(), 0);
+        if b.len() != 8 || !b.iter().all(u8::is_ascii_digit) {
+            return Err(err());
+        }
+
+        let (month, d, h, m) = (
+            parse_digits(&b[0..2]).ok_or_else(err)?,
+            parse_digits(&b[2..4]).ok_or_else(err)?,
+            parse_digits(&b[4..6]).ok_or_else(err)?,
+            parse_digits(&b[6..8]).ok_or_else(err)?,
+        );
+        Self::new(month, d, h, m).ok_or_else(err)
+    }
+}
+
+/// Timestamps are ordered by format first, so only timestamps of the same
+/// format are ordered chronologically.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Timestamp {
     /// Day of month, Hour and Minute in UTC
     DDHHMM(u8, u8, u8),
     /// Hour, Minute and Second in UTC
     HHMMSS(u8, u8, u8),
+    /// Day of month, Hour and Minute in the sender's local time.
+    /// The APRS spec discourages this, and it isn't allowed in status reports.
+    DDHHMMLocal(u8, u8, u8),
     /// Unsupported timestamp format
-    Unsupported(Vec<u8>),
+    Unsupported(#[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))] Vec<u8>),
 }
 
 impl Timestamp {
@@ -54,6 +125,15 @@ impl Timestamp {
         }
     }
 
+    /// Day of month, Hour and Minute in local time
+    pub fn new_dhm_local(d: u8, h: u8, m: u8) -> Option<Self> {
+        if d <= 99 && h <= 99 && m <= 99 {
+            Some(Self::DDHHMMLocal(d, h, m))
+        } else {
+            None
+        }
+    }
+
     /// Hour, Minute and Second in UTC
     pub fn new_hms(h: u8, m: u8, s: u8) -> Option<Self> {
         if h <= 99 && m <= 99 && s <= 99 {
@@ -63,10 +143,67 @@ impl Timestamp {
         }
     }
 
+    /// Resolves the timestamp to the latest matching time before `received_at`,
+    /// so `235900h` received at 00:01 belongs to the previous day, and `310000z`
+    /// received on the 1st of March belongs to the 31st of January.
+    ///
+    /// To allow for senders whose clocks are ahead, the result can be up to an
+    /// hour after `received_at`, see [`FutureTimestampPolicy`].
+    /// Returns `None` for local time, which has an unknown offset, and for
+    /// timestamps that are out of range.
+    #[cfg(feature = "std")]
+    pub fn resolve(&self, received_at: SystemTime) -> Option<SystemTime> {
+        let secs = received_at.duration_since(UNIX_EPOCH).ok()?.as_secs();
+        let days = secs / 86400;
+
+        let candidates = match *self {
+            Self::HHMMSS(h, m, s) => {
+                if h > 23 || m > 59 || s > 59 {
+                    return None;
+                }
+                let t = u64::from(h) * 3600 + u64::from(m) * 60 + u64::from(s);
+
+                vec![
+                    days.checked_sub(1).map(|d| d * 86400 + t),
+                    Some(days * 86400 + t),
+                    Some((days + 1) * 86400 + t),
+                ]
+            }
+            Self::DDHHMM(d, h, m) => {
+                if d == 0 || h > 23 || m > 59 {
+                    return None;
+                }
+                let (d, t) = (u64::from(d), u64::from(h) * 3600 + u64::from(m) * 60);
+
+                // the 31st can be two months back, e.g. from the 1st of March
+                let (year, month, _) = civil_from_days(days);
+                let month = year * 12 + month - 1;
+                (month.saturating_sub(2)..=month + 1)
+                    .map(|month| {
+                        let (year, month) = (month / 12, month % 12 + 1);
+                        if d > days_in_month(year, month) {
+                            return None;
+                        }
+                        days_from_civil(year, month, d).map(|days| days * 86400 + t)
+                    })
+                    .collect()
+            }
+            Self::DDHHMMLocal(_, _, _) | Self::Unsupported(_) => return None,
+        };
+
+        candidates
+            .into_iter()
+            .flatten()
+            .filter(|&c| c <= secs + 3600)
+            .max()
+            .map(|c| UNIX_EPOCH + Duration::from_secs(c))
+    }
+
     pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
         match self {
             Self::DDHHMM(d, h, m) => write!(buf, "{:02}{:02}{:02}z", d, h, m)?,
             Self::HHMMSS(h, m, s) => write!(buf, "{:02}{:02}{:02}h", h, m, s)?,
+            Self::DDHHMMLocal(d, h, m) => write!(buf, "{:02}{:02}{:02}/", d, h, m)?,
             Self::Unsupported(s) => buf.write_all(s)?,
         };
 
@@ -74,42 +211,219 @@ impl Timestamp {
     }
 }
 
+#[cfg(feature = "chrono")]
+impl Timestamp {
+    /// The day, hour and minute of `time`, as a zulu `DDHHMM` timestamp.
+    pub fn dhm_from_chrono(time: &DateTime<Utc>) -> Self {
+        Self::DDHHMM(time.day() as u8, time.hour() as u8, time.minute() as u8)
+    }
+
+    /// The hour, minute and second of `time`, as an `HHMMSS` timestamp.
+    pub fn hms_from_chrono(time: &DateTime<Utc>) -> Self {
+        Self::HHMMSS(time.hour() as u8, time.minute() as u8, time.second() as u8)
+    }
+
+    /// Like [`Timestamp::resolve`], with a chrono reference time.
+    pub fn resolve_chrono(&self, received_at: &DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.resolve(SystemTime::from(*received_at))
+            .map(DateTime::from)
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp {
+    /// The day, hour and minute of `time` in UTC, as a zulu `DDHHMM` timestamp.
+    pub fn dhm_from_offset_date_time(time: OffsetDateTime) -> Self {
+        let time = time.to_offset(UtcOffset::UTC);
+        Self::DDHHMM(time.day(), time.hour(), time.minute())
+    }
+
+    /// The hour, minute and second of `time` in UTC, as an `HHMMSS` timestamp.
+    pub fn hms_from_offset_date_time(time: OffsetDateTime) -> Self {
+        let time = time.to_offset(UtcOffset::UTC);
+        Self::HHMMSS(time.hour(), time.minute(), time.second())
+    }
+
+    /// Like [`Timestamp::resolve`], with an `OffsetDateTime` reference time.
+    /// The result is in UTC.
+    pub fn resolve_offset_date_time(&self, received_at: OffsetDateTime) -> Option<OffsetDateTime> {
+        self.resolve(SystemTime::from(received_at))
+            .map(OffsetDateTime::from)
+    }
+}
+
 impl TryFrom<&[u8]> for Timestamp {
     type Error = DecodeError;
 
     fn try_from(b: &[u8]) -> Result<Self, Self::Error> {
         if b.len() != 7 {
-            return Err(DecodeError::InvalidTimestamp(b.to_owned()));
+            return Err(DecodeError::InvalidTimestamp(b.to_owned(), 0));
         }
 
+        let digits = parse_fields(&b[0..6]);
+
+        // local time has always been passed through as-is,
+        // so keep doing that if it's malformed
         if b[6] == b'/' {
-            return Ok(Timestamp::Unsupported(b.to_owned()));
+            return Ok(match digits {
+                Some((one, two, three)) => Timestamp::DDHHMMLocal(one, two, three),
+                None => Timestamp::Unsupported(b.to_owned()),
+            });
         }
 
-        let one =
-            parse_bytes(&b[0..2]).ok_or_else(|| DecodeError::InvalidTimestamp(b.to_owned()))?;
-        let two =
-            parse_bytes(&b[2..4]).ok_or_else(|| DecodeError::InvalidTimestamp(b.to_owned()))?;
-        let three =
-            parse_bytes(&b[4..6]).ok_or_else(|| DecodeError::InvalidTimestamp(b.to_owned()))?;
+        let (one, two, three) =
+            digits.ok_or_else(|| DecodeError::InvalidTimestamp(b.to_owned(), 0))?;
 
         Ok(match b[6] {
             b'z' | b'Z' => Timestamp::DDHHMM(one, two, three),
             b'h' | b'H' => Timestamp::HHMMSS(one, two, three),
-            _ => return Err(DecodeError::InvalidTimestamp(b.to_owned())),
+            _ => return Err(DecodeError::InvalidTimestamp(b.to_owned(), 0)),
         })
     }
 }
 
+// parses the three two-digit fields of a timestamp
+fn parse_fields(b: &[u8]) -> Option<(u8, u8, u8)> {
+    Some((
+        parse_digits(&b[0..2])?,
+        parse_digits(&b[2..4])?,
+        parse_digits(&b[4..6])?,
+    ))
+}
+
 impl From<DhmTimestamp> for Timestamp {
     fn from(t: DhmTimestamp) -> Self {
         Self::DDHHMM(t.0, t.1, t.2)
     }
 }
 
+/// What to do with a resolved timestamp that lies in the future
+/// relative to the time the packet was received.
+///
+/// APRS timestamps only carry the day of month or the time of day, so a
+/// timestamp that appears to be in the future usually belongs to the previous
+/// month (for `DDHHMM`) or the previous day (for `HHMMSS`).
+#[cfg(feature = "std")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FutureTimestampPolicy {
+    /// Keep the future timestamp as-is.
+    Keep,
+    /// Replace the future timestamp with the reference time.
+    ClampToReference,
+    /// Move the timestamp back by one day (`HHMMSS`) or one month (`DDHHMM`).
+    RollBack,
+    /// Discard the future timestamp.
+    Reject,
+}
+
+#[cfg(feature = "std")]
+impl FutureTimestampPolicy {
+    /// Applies the policy to `resolved`, the absolute time that `timestamp` was resolved to,
+    /// e.g. by [`Timestamp::resolve`].
+    /// Timestamps that aren't after `reference` are always returned unchanged.
+    /// Returns `None` if the timestamp was rejected or can't be rolled back.
+    ///
+    /// To tolerate small clock differences between stations, pass a `reference`
+    /// that is slightly ahead of the actual reception time.
+    pub fn apply(
+        self,
+        timestamp: &Timestamp,
+        resolved: SystemTime,
+        reference: SystemTime,
+    ) -> Option<SystemTime> {
+        if resolved <= reference {
+            return Some(resolved);
+        }
+
+        match self {
+            Self::Keep => Some(resolved),
+            Self::ClampToReference => Some(reference),
+            Self::Reject => None,
+            Self::RollBack => match timestamp {
+                Timestamp::HHMMSS(_, _, _) => resolved.checked_sub(Duration::from_secs(86400)),
+                Timestamp::DDHHMM(_, _, _) | Timestamp::DDHHMMLocal(_, _, _) => {
+                    previous_month(resolved)
+                }
+                Timestamp::Unsupported(_) => None,
+            },
+        }
+    }
+}
+
+/// Moves `t` back to the same day and time of the most recent earlier month
+/// that has that day.
+#[cfg(feature = "std")]
+fn previous_month(t: SystemTime) -> Option<SystemTime> {
+    let secs = t.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let (days, time_of_day) = (secs / 86400, secs % 86400);
+    let (mut year, mut month, day) = civil_from_days(days);
+
+    loop {
+        if month == 1 {
+            year = year.checked_sub(1)?;
+            month = 12;
+        } else {
+            month -= 1;
+        }
+
+        if day <= days_in_month(year, month) {
+            let days = days_from_civil(year, month, day)?;
+            return Some(UNIX_EPOCH + Duration::from_secs(days * 86400 + time_of_day));
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Conversions between days since the unix epoch and (year, month, day),
+// based on Howard Hinnant's `civil_from_days` and `days_from_civil` algorithms.
+// Only dates on or after the epoch are supported.
+#[cfg(feature = "std")]
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(feature = "std")]
+fn days_from_civil(year: u64, month: u64, day: u64) -> Option<u64> {
+    let year = if month <= 2 {
+        year.checked_sub(1)?
+    } else {
+        year
+    };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    (era * 146097 + doe).checked_sub(719468)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(feature = "chrono")]
+    use chrono::TimeZone;
+    #[cfg(feature = "time")]
+    use time::{Date, Month};
 
     #[test]
     fn parse_ddhhmm() {
@@ -141,7 +455,15 @@ mod tests {
     fn parse_local_time() {
         assert_eq!(
             Timestamp::try_from(&b"123456/"[..]),
-            Ok(Timestamp::Unsupported(b"123456/".to_vec()))
+            Ok(Timestamp::DDHHMMLocal(12, 34, 56))
+        );
+    }
+
+    #[test]
+    fn parse_malformed_local_time() {
+        assert_eq!(
+            Timestamp::try_from(&b"12a456/"[..]),
+            Ok(Timestamp::Unsupported(b"12a456/".to_vec()))
         );
     }
 
@@ -149,7 +471,7 @@ mod tests {
     fn invalid_timestamp() {
         assert_eq!(
             Timestamp::try_from(&b"1234567"[..]),
-            Err(DecodeError::InvalidTimestamp(b"1234567".to_vec()))
+            Err(DecodeError::InvalidTimestamp(b"1234567".to_vec(), 0))
         );
     }
 
@@ -157,7 +479,7 @@ mod tests {
     fn invalid_timestamp2() {
         assert_eq!(
             Timestamp::try_from(&b"123a56z"[..]),
-            Err(DecodeError::InvalidTimestamp(b"123a56z".to_vec()))
+            Err(DecodeError::InvalidTimestamp(b"123a56z".to_vec(), 0))
         );
     }
 
@@ -175,6 +497,16 @@ mod tests {
         assert_eq!(b"654321h"[..], buf);
     }
 
+    #[test]
+    fn encode_ddhhmm_local() {
+        let mut buf = vec![];
+        Timestamp::new_dhm_local(1, 23, 45)
+            .unwrap()
+            .encode(&mut buf)
+            .unwrap();
+        assert_eq!(b"012345/"[..], buf);
+    }
+
     #[test]
     fn encode_local_time() {
         let mut buf = vec![];
@@ -192,7 +524,7 @@ mod tests {
 
     #[test]
     fn convert_timestamp_to_dhm_timestamp_success() {
-        use std::convert::TryInto;
+        use core::convert::TryInto;
 
         let timestamp = Timestamp::new_dhm(65, 43, 21).unwrap();
         assert_eq!(
@@ -203,10 +535,228 @@ mod tests {
 
     #[test]
     fn convert_timestamp_to_dhm_timestamp_failure() {
-        use std::convert::TryInto;
+        use core::convert::TryInto;
 
         let timestamp = Timestamp::new_hms(65, 43, 21).unwrap();
         let dhm: Result<DhmTimestamp, ()> = timestamp.try_into();
         assert_eq!(Err(()), dhm);
     }
+
+    #[cfg(feature = "std")]
+    fn time(days: u64, secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(days * 86400 + secs)
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn civil_days_roundtrip() {
+        assert_eq!((1970, 1, 1), civil_from_days(0));
+        assert_eq!((2000, 2, 29), civil_from_days(11016));
+        assert_eq!((2024, 3, 1), civil_from_days(19783));
+
+        for days in 0..100_000 {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(Some(days), days_from_civil(y, m, d));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn future_policy_ignores_past_timestamps() {
+        let ts = Timestamp::HHMMSS(12, 0, 0);
+        let resolved = time(19783, 3600);
+        let reference = time(19783, 7200);
+
+        for policy in [
+            FutureTimestampPolicy::Keep,
+            FutureTimestampPolicy::ClampToReference,
+            FutureTimestampPolicy::RollBack,
+            FutureTimestampPolicy::Reject,
+        ] {
+            assert_eq!(Some(resolved), policy.apply(&ts, resolved, reference));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn future_policy_keep_clamp_reject() {
+        let ts = Timestamp::HHMMSS(12, 0, 0);
+        let resolved = time(19783, 7200);
+        let reference = time(19783, 3600);
+
+        assert_eq!(
+            Some(resolved),
+            FutureTimestampPolicy::Keep.apply(&ts, resolved, reference)
+        );
+        assert_eq!(
+            Some(reference),
+            FutureTimestampPolicy::ClampToReference.apply(&ts, resolved, reference)
+        );
+        assert_eq!(
+            None,
+            FutureTimestampPolicy::Reject.apply(&ts, resolved, reference)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn future_policy_roll_back() {
+        let reference = time(19783, 0);
+
+        // HMS goes back a day
+        assert_eq!(
+            Some(time(19782, 3600)),
+            FutureTimestampPolicy::RollBack.apply(
+                &Timestamp::HHMMSS(1, 0, 0),
+                time(19783, 3600),
+                reference
+            )
+        );
+
+        // DHM goes back a month: 2024-03-01 -> 2024-02-01
+        assert_eq!(
+            Some(time(19754, 3600)),
+            FutureTimestampPolicy::RollBack.apply(
+                &Timestamp::DDHHMM(1, 1, 0),
+                time(19783, 3600),
+                reference
+            )
+        );
+
+        // 2024-03-30 doesn't exist in February, so it goes back to 2024-01-30
+        assert_eq!(
+            Some(time(19752, 0)),
+            FutureTimestampPolicy::RollBack.apply(
+                &Timestamp::DDHHMM(30, 0, 0),
+                time(19812, 0),
+                reference
+            )
+        );
+
+        assert_eq!(
+            None,
+            FutureTimestampPolicy::RollBack.apply(
+                &Timestamp::Unsupported(b"123456/".to_vec()),
+                time(19812, 0),
+                reference
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn resolve_hms() {
+        // 2024-03-01 00:01
+        let received = time(19783, 60);
+        assert_eq!(
+            Some(time(19782, 86340)),
+            Timestamp::HHMMSS(23, 59, 0).resolve(received)
+        );
+        assert_eq!(
+            Some(time(19783, 30)),
+            Timestamp::HHMMSS(0, 0, 30).resolve(received)
+        );
+        // slightly ahead of the receiver
+        assert_eq!(
+            Some(time(19783, 120)),
+            Timestamp::HHMMSS(0, 2, 0).resolve(received)
+        );
+        assert_eq!(None, Timestamp::HHMMSS(24, 0, 0).resolve(received));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn resolve_dhm() {
+        // 2024-03-01 00:01
+        let received = time(19783, 60);
+
+        // 2024-02-29 23:59
+        assert_eq!(
+            Some(time(19782, 86340)),
+            Timestamp::DDHHMM(29, 23, 59).resolve(received)
+        );
+        // 2024-02-15, as there's no 31st in February
+        assert_eq!(
+            Some(time(19768, 0)),
+            Timestamp::DDHHMM(15, 0, 0).resolve(received)
+        );
+        assert_eq!(
+            Some(time(19783, 0)),
+            Timestamp::DDHHMM(1, 0, 0).resolve(received)
+        );
+        // 2024-01-31
+        assert_eq!(
+            Some(time(19753, 0)),
+            Timestamp::DDHHMM(31, 0, 0).resolve(received)
+        );
+
+        assert_eq!(None, Timestamp::DDHHMM(0, 0, 0).resolve(received));
+        assert_eq!(None, Timestamp::DDHHMMLocal(1, 0, 0).resolve(received));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono() {
+        let time = Utc.with_ymd_and_hms(2023, 3, 1, 0, 1, 2).unwrap();
+        assert_eq!(
+            Timestamp::DDHHMM(1, 0, 1),
+            Timestamp::dhm_from_chrono(&time)
+        );
+        assert_eq!(
+            Timestamp::HHMMSS(0, 1, 2),
+            Timestamp::hms_from_chrono(&time)
+        );
+
+        assert_eq!(
+            Some(Utc.with_ymd_and_hms(2023, 2, 28, 23, 59, 0).unwrap()),
+            Timestamp::HHMMSS(23, 59, 0).resolve_chrono(&time)
+        );
+        assert_eq!(
+            Some(Utc.with_ymd_and_hms(2023, 1, 31, 0, 0, 0).unwrap()),
+            Timestamp::DDHHMM(31, 0, 0).resolve_chrono(&time)
+        );
+        assert_eq!(None, Timestamp::DDHHMMLocal(1, 0, 0).resolve_chrono(&time));
+    }
+
+    #[cfg(feature = "time")]
+    fn offset_date_time(
+        year: i32,
+        month: Month,
+        day: u8,
+        (h, m, s): (u8, u8, u8),
+        offset_hours: i8,
+    ) -> OffsetDateTime {
+        Date::from_calendar_date(year, month, day)
+            .unwrap()
+            .with_hms(h, m, s)
+            .unwrap()
+            .assume_offset(UtcOffset::from_hms(offset_hours, 0, 0).unwrap())
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn time_crate() {
+        let time = offset_date_time(2023, Month::March, 1, (1, 1, 2), 1);
+        assert_eq!(
+            Timestamp::DDHHMM(1, 0, 1),
+            Timestamp::dhm_from_offset_date_time(time)
+        );
+        assert_eq!(
+            Timestamp::HHMMSS(0, 1, 2),
+            Timestamp::hms_from_offset_date_time(time)
+        );
+
+        assert_eq!(
+            Some(offset_date_time(2023, Month::February, 28, (23, 59, 0), 0)),
+            Timestamp::HHMMSS(23, 59, 0).resolve_offset_date_time(time)
+        );
+        assert_eq!(
+            Some(offset_date_time(2023, Month::January, 31, (0, 0, 0), 0)),
+            Timestamp::DDHHMM(31, 0, 0).resolve_offset_date_time(time)
+        );
+        assert_eq!(
+            None,
+            Timestamp::DDHHMMLocal(1, 0, 0).resolve_offset_date_time(time)
+        );
+    }
 }
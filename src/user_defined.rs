@@ -0,0 +1,87 @@
+//! User-defined data lets experimenters send their own formats. It starts with
+//! the '{' APRS Data Type Identifier, followed by a one character user ID, which
+//! identifies the author of the format, and a one character packet type.
+//! The rest is free-form.
+//!
+//! Example:
+//! - "{Q1qwerty" (user `Q`, packet type `1`)
+
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use Callsign;
+use DecodeError;
+use EncodeError;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AprsUserDefined {
+    pub to: Callsign,
+    pub user_id: u8,
+    pub packet_type: u8,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
+    pub data: Vec<u8>,
+}
+
+impl AprsUserDefined {
+    pub fn new(to: Callsign, user_id: u8, packet_type: u8, data: Vec<u8>) -> Self {
+        Self {
+            to,
+            user_id,
+            packet_type,
+            data,
+        }
+    }
+
+    /// Decodes the information field after the `{`.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        match b {
+            [user_id, packet_type, data @ ..] => Ok(Self {
+                to,
+                user_id: *user_id,
+                packet_type: *packet_type,
+                data: data.to_owned(),
+            }),
+            _ => Err(DecodeError::InvalidUserDefined(b.to_owned(), 0)),
+        }
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_all(&[b'{', self.user_id, self.packet_type])?;
+        buf.write_all(&self.data)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_callsign() -> Callsign {
+        Callsign::new_no_ssid("APRS")
+    }
+
+    #[test]
+    fn decode_and_encode() {
+        let original = &b"{Q1qwerty"[..];
+        let data = AprsUserDefined::decode(&original[1..], default_callsign()).unwrap();
+
+        assert_eq!(
+            AprsUserDefined::new(default_callsign(), b'Q', b'1', b"qwerty".to_vec()),
+            data
+        );
+
+        let mut buf = vec![];
+        data.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn decode_invalid() {
+        assert_eq!(
+            Err(DecodeError::InvalidUserDefined(b"Q".to_vec(), 0)),
+            AprsUserDefined::decode(b"Q", default_callsign())
+        );
+    }
+}
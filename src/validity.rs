@@ -0,0 +1,277 @@
+//! Opt-in heuristics for spotting positions that are technically valid but almost
+//! certainly bogus, such as a tracker without a GPS fix reporting 0°N 0°E
+//! ("null island") or a station that appears to jump across the globe between
+//! two consecutive packets.
+//!
+//! Nothing here rejects packets. The [`PositionValidator`] only reports
+//! [`PositionWarning`]s so that consumers can decide whether to plot, dim or drop
+//! the position.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, SystemTime};
+
+use lonlat::distance_km;
+use AprsPacket;
+use Callsign;
+use Latitude;
+use Longitude;
+
+const KM_PER_NAUTICAL_MILE: f64 = 1.852;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PositionWarning {
+    /// The position is exactly 0° latitude, 0° longitude.
+    NullIsland,
+    /// The exact same coordinates have been reported by this many distinct stations,
+    /// which usually means they are an unconfigured default.
+    SharedCoordinates(usize),
+    /// Reaching this position from the station's previous one would have required
+    /// travelling at the given speed in knots.
+    ImpossibleSpeed(f64),
+}
+
+#[derive(Debug, Clone)]
+pub struct PositionValidator {
+    max_speed_knots: f64,
+    shared_threshold: usize,
+    window: Duration,
+
+    last_seen: HashMap<Callsign, (Latitude, Longitude, SystemTime)>,
+    reporters: HashMap<(u64, u64), HashMap<Callsign, SystemTime>>,
+    // every position checked, oldest first, to expire the maps above
+    order: VecDeque<(SystemTime, Callsign, (u64, u64))>,
+}
+
+impl PositionValidator {
+    /// Creates a validator that flags speeds above 1000 knots and coordinates
+    /// shared by three or more stations, and remembers positions for an hour.
+    pub fn new() -> Self {
+        Self {
+            max_speed_knots: 1000.0,
+            shared_threshold: 3,
+            window: Duration::from_secs(3600),
+            last_seen: HashMap::new(),
+            reporters: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Sets the speed above which a jump between two positions is flagged.
+    pub fn with_max_speed_knots(mut self, max_speed_knots: f64) -> Self {
+        self.max_speed_knots = max_speed_knots;
+        self
+    }
+
+    /// Sets how many distinct stations must report identical coordinates before
+    /// they are flagged as a likely default.
+    pub fn with_shared_threshold(mut self, shared_threshold: usize) -> Self {
+        self.shared_threshold = shared_threshold;
+        self
+    }
+
+    /// Sets how long positions are remembered. Stations that haven't reported
+    /// a position for that long are forgotten, so memory use stays bounded.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Checks the position carried by `packet`, if any, and remembers it for
+    /// subsequent checks. `received_at` is used to compute the station's speed.
+    pub fn check(&mut self, packet: &AprsPacket, received_at: SystemTime) -> Vec<PositionWarning> {
+        let mut warnings = vec![];
+        self.expire(received_at);
+
+        let (latitude, longitude) = match packet.data.coordinates() {
+            Some(c) => c,
+            None => return warnings,
+        };
+
+        if *latitude == 0.0 && *longitude == 0.0 {
+            warnings.push(PositionWarning::NullIsland);
+        }
+
+        let key = (latitude.to_bits(), longitude.to_bits());
+        self.order
+            .push_back((received_at, packet.from.clone(), key));
+        let reporters = self.reporters.entry(key).or_default();
+        reporters.insert(packet.from.clone(), received_at);
+        if reporters.len() >= self.shared_threshold {
+            warnings.push(PositionWarning::SharedCoordinates(reporters.len()));
+        }
+
+        let previous = self
+            .last_seen
+            .insert(packet.from.clone(), (latitude, longitude, received_at));
+        if let Some((prev_lat, prev_lon, prev_time)) = previous {
+            // packets arriving out of order or within the same instant tell us nothing
+            let elapsed = received_at
+                .duration_since(prev_time)
+                .map(|d| d.as_secs_f64())
+                .unwrap_or(0.0);
+
+            if elapsed > 0.0 {
+                let distance = distance_km(*prev_lat, *prev_lon, *latitude, *longitude);
+                let knots = distance / KM_PER_NAUTICAL_MILE / (elapsed / 3600.0);
+
+                if knots > self.max_speed_knots {
+                    warnings.push(PositionWarning::ImpossibleSpeed(knots));
+                }
+            }
+        }
+
+        warnings
+    }
+
+    /// Forgets all previously seen stations and coordinates.
+    pub fn clear(&mut self) {
+        self.last_seen.clear();
+        self.reporters.clear();
+        self.order.clear();
+    }
+
+    fn expire(&mut self, now: SystemTime) {
+        while let Some((seen, _, _)) = self.order.front() {
+            // a clock that went backwards counts as no time having passed
+            if now.duration_since(*seen).unwrap_or_default() < self.window {
+                break;
+            }
+            if let Some((seen, station, key)) = self.order.pop_front() {
+                self.forget(seen, &station, key);
+            }
+        }
+    }
+
+    // Removes what was remembered at `seen`, unless it has been updated since
+    fn forget(&mut self, seen: SystemTime, station: &Callsign, key: (u64, u64)) {
+        if matches!(self.last_seen.get(station), Some((_, _, t)) if *t == seen) {
+            self.last_seen.remove(station);
+        }
+        if let Some(reporters) = self.reporters.get_mut(&key) {
+            if reporters.get(station) == Some(&seen) {
+                reporters.remove(station);
+            }
+            if reporters.is_empty() {
+                self.reporters.remove(&key);
+            }
+        }
+    }
+}
+
+impl Default for PositionValidator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn packet(s: &str) -> AprsPacket {
+        AprsPacket::decode_textual(s.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn flags_null_island() {
+        let mut validator = PositionValidator::new();
+
+        assert_eq!(
+            vec![PositionWarning::NullIsland],
+            validator.check(
+                &packet("N0CALL>APRS:!0000.00N/00000.00E-"),
+                SystemTime::UNIX_EPOCH
+            )
+        );
+        assert_eq!(
+            Vec::<PositionWarning>::new(),
+            validator.check(
+                &packet("N0CALL>APRS:!4903.50N/07201.75W-"),
+                SystemTime::UNIX_EPOCH
+            )
+        );
+    }
+
+    #[test]
+    fn ignores_packets_without_position() {
+        let mut validator = PositionValidator::new();
+
+        assert!(validator
+            .check(&packet("N0CALL>APRS:>Status"), SystemTime::UNIX_EPOCH)
+            .is_empty());
+    }
+
+    #[test]
+    fn flags_shared_coordinates() {
+        let mut validator = PositionValidator::new().with_shared_threshold(2);
+        let t = SystemTime::UNIX_EPOCH;
+
+        assert!(validator
+            .check(&packet("N0CALL>APRS:!4903.50N/07201.75W-"), t)
+            .is_empty());
+        // the same station again doesn't count twice
+        assert!(validator
+            .check(&packet("N0CALL>APRS:!4903.50N/07201.75W-"), t)
+            .is_empty());
+        assert_eq!(
+            vec![PositionWarning::SharedCoordinates(2)],
+            validator.check(&packet("N1CALL>APRS:!4903.50N/07201.75W-"), t)
+        );
+    }
+
+    #[test]
+    fn forgets_old_positions() {
+        let mut validator = PositionValidator::new().with_shared_threshold(2);
+        let t = SystemTime::UNIX_EPOCH;
+        let hour = Duration::from_secs(3600);
+
+        validator.check(&packet("N0CALL>APRS:!4903.50N/07201.75W-"), t);
+        validator.check(&packet("N1CALL>APRS:!5003.50N/07201.75W-"), t);
+        // N1CALL is still current, N0CALL isn't
+        validator.check(&packet("N1CALL>APRS:!5003.50N/07201.75W-"), t + hour / 2);
+        assert!(validator
+            .check(&packet("N2CALL>APRS:!4903.50N/07201.75W-"), t + hour)
+            .is_empty());
+        assert_eq!(2, validator.last_seen.len());
+        assert!(!validator
+            .last_seen
+            .contains_key(&Callsign::new_no_ssid("N0CALL")));
+        assert_eq!(
+            vec![PositionWarning::SharedCoordinates(2)],
+            validator.check(&packet("N3CALL>APRS:!5003.50N/07201.75W-"), t + hour)
+        );
+
+        validator.check(&packet("N0CALL>APRS:>Status"), t + hour * 3);
+        assert!(validator.last_seen.is_empty());
+        assert!(validator.reporters.is_empty());
+        assert!(validator.order.is_empty());
+    }
+
+    #[test]
+    fn flags_impossible_speed() {
+        let mut validator = PositionValidator::new();
+        let t = SystemTime::UNIX_EPOCH;
+
+        validator.check(&packet("N0CALL>APRS:!4903.50N/07201.75W-"), t);
+
+        // roughly 60 nautical miles in an hour is fine
+        assert!(validator
+            .check(
+                &packet("N0CALL>APRS:!5003.50N/07201.75W-"),
+                t + Duration::from_secs(3600)
+            )
+            .is_empty());
+
+        // across the Atlantic a minute later is not
+        let warnings = validator.check(
+            &packet("N0CALL>APRS:!5003.50N/00201.75W-"),
+            t + Duration::from_secs(3660),
+        );
+        assert_eq!(1, warnings.len());
+        assert!(matches!(
+            warnings[0],
+            PositionWarning::ImpossibleSpeed(knots) if knots > 1000.0
+        ));
+    }
+}
@@ -1,8 +1,8 @@
-use std::io::{self, Write};
-
+use io::{self, Write};
 use Callsign;
 
 #[derive(Eq, PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Via {
     Callsign(Callsign, bool),
     QConstruct(QConstruct),
@@ -41,6 +41,12 @@ impl Via {
         }
     }
 
+    /// Classifies the special entries that control gating.
+    pub fn token(&self) -> Option<PathToken> {
+        self.callsign()
+            .and_then(|(c, _)| PathToken::from_callsign(c))
+    }
+
     pub fn callsign_mut(&mut self) -> Option<(&mut Callsign, &mut bool)> {
         match self {
             Self::Callsign(c, heard) => Some((c, heard)),
@@ -49,9 +55,51 @@ impl Via {
     }
 }
 
+/// Special path entries that control gating between RF and APRS-IS.
+/// They can be marked as used, e.g. `TCPIP*`.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum PathToken {
+    /// `TCPIP`, sent to APRS-IS by a verified station
+    TcpIp,
+    /// `TCPXX`, sent to APRS-IS by an unverified station
+    TcpXx,
+    /// `NOGATE`, not to be gated in either direction
+    NoGate,
+    /// `RFONLY`, not to be gated to APRS-IS
+    RfOnly,
+}
+
+impl PathToken {
+    pub fn from_callsign(c: &Callsign) -> Option<Self> {
+        if c.ssid().is_some() {
+            return None;
+        }
+
+        let token = match c.call() {
+            "TCPIP" => PathToken::TcpIp,
+            "TCPXX" => PathToken::TcpXx,
+            "NOGATE" => PathToken::NoGate,
+            "RFONLY" => PathToken::RfOnly,
+            _ => return None,
+        };
+
+        Some(token)
+    }
+
+    pub fn as_textual(&self) -> &'static str {
+        match self {
+            PathToken::TcpIp => "TCPIP",
+            PathToken::TcpXx => "TCPXX",
+            PathToken::NoGate => "NOGATE",
+            PathToken::RfOnly => "RFONLY",
+        }
+    }
+}
+
 // Can't be encoded/decoded as ax.25
 // These should never go on the air
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum QConstruct {
     AC,
     AX,
@@ -98,4 +146,19 @@ impl QConstruct {
             QConstruct::AI => "qAI",
         }
     }
+
+    /// Whether the server received the packet over a verified connection,
+    /// from the sender itself or from an igate.
+    pub fn is_verified(&self) -> bool {
+        matches!(self, QConstruct::AC | QConstruct::Ao | QConstruct::AR)
+    }
+
+    /// Whether the packet was heard over the air and gated to APRS-IS by an igate.
+    /// The callsign after the q-construct is then the igate.
+    pub fn is_rf_gated(&self) -> bool {
+        matches!(
+            self,
+            QConstruct::Ao | QConstruct::AO | QConstruct::Ar | QConstruct::AR
+        )
+    }
 }
@@ -0,0 +1,845 @@
+//! Weather observations, as reported by APRS weather stations.
+//!
+//! All values are kept in the units used on the wire:
+//! degrees, miles per hour, degrees Fahrenheit, hundredths of an inch of rain,
+//! percent relative humidity and tenths of a millibar.
+//! Accessors such as [`WeatherData::temperature_celsius`] convert them to metric units.
+//!
+//! Stations without a particular sensor fill its field with dots (`c...`),
+//! spaces or dashes. These are decoded as `None` and encoded as dots.
+//!
+//! Stations without a position send positionless weather reports starting with `_`,
+//! e.g. `_10090556c220s004g005t077r000p000P000h50b09900wRSW`.
+//!
+//! Some older weather stations are connected directly to a TNC and send their own
+//! raw formats instead: `$ULTW` from Ultimeter 2000 stations, and `!!` from
+//! Peet Bros stations in data logging mode. Both are a series of four-digit
+//! hex fields, in metric units and tenths.
+
+use core::convert::TryFrom;
+
+use bytes::parse_bytes;
+#[cfg(not(any(feature = "std", test)))]
+use float::F64Ext;
+use io::Write;
+#[cfg(not(feature = "std"))]
+use prelude::*;
+use Callsign;
+use DecodeError;
+use EncodeError;
+use MdhmTimestamp;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AprsWeather {
+    pub to: Callsign,
+    pub timestamp: MdhmTimestamp,
+    pub data: WeatherData,
+    /// Software type, e.g. `w` for WinAPRS
+    pub software: Option<u8>,
+    /// Weather station type, e.g. `RSW` for Radio Shack
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
+    pub unit: Vec<u8>,
+}
+
+impl AprsWeather {
+    /// Decodes the information field after the `_`.
+    /// Everything after the weather fields is taken as the software and unit type.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        let timestamp = MdhmTimestamp::try_from(b.get(0..8).unwrap_or(b))?;
+        let (data, rest) = WeatherData::decode(&b[8..]);
+
+        Ok(Self {
+            to,
+            timestamp,
+            data,
+            software: rest.first().copied(),
+            unit: rest.get(1..).unwrap_or_default().to_owned(),
+        })
+    }
+
+    /// A unit type can't be encoded without a software type before it.
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        if self.software.is_none() && !self.unit.is_empty() {
+            return Err(EncodeError::InvalidData);
+        }
+
+        buf.write_all(b"_")?;
+        self.timestamp.encode(buf)?;
+        self.data.encode(buf)?;
+        if let Some(software) = self.software {
+            buf.write_all(&[software])?;
+            buf.write_all(&self.unit)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WeatherData {
+    /// Wind direction in degrees
+    pub wind_direction: Option<u16>,
+    /// Sustained one-minute wind speed in mph
+    pub wind_speed: Option<u16>,
+    /// Peak wind speed in the last five minutes in mph
+    pub wind_gust: Option<u16>,
+    /// Temperature in degrees Fahrenheit
+    pub temperature: Option<i16>,
+    /// Rainfall in the last hour in hundredths of an inch
+    pub rain_last_hour: Option<u16>,
+    /// Rainfall in the last 24 hours in hundredths of an inch
+    pub rain_last_24_hours: Option<u16>,
+    /// Rainfall since midnight in hundredths of an inch
+    pub rain_since_midnight: Option<u16>,
+    /// Relative humidity in percent
+    pub humidity: Option<u8>,
+    /// Barometric pressure in tenths of a millibar
+    pub barometric_pressure: Option<u32>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RawWeatherFormat {
+    /// `$ULTW` packets from Ultimeter 2000 stations
+    Ultimeter,
+    /// `!!` packets from Peet Bros stations in data logging mode
+    PeetBros,
+}
+
+impl RawWeatherFormat {
+    /// The bytes the information field starts with
+    pub fn prefix(self) -> &'static [u8] {
+        match self {
+            RawWeatherFormat::Ultimeter => b"$ULTW",
+            RawWeatherFormat::PeetBros => b"!!",
+        }
+    }
+
+    pub fn data_type_identifier(self) -> u8 {
+        self.prefix()[0]
+    }
+}
+
+/// A weather report in one of the raw formats.
+/// Converting these to [`WeatherData`] loses precision,
+/// so the original information field is kept and used for encoding.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(try_from = "RawWeatherFields"))]
+pub struct AprsRawWeather {
+    pub to: Callsign,
+    pub format: RawWeatherFormat,
+    pub data: WeatherData,
+    #[cfg_attr(feature = "serde", serde(with = "::serde_support::text"))]
+    raw: Vec<u8>,
+}
+
+impl AprsRawWeather {
+    /// Decodes the whole information field, including the `$ULTW` or `!!` prefix.
+    pub fn decode(b: &[u8], to: Callsign) -> Result<Self, DecodeError> {
+        let err = || DecodeError::InvalidWeather(b.to_owned(), 0);
+
+        let (format, fields) = [RawWeatherFormat::Ultimeter, RawWeatherFormat::PeetBros]
+            .iter()
+            .find_map(|&f| Some((f, b.strip_prefix(f.prefix())?)))
+            .ok_or_else(err)?;
+
+        // trailing line endings are common, since these come straight from the station
+        let fields = fields
+            .strip_suffix(b"\r\n")
+            .or_else(|| fields.strip_suffix(b"\r"))
+            .unwrap_or(fields);
+        if fields.len() % 4 != 0 {
+            return Err(err());
+        }
+        let fields = fields
+            .chunks(4)
+            .map(decode_hex_field)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(err)?;
+        let field = |i: usize| fields.get(i).copied().flatten();
+
+        // wind direction is 0-255 for a full circle
+        let direction = |d: u16| (f64::from(d & 0xff) * 360.0 / 256.0).round() as u16;
+        let mph = |kph: u16| (f64::from(kph) / 10.0 * 0.621371).round() as u16;
+        let tenths = |v: u16| (f64::from(v as i16) / 10.0).round() as i16;
+
+        let data = match format {
+            RawWeatherFormat::Ultimeter => WeatherData {
+                wind_direction: field(1).map(direction),
+                wind_speed: field(12).map(mph),
+                wind_gust: field(0).map(mph),
+                temperature: field(2).map(tenths),
+                rain_since_midnight: field(11),
+                humidity: field(8).map(|h| tenths(h).clamp(0, 100) as u8),
+                barometric_pressure: field(4).map(u32::from),
+                ..Default::default()
+            },
+            RawWeatherFormat::PeetBros => WeatherData {
+                wind_direction: field(1).map(direction),
+                wind_speed: field(11).or_else(|| field(0)).map(mph),
+                temperature: field(2).map(tenths),
+                rain_since_midnight: field(10),
+                humidity: field(6).map(|h| tenths(h).clamp(0, 100) as u8),
+                barometric_pressure: field(4).map(u32::from),
+                ..Default::default()
+            },
+        };
+
+        Ok(Self {
+            to,
+            format,
+            data,
+            raw: b.to_owned(),
+        })
+    }
+
+    /// The original information field
+    pub fn raw(&self) -> &[u8] {
+        &self.raw
+    }
+
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        buf.write_all(&self.raw)?;
+
+        Ok(())
+    }
+}
+
+// The raw field is what gets encoded, so it's checked against the format when deserializing
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "AprsRawWeather")]
+struct RawWeatherFields {
+    to: Callsign,
+    format: RawWeatherFormat,
+    data: WeatherData,
+    #[serde(with = "::serde_support::text")]
+    raw: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawWeatherFields> for AprsRawWeather {
+    type Error = &'static str;
+
+    fn try_from(f: RawWeatherFields) -> Result<Self, Self::Error> {
+        if !f.raw.starts_with(f.format.prefix()) {
+            return Err("raw weather data doesn't start with the prefix of its format");
+        }
+
+        Ok(Self {
+            to: f.to,
+            format: f.format,
+            data: f.data,
+            raw: f.raw,
+        })
+    }
+}
+
+// `----` marks a missing value
+fn decode_hex_field(b: &[u8]) -> Option<Option<u16>> {
+    if b == b"----" {
+        return Some(None);
+    }
+    if !b.iter().all(u8::is_ascii_hexdigit) {
+        return None;
+    }
+
+    let s = core::str::from_utf8(b).ok()?;
+    u16::from_str_radix(s, 16).ok().map(Some)
+}
+
+impl WeatherData {
+    /// Decodes a run of weather fields such as `c220s004g005t077r000p000P000h50b09900`.
+    /// Fields may appear in any order. Decoding stops at the first byte that doesn't
+    /// start a valid field, or at a field that was already seen.
+    /// Returns the decoded data and the remaining bytes.
+    pub fn decode(mut b: &[u8]) -> (Self, &[u8]) {
+        let mut data = Self::default();
+
+        while let Some(&tag) = b.first() {
+            let width = match tag {
+                b'h' => 2,
+                b'b' => 5,
+                b'c' | b's' | b'g' | b't' | b'r' | b'p' | b'P' => 3,
+                _ => break,
+            };
+
+            let value = match b.get(1..=width) {
+                Some(v) => v,
+                None => break,
+            };
+
+            let parsed = match tag {
+                b'c' => set_field(&mut data.wind_direction, value),
+                b's' => set_field(&mut data.wind_speed, value),
+                b'g' => set_field(&mut data.wind_gust, value),
+                b't' => set_field(&mut data.temperature, value),
+                b'r' => set_field(&mut data.rain_last_hour, value),
+                b'p' => set_field(&mut data.rain_last_24_hours, value),
+                b'P' => set_field(&mut data.rain_since_midnight, value),
+                b'b' => set_field(&mut data.barometric_pressure, value),
+                b'h' => {
+                    // h00 means 100%
+                    let parsed = set_field(&mut data.humidity, value);
+                    if data.humidity == Some(0) {
+                        data.humidity = Some(100);
+                    }
+                    parsed
+                }
+                _ => unreachable!(),
+            };
+
+            if !parsed {
+                break;
+            }
+
+            b = &b[(width + 1)..];
+        }
+
+        (data, b)
+    }
+
+    /// Decodes the weather fields in the comment of a position report with the weather symbol.
+    /// These start with the wind direction and speed as `DDD/SSS` rather than `cDDDsSSS`.
+    pub(crate) fn decode_position_comment(b: &[u8]) -> (Self, &[u8]) {
+        let wind = b.get(0..7).filter(|w| w[3] == b'/');
+        let mut data = Self::default();
+        let (direction, speed) = match wind {
+            Some(w) => (&w[0..3], &w[4..7]),
+            None => return Self::decode(b),
+        };
+        if !set_field(&mut data.wind_direction, direction)
+            || !set_field(&mut data.wind_speed, speed)
+        {
+            return Self::decode(b);
+        }
+
+        let (rest, remaining) = Self::decode(&b[7..]);
+        data.wind_gust = rest.wind_gust;
+        data.temperature = rest.temperature;
+        data.rain_last_hour = rest.rain_last_hour;
+        data.rain_last_24_hours = rest.rain_last_24_hours;
+        data.rain_since_midnight = rest.rain_since_midnight;
+        data.humidity = rest.humidity;
+        data.barometric_pressure = rest.barometric_pressure;
+
+        (data, remaining)
+    }
+
+    /// Encodes the wind and temperature fields, followed by any rain, humidity and
+    /// pressure fields that are present. Missing wind and temperature values are written as dots.
+    /// Values too large for their field, e.g. a temperature of -100, are rejected.
+    pub fn encode<W: Write>(&self, buf: &mut W) -> Result<(), EncodeError> {
+        encode_field(buf, b'c', self.wind_direction, 3)?;
+        encode_field(buf, b's', self.wind_speed, 3)?;
+        encode_field(buf, b'g', self.wind_gust, 3)?;
+        encode_field(buf, b't', self.temperature, 3)?;
+
+        if self.rain_last_hour.is_some() {
+            encode_field(buf, b'r', self.rain_last_hour, 3)?;
+        }
+        if self.rain_last_24_hours.is_some() {
+            encode_field(buf, b'p', self.rain_last_24_hours, 3)?;
+        }
+        if self.rain_since_midnight.is_some() {
+            encode_field(buf, b'P', self.rain_since_midnight, 3)?;
+        }
+        if let Some(h) = self.humidity {
+            encode_field(buf, b'h', Some(h % 100), 2)?;
+        }
+        if self.barometric_pressure.is_some() {
+            encode_field(buf, b'b', self.barometric_pressure, 5)?;
+        }
+
+        Ok(())
+    }
+
+    /// Temperature in degrees Celsius, rounded to a tenth of a degree.
+    pub fn temperature_celsius(&self) -> Option<f64> {
+        self.temperature
+            .map(|t| round_to_tenth(fahrenheit_to_celsius(f64::from(t))))
+    }
+
+    /// Sustained wind speed in meters per second, rounded to a tenth.
+    pub fn wind_speed_ms(&self) -> Option<f64> {
+        self.wind_speed.map(mph_to_ms)
+    }
+
+    /// Peak wind speed in meters per second, rounded to a tenth.
+    pub fn wind_gust_ms(&self) -> Option<f64> {
+        self.wind_gust.map(mph_to_ms)
+    }
+
+    /// Rainfall in the last hour in millimeters, rounded to a tenth.
+    pub fn rain_mm(&self) -> Option<f64> {
+        self.rain_last_hour.map(hundredths_inch_to_mm)
+    }
+
+    /// Rainfall in the last 24 hours in millimeters, rounded to a tenth.
+    pub fn rain_last_24_hours_mm(&self) -> Option<f64> {
+        self.rain_last_24_hours.map(hundredths_inch_to_mm)
+    }
+
+    /// Rainfall since midnight in millimeters, rounded to a tenth.
+    pub fn rain_since_midnight_mm(&self) -> Option<f64> {
+        self.rain_since_midnight.map(hundredths_inch_to_mm)
+    }
+
+    /// Barometric pressure in hectopascals (millibars). This is exact.
+    pub fn barometric_pressure_hpa(&self) -> Option<f64> {
+        self.barometric_pressure.map(|p| f64::from(p) / 10.0)
+    }
+
+    /// Dew point in degrees Fahrenheit, using the Magnus formula.
+    /// Requires both temperature and humidity.
+    pub fn dew_point(&self) -> Option<f64> {
+        const A: f64 = 17.625;
+        const B: f64 = 243.04;
+
+        let t = fahrenheit_to_celsius(f64::from(self.temperature?));
+        let rh = f64::from(self.humidity?);
+        if rh <= 0.0 {
+            return None;
+        }
+
+        let gamma = (rh / 100.0).ln() + (A * t) / (B + t);
+        let dew_point = B * gamma / (A - gamma);
+
+        Some(celsius_to_fahrenheit(dew_point))
+    }
+
+    /// Wind chill in degrees Fahrenheit, using the NWS formula.
+    /// The formula is only defined at or below 50°F with at least 3 mph of wind,
+    /// outside of that the air temperature is returned.
+    pub fn wind_chill(&self) -> Option<f64> {
+        let t = f64::from(self.temperature?);
+        let v = f64::from(self.wind_speed?);
+
+        if t > 50.0 || v < 3.0 {
+            return Some(t);
+        }
+
+        let v = v.powf(0.16);
+        Some(35.74 + 0.6215 * t - 35.75 * v + 0.4275 * t * v)
+    }
+
+    /// Heat index in degrees Fahrenheit, using the NWS algorithm
+    /// (Steadman's approximation, falling back to the Rothfusz regression in hot weather).
+    pub fn heat_index(&self) -> Option<f64> {
+        let t = f64::from(self.temperature?);
+        let rh = f64::from(self.humidity?);
+
+        let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+        if (simple + t) / 2.0 < 80.0 {
+            return Some(simple);
+        }
+
+        let mut hi = -42.379 + 2.04901523 * t + 10.14333127 * rh
+            - 0.22475541 * t * rh
+            - 0.00683783 * t * t
+            - 0.05481717 * rh * rh
+            + 0.00122874 * t * t * rh
+            + 0.00085282 * t * rh * rh
+            - 0.00000199 * t * t * rh * rh;
+
+        if rh < 13.0 && (80.0..=112.0).contains(&t) {
+            hi -= ((13.0 - rh) / 4.0) * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+        } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+            hi += ((rh - 85.0) / 10.0) * ((87.0 - t) / 5.0);
+        }
+
+        Some(hi)
+    }
+}
+
+// Parses a fixed-width field into `field`, treating placeholders as a missing value.
+// Returns false if the value is invalid or the field was already set.
+fn set_field<T: core::str::FromStr>(field: &mut Option<T>, value: &[u8]) -> bool {
+    if field.is_some() {
+        return false;
+    }
+
+    if is_placeholder(value) {
+        return true;
+    }
+
+    *field = parse_bytes(value);
+    field.is_some()
+}
+
+fn is_placeholder(value: &[u8]) -> bool {
+    value.iter().all(|&b| b == b'.' || b == b' ')
+        || (!value.is_empty() && value.iter().all(|&b| b == b'-'))
+}
+
+// Values that don't fit in the field, including a minus sign, are rejected
+fn encode_field<W: Write, T: Into<i64>>(
+    buf: &mut W,
+    tag: u8,
+    value: Option<T>,
+    width: u32,
+) -> Result<(), EncodeError> {
+    let value = value.map(Into::into);
+    if let Some(v) = value {
+        let max = 10i64.pow(width) - 1;
+        let min = -(10i64.pow(width - 1) - 1);
+        if v < min || v > max {
+            return Err(EncodeError::InvalidData);
+        }
+    }
+
+    buf.write_all(&[tag])?;
+
+    match value {
+        Some(v) => write!(buf, "{:0width$}", v, width = width as usize)?,
+        None => {
+            for _ in 0..width {
+                buf.write_all(b".")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Halfway values round away from zero
+fn round_to_tenth(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+fn mph_to_ms(mph: u16) -> f64 {
+    round_to_tenth(f64::from(mph) * 0.44704)
+}
+
+fn hundredths_inch_to_mm(rain: u16) -> f64 {
+    round_to_tenth(f64::from(rain) * 0.254)
+}
+
+fn fahrenheit_to_celsius(f: f64) -> f64 {
+    (f - 32.0) * 5.0 / 9.0
+}
+
+fn celsius_to_fahrenheit(c: f64) -> f64 {
+    c * 9.0 / 5.0 + 32.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metric() {
+        let wx = WeatherData {
+            wind_speed: Some(10),
+            wind_gust: Some(1),
+            temperature: Some(-40),
+            rain_last_hour: Some(1),
+            rain_last_24_hours: Some(100),
+            rain_since_midnight: Some(2),
+            barometric_pressure: Some(10132),
+            ..Default::default()
+        };
+
+        assert_eq!(Some(-40.0), wx.temperature_celsius());
+        assert_eq!(Some(4.5), wx.wind_speed_ms());
+        assert_eq!(Some(0.4), wx.wind_gust_ms());
+        assert_eq!(Some(0.3), wx.rain_mm());
+        assert_eq!(Some(25.4), wx.rain_last_24_hours_mm());
+        assert_eq!(Some(0.5), wx.rain_since_midnight_mm());
+        assert_eq!(Some(1013.2), wx.barometric_pressure_hpa());
+
+        // 77°F is exactly 25°C, 33°F rounds to 0.6°C
+        let wx = WeatherData {
+            temperature: Some(77),
+            ..Default::default()
+        };
+        assert_eq!(Some(25.0), wx.temperature_celsius());
+        let wx = WeatherData {
+            temperature: Some(33),
+            ..Default::default()
+        };
+        assert_eq!(Some(0.6), wx.temperature_celsius());
+
+        assert_eq!(None, WeatherData::default().temperature_celsius());
+        assert_eq!(None, WeatherData::default().rain_mm());
+    }
+
+    #[test]
+    fn dew_point() {
+        let wx = WeatherData {
+            temperature: Some(77),
+            humidity: Some(50),
+            ..Default::default()
+        };
+        assert_relative_eq!(56.9, wx.dew_point().unwrap(), epsilon = 0.1);
+
+        let wx = WeatherData {
+            temperature: Some(77),
+            humidity: Some(100),
+            ..Default::default()
+        };
+        assert_relative_eq!(77.0, wx.dew_point().unwrap(), epsilon = 0.001);
+
+        assert_eq!(None, WeatherData::default().dew_point());
+    }
+
+    #[test]
+    fn wind_chill() {
+        let wx = WeatherData {
+            temperature: Some(5),
+            wind_speed: Some(25),
+            ..Default::default()
+        };
+        // NWS wind chill chart
+        assert_relative_eq!(-17.0, wx.wind_chill().unwrap(), epsilon = 0.5);
+
+        // too warm for wind chill
+        let wx = WeatherData {
+            temperature: Some(60),
+            wind_speed: Some(25),
+            ..Default::default()
+        };
+        assert_relative_eq!(60.0, wx.wind_chill().unwrap());
+    }
+
+    #[test]
+    fn heat_index() {
+        let wx = WeatherData {
+            temperature: Some(90),
+            humidity: Some(60),
+            ..Default::default()
+        };
+        // NWS heat index chart
+        assert_relative_eq!(100.0, wx.heat_index().unwrap(), epsilon = 0.5);
+
+        // mild weather uses the simple formula
+        let wx = WeatherData {
+            temperature: Some(70),
+            humidity: Some(50),
+            ..Default::default()
+        };
+        assert_relative_eq!(69.05, wx.heat_index().unwrap(), epsilon = 0.01);
+    }
+
+    #[test]
+    fn positionless() {
+        let original = &b"_10090556c220s004g005t077r000p000P000h50b09900wRSW"[..];
+        let wx = AprsWeather::decode(&original[1..], Callsign::new_no_ssid("APRS")).unwrap();
+
+        assert_eq!(MdhmTimestamp::new(10, 9, 5, 56), Some(wx.timestamp));
+        assert_eq!(Some(220), wx.data.wind_direction);
+        assert_eq!(Some(77), wx.data.temperature);
+        assert_eq!(Some(9900), wx.data.barometric_pressure);
+        assert_eq!(Some(b'w'), wx.software);
+        assert_eq!(b"RSW", &wx.unit[..]);
+
+        let mut buf = vec![];
+        wx.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+
+        let mut wx = wx;
+        wx.software = None;
+        assert!(matches!(
+            wx.encode(&mut vec![]),
+            Err(EncodeError::InvalidData)
+        ));
+    }
+
+    #[test]
+    fn positionless_invalid_timestamp() {
+        assert_eq!(
+            Err(DecodeError::InvalidTimestamp(b"1009".to_vec(), 0)),
+            AprsWeather::decode(b"1009", Callsign::new_no_ssid("APRS"))
+        );
+        assert_eq!(
+            Err(DecodeError::InvalidTimestamp(b"13090556".to_vec(), 0)),
+            AprsWeather::decode(b"13090556c220s004", Callsign::new_no_ssid("APRS"))
+        );
+    }
+
+    #[test]
+    fn decode_position_comment() {
+        let (wx, rest) = WeatherData::decode_position_comment(b"220/004g005t077r000h50 Home");
+        assert_eq!(
+            WeatherData {
+                wind_direction: Some(220),
+                wind_speed: Some(4),
+                wind_gust: Some(5),
+                temperature: Some(77),
+                rain_last_hour: Some(0),
+                humidity: Some(50),
+                ..Default::default()
+            },
+            wx
+        );
+        assert_eq!(b" Home", rest);
+
+        let (wx, _) = WeatherData::decode_position_comment(b".../...g...t050");
+        assert_eq!(None, wx.wind_direction);
+        assert_eq!(Some(50), wx.temperature);
+
+        let (wx, _) = WeatherData::decode_position_comment(b"c220s004t077");
+        assert_eq!(Some(220), wx.wind_direction);
+    }
+
+    #[test]
+    fn ultimeter() {
+        let original = &b"$ULTW0031003702CE0069----000086A0008603E80146031600CB0008"[..];
+        let wx = AprsRawWeather::decode(original, Callsign::new_no_ssid("APRS")).unwrap();
+
+        assert_eq!(RawWeatherFormat::Ultimeter, wx.format);
+        assert_eq!(
+            WeatherData {
+                wind_direction: Some(77),
+                wind_speed: Some(0),
+                wind_gust: Some(3),
+                temperature: Some(72),
+                rain_since_midnight: Some(203),
+                humidity: Some(100),
+                ..Default::default()
+            },
+            wx.data
+        );
+
+        let mut buf = vec![];
+        wx.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+    }
+
+    #[test]
+    fn peet_bros() {
+        let wx = AprsRawWeather::decode(
+            b"!!000A000000A600B5------------001C01D500000017----",
+            Callsign::new_no_ssid("APRS"),
+        )
+        .unwrap();
+
+        assert_eq!(RawWeatherFormat::PeetBros, wx.format);
+        assert_eq!(Some(0), wx.data.wind_direction);
+        assert_eq!(Some(1), wx.data.wind_speed);
+        assert_eq!(Some(17), wx.data.temperature);
+        assert_eq!(None, wx.data.barometric_pressure);
+        assert_eq!(None, wx.data.humidity);
+
+        assert_eq!(
+            Err(DecodeError::InvalidWeather(b"!!00X0".to_vec(), 0)),
+            AprsRawWeather::decode(b"!!00X0", Callsign::new_no_ssid("APRS"))
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn deserialize_checks_raw() {
+        let fields = |raw: &[u8]| RawWeatherFields {
+            to: Callsign::new_no_ssid("APRS"),
+            format: RawWeatherFormat::PeetBros,
+            data: WeatherData::default(),
+            raw: raw.to_vec(),
+        };
+
+        let wx = AprsRawWeather::try_from(fields(b"!!000A")).unwrap();
+        assert_eq!(b'!', wx.format.data_type_identifier());
+        assert!(AprsRawWeather::try_from(fields(b"")).is_err());
+        assert!(AprsRawWeather::try_from(fields(b"$ULTW000A")).is_err());
+    }
+
+    #[test]
+    fn decode_all_fields() {
+        let (wx, rest) = WeatherData::decode(&b"c220s004g005t077r001p002P003h50b09900wRSW"[..]);
+
+        assert_eq!(
+            WeatherData {
+                wind_direction: Some(220),
+                wind_speed: Some(4),
+                wind_gust: Some(5),
+                temperature: Some(77),
+                rain_last_hour: Some(1),
+                rain_last_24_hours: Some(2),
+                rain_since_midnight: Some(3),
+                humidity: Some(50),
+                barometric_pressure: Some(9900),
+            },
+            wx
+        );
+        assert_eq!(b"wRSW", rest);
+    }
+
+    #[test]
+    fn decode_placeholders() {
+        let (wx, rest) = WeatherData::decode(&b"c...s   g---t-05h00b.....Hello"[..]);
+
+        assert_eq!(
+            WeatherData {
+                temperature: Some(-5),
+                humidity: Some(100),
+                ..Default::default()
+            },
+            wx
+        );
+        assert_eq!(b"Hello", rest);
+    }
+
+    #[test]
+    fn decode_stops_at_invalid_or_repeated_fields() {
+        let (wx, rest) = WeatherData::decode(&b"c220t7a7"[..]);
+        assert_eq!(Some(220), wx.wind_direction);
+        assert_eq!(None, wx.temperature);
+        assert_eq!(b"t7a7", rest);
+
+        // a second `s` is snowfall, which we don't decode
+        let (wx, rest) = WeatherData::decode(&b"s004t077s001"[..]);
+        assert_eq!(Some(4), wx.wind_speed);
+        assert_eq!(b"s001", rest);
+    }
+
+    #[test]
+    fn encode() {
+        let mut buf = vec![];
+        WeatherData {
+            wind_gust: Some(5),
+            temperature: Some(-5),
+            humidity: Some(100),
+            ..Default::default()
+        }
+        .encode(&mut buf)
+        .unwrap();
+        assert_eq!(&b"c...s...g005t-05h00"[..], buf);
+
+        let original = &b"c220s004g005t077r001p002P003h50b09900"[..];
+        let mut buf = vec![];
+        WeatherData::decode(original).0.encode(&mut buf).unwrap();
+        assert_eq!(original, buf);
+
+        for wx in &[
+            WeatherData {
+                temperature: Some(-100),
+                ..Default::default()
+            },
+            WeatherData {
+                wind_direction: Some(1000),
+                ..Default::default()
+            },
+            WeatherData {
+                barometric_pressure: Some(100000),
+                ..Default::default()
+            },
+        ] {
+            assert!(matches!(
+                wx.encode(&mut vec![]),
+                Err(EncodeError::InvalidData)
+            ));
+        }
+        let mut buf = vec![];
+        WeatherData {
+            temperature: Some(-99),
+            barometric_pressure: Some(99999),
+            ..Default::default()
+        }
+        .encode(&mut buf)
+        .unwrap();
+        assert_eq!(&b"c...s...g...t-99b99999"[..], buf);
+    }
+}